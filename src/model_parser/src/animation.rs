@@ -0,0 +1,197 @@
+use crate::error::{ModelParserError, Result};
+use glam::{Mat4, Quat, Vec3};
+use gltf::animation::{Interpolation, util::ReadOutputs};
+use serde::{Deserialize, Serialize};
+
+/// Animation and time at which glTF scenes are posed before sampling
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AnimationPose {
+    /// Animation name, or its index in the file
+    pub animation: String,
+
+    /// Time in seconds, held at the first or last keyframe outside the animation
+    pub time: f32,
+}
+
+/// Node transforms and morph weights of a glTF document at a pose
+pub struct SceneState {
+    /// World transform of every node, by node index
+    pub world: Vec<Mat4>,
+
+    /// Morph target weights set by the animation, by node index
+    pub weights: Vec<Option<Vec<f32>>>,
+}
+
+/// Evaluate every node of the document at `pose`, or at the rest pose stored in the nodes
+pub fn evaluate(
+    document: &gltf::Document,
+    buffers: &[gltf::buffer::Data],
+    pose: Option<&AnimationPose>,
+) -> Result<SceneState> {
+    let mut local: Vec<Mat4> = document
+        .nodes()
+        .map(|node| Mat4::from_cols_array_2d(&node.transform().matrix()))
+        .collect();
+    let mut weights = vec![None; local.len()];
+
+    if let Some(pose) = pose {
+        let animation = find(document, &pose.animation)?;
+
+        // Channels override single components of the nodes' rest transforms
+        let mut trs: Vec<(Vec3, Quat, Vec3)> = document
+            .nodes()
+            .map(|node| {
+                let (t, r, s) = node.transform().decomposed();
+                (Vec3::from(t), Quat::from_array(r), Vec3::from(s))
+            })
+            .collect();
+        let mut animated = vec![false; local.len()];
+
+        for channel in animation.channels() {
+            let reader = channel.reader(|buffer| buffers.get(buffer.index()).map(|data| &data.0[..]));
+            let (Some(inputs), Some(outputs)) = (reader.read_inputs(), reader.read_outputs()) else {
+                continue;
+            };
+            let times: Vec<f32> = inputs.collect();
+            let interpolation = channel.sampler().interpolation();
+            let node = channel.target().node().index();
+            let (translation, rotation, scale) = &mut trs[node];
+
+            match outputs {
+                ReadOutputs::Translations(values) => {
+                    let values: Vec<f32> = values.flatten().collect();
+                    *translation = Vec3::from_slice(&sample(&times, &values, 3, interpolation, pose.time, false));
+                }
+                ReadOutputs::Rotations(values) => {
+                    let values: Vec<f32> = values.into_f32().flatten().collect();
+                    *rotation = Quat::from_slice(&sample(&times, &values, 4, interpolation, pose.time, true));
+                }
+                ReadOutputs::Scales(values) => {
+                    let values: Vec<f32> = values.flatten().collect();
+                    *scale = Vec3::from_slice(&sample(&times, &values, 3, interpolation, pose.time, false));
+                }
+                ReadOutputs::MorphTargetWeights(values) => {
+                    let values: Vec<f32> = values.into_f32().collect();
+                    let keyframes = times.len() * if interpolation == Interpolation::CubicSpline { 3 } else { 1 };
+                    let width = values.len() / keyframes.max(1);
+                    weights[node] = Some(sample(&times, &values, width, interpolation, pose.time, false));
+                    continue;
+                }
+            }
+            animated[node] = true;
+        }
+
+        for ((matrix, (translation, rotation, scale)), _) in
+            local.iter_mut().zip(trs).zip(&animated).filter(|(_, animated)| **animated)
+        {
+            *matrix = Mat4::from_scale_rotation_translation(scale, rotation.normalize(), translation);
+        }
+    }
+
+    // Nodes have at most one parent, so world transforms follow from the roots down
+    let mut world = vec![Mat4::IDENTITY; local.len()];
+    let mut is_child = vec![false; local.len()];
+    document.nodes().flat_map(|node| node.children()).for_each(|child| is_child[child.index()] = true);
+    let mut stack: Vec<(gltf::Node, Mat4)> =
+        document.nodes().filter(|node| !is_child[node.index()]).map(|node| (node, Mat4::IDENTITY)).collect();
+    while let Some((node, parent)) = stack.pop() {
+        let transform = parent * local[node.index()];
+        world[node.index()] = transform;
+        stack.extend(node.children().map(|child| (child, transform)));
+    }
+
+    Ok(SceneState { world, weights })
+}
+
+/// Animation by name, or by its index in the file
+pub fn find<'a>(document: &'a gltf::Document, name: &str) -> Result<gltf::Animation<'a>> {
+    document
+        .animations()
+        .find(|a| a.name() == Some(name))
+        .or_else(|| name.parse().ok().and_then(|index| document.animations().nth(index)))
+        .ok_or_else(|| {
+            let names: Vec<String> = document
+                .animations()
+                .map(|a| a.name().map(str::to_string).unwrap_or_else(|| a.index().to_string()))
+                .collect();
+            ModelParserError::InvalidData(format!(
+                "animation '{}' not found (available: {})",
+                name,
+                if names.is_empty() { "none".to_string() } else { names.join(", ") }
+            ))
+        })
+}
+
+/// First and last keyframe times of an animation, in seconds
+pub fn time_range(animation: &gltf::Animation, buffers: &[gltf::buffer::Data]) -> (f32, f32) {
+    animation
+        .channels()
+        .filter_map(|channel| channel.reader(|buffer| buffers.get(buffer.index()).map(|data| &data.0[..])).read_inputs())
+        .flatten()
+        .fold(None, |range: Option<(f32, f32)>, t| {
+            Some(range.map_or((t, t), |(start, end)| (start.min(t), end.max(t))))
+        })
+        .unwrap_or((0.0, 0.0))
+}
+
+/// Joint matrices of `skin`, taking bind-pose vertices to world space
+pub fn joint_matrices(skin: &gltf::Skin, buffers: &[gltf::buffer::Data], world: &[Mat4]) -> Vec<Mat4> {
+    let inverse_bind: Vec<Mat4> = skin
+        .reader(|buffer| buffers.get(buffer.index()).map(|data| &data.0[..]))
+        .read_inverse_bind_matrices()
+        .map(|matrices| matrices.map(|m| Mat4::from_cols_array_2d(&m)).collect())
+        .unwrap_or_default();
+
+    skin.joints()
+        .enumerate()
+        .map(|(i, joint)| world[joint.index()] * inverse_bind.get(i).copied().unwrap_or(Mat4::IDENTITY))
+        .collect()
+}
+
+/// Value of one keyframe track at `time`; `values` holds `width` components per keyframe, or
+/// in-tangent, value and out-tangent per keyframe for cubic splines
+fn sample(times: &[f32], values: &[f32], width: usize, interpolation: Interpolation, time: f32, rotation: bool) -> Vec<f32> {
+    let cubic = interpolation == Interpolation::CubicSpline;
+    let stride = if cubic { 3 * width } else { width };
+    let offset = if cubic { width } else { 0 };
+    let keyframes = times.len().min(values.len() / stride.max(1));
+    let value = |k: usize| &values[k * stride + offset..k * stride + offset + width];
+    if keyframes == 0 {
+        return vec![0.0; width];
+    }
+
+    let next = times[..keyframes].partition_point(|&t| t <= time);
+    if next == 0 {
+        return value(0).to_vec();
+    }
+    if next == keyframes {
+        return value(keyframes - 1).to_vec();
+    }
+
+    let k = next - 1;
+    let dt = times[next] - times[k];
+    let s = if dt > 0.0 { (time - times[k]) / dt } else { 0.0 };
+    let result = match interpolation {
+        Interpolation::Step => value(k).to_vec(),
+        Interpolation::Linear if rotation => {
+            let [a, b] = [value(k), value(next)].map(Quat::from_slice);
+            return a.slerp(b, s).normalize().to_array().to_vec();
+        }
+        Interpolation::Linear => value(k).iter().zip(value(next)).map(|(a, b)| a + (b - a) * s).collect(),
+        Interpolation::CubicSpline => {
+            let out_tangent = &values[k * stride + 2 * width..(k + 1) * stride];
+            let in_tangent = &values[next * stride..next * stride + width];
+            let (s2, s3) = (s * s, s * s * s);
+            (0..width)
+                .map(|c| {
+                    (2.0 * s3 - 3.0 * s2 + 1.0) * value(k)[c]
+                        + (s3 - 2.0 * s2 + s) * dt * out_tangent[c]
+                        + (-2.0 * s3 + 3.0 * s2) * value(next)[c]
+                        + (s3 - s2) * dt * in_tangent[c]
+                })
+                .collect()
+        }
+    };
+
+    if rotation { Quat::from_slice(&result).normalize().to_array().to_vec() } else { result }
+}
@@ -0,0 +1,81 @@
+use serde::{Deserialize, Serialize};
+
+/// Storage type of a generic per-point attribute
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AttributeType {
+    U8,
+    U16,
+    U32,
+    I32,
+    F32,
+    F64,
+}
+
+impl AttributeType {
+    /// Size in bytes of one value
+    pub fn size(&self) -> u32 {
+        match self {
+            AttributeType::U8 => 1,
+            AttributeType::U16 => 2,
+            AttributeType::U32 | AttributeType::I32 | AttributeType::F32 => 4,
+            AttributeType::F64 => 8,
+        }
+    }
+
+    /// EPT schema type name ("unsigned", "signed" or "floating")
+    pub fn ept_type(&self) -> &'static str {
+        match self {
+            AttributeType::U8 | AttributeType::U16 | AttributeType::U32 => "unsigned",
+            AttributeType::I32 => "signed",
+            AttributeType::F32 | AttributeType::F64 => "floating",
+        }
+    }
+
+    /// Encode a value as little-endian bytes, rounding and clamping integers to the type range
+    pub fn encode(&self, value: f64, out: &mut Vec<u8>) {
+        match self {
+            AttributeType::U8 => out.push(value.round().clamp(0.0, u8::MAX as f64) as u8),
+            AttributeType::U16 => {
+                out.extend_from_slice(&(value.round().clamp(0.0, u16::MAX as f64) as u16).to_le_bytes())
+            }
+            AttributeType::U32 => {
+                out.extend_from_slice(&(value.round().clamp(0.0, u32::MAX as f64) as u32).to_le_bytes())
+            }
+            AttributeType::I32 => out.extend_from_slice(
+                &(value.round().clamp(i32::MIN as f64, i32::MAX as f64) as i32).to_le_bytes(),
+            ),
+            AttributeType::F32 => out.extend_from_slice(&(value as f32).to_le_bytes()),
+            AttributeType::F64 => out.extend_from_slice(&value.to_le_bytes()),
+        }
+    }
+
+    /// Decode a little-endian value written by [`AttributeType::encode`]
+    pub fn decode(&self, bytes: &[u8]) -> f64 {
+        match self {
+            AttributeType::U8 => bytes[0] as f64,
+            AttributeType::U16 => u16::from_le_bytes([bytes[0], bytes[1]]) as f64,
+            AttributeType::U32 => u32::from_le_bytes(bytes[..4].try_into().unwrap()) as f64,
+            AttributeType::I32 => i32::from_le_bytes(bytes[..4].try_into().unwrap()) as f64,
+            AttributeType::F32 => f32::from_le_bytes(bytes[..4].try_into().unwrap()) as f64,
+            AttributeType::F64 => f64::from_le_bytes(bytes[..8].try_into().unwrap()),
+        }
+    }
+}
+
+/// Name and storage type of a generic per-point attribute
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AttributeDescriptor {
+    pub name: String,
+
+    #[serde(rename = "type")]
+    pub data_type: AttributeType,
+}
+
+impl AttributeDescriptor {
+    pub fn new(name: impl Into<String>, data_type: AttributeType) -> Self {
+        Self {
+            name: name.into(),
+            data_type,
+        }
+    }
+}
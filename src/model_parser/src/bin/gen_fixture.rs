@@ -0,0 +1,53 @@
+use clap::Parser;
+use model_parser::{PointCloudConfig, fixtures::Fixture};
+use std::path::PathBuf;
+
+#[derive(Parser, Debug)]
+#[command(name = "gen_fixture")]
+#[command(about = "Generate procedural test models and reference point clouds", long_about = None)]
+struct Args {
+    /// Output directory
+    #[arg(short, long)]
+    output: PathBuf,
+
+    /// Fixtures to generate: sphere, cube, blob, degenerate (default: all)
+    #[arg(short, long = "fixture")]
+    fixtures: Vec<String>,
+
+    /// Points in each reference cloud
+    #[arg(short = 'n', long, default_value_t = 2000)]
+    point_count: usize,
+
+    /// Seed used for the reference clouds
+    #[arg(long, default_value_t = 0)]
+    seed: u64,
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args = Args::parse();
+
+    let fixtures = if args.fixtures.is_empty() {
+        Fixture::ALL.to_vec()
+    } else {
+        args.fixtures
+            .iter()
+            .map(|name| match Fixture::from_name(&name.to_lowercase()) {
+                Some(fixture) => fixture,
+                None => {
+                    eprintln!("Error: Unknown fixture '{}'. Use: sphere, cube, blob or degenerate", name);
+                    std::process::exit(1);
+                }
+            })
+            .collect()
+    };
+
+    let config = PointCloudConfig::new(args.point_count).with_seed(Some(args.seed));
+    let files = model_parser::fixtures::generate(&args.output, &fixtures, &config)?;
+
+    println!("✓ Generated {} fixture files in {:?}", files.len(), args.output);
+    for file in files {
+        println!("  - {}", file.file_name().and_then(|n| n.to_str()).unwrap_or(""));
+    }
+
+    Ok(())
+}
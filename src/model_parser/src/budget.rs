@@ -0,0 +1,207 @@
+use crate::{
+    error::{ModelParserError, Result},
+    expression::Expression,
+    point_cloud::{JsonLayout, PointCloud, PointCloudArrays},
+    random,
+};
+use rand::{Rng, seq::SliceRandom};
+use rayon::prelude::*;
+
+/// Points serialized when measuring the JSON cost per point
+const JSON_SAMPLE_POINTS: usize = 1000;
+
+/// Estimated size of an output: a fixed overhead plus a cost per point
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SizeEstimate {
+    pub bytes_per_point: f64,
+    pub overhead: u64,
+}
+
+impl SizeEstimate {
+    /// Estimated total bytes for `points` points
+    pub fn total(&self, points: usize) -> u64 {
+        self.overhead + (self.bytes_per_point * points as f64).ceil() as u64
+    }
+
+    /// Largest point count whose estimate fits in `max_bytes`
+    pub fn points_within(&self, max_bytes: u64) -> usize {
+        if self.bytes_per_point <= 0.0 {
+            return usize::MAX;
+        }
+        (max_bytes.saturating_sub(self.overhead) as f64 / self.bytes_per_point).floor() as usize
+    }
+}
+
+/// Parse a size such as "20MB", "512 KiB", "1.5GB" or "1048576" into bytes.
+/// Decimal (KB/MB/GB) and binary (KiB/MiB/GiB) units are both accepted
+pub fn parse_size(text: &str) -> Result<u64> {
+    let text = text.trim();
+    let split = text
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+
+    let invalid = || ModelParserError::InvalidData(format!("invalid size '{}', expected e.g. 20MB or 512KiB", text));
+    let number: f64 = number.parse().map_err(|_| invalid())?;
+    let multiplier: f64 = match unit.trim().to_lowercase().as_str() {
+        "" | "b" => 1.0,
+        "kb" | "k" => 1e3,
+        "mb" | "m" => 1e6,
+        "gb" | "g" => 1e9,
+        "kib" => 1024.0,
+        "mib" => 1024.0 * 1024.0,
+        "gib" => 1024.0 * 1024.0 * 1024.0,
+        _ => return Err(invalid()),
+    };
+    Ok((number * multiplier) as u64)
+}
+
+/// Estimate the JSON size in `layout` by serializing a sample of the points
+pub fn estimate_json(point_cloud: &PointCloud, layout: JsonLayout) -> Result<SizeEstimate> {
+    let size = |cloud: &PointCloud| -> Result<u64> {
+        Ok(match layout {
+            JsonLayout::Points => serde_json::to_string_pretty(cloud)?.len(),
+            JsonLayout::Arrays => serde_json::to_string(&PointCloudArrays::from(cloud))?.len(),
+        } as u64)
+    };
+
+    let mut empty = point_cloud.clone();
+    empty.points.clear();
+    let overhead = size(&empty)?;
+
+    let step = (point_cloud.points.len() / JSON_SAMPLE_POINTS).max(1);
+    let mut sample = empty;
+    sample.points = point_cloud.points.iter().step_by(step).take(JSON_SAMPLE_POINTS).cloned().collect();
+    if sample.points.is_empty() {
+        return Ok(SizeEstimate { bytes_per_point: 0.0, overhead });
+    }
+
+    let sample_size = size(&sample)?;
+    Ok(SizeEstimate {
+        bytes_per_point: sample_size.saturating_sub(overhead) as f64 / sample.points.len() as f64,
+        overhead,
+    })
+}
+
+/// Round JSON values to the precision a viewer can show: positions to about 1/65536
+/// of the cloud extent, normals and colors to 4 decimals. Shortens every number in the output
+pub fn quantize_for_json(point_cloud: &mut PointCloud) {
+    let extent = (0..3)
+        .map(|i| point_cloud.metadata.bounds_max[i] - point_cloud.metadata.bounds_min[i])
+        .fold(0.0f32, f32::max);
+    let position_decimals = if extent > 0.0 {
+        (-(extent / 65536.0).log10()).ceil().clamp(0.0, 9.0) as i32
+    } else {
+        6
+    };
+
+    fn round(value: &mut f32, decimals: i32) {
+        let factor = 10f64.powi(decimals);
+        *value = ((*value as f64 * factor).round() / factor) as f32;
+    }
+
+    point_cloud.points.par_iter_mut().for_each(|point| {
+        point.position.iter_mut().for_each(|v| round(v, position_decimals));
+        if let Some(normal) = point.normal.as_mut() {
+            normal.iter_mut().for_each(|v| round(v, 4));
+        }
+        if let Some(color) = point.color.as_mut() {
+            color.iter_mut().for_each(|v| round(v, 4));
+        }
+    });
+    point_cloud.update_metadata();
+}
+
+/// Keep a random subset of `count` points, preserving their order
+pub fn thin(point_cloud: &mut PointCloud, count: usize, seed: Option<u64>) {
+    if count >= point_cloud.points.len() {
+        return;
+    }
+
+    let mut rng = random::chunk_rng(seed, random::stream::THINNING, 0);
+    let mut keep = vec![false; point_cloud.points.len()];
+    let mut indices: Vec<usize> = (0..point_cloud.points.len()).collect();
+    let (selected, _) = indices.partial_shuffle(&mut rng, count);
+    for &index in selected.iter() {
+        keep[index] = true;
+    }
+
+    point_cloud.retain_by_mask(&keep);
+}
+
+/// Relative chances of points surviving `thin_weighted`, from "weight:expression" rules such
+/// as "0.1:classification == 2" or "all:edge > 0.5". The first rule a point matches sets its
+/// weight, 1 when none does; "all" keeps every matching point before any other is kept
+#[derive(Default)]
+pub struct BudgetWeights {
+    rules: Vec<(f64, Expression)>,
+}
+
+impl BudgetWeights {
+    pub fn parse<S: AsRef<str>>(specs: &[S]) -> Result<Self> {
+        let rules = specs
+            .iter()
+            .map(|spec| {
+                let spec = spec.as_ref();
+                let invalid = || {
+                    ModelParserError::InvalidData(format!(
+                        "invalid budget weight '{}', expected e.g. 0.1:classification == 2 or all:edge > 0.5",
+                        spec
+                    ))
+                };
+                let (weight, expression) = spec.split_once(':').ok_or_else(invalid)?;
+                let weight = match weight.trim() {
+                    "all" => f64::INFINITY,
+                    weight => weight.parse::<f64>().ok().filter(|w| w.is_finite() && *w >= 0.0).ok_or_else(invalid)?,
+                };
+                Ok((weight, Expression::parse(expression.trim())?))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self { rules })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Weight of every point of `point_cloud`
+    pub fn evaluate(&self, point_cloud: &PointCloud) -> Result<Vec<f64>> {
+        let mut weights = vec![None; point_cloud.points.len()];
+        for (weight, expression) in &self.rules {
+            for (slot, selected) in weights.iter_mut().zip(expression.select(point_cloud)?) {
+                if selected && slot.is_none() {
+                    *slot = Some(*weight);
+                }
+            }
+        }
+        Ok(weights.into_iter().map(|weight| weight.unwrap_or(1.0)).collect())
+    }
+}
+
+/// Keep `count` points, preserving their order, where each point's chance of being kept
+/// grows with its weight (weighted sampling without replacement, Efraimidis-Spirakis).
+/// Infinite weights are kept first and zero weights only when nothing else is left
+pub fn thin_weighted(point_cloud: &mut PointCloud, count: usize, weights: &[f64], seed: Option<u64>) {
+    if count >= point_cloud.points.len() {
+        return;
+    }
+
+    // Key ln(u) / w: the largest keys are the sample; 0 for infinite weights, -inf for zero
+    let mut rng = random::chunk_rng(seed, random::stream::THINNING, 0);
+    let mut keys: Vec<(f64, usize)> = weights
+        .iter()
+        .enumerate()
+        .map(|(index, &weight)| {
+            let u: f64 = rng.random_range(f64::MIN_POSITIVE..1.0);
+            let key = if weight > 0.0 { u.ln() / weight } else { f64::NEG_INFINITY };
+            (key, index)
+        })
+        .collect();
+    keys.select_nth_unstable_by(count, |a, b| b.0.total_cmp(&a.0).then(a.1.cmp(&b.1)));
+
+    let mut keep = vec![false; point_cloud.points.len()];
+    for &(_, index) in &keys[..count] {
+        keep[index] = true;
+    }
+    point_cloud.retain_by_mask(&keep);
+}
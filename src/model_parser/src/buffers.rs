@@ -0,0 +1,167 @@
+use crate::{
+    attributes::AttributeType,
+    budget::SizeEstimate,
+    color,
+    error::Result,
+    point_cloud::{Point, PointCloud, PointCloudMetadata},
+};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Small JSON descriptor of a binary buffer file holding one tightly packed array per
+/// property, ready to upload into WebGL/WebGPU vertex buffers without parsing
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BufferDescriptor {
+    /// Binary file name, relative to the descriptor
+    pub buffer: String,
+
+    /// Size of the binary file in bytes
+    pub byte_length: u64,
+
+    /// Number of points (elements of every array)
+    pub count: usize,
+
+    pub arrays: Vec<BufferArray>,
+
+    pub metadata: PointCloudMetadata,
+}
+
+/// One array of the binary buffer
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BufferArray {
+    /// "position", "normal", "color", "alpha", "material_id" or a generic attribute name
+    pub name: String,
+
+    /// Typed array name: "Float32", "Float64", "Uint8", "Uint16", "Uint32" or "Int32"
+    pub component_type: String,
+
+    /// Components per point
+    pub components: u32,
+
+    /// Whether integer values represent 0.0-1.0 (colors and alpha)
+    pub normalized: bool,
+
+    /// Offset in bytes from the start of the buffer; a multiple of the component size
+    pub byte_offset: u64,
+
+    pub byte_length: u64,
+}
+
+/// Typed array name of an attribute type
+fn component_type(data_type: AttributeType) -> &'static str {
+    match data_type {
+        AttributeType::U8 => "Uint8",
+        AttributeType::U16 => "Uint16",
+        AttributeType::U32 => "Uint32",
+        AttributeType::I32 => "Int32",
+        AttributeType::F32 => "Float32",
+        AttributeType::F64 => "Float64",
+    }
+}
+
+/// Where the values of an array come from
+#[derive(Clone, Copy)]
+enum Source {
+    Position,
+    Normal,
+    Color,
+    Alpha,
+    MaterialId,
+    Attribute(usize),
+}
+
+/// Arrays written for a cloud: positions, normals, colors and alpha when every point has them
+/// (colors and alpha as normalized bytes), material ids when tracked, then the generic attributes
+fn layout(point_cloud: &PointCloud) -> Vec<(Source, BufferArray, AttributeType)> {
+    let points = &point_cloud.points;
+    let all = |present: fn(&Point) -> bool| !points.is_empty() && points.iter().all(present);
+    let array = |name: &str, data_type: AttributeType, components: u32, normalized: bool| BufferArray {
+        name: name.to_string(),
+        component_type: component_type(data_type).to_string(),
+        components,
+        normalized,
+        byte_offset: 0,
+        byte_length: (data_type.size() * components) as u64 * points.len() as u64,
+    };
+
+    let mut arrays = vec![(Source::Position, array("position", AttributeType::F32, 3, false), AttributeType::F32)];
+    if all(|p| p.normal.is_some()) {
+        arrays.push((Source::Normal, array("normal", AttributeType::F32, 3, false), AttributeType::F32));
+    }
+    if all(|p| p.color.is_some()) {
+        arrays.push((Source::Color, array("color", AttributeType::U8, 3, true), AttributeType::U8));
+    }
+    if all(|p| p.alpha.is_some()) {
+        arrays.push((Source::Alpha, array("alpha", AttributeType::U8, 1, true), AttributeType::U8));
+    }
+    if all(|p| p.material_id.is_some()) {
+        arrays.push((Source::MaterialId, array("material_id", AttributeType::U32, 1, false), AttributeType::U32));
+    }
+    for (i, attribute) in point_cloud.metadata.attributes.iter().enumerate() {
+        arrays.push((Source::Attribute(i), array(&attribute.name, attribute.data_type, 1, false), attribute.data_type));
+    }
+
+    // Typed array views need offsets aligned to their element size
+    let mut offset = 0u64;
+    for (_, array, data_type) in arrays.iter_mut() {
+        offset = offset.next_multiple_of(data_type.size() as u64);
+        array.byte_offset = offset;
+        offset += array.byte_length;
+    }
+    arrays
+}
+
+/// Write `point_cloud` as `path` (the descriptor) and a binary file beside it with the same
+/// name and a `.bin` extension. Arrays are little-endian, non-interleaved and aligned
+pub fn write_buffers(point_cloud: &PointCloud, path: &Path) -> Result<BufferDescriptor> {
+    let bin_path = path.with_extension("bin");
+    let layout = layout(point_cloud);
+    let mut data = Vec::new();
+
+    for (source, array, data_type) in &layout {
+        data.resize(array.byte_offset as usize, 0);
+        for point in &point_cloud.points {
+            match *source {
+                Source::Position => point.position.iter().for_each(|v| data.extend_from_slice(&v.to_le_bytes())),
+                Source::Normal => {
+                    point.normal.unwrap_or_default().iter().for_each(|v| data.extend_from_slice(&v.to_le_bytes()))
+                }
+                Source::Color => data.extend_from_slice(&color::to_rgb8(point.color.unwrap_or_default())),
+                Source::Alpha => data.push(color::to_u8(point.alpha.unwrap_or(1.0))),
+                Source::MaterialId => data.extend_from_slice(&point.material_id.unwrap_or(0).to_le_bytes()),
+                Source::Attribute(i) => data_type.encode(point.attributes.get(i).copied().unwrap_or(0.0), &mut data),
+            }
+        }
+    }
+    std::fs::write(&bin_path, &data)?;
+
+    let descriptor = BufferDescriptor {
+        buffer: bin_path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default(),
+        byte_length: data.len() as u64,
+        count: point_cloud.points.len(),
+        arrays: layout.into_iter().map(|(_, array, _)| array).collect(),
+        metadata: point_cloud.metadata.clone(),
+    };
+    std::fs::write(path, serde_json::to_string_pretty(&descriptor)?)?;
+    Ok(descriptor)
+}
+
+/// Size of the binary output: the descriptor plus the packed bytes of each point
+pub fn estimate(point_cloud: &PointCloud) -> Result<SizeEstimate> {
+    let layout = layout(point_cloud);
+    let bytes_per_point: u32 = layout.iter().map(|(_, array, data_type)| data_type.size() * array.components).sum();
+
+    // Descriptor with room for larger numbers, plus worst-case alignment padding
+    let descriptor = BufferDescriptor {
+        buffer: String::new(),
+        byte_length: u64::MAX,
+        count: usize::MAX,
+        arrays: layout
+            .iter()
+            .map(|(_, array, _)| BufferArray { byte_offset: u64::MAX, byte_length: u64::MAX, ..array.clone() })
+            .collect(),
+        metadata: point_cloud.metadata.clone(),
+    };
+    let overhead = serde_json::to_string_pretty(&descriptor)?.len() as u64 + 8 * layout.len() as u64;
+    Ok(SizeEstimate { bytes_per_point: bytes_per_point as f64, overhead })
+}
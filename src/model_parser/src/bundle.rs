@@ -0,0 +1,85 @@
+use crate::{
+    error::Result,
+    manifest::MANIFEST_FILE,
+    viewer::{self, ViewerConfig},
+};
+use flate2::{Compression, write::GzEncoder};
+use rayon::prelude::*;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Header rules for static hosts that read a `_headers` file (Netlify, Cloudflare Pages);
+/// the same values apply to nginx/S3/CDN configurations
+const HEADERS_FILE: &str = "\
+# Hosting hints for this point cloud bundle.
+# Tiles are immutable per build; metadata may change when the bundle is re-published.
+# Every asset also exists as <name>.gz: serve it with `Content-Encoding: gzip`
+# (nginx: gzip_static on; S3/CDN: upload the .gz body with that header).
+/*
+  Access-Control-Allow-Origin: *
+  Access-Control-Allow-Methods: GET, HEAD, OPTIONS
+  Access-Control-Allow-Headers: Range
+  Access-Control-Expose-Headers: Content-Length, Content-Range
+
+/ept-data/*
+  Content-Type: application/octet-stream
+  Cache-Control: public, max-age=31536000, immutable
+
+/*.json
+  Content-Type: application/json
+  Cache-Control: public, max-age=300
+";
+
+/// Turn an EPT output directory into a deployable bundle: an index.html viewer,
+/// a `_headers` file with CORS/caching hints and gzip-precompressed copies of every asset.
+/// Returns the files that were added
+pub fn write_bundle(output_dir: &Path, config: &ViewerConfig) -> Result<Vec<PathBuf>> {
+    let index_path = output_dir.join("index.html");
+    std::fs::write(&index_path, viewer::render_ept_viewer(config))?;
+
+    let headers_path = output_dir.join("_headers");
+    std::fs::write(&headers_path, HEADERS_FILE)?;
+
+    let mut assets = Vec::new();
+    collect_assets(output_dir, &mut assets)?;
+
+    let compressed = assets
+        .par_iter()
+        .map(|asset| gzip_file(asset))
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut written = vec![index_path, headers_path];
+    written.extend(compressed);
+    Ok(written)
+}
+
+/// Files worth precompressing: everything except hints, manifests and existing archives
+fn collect_assets(dir: &Path, assets: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_assets(&path, assets)?;
+            continue;
+        }
+
+        let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+        let skip = name == "_headers" || name == MANIFEST_FILE || name.ends_with(".gz");
+        if !skip {
+            assets.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Write `<path>.gz` next to `path`
+fn gzip_file(path: &Path) -> Result<PathBuf> {
+    let data = std::fs::read(path)?;
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+    encoder.write_all(&data)?;
+
+    let mut gz_path = path.as_os_str().to_owned();
+    gz_path.push(".gz");
+    let gz_path = PathBuf::from(gz_path);
+    std::fs::write(&gz_path, encoder.finish()?)?;
+    Ok(gz_path)
+}
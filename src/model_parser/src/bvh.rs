@@ -0,0 +1,289 @@
+use crate::mesh::{Mesh, ray_triangle};
+use glam::Vec3;
+
+const LEAF_SIZE: usize = 4;
+
+/// Static bounding volume hierarchy over the triangles of a mesh for ray casts and
+/// closest-point queries. Triangles are reported by their index in the mesh's index buffer
+pub struct Bvh {
+    triangles: Vec<[Vec3; 3]>,
+    /// Triangle indices, permuted so every node owns a contiguous range
+    indices: Vec<usize>,
+    nodes: Vec<BvhNode>,
+}
+
+struct BvhNode {
+    min: Vec3,
+    max: Vec3,
+    kind: BvhNodeKind,
+}
+
+enum BvhNodeKind {
+    Leaf { start: usize, end: usize },
+    Split { left: usize, right: usize },
+}
+
+/// Closest point of a mesh surface to a query position
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SurfacePoint {
+    /// Triangle the point lies on
+    pub triangle: usize,
+    pub position: Vec3,
+    pub distance: f32,
+}
+
+impl Bvh {
+    /// Build over the triangles of `mesh`
+    pub fn new(mesh: &Mesh) -> Self {
+        Self::from_triangles((0..mesh.triangle_count()).map(|t| mesh.triangle(t)).collect())
+    }
+
+    pub fn from_triangles(triangles: Vec<[Vec3; 3]>) -> Self {
+        let mut bvh = Self {
+            indices: (0..triangles.len()).collect(),
+            triangles,
+            nodes: Vec::new(),
+        };
+
+        if !bvh.triangles.is_empty() {
+            let centroids: Vec<Vec3> = bvh.triangles.iter().map(|[a, b, c]| (*a + *b + *c) / 3.0).collect();
+            let len = bvh.indices.len();
+            bvh.build(&centroids, 0, len);
+        }
+
+        bvh
+    }
+
+    pub fn len(&self) -> usize {
+        self.triangles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.triangles.is_empty()
+    }
+
+    /// Corner positions of a triangle
+    pub fn triangle(&self, triangle: usize) -> [Vec3; 3] {
+        self.triangles[triangle]
+    }
+
+    fn build(&mut self, centroids: &[Vec3], start: usize, end: usize) -> usize {
+        let node_index = self.nodes.len();
+        let (min, max) = self.indices[start..end].iter().flat_map(|&i| self.triangles[i]).fold(
+            (Vec3::splat(f32::MAX), Vec3::splat(f32::MIN)),
+            |(min, max), v| (min.min(v), max.max(v)),
+        );
+
+        if end - start <= LEAF_SIZE {
+            self.nodes.push(BvhNode { min, max, kind: BvhNodeKind::Leaf { start, end } });
+            return node_index;
+        }
+
+        // Split at the median centroid along the axis where the centroids spread the most
+        let (centroid_min, centroid_max) = self.indices[start..end].iter().fold(
+            (Vec3::splat(f32::MAX), Vec3::splat(f32::MIN)),
+            |(min, max), &i| (min.min(centroids[i]), max.max(centroids[i])),
+        );
+        let extent = centroid_max - centroid_min;
+        let axis = if extent.x >= extent.y && extent.x >= extent.z {
+            0
+        } else if extent.y >= extent.z {
+            1
+        } else {
+            2
+        };
+
+        let mid = (start + end) / 2;
+        self.indices[start..end].select_nth_unstable_by(mid - start, |&a, &b| {
+            centroids[a][axis].total_cmp(&centroids[b][axis])
+        });
+
+        // Reserve the slot, then fill in children
+        self.nodes.push(BvhNode { min, max, kind: BvhNodeKind::Leaf { start, end } });
+        let left = self.build(centroids, start, mid);
+        let right = self.build(centroids, mid, end);
+        self.nodes[node_index].kind = BvhNodeKind::Split { left, right };
+
+        node_index
+    }
+
+    /// Closest triangle hit by the ray within `max_distance` as (triangle, distance)
+    pub fn intersect_ray(&self, origin: Vec3, direction: Vec3, max_distance: f32) -> Option<(usize, f32)> {
+        if self.is_empty() {
+            return None;
+        }
+
+        let inverse = direction.recip();
+        let mut best: Option<(usize, f32)> = None;
+        let mut limit = max_distance;
+        let mut stack = vec![0];
+        while let Some(node) = stack.pop() {
+            let node = &self.nodes[node];
+            if ray_box(origin, inverse, node.min, node.max).is_none_or(|entry| entry > limit) {
+                continue;
+            }
+            match node.kind {
+                BvhNodeKind::Leaf { start, end } => {
+                    for &triangle in &self.indices[start..end] {
+                        if let Some(distance) = ray_triangle(origin, direction, self.triangles[triangle])
+                            && distance <= limit
+                        {
+                            limit = distance;
+                            best = Some((triangle, distance));
+                        }
+                    }
+                }
+                BvhNodeKind::Split { left, right } => {
+                    // Visit the nearer child first so hits prune the farther one
+                    let entry = |child: usize| {
+                        let child = &self.nodes[child];
+                        ray_box(origin, inverse, child.min, child.max).unwrap_or(f32::INFINITY)
+                    };
+                    if entry(left) <= entry(right) {
+                        stack.extend([right, left]);
+                    } else {
+                        stack.extend([left, right]);
+                    }
+                }
+            }
+        }
+
+        best
+    }
+
+    /// Whether anything blocks the ray within `max_distance`; cheaper than `intersect_ray`
+    /// as it stops at the first hit
+    pub fn occluded(&self, origin: Vec3, direction: Vec3, max_distance: f32) -> bool {
+        if self.is_empty() {
+            return false;
+        }
+
+        let inverse = direction.recip();
+        let mut stack = vec![0];
+        while let Some(node) = stack.pop() {
+            let node = &self.nodes[node];
+            if ray_box(origin, inverse, node.min, node.max).is_none_or(|entry| entry > max_distance) {
+                continue;
+            }
+            match node.kind {
+                BvhNodeKind::Leaf { start, end } => {
+                    let hit = self.indices[start..end].iter().any(|&triangle| {
+                        ray_triangle(origin, direction, self.triangles[triangle])
+                            .is_some_and(|distance| distance <= max_distance)
+                    });
+                    if hit {
+                        return true;
+                    }
+                }
+                BvhNodeKind::Split { left, right } => stack.extend([left, right]),
+            }
+        }
+
+        false
+    }
+
+    /// Closest point of the surface to `query`, or None for an empty hierarchy
+    pub fn closest_point(&self, query: Vec3) -> Option<SurfacePoint> {
+        if self.is_empty() {
+            return None;
+        }
+
+        let mut best: Option<SurfacePoint> = None;
+        let mut best_sq = f32::INFINITY;
+        let mut stack = vec![0];
+        while let Some(node) = stack.pop() {
+            let node = &self.nodes[node];
+            if box_distance_sq(query, node.min, node.max) >= best_sq {
+                continue;
+            }
+            match node.kind {
+                BvhNodeKind::Leaf { start, end } => {
+                    for &triangle in &self.indices[start..end] {
+                        let position = closest_point_on_triangle(query, self.triangles[triangle]);
+                        let distance_sq = position.distance_squared(query);
+                        if distance_sq < best_sq {
+                            best_sq = distance_sq;
+                            best = Some(SurfacePoint { triangle, position, distance: distance_sq.sqrt() });
+                        }
+                    }
+                }
+                BvhNodeKind::Split { left, right } => {
+                    let distance = |child: usize| {
+                        let child = &self.nodes[child];
+                        box_distance_sq(query, child.min, child.max)
+                    };
+                    if distance(left) <= distance(right) {
+                        stack.extend([right, left]);
+                    } else {
+                        stack.extend([left, right]);
+                    }
+                }
+            }
+        }
+
+        best
+    }
+}
+
+/// Distance along the ray to where it enters the box (0 when it starts inside), or None on a miss
+fn ray_box(origin: Vec3, inverse_direction: Vec3, min: Vec3, max: Vec3) -> Option<f32> {
+    let mut near = 0.0f32;
+    let mut far = f32::INFINITY;
+    for axis in 0..3 {
+        let t0 = (min[axis] - origin[axis]) * inverse_direction[axis];
+        let t1 = (max[axis] - origin[axis]) * inverse_direction[axis];
+        // f32::min/max skip the NaN of an axis-parallel ray starting on a slab face
+        near = near.max(t0.min(t1));
+        far = far.min(t0.max(t1));
+    }
+    (near <= far).then_some(near)
+}
+
+fn box_distance_sq(point: Vec3, min: Vec3, max: Vec3) -> f32 {
+    (point - point.clamp(min, max)).length_squared()
+}
+
+/// Closest point on a triangle, by the region of the triangle's Voronoi diagram `point` is in
+pub fn closest_point_on_triangle(point: Vec3, [a, b, c]: [Vec3; 3]) -> Vec3 {
+    let ab = b - a;
+    let ac = c - a;
+    let ap = point - a;
+    let d1 = ab.dot(ap);
+    let d2 = ac.dot(ap);
+    if d1 <= 0.0 && d2 <= 0.0 {
+        return a;
+    }
+
+    let bp = point - b;
+    let d3 = ab.dot(bp);
+    let d4 = ac.dot(bp);
+    if d3 >= 0.0 && d4 <= d3 {
+        return b;
+    }
+
+    let vc = d1 * d4 - d3 * d2;
+    if vc <= 0.0 && d1 >= 0.0 && d3 <= 0.0 {
+        return a + ab * (d1 / (d1 - d3));
+    }
+
+    let cp = point - c;
+    let d5 = ab.dot(cp);
+    let d6 = ac.dot(cp);
+    if d6 >= 0.0 && d5 <= d6 {
+        return c;
+    }
+
+    let vb = d5 * d2 - d1 * d6;
+    if vb <= 0.0 && d2 >= 0.0 && d6 <= 0.0 {
+        return a + ac * (d2 / (d2 - d6));
+    }
+
+    let va = d3 * d6 - d5 * d4;
+    if va <= 0.0 && d4 - d3 >= 0.0 && d5 - d6 >= 0.0 {
+        return b + (c - b) * ((d4 - d3) / ((d4 - d3) + (d5 - d6)));
+    }
+
+    // Inside the face
+    let denom = 1.0 / (va + vb + vc);
+    a + ab * (vb * denom) + ac * (vc * denom)
+}
@@ -0,0 +1,204 @@
+use crate::{
+    config::{PointCloudConfig, UpAxis},
+    error::{ModelParserError, Result},
+    mesh::Mesh,
+    xml::Element,
+};
+use glam::Vec3;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Load the meshes of a COLLADA (.dae) document: `triangles`, `polylist` and `polygons`
+/// primitives with their positions, normals and vertex colors. Geometry stays in the space
+/// of each mesh; scene node transforms are not applied
+pub fn load(path: &Path, config: &PointCloudConfig) -> Result<Mesh> {
+    let text = std::fs::read_to_string(path)?;
+    let document = Element::parse(&text)
+        .map_err(|message| ModelParserError::InvalidData(format!("{}: {}", path.display(), message)))?;
+    let invalid = |message: String| ModelParserError::InvalidData(format!("{}: {}", path.display(), message));
+
+    let mut mesh = Mesh::new();
+    mesh.up_axis = match document.child("asset").and_then(|asset| asset.child("up_axis")).map(|axis| axis.text.trim()) {
+        Some("Y_UP") => Some(UpAxis::Y),
+        Some("Z_UP") => Some(UpAxis::Z),
+        _ => None,
+    };
+    let mut has_colors = false;
+    let mut material_index: HashMap<String, u32> = HashMap::new();
+
+    let geometries = document
+        .children("library_geometries")
+        .flat_map(|library| library.children("geometry"));
+    for geometry in geometries {
+        let Some(geometry_mesh) = geometry.child("mesh") else {
+            continue;
+        };
+
+        let sources: HashMap<&str, Source> = geometry_mesh
+            .children("source")
+            .filter_map(|source| Some((source.attribute("id")?, Source::read(source)?)))
+            .collect();
+
+        // <vertices> bundles per-vertex inputs, referenced by primitives as VERTEX
+        let vertex_inputs: Vec<(&str, &str)> = geometry_mesh
+            .child("vertices")
+            .map(|vertices| {
+                vertices
+                    .children("input")
+                    .filter_map(|input| Some((input.attribute("semantic")?, reference(input.attribute("source")?))))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        for primitive in &geometry_mesh.children {
+            let kind = primitive.name.as_str();
+            if !matches!(kind, "triangles" | "polylist" | "polygons") {
+                continue;
+            }
+
+            // Inputs as (semantic, source, offset); VERTEX expands into the <vertices> inputs
+            let mut inputs: Vec<(&str, &str, usize)> = Vec::new();
+            let mut stride = 0;
+            for input in primitive.children("input") {
+                let semantic = input.attribute("semantic").unwrap_or("");
+                let offset: usize = input.attribute("offset").and_then(|o| o.parse().ok()).unwrap_or(0);
+                stride = stride.max(offset + 1);
+                let source = input.attribute("source").map(reference).unwrap_or("");
+                if semantic == "VERTEX" {
+                    inputs.extend(vertex_inputs.iter().map(|&(semantic, source)| (semantic, source, offset)));
+                } else {
+                    inputs.push((semantic, source, offset));
+                }
+            }
+            let find = |semantic: &str| {
+                inputs
+                    .iter()
+                    .find(|input| input.0 == semantic)
+                    .and_then(|&(_, source, offset)| Some((sources.get(source)?, offset)))
+            };
+            let Some(positions) = find("POSITION") else {
+                continue;
+            };
+            let normals = find("NORMAL");
+            let colors = find("COLOR");
+            has_colors |= colors.is_some();
+
+            let material = primitive.attribute("material").unwrap_or("default").to_string();
+            let next = mesh.materials.len() as u32;
+            let material_id = *material_index.entry(material.clone()).or_insert_with(|| {
+                mesh.materials.push(material);
+                next
+            });
+
+            // Polygon sizes: fixed for triangles, <vcount> for polylist, one <p> per polygon
+            let mut polygons: Vec<Vec<usize>> = Vec::new();
+            match kind {
+                "triangles" => {
+                    let indices = parse_indices(primitive.child("p").map_or("", |p| p.text.as_str())).map_err(&invalid)?;
+                    polygons.extend(indices.chunks_exact(stride * 3).map(<[usize]>::to_vec));
+                }
+                "polylist" => {
+                    let counts = parse_indices(primitive.child("vcount").map_or("", |v| v.text.as_str())).map_err(&invalid)?;
+                    let indices = parse_indices(primitive.child("p").map_or("", |p| p.text.as_str())).map_err(&invalid)?;
+                    let mut start = 0;
+                    for count in counts {
+                        let end = start + count * stride;
+                        polygons.push(indices.get(start..end).ok_or_else(|| invalid("polylist <p> too short".to_string()))?.to_vec());
+                        start = end;
+                    }
+                }
+                _ => {
+                    for p in primitive.children("p") {
+                        polygons.push(parse_indices(&p.text).map_err(&invalid)?);
+                    }
+                }
+            }
+
+            for polygon in polygons {
+                let corners: Vec<&[usize]> = polygon.chunks_exact(stride).collect();
+                if corners.len() < 3 {
+                    continue;
+                }
+                let corner_positions = corners
+                    .iter()
+                    .map(|corner| positions.0.vec3(corner[positions.1]))
+                    .collect::<Option<Vec<_>>>()
+                    .ok_or_else(|| invalid("position index out of range".to_string()))?;
+                let face_normal = (corner_positions[1] - corner_positions[0])
+                    .cross(corner_positions[2] - corner_positions[0])
+                    .normalize_or_zero();
+
+                let first = mesh.vertices.len();
+                for (corner, position) in corners.iter().zip(corner_positions) {
+                    mesh.vertices.push(position);
+                    mesh.material_ids.push(material_id);
+                    if config.include_normals {
+                        let normal = normals.and_then(|(source, offset)| source.vec3(corner[offset]));
+                        mesh.normals.push(normal.map_or(face_normal, Vec3::normalize_or_zero));
+                    }
+                    if config.include_colors {
+                        let color = colors.and_then(|(source, offset)| source.vec3(corner[offset]));
+                        mesh.colors.push(color.unwrap_or(Vec3::ONE));
+                    }
+                }
+                for i in 1..corners.len() - 1 {
+                    mesh.indices.extend([first, first + i, first + i + 1]);
+                }
+            }
+        }
+    }
+
+    if mesh.indices.is_empty() {
+        return Err(ModelParserError::NoMeshData);
+    }
+    mesh.double_sided = vec![false; mesh.materials.len()];
+    mesh.has_source_colors = has_colors && config.include_colors;
+
+    Ok(mesh)
+}
+
+/// A `<source>` float array read through its accessor
+struct Source {
+    values: Vec<f32>,
+    stride: usize,
+}
+
+impl Source {
+    fn read(element: &Element) -> Option<Self> {
+        let values = element
+            .child("float_array")?
+            .text
+            .split_whitespace()
+            .map(|v| v.parse::<f32>())
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .ok()?;
+        let stride = element
+            .child("technique_common")
+            .and_then(|technique| technique.child("accessor"))
+            .and_then(|accessor| accessor.attribute("stride"))
+            .and_then(|stride| stride.parse().ok())
+            .unwrap_or(3);
+        Some(Self { values, stride })
+    }
+
+    /// First three components of an element (colors may carry alpha as a fourth)
+    fn vec3(&self, index: usize) -> Option<Vec3> {
+        if self.stride < 3 {
+            return None;
+        }
+        let start = index * self.stride;
+        let v = self.values.get(start..start + 3)?;
+        Some(Vec3::new(v[0], v[1], v[2]))
+    }
+}
+
+/// Id from a "#id" URI fragment
+fn reference(uri: &str) -> &str {
+    uri.strip_prefix('#').unwrap_or(uri)
+}
+
+fn parse_indices(text: &str) -> std::result::Result<Vec<usize>, String> {
+    text.split_whitespace()
+        .map(|v| v.parse().map_err(|_| format!("invalid index '{}'", v)))
+        .collect()
+}
@@ -0,0 +1,174 @@
+use glam::Vec3;
+use serde::{Deserialize, Serialize};
+
+/// Color space of point colors
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ColorSpace {
+    /// Linear light, as used by glTF vertex colors and material factors
+    #[default]
+    Linear,
+
+    /// sRGB transfer curve, as used by textures and most 8-bit displays
+    Srgb,
+}
+
+/// Bit depth of integer color channels in binary outputs
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ColorDepth {
+    /// 8 bits per channel
+    #[default]
+    Eight,
+
+    /// 16 bits per channel (the LAS convention)
+    Sixteen,
+}
+
+impl ColorDepth {
+    /// Size in bytes of one channel
+    pub fn size(&self) -> u32 {
+        match self {
+            ColorDepth::Eight => 1,
+            ColorDepth::Sixteen => 2,
+        }
+    }
+}
+
+/// Pattern added before rounding colors to 8 bits, trading banding on smooth gradients for
+/// fine noise that averages out across neighboring points
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Dither {
+    /// Round to nearest
+    #[default]
+    None,
+
+    /// 4x4x4 Bayer-style threshold matrix over a grid of point-spacing cells
+    Ordered,
+
+    /// Low-discrepancy (R3 sequence) thresholds over the same grid, approximating blue noise
+    BlueNoise,
+}
+
+impl Dither {
+    /// Offset in 8-bit steps, within [-0.5, 0.5), added before rounding a point in grid `cell`
+    pub fn offset(&self, cell: [i64; 3]) -> f32 {
+        match self {
+            Dither::None => 0.0,
+            Dither::Ordered => {
+                // Interleave the bits of an invertible mix of the cell coordinates, most
+                // significant first, so each 4x4x4 block takes all 64 thresholds once
+                let [x, y, z] = cell.map(|c| c.rem_euclid(4) as u32);
+                let (a, b, c) = (x ^ y, y ^ z, z);
+                let mut rank = 0;
+                for bit in 0..2 {
+                    rank = (rank << 3) | ((a >> bit & 1) << 2) | ((b >> bit & 1) << 1) | (c >> bit & 1);
+                }
+                (rank as f32 + 0.5) / 64.0 - 0.5
+            }
+            Dither::BlueNoise => {
+                // Martin Roberts' R3 sequence: cell coordinates weighted by the powers of
+                // 1/φ₃ (the three-dimensional golden ratio) spread neighbors' thresholds evenly
+                const ALPHA: [f64; 3] = [0.819_172_513_396_164_4, 0.671_043_606_703_789, 0.549_700_477_901_970_2];
+                let value = cell
+                    .iter()
+                    .zip(ALPHA)
+                    .map(|(&c, alpha)| (c as f64 * alpha).rem_euclid(1.0))
+                    .sum::<f64>();
+                (value.rem_euclid(1.0) - 0.5) as f32
+            }
+        }
+    }
+}
+
+/// Relative luminance of a linear color (Rec. 709 weights)
+pub fn luminance(color: Vec3) -> f32 {
+    color.dot(Vec3::new(0.2126, 0.7152, 0.0722))
+}
+
+/// Convert a single linear channel to sRGB
+pub fn linear_to_srgb(c: f32) -> f32 {
+    let c = c.clamp(0.0, 1.0);
+    if c <= 0.003_130_8 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// Convert a single sRGB channel to linear
+pub fn srgb_to_linear(c: f32) -> f32 {
+    let c = c.clamp(0.0, 1.0);
+    if c <= 0.040_45 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Hue (0.0-1.0), saturation and value of an RGB color
+pub fn rgb_to_hsv(color: Vec3) -> [f32; 3] {
+    let max = color.max_element();
+    let delta = max - color.min_element();
+    let saturation = if max > 0.0 { delta / max } else { 0.0 };
+    let hue = if delta <= 0.0 {
+        0.0
+    } else if max == color.x {
+        ((color.y - color.z) / delta).rem_euclid(6.0)
+    } else if max == color.y {
+        (color.z - color.x) / delta + 2.0
+    } else {
+        (color.x - color.y) / delta + 4.0
+    };
+    [hue / 6.0, saturation, max]
+}
+
+/// RGB color from hue (0.0-1.0), saturation and value
+pub fn hsv_to_rgb([hue, saturation, value]: [f32; 3]) -> Vec3 {
+    let channel = |n: f32| {
+        let k = (n + hue.rem_euclid(1.0) * 6.0) % 6.0;
+        value - value * saturation * k.min(4.0 - k).clamp(0.0, 1.0)
+    };
+    Vec3::new(channel(5.0), channel(3.0), channel(1.0))
+}
+
+/// Convert a color between color spaces
+pub fn convert(color: Vec3, from: ColorSpace, to: ColorSpace) -> Vec3 {
+    match (from, to) {
+        (ColorSpace::Linear, ColorSpace::Srgb) => Vec3::new(
+            linear_to_srgb(color.x),
+            linear_to_srgb(color.y),
+            linear_to_srgb(color.z),
+        ),
+        (ColorSpace::Srgb, ColorSpace::Linear) => Vec3::new(
+            srgb_to_linear(color.x),
+            srgb_to_linear(color.y),
+            srgb_to_linear(color.z),
+        ),
+        _ => color,
+    }
+}
+
+/// Quantize a 0.0-1.0 channel to 8 bits with round-to-nearest
+pub fn to_u8(c: f32) -> u8 {
+    (c.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+/// Quantize an RGB color to 8 bits per channel
+pub fn to_rgb8(color: [f32; 3]) -> [u8; 3] {
+    [to_u8(color[0]), to_u8(color[1]), to_u8(color[2])]
+}
+
+/// Quantize an RGB color to 8 bits per channel after adding a dither `offset` (in 8-bit
+/// steps, see [`Dither::offset`]); the same offset on every channel keeps hues stable
+pub fn to_rgb8_dithered(color: [f32; 3], offset: f32) -> [u8; 3] {
+    color.map(|c| (c.clamp(0.0, 1.0) * 255.0 + offset).round().clamp(0.0, 255.0) as u8)
+}
+
+/// Quantize a 0.0-1.0 channel to 16 bits with round-to-nearest
+pub fn to_u16(c: f32) -> u16 {
+    (c.clamp(0.0, 1.0) * 65535.0).round() as u16
+}
+
+/// Quantize an RGB color to 16 bits per channel
+pub fn to_rgb16(color: [f32; 3]) -> [u16; 3] {
+    [to_u16(color[0]), to_u16(color[1]), to_u16(color[2])]
+}
@@ -0,0 +1,157 @@
+use crate::{
+    color::{self, ColorSpace},
+    error::{ModelParserError, Result},
+};
+use glam::Vec3;
+
+/// Piecewise-linear color ramp over 0.0-1.0. Stop colors are sRGB encoded, as in
+/// the published definitions of the built-in maps
+#[derive(Debug, Clone, PartialEq)]
+pub struct Colormap {
+    stops: Vec<(f32, Vec3)>,
+}
+
+/// Names accepted by `Colormap::from_name`
+pub const BUILTIN_COLORMAPS: [&str; 4] = ["viridis", "turbo", "terrain", "grayscale"];
+
+impl Colormap {
+    /// Ramp through `(position, srgb color)` stops; positions must be finite and are sorted
+    pub fn new(mut stops: Vec<(f32, Vec3)>) -> Result<Self> {
+        if stops.is_empty() {
+            return Err(ModelParserError::InvalidData("colormap needs at least one stop".to_string()));
+        }
+        if stops.iter().any(|(t, c)| !t.is_finite() || !c.is_finite()) {
+            return Err(ModelParserError::InvalidData("colormap stops must be finite".to_string()));
+        }
+        stops.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Ok(Self { stops })
+    }
+
+    /// Evenly spaced ramp through `colors`
+    pub fn from_colors(colors: &[Vec3]) -> Result<Self> {
+        let last = colors.len().saturating_sub(1).max(1) as f32;
+        Self::new(colors.iter().enumerate().map(|(i, &c)| (i as f32 / last, c)).collect())
+    }
+
+    pub fn viridis() -> Self {
+        Self::from_hex(&[
+            "#440154", "#482475", "#414487", "#355f8d", "#2a788e", "#21918c", "#22a884", "#44bf70", "#7ad151",
+            "#bddf26", "#fde725",
+        ])
+    }
+
+    pub fn turbo() -> Self {
+        Self::from_hex(&[
+            "#30123b", "#4662d7", "#36aaf9", "#1ae4b6", "#72fe5e", "#c8ef34", "#faba39", "#f66b19", "#cb2a04",
+            "#a01101", "#7a0403",
+        ])
+    }
+
+    /// Water, lowland, highland, rock and snow
+    pub fn terrain() -> Self {
+        Self {
+            stops: vec![
+                (0.0, Vec3::new(0.2, 0.2, 0.6)),
+                (0.15, Vec3::new(0.0, 0.6, 1.0)),
+                (0.25, Vec3::new(0.0, 0.8, 0.4)),
+                (0.5, Vec3::new(1.0, 1.0, 0.6)),
+                (0.75, Vec3::new(0.5, 0.36, 0.33)),
+                (1.0, Vec3::ONE),
+            ],
+        }
+    }
+
+    pub fn grayscale() -> Self {
+        Self {
+            stops: vec![(0.0, Vec3::ZERO), (1.0, Vec3::ONE)],
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "viridis" => Some(Self::viridis()),
+            "turbo" => Some(Self::turbo()),
+            "terrain" => Some(Self::terrain()),
+            "grayscale" | "greyscale" | "gray" | "grey" => Some(Self::grayscale()),
+            _ => None,
+        }
+    }
+
+    /// A built-in name, or comma-separated custom stops: either colors spread evenly
+    /// (`#000000,#ff0000,#ffffff`) or explicit positions (`0:#000000,0.8:#ff0000,1:#ffffff`)
+    pub fn parse(spec: &str) -> Result<Self> {
+        if let Some(colormap) = Self::from_name(spec) {
+            return Ok(colormap);
+        }
+
+        let parts: Vec<&str> = spec.split(',').map(str::trim).collect();
+        if parts.iter().all(|part| part.contains(':')) {
+            let stops = parts
+                .iter()
+                .map(|part| {
+                    let (position, hex) = part.split_once(':').unwrap_or_default();
+                    let position = position.trim().parse::<f32>().map_err(|_| {
+                        ModelParserError::InvalidData(format!("invalid colormap stop position '{}'", position))
+                    })?;
+                    Ok((position, parse_hex(hex.trim())?))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            Self::new(stops)
+        } else {
+            let colors = parts.iter().map(|hex| parse_hex(hex)).collect::<Result<Vec<_>>>()?;
+            Self::from_colors(&colors)
+        }
+    }
+
+    fn from_hex(colors: &[&str]) -> Self {
+        let colors: Vec<Vec3> = colors.iter().filter_map(|hex| parse_hex(hex).ok()).collect();
+        Self::from_colors(&colors).unwrap_or_else(|_| Self::grayscale())
+    }
+
+    pub fn stops(&self) -> &[(f32, Vec3)] {
+        &self.stops
+    }
+
+    /// sRGB color at `t`, clamped to the first and last stop
+    pub fn sample(&self, t: f32) -> Vec3 {
+        let t = if t.is_nan() { 0.0 } else { t };
+        let first = self.stops[0];
+        let last = self.stops[self.stops.len() - 1];
+        if t <= first.0 {
+            return first.1;
+        }
+        if t >= last.0 {
+            return last.1;
+        }
+
+        let upper = self.stops.partition_point(|(position, _)| *position <= t);
+        let (t0, c0) = self.stops[upper - 1];
+        let (t1, c1) = self.stops[upper];
+        let span = t1 - t0;
+        if span <= 0.0 {
+            return c1;
+        }
+        c0.lerp(c1, (t - t0) / span)
+    }
+
+    /// Color at `t` encoded for `color_space`
+    pub fn sample_in(&self, t: f32, color_space: ColorSpace) -> Vec3 {
+        color::convert(self.sample(t), ColorSpace::Srgb, color_space)
+    }
+}
+
+/// Parse `#rrggbb` (leading '#' optional) into a 0.0-1.0 color
+pub fn parse_hex(hex: &str) -> Result<Vec3> {
+    let digits = hex.trim_start_matches('#');
+    let invalid = || ModelParserError::InvalidData(format!("invalid color '{}', expected #rrggbb", hex));
+    if digits.len() != 6 {
+        return Err(invalid());
+    }
+
+    let channel = |i: usize| {
+        u8::from_str_radix(&digits[i..i + 2], 16)
+            .map(|v| v as f32 / 255.0)
+            .map_err(|_| invalid())
+    };
+    Ok(Vec3::new(channel(0)?, channel(2)?, channel(4)?))
+}
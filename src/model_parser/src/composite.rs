@@ -0,0 +1,290 @@
+use crate::{
+    ept::EptMetadata,
+    error::{ModelParserError, Result},
+    instances::Placement,
+};
+use glam::{DMat4, DVec3};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::path::{Component, Path, PathBuf};
+
+/// Index of the EPT datasets of a composite, relative to the output directory
+pub const EPT_SOURCES_FILE: &str = "ept-sources/list.json";
+
+/// Parent tileset of the 3D Tiles datasets of a composite
+pub const TILESET_FILE: &str = "tileset.json";
+
+/// Composition file: already-built datasets and the transform placing each one in the
+/// composite's coordinates. Paths are relative to the composition file
+///
+/// ```json
+/// { "datasets": [
+///     { "path": "downtown" },
+///     { "path": "harbor/tileset.json", "translation": [1200, 0, -350], "rotation": [0, 0.383, 0, 0.924] }
+/// ] }
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Composition {
+    pub datasets: Vec<CompositeDataset>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompositeDataset {
+    /// EPT directory or its ept.json, or 3D Tiles directory or its tileset.json
+    pub path: PathBuf,
+
+    /// Transform from the dataset's coordinates to the composite's
+    #[serde(flatten)]
+    pub placement: Placement,
+}
+
+/// Entry of the EPT sources list
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EptSource {
+    /// ept.json of the dataset, relative to the list
+    pub path: String,
+
+    /// Conforming bounds of the dataset once transformed: [minx, miny, minz, maxx, maxy, maxz]
+    pub bounds: [f64; 6],
+
+    pub points: u64,
+
+    /// Column-major transform from the dataset's coordinates to the composite's
+    pub transform: [f64; 16],
+}
+
+/// Files written by `compose`
+#[derive(Debug, Clone, Default)]
+pub struct CompositeReport {
+    pub ept_sources: Option<PathBuf>,
+    pub tileset: Option<PathBuf>,
+
+    /// Datasets referenced by the written indexes
+    pub datasets: usize,
+
+    /// Points of the EPT datasets (3D Tiles do not record their point count)
+    pub points: u64,
+}
+
+impl Composition {
+    pub fn load_from_file(path: &Path) -> Result<Self> {
+        let json = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&json)?)
+    }
+}
+
+enum DatasetKind {
+    Ept,
+    Tileset,
+}
+
+/// Index file of a dataset: a directory resolves to the ept.json or tileset.json inside it
+fn locate(path: &Path) -> Result<(PathBuf, DatasetKind)> {
+    if !path.exists() {
+        return Err(ModelParserError::InvalidData(format!("dataset {:?} does not exist", path)));
+    }
+
+    let file = if path.is_dir() {
+        ["ept.json", TILESET_FILE]
+            .iter()
+            .map(|name| path.join(name))
+            .find(|file| file.is_file())
+            .ok_or_else(|| {
+                ModelParserError::InvalidData(format!("{:?} contains neither ept.json nor tileset.json", path))
+            })?
+    } else {
+        path.to_path_buf()
+    };
+
+    let kind = if file.file_name().is_some_and(|name| name == "ept.json") {
+        DatasetKind::Ept
+    } else {
+        DatasetKind::Tileset
+    };
+    Ok((file, kind))
+}
+
+/// Write the parent indexes of the datasets listed in `composition_path` into `output_dir`:
+/// an EPT sources list for the EPT datasets and a tileset referencing the 3D Tiles datasets
+/// as external tilesets, each with its dataset's transform
+pub fn compose(composition_path: &Path, output_dir: &Path) -> Result<CompositeReport> {
+    let composition = Composition::load_from_file(composition_path)?;
+    if composition.datasets.is_empty() {
+        return Err(ModelParserError::InvalidData("composition lists no datasets".to_string()));
+    }
+
+    std::fs::create_dir_all(output_dir)?;
+    let base = composition_path.parent().unwrap_or(Path::new(""));
+    let sources_path = output_dir.join(EPT_SOURCES_FILE);
+    let sources_dir = sources_path.parent().unwrap_or(output_dir);
+
+    let mut sources = Vec::new();
+    let mut children = Vec::new();
+    let mut tiles_bounds = empty_bounds();
+    let mut geometric_error = 0.0f64;
+
+    for dataset in &composition.datasets {
+        let (file, kind) = locate(&base.join(&dataset.path))?;
+        let transform = dataset.placement.to_matrix()?.as_dmat4();
+
+        match kind {
+            DatasetKind::Ept => {
+                let metadata: EptMetadata = serde_json::from_str(&std::fs::read_to_string(&file)?)?;
+                let [x0, y0, z0, x1, y1, z1] = metadata.bounds_conforming;
+                sources.push(EptSource {
+                    path: relative_path(&file, sources_dir)?,
+                    bounds: transformed_bounds(&corners(DVec3::new(x0, y0, z0), DVec3::new(x1, y1, z1)), transform),
+                    points: metadata.points,
+                    transform: transform.to_cols_array(),
+                });
+            }
+            DatasetKind::Tileset => {
+                let tileset: Value = serde_json::from_str(&std::fs::read_to_string(&file)?)?;
+                let error = tileset["geometricError"].as_f64().unwrap_or(0.0);
+                let root = &tileset["root"];
+                let root_transform = match root["transform"].as_array() {
+                    Some(values) => DMat4::from_cols_slice(&numbers(values, 16, &file)?),
+                    None => DMat4::IDENTITY,
+                };
+
+                // The child tile's volume is in its own (transformed) frame, so it holds the
+                // external root's volume and the composite's volume holds it transformed again
+                let local = corners_of_volume(&root["boundingVolume"], &file)?;
+                let tile_bounds = transformed_bounds(&local, root_transform);
+                let [x0, y0, z0, x1, y1, z1] = tile_bounds;
+                let world = transformed_bounds(&corners(DVec3::new(x0, y0, z0), DVec3::new(x1, y1, z1)), transform);
+                tiles_bounds = union(tiles_bounds, world);
+                geometric_error = geometric_error.max(error);
+
+                children.push(json!({
+                    "boundingVolume": { "box": bounds_box(&tile_bounds) },
+                    "geometricError": error,
+                    "transform": transform.to_cols_array(),
+                    "content": { "uri": relative_path(&file, output_dir)? },
+                }));
+            }
+        }
+    }
+
+    let mut report = CompositeReport {
+        datasets: sources.len() + children.len(),
+        points: sources.iter().map(|source| source.points).sum(),
+        ..Default::default()
+    };
+
+    if !sources.is_empty() {
+        std::fs::create_dir_all(sources_dir)?;
+        std::fs::write(&sources_path, serde_json::to_string_pretty(&sources)?)?;
+        report.ept_sources = Some(sources_path);
+    }
+
+    if !children.is_empty() {
+        let tileset = json!({
+            "asset": { "version": "1.0" },
+            "geometricError": geometric_error,
+            "root": {
+                "boundingVolume": { "box": bounds_box(&tiles_bounds) },
+                "geometricError": geometric_error,
+                "refine": "ADD",
+                "children": children,
+            },
+        });
+        let tileset_path = output_dir.join(TILESET_FILE);
+        std::fs::write(&tileset_path, serde_json::to_string_pretty(&tileset)?)?;
+        report.tileset = Some(tileset_path);
+    }
+
+    Ok(report)
+}
+
+/// Exactly `count` numbers of a JSON array
+fn numbers(values: &[Value], count: usize, file: &Path) -> Result<Vec<f64>> {
+    let numbers: Vec<f64> = values.iter().filter_map(Value::as_f64).collect();
+    if numbers.len() != count || values.len() != count {
+        return Err(ModelParserError::InvalidData(format!(
+            "{:?}: expected {} numbers, got {:?}",
+            file, count, values
+        )));
+    }
+    Ok(numbers)
+}
+
+/// Corners of a 3D Tiles box or sphere bounding volume
+fn corners_of_volume(volume: &Value, file: &Path) -> Result<Vec<DVec3>> {
+    if let Some(values) = volume["box"].as_array() {
+        let v = numbers(values, 12, file)?;
+        let [center, x, y, z] = [0, 3, 6, 9].map(|i| DVec3::new(v[i], v[i + 1], v[i + 2]));
+        let sign = |i: usize, bit: usize| if i & bit == 0 { -1.0 } else { 1.0 };
+        return Ok((0..8).map(|i| center + x * sign(i, 1) + y * sign(i, 2) + z * sign(i, 4)).collect());
+    }
+
+    if let Some(values) = volume["sphere"].as_array() {
+        let v = numbers(values, 4, file)?;
+        let (center, radius) = (DVec3::new(v[0], v[1], v[2]), DVec3::splat(v[3]));
+        return Ok(corners(center - radius, center + radius));
+    }
+
+    Err(ModelParserError::InvalidData(format!(
+        "{:?}: the root tile needs a box or sphere bounding volume (geographic regions are not supported)",
+        file
+    )))
+}
+
+fn corners(min: DVec3, max: DVec3) -> Vec<DVec3> {
+    (0..8)
+        .map(|i| DVec3::new(
+            if i & 1 == 0 { min.x } else { max.x },
+            if i & 2 == 0 { min.y } else { max.y },
+            if i & 4 == 0 { min.z } else { max.z },
+        ))
+        .collect()
+}
+
+fn empty_bounds() -> [f64; 6] {
+    [f64::MAX, f64::MAX, f64::MAX, f64::MIN, f64::MIN, f64::MIN]
+}
+
+fn union(a: [f64; 6], b: [f64; 6]) -> [f64; 6] {
+    [a[0].min(b[0]), a[1].min(b[1]), a[2].min(b[2]), a[3].max(b[3]), a[4].max(b[4]), a[5].max(b[5])]
+}
+
+/// Axis-aligned bounds of `points` after `transform`
+fn transformed_bounds(points: &[DVec3], transform: DMat4) -> [f64; 6] {
+    points.iter().map(|&p| transform.transform_point3(p)).fold(empty_bounds(), |bounds, p| {
+        union(bounds, [p.x, p.y, p.z, p.x, p.y, p.z])
+    })
+}
+
+/// 3D Tiles box (center and half axes) of axis-aligned bounds
+pub(crate) fn bounds_box(bounds: &[f64; 6]) -> [f64; 12] {
+    let min = DVec3::new(bounds[0], bounds[1], bounds[2]);
+    let max = DVec3::new(bounds[3], bounds[4], bounds[5]);
+    let (center, half) = ((min + max) * 0.5, (max - min) * 0.5);
+    [center.x, center.y, center.z, half.x, 0.0, 0.0, 0.0, half.y, 0.0, 0.0, 0.0, half.z]
+}
+
+/// Path of `file` relative to the directory `from`, with '/' separators
+fn relative_path(file: &Path, from: &Path) -> Result<String> {
+    let file = std::path::absolute(file)?;
+    let from = std::path::absolute(from)?;
+    let normal = |path: &Path| -> Vec<String> {
+        let mut parts: Vec<String> = Vec::new();
+        for component in path.components() {
+            match component {
+                Component::ParentDir => {
+                    parts.pop();
+                }
+                Component::CurDir => {}
+                other => parts.push(other.as_os_str().to_string_lossy().into_owned()),
+            }
+        }
+        parts
+    };
+
+    let (file, from) = (normal(&file), normal(&from));
+    let common = file.iter().zip(&from).take_while(|(a, b)| a == b).count();
+    let parts: Vec<&str> = std::iter::repeat_n("..", from.len() - common)
+        .chain(file[common..].iter().map(String::as_str))
+        .collect();
+    Ok(parts.join("/"))
+}
@@ -1,3 +1,16 @@
+use crate::{
+    animation::AnimationPose,
+    color::ColorSpace,
+    grading::ColorGrading,
+    lidar::{LidarNoise, VirtualScanner},
+    lighting::LightBaking,
+    palette::Palette,
+    selection::NameFilter,
+    stylize::Stylize,
+    validation::ValidationPolicy,
+    weld,
+    xyz::ColumnMapping,
+};
 use serde::{Deserialize, Serialize};
 
 /// Configuration for point cloud generation
@@ -15,11 +28,207 @@ pub struct PointCloudConfig {
     /// Include vertex colors in output
     pub include_colors: bool,
 
+    /// Record the source material index per point
+    #[serde(default)]
+    pub include_material_ids: bool,
+
     /// Scale factor for the model
     pub scale: f32,
 
-    /// Add random jitter to points (0.0 = no jitter, 1.0 = maximum jitter)
+    /// Per-axis scale factors applied to the mesh about `scale_pivot` before sampling,
+    /// on top of the uniform `scale`
+    #[serde(default = "default_axis_scale")]
+    pub axis_scale: [f32; 3],
+
+    /// Fixed point of `axis_scale`
+    #[serde(default)]
+    pub scale_pivot: ScalePivot,
+
+    /// Up axis of the source; Z-up models are turned Y-up before scaling and sampling
+    #[serde(default)]
+    pub up_axis: UpAxis,
+
+    /// Add random jitter to points (0.0 = no jitter), measured as `jitter_mode` describes.
+    ///
+    /// Earlier versions clamped this to 0..1 and moved points by up to a tenth of it in model
+    /// units, times `scale`; it is now the distance in output units itself, so the old look
+    /// takes `jitter × 0.1 × scale`
     pub jitter: f32,
+
+    /// How `jitter` is measured and the direction points are moved in
+    #[serde(default)]
+    pub jitter_mode: JitterMode,
+
+    /// How sampled normals are oriented
+    #[serde(default)]
+    pub normal_orientation: NormalOrientation,
+
+    /// Emit a second, back-facing sample for points on doubleSided materials
+    #[serde(default)]
+    pub two_sided: bool,
+
+    /// Estimate a per-point splat radius and export it as the "radius" attribute
+    #[serde(default)]
+    pub splat_radius: Option<SplatRadius>,
+
+    /// Bake ambient occlusion into the "occlusion" attribute
+    #[serde(default)]
+    pub ambient_occlusion: Option<AmbientOcclusion>,
+
+    /// Scanner placement and beam pattern used by `SamplingStrategy::Scanner`
+    #[serde(default)]
+    pub scanner: VirtualScanner,
+
+    /// Simulate scanner range noise, dropout and intensity
+    #[serde(default)]
+    pub lidar_noise: Option<LidarNoise>,
+
+    /// Derive an "intensity" attribute from material albedo, metallic and roughness
+    #[serde(default)]
+    pub synthesize_intensity: bool,
+
+    /// Add per-point "metallic", "roughness" and "emissive_r/g/b" attributes from the materials
+    #[serde(default)]
+    pub material_attributes: bool,
+
+    /// Add a "random_key" attribute hashed from each position for stable progressive display
+    #[serde(default)]
+    pub random_key: bool,
+
+    /// Bake directional and ambient lighting into point colors
+    #[serde(default)]
+    pub light_baking: Option<LightBaking>,
+
+    /// Random normal tilt and color hue/value noise for stylized output
+    #[serde(default)]
+    pub stylize: Option<Stylize>,
+
+    /// Exposure, gamma and saturation applied to colors after lighting
+    #[serde(default)]
+    pub color_grading: Option<ColorGrading>,
+
+    /// Quantize colors to a palette after grading, storing each point's entry index
+    #[serde(default)]
+    pub palette: Option<Palette>,
+
+    /// Seed for all random stages; when set, identical inputs give bit-identical outputs
+    #[serde(default)]
+    pub seed: Option<u64>,
+
+    /// Color space of the output point colors (source colors are converted to it)
+    #[serde(default = "default_color_space")]
+    pub color_space: ColorSpace,
+
+    /// Handling of NaN/Inf positions, degenerate normals and out-of-range colors
+    #[serde(default)]
+    pub validation: ValidationPolicy,
+
+    /// glTF morph target weights applied to every mesh instead of the weights stored in the file
+    #[serde(default)]
+    pub morph_weights: Option<Vec<f32>>,
+
+    /// glTF meshes to convert, selected by node and mesh name patterns
+    #[serde(default)]
+    pub mesh_filter: NameFilter,
+
+    /// glTF scene to convert, by name or index; the document's default scene otherwise
+    #[serde(default)]
+    pub scene: Option<String>,
+
+    /// glTF material variant (`KHR_materials_variants`) to convert, by name or index; the
+    /// primitives' default materials otherwise
+    #[serde(default)]
+    pub material_variant: Option<String>,
+
+    /// glTF animation and time to pose nodes and skinned meshes at; the rest pose otherwise
+    #[serde(default)]
+    pub pose: Option<AnimationPose>,
+
+    /// Points per unit of output length sampled along line primitives; by default the
+    /// spacing of the surface samples
+    #[serde(default)]
+    pub line_density: Option<f32>,
+
+    /// Merge mesh vertices closer than this distance (and within `weld_normal_angle`)
+    /// before sampling; `Some(0.0)` merges identical positions only
+    #[serde(default)]
+    pub weld: Option<f32>,
+
+    /// Largest angle between the normals of welded vertices, in degrees
+    #[serde(default = "default_weld_normal_angle")]
+    pub weld_normal_angle: f32,
+
+    /// Simplify meshes above this many triangles down to it before sampling
+    #[serde(default)]
+    pub decimate: Option<usize>,
+
+    /// Column layout of XYZ/CSV text inputs; when unset a header line or `x y z [r g b]` is used
+    #[serde(default)]
+    pub text_columns: Option<ColumnMapping>,
+}
+
+fn default_color_space() -> ColorSpace {
+    ColorSpace::Srgb
+}
+
+fn default_axis_scale() -> [f32; 3] {
+    [1.0, 1.0, 1.0]
+}
+
+fn default_weld_normal_angle() -> f32 {
+    weld::DEFAULT_NORMAL_ANGLE
+}
+
+/// Point that stays in place when `axis_scale` is applied
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ScalePivot {
+    /// Model origin
+    #[default]
+    Origin,
+
+    /// Mean of the mesh vertices
+    Centroid,
+
+    /// Center of the bounding box
+    BoundsCenter,
+
+    /// Minimum corner of the bounding box
+    BoundsMin,
+
+    /// Bottom center of the bounding box (minimum Y), for scaling objects standing on the ground
+    Base,
+}
+
+/// Up axis of a source model. Clouds are Y-up like glTF, so Z-up models are turned a quarter
+/// turn about X: +Z becomes +Y and +Y becomes -Z
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum UpAxis {
+    /// The axis the file declares (glTF is always Y-up, COLLADA records it), otherwise
+    /// detected from the shape of meshes; undeclared point sets are kept as they are
+    #[default]
+    Auto,
+
+    /// Y-up, kept as authored
+    Y,
+
+    /// Z-up, as in most CAD, GIS and 3D printing tools
+    Z,
+}
+
+/// Meaning of `PointCloudConfig::jitter`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum JitterMode {
+    /// Up to `jitter` output units (after `scale`) along each axis, independently
+    #[default]
+    Absolute,
+
+    /// Up to `jitter` times the diagonal of the model's bounding box along each axis, so the
+    /// same value gives the same look at any model scale. Fractions above 1 are capped at 1
+    BoundsFraction,
+
+    /// Up to `jitter` output units along the surface normal, in or out, so points stay on
+    /// the surface's profile and only gain thickness; points without normals are not moved
+    Normal,
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
@@ -32,6 +241,50 @@ pub enum SamplingStrategy {
 
     /// Use only mesh vertices
     Vertices,
+
+    /// Keep only surfaces hit by beams from virtual scanner positions
+    Scanner,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum NormalOrientation {
+    /// Keep normals as authored in the source model
+    #[default]
+    AsAuthored,
+
+    /// Negate every normal
+    Flipped,
+
+    /// Flip normals so they face the given viewpoint (in output coordinates)
+    TowardViewpoint([f32; 3]),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum SplatRadius {
+    /// Radius of a disc whose area equals the mesh surface area per point
+    SurfaceArea,
+
+    /// Mean distance to the k nearest neighbouring points
+    KNearest(usize),
+}
+
+/// Ambient occlusion baking parameters
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct AmbientOcclusion {
+    /// Rays cast per point
+    pub samples: usize,
+
+    /// Occluders further than this (in output units) are ignored
+    pub max_distance: f32,
+}
+
+impl Default for AmbientOcclusion {
+    fn default() -> Self {
+        Self {
+            samples: 16,
+            max_distance: 1.0,
+        }
+    }
 }
 
 impl Default for PointCloudConfig {
@@ -41,8 +294,39 @@ impl Default for PointCloudConfig {
             sampling_strategy: SamplingStrategy::AreaWeighted,
             include_normals: true,
             include_colors: true,
+            include_material_ids: false,
             scale: 1.0,
+            axis_scale: default_axis_scale(),
+            scale_pivot: ScalePivot::Origin,
+            up_axis: UpAxis::Auto,
             jitter: 0.0,
+            jitter_mode: JitterMode::Absolute,
+            normal_orientation: NormalOrientation::AsAuthored,
+            two_sided: false,
+            color_space: default_color_space(),
+            splat_radius: None,
+            ambient_occlusion: None,
+            light_baking: None,
+            stylize: None,
+            color_grading: None,
+            palette: None,
+            lidar_noise: None,
+            synthesize_intensity: false,
+            material_attributes: false,
+            random_key: false,
+            scanner: VirtualScanner::default(),
+            seed: None,
+            validation: ValidationPolicy::Fix,
+            text_columns: None,
+            line_density: None,
+            morph_weights: None,
+            mesh_filter: NameFilter::default(),
+            scene: None,
+            material_variant: None,
+            pose: None,
+            weld: None,
+            weld_normal_angle: weld::DEFAULT_NORMAL_ANGLE,
+            decimate: None,
         }
     }
 }
@@ -70,13 +354,161 @@ impl PointCloudConfig {
         self
     }
 
+    pub fn with_material_ids(mut self, include: bool) -> Self {
+        self.include_material_ids = include;
+        self
+    }
+
     pub fn with_scale(mut self, scale: f32) -> Self {
         self.scale = scale;
         self
     }
 
+    /// Scale each axis separately about `pivot` (normals are transformed to match)
+    pub fn with_axis_scale(mut self, factors: [f32; 3], pivot: ScalePivot) -> Self {
+        self.axis_scale = factors;
+        self.scale_pivot = pivot;
+        self
+    }
+
+    pub fn with_up_axis(mut self, up_axis: UpAxis) -> Self {
+        self.up_axis = up_axis;
+        self
+    }
+
     pub fn with_jitter(mut self, jitter: f32) -> Self {
-        self.jitter = jitter.clamp(0.0, 1.0);
+        self.jitter = jitter.max(0.0);
+        self
+    }
+
+    pub fn with_jitter_mode(mut self, mode: JitterMode) -> Self {
+        self.jitter_mode = mode;
+        self
+    }
+
+    pub fn with_normal_orientation(mut self, orientation: NormalOrientation) -> Self {
+        self.normal_orientation = orientation;
+        self
+    }
+
+    pub fn with_color_space(mut self, color_space: ColorSpace) -> Self {
+        self.color_space = color_space;
+        self
+    }
+
+    pub fn with_splat_radius(mut self, splat_radius: Option<SplatRadius>) -> Self {
+        self.splat_radius = splat_radius;
+        self
+    }
+
+    pub fn with_ambient_occlusion(mut self, ambient_occlusion: Option<AmbientOcclusion>) -> Self {
+        self.ambient_occlusion = ambient_occlusion;
+        self
+    }
+
+    pub fn with_light_baking(mut self, light_baking: Option<LightBaking>) -> Self {
+        self.light_baking = light_baking;
+        self
+    }
+
+    pub fn with_stylize(mut self, stylize: Option<Stylize>) -> Self {
+        self.stylize = stylize.filter(Stylize::is_active);
+        self
+    }
+
+    pub fn with_color_grading(mut self, color_grading: Option<ColorGrading>) -> Self {
+        self.color_grading = color_grading.filter(|grading| !grading.is_identity());
+        self
+    }
+
+    pub fn with_palette(mut self, palette: Option<Palette>) -> Self {
+        self.palette = palette;
+        self
+    }
+
+    pub fn with_lidar_noise(mut self, lidar_noise: Option<LidarNoise>) -> Self {
+        self.lidar_noise = lidar_noise;
+        self
+    }
+
+    pub fn with_synthesized_intensity(mut self, synthesize: bool) -> Self {
+        self.synthesize_intensity = synthesize;
+        self
+    }
+
+    pub fn with_material_attributes(mut self, include: bool) -> Self {
+        self.material_attributes = include;
+        self
+    }
+
+    pub fn with_random_key(mut self, random_key: bool) -> Self {
+        self.random_key = random_key;
+        self
+    }
+
+    pub fn with_scanner(mut self, scanner: VirtualScanner) -> Self {
+        self.scanner = scanner;
+        self
+    }
+
+    pub fn with_seed(mut self, seed: Option<u64>) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    pub fn with_validation(mut self, policy: ValidationPolicy) -> Self {
+        self.validation = policy;
+        self
+    }
+
+    pub fn with_morph_weights(mut self, weights: Option<Vec<f32>>) -> Self {
+        self.morph_weights = weights;
+        self
+    }
+
+    pub fn with_mesh_filter(mut self, include: Vec<String>, exclude: Vec<String>) -> Self {
+        self.mesh_filter = NameFilter { include, exclude };
+        self
+    }
+
+    pub fn with_scene(mut self, scene: Option<String>) -> Self {
+        self.scene = scene;
+        self
+    }
+
+    pub fn with_material_variant(mut self, variant: Option<String>) -> Self {
+        self.material_variant = variant;
+        self
+    }
+
+    pub fn with_pose(mut self, pose: Option<AnimationPose>) -> Self {
+        self.pose = pose;
+        self
+    }
+
+    pub fn with_line_density(mut self, density: Option<f32>) -> Self {
+        self.line_density = density;
+        self
+    }
+
+    pub fn with_welding(mut self, tolerance: Option<f32>, normal_angle: f32) -> Self {
+        self.weld = tolerance;
+        self.weld_normal_angle = normal_angle;
+        self
+    }
+
+    pub fn with_decimation(mut self, target_triangles: Option<usize>) -> Self {
+        self.decimate = target_triangles;
+        self
+    }
+
+    pub fn with_text_columns(mut self, columns: Option<ColumnMapping>) -> Self {
+        self.text_columns = columns;
+        self
+    }
+
+    pub fn with_two_sided(mut self, two_sided: bool) -> Self {
+        self.two_sided = two_sided;
         self
     }
 }
@@ -0,0 +1,147 @@
+use crate::{
+    budget::SizeEstimate,
+    ept::{EptBuildReport, EptBuilder, OctreeKey},
+    error::{ModelParserError, Result},
+    las::{self, LasWriter},
+    point_cloud::PointCloud,
+};
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::io::{BufWriter, Seek, SeekFrom, Write};
+use std::path::Path;
+
+/// User id of the COPC info VLR and the hierarchy EVLR
+const COPC_USER_ID: &str = "copc";
+
+/// Size of the COPC info VLR data
+const INFO_SIZE: usize = 160;
+
+/// Size of one hierarchy entry: key, offset, byte size and point count
+const HIERARCHY_ENTRY_SIZE: usize = 32;
+
+/// Writes clouds as Cloud-Optimized Point Clouds (COPC 1.0): a single LAZ file whose LASzip
+/// chunks are the nodes of an octree, with the octree's cube in a COPC info VLR and each
+/// node's chunk offset, size and point count in a hierarchy EVLR. Readers fetch the nodes they
+/// need with range requests, so one file on object storage replaces the EPT directory. The
+/// octree is built as for EPT output and the records are those of `--format laz`
+#[derive(Default)]
+pub struct CopcWriter {
+    octree: EptBuilder,
+    las: LasWriter,
+}
+
+impl CopcWriter {
+    /// Writer using the node capacities, depth, bounds, seed and LOD averaging of `octree`
+    /// (tile overlap is not applied) and the scale and offset of `las`
+    pub fn new(octree: EptBuilder, las: LasWriter) -> Self {
+        Self { octree, las: las.with_compression(true) }
+    }
+
+    /// Estimated output size: as for LAZ, plus the COPC info and a hierarchy entry per node
+    pub fn estimate_size(&self, point_cloud: &PointCloud) -> SizeEstimate {
+        let estimate = self.las.estimate(point_cloud);
+        let nodes = point_cloud.points.len().div_ceil(self.octree.capacity(0).max(1)) as u64;
+        SizeEstimate {
+            overhead: estimate.overhead
+                + (las::VLR_HEADER_SIZE + INFO_SIZE + las::EVLR_HEADER_SIZE) as u64
+                + nodes * (HIERARCHY_ENTRY_SIZE as u64 + 16),
+            ..estimate
+        }
+    }
+
+    pub fn write(&self, point_cloud: &PointCloud, path: &Path) -> Result<EptBuildReport> {
+        let (point_cloud, _, bounds, outside) = self.octree.frame(point_cloud);
+        let point_cloud = point_cloud.as_ref();
+        let nodes = self.octree.octree(point_cloud, &bounds);
+        let parents: HashSet<OctreeKey> = nodes.iter().filter_map(|(key, _)| key.parent()).collect();
+        let averages = self.octree.level_averages(point_cloud, &nodes, &bounds);
+
+        // Records go in node order, each node one LASzip chunk
+        let ordered = PointCloud {
+            points: nodes
+                .iter()
+                .flat_map(|(key, indices)| {
+                    let level = averages.get(key.depth as usize).filter(|_| parents.contains(key));
+                    indices.iter().map(move |&index| {
+                        let point = &point_cloud.points[index];
+                        level.map_or(Cow::Borrowed(point), |level| level.apply(point)).into_owned()
+                    })
+                })
+                .collect(),
+            metadata: point_cloud.metadata.clone(),
+        };
+        let records = self.las.records(&ordered)?;
+
+        // The COPC info must be the first VLR; it is rewritten once the hierarchy is placed
+        let shift = point_cloud.metadata.offset.unwrap_or([0.0; 3]);
+        let center = [0, 1, 2].map(|i| (bounds[i] + bounds[i + 3]) * 0.5 + shift[i]);
+        let size = bounds[3] - bounds[0];
+        let spacing = self.octree.geometric_error(&OctreeKey::root(), true, size);
+        let (laz, laz_vlr) = records.laz_vlr(true)?;
+        let mut vlrs = vec![copc_info(center, size * 0.5, spacing, 0, 0)];
+        vlrs.push(laz_vlr);
+        vlrs.extend(records.extra_bytes_vlr());
+
+        let mut writer = BufWriter::new(std::fs::File::create(path)?);
+        writer.write_all(&records.header(&vlrs, true, None))?;
+        vlrs.iter().try_for_each(|vlr| writer.write_all(vlr))?;
+
+        let mut hierarchy = Vec::with_capacity(nodes.len() * HIERARCHY_ENTRY_SIZE);
+        {
+            let mut record = Vec::with_capacity(records.record_length());
+            let mut compressor = laz::LasZipCompressor::new(&mut writer, laz)
+                .map_err(|e| ModelParserError::InvalidData(format!("LASzip: {}", e)))?;
+            compressor.reserve_offset_to_chunk_table()?;
+            let mut index = 0;
+            for (key, indices) in &nodes {
+                let start = compressor.get_mut().stream_position()?;
+                for _ in 0..indices.len() {
+                    records.encode(index, &mut record);
+                    compressor.compress_one(&record)?;
+                    index += 1;
+                }
+                compressor.finish_current_chunk()?;
+                let end = compressor.get_mut().stream_position()?;
+
+                for component in [key.depth, key.x, key.y, key.z] {
+                    hierarchy.extend_from_slice(&(component as i32).to_le_bytes());
+                }
+                hierarchy.extend_from_slice(&start.to_le_bytes());
+                hierarchy.extend_from_slice(&((end - start) as i32).to_le_bytes());
+                hierarchy.extend_from_slice(&(indices.len() as i32).to_le_bytes());
+            }
+            compressor.done()?;
+        }
+
+        // A single hierarchy page after the chunk table, then the header and info pointing at it
+        let evlr_start = writer.stream_position()?;
+        writer.write_all(&las::evlr(COPC_USER_ID, 1000, "EPT hierarchy", &hierarchy))?;
+        let hierarchy_offset = evlr_start + las::EVLR_HEADER_SIZE as u64;
+        vlrs[0] = copc_info(center, size * 0.5, spacing, hierarchy_offset, hierarchy.len() as u64);
+        writer.seek(SeekFrom::Start(0))?;
+        writer.write_all(&records.header(&vlrs, true, Some((evlr_start, 1))))?;
+        writer.write_all(&vlrs[0])?;
+        writer.flush()?;
+
+        Ok(EptBuildReport {
+            points_written: ordered.points.len() as u64,
+            outside_bounds: outside,
+            out_of_bounds: self.octree.out_of_bounds(),
+            tiles_resumed: 0,
+        })
+    }
+}
+
+/// COPC info VLR: the octree cube as center and half size, the root's point spacing and the
+/// location of the root hierarchy page
+fn copc_info(center: [f64; 3], halfsize: f64, spacing: f64, hierarchy_offset: u64, hierarchy_size: u64) -> Vec<u8> {
+    let mut data = Vec::with_capacity(INFO_SIZE);
+    for value in [center[0], center[1], center[2], halfsize, spacing] {
+        data.extend_from_slice(&value.to_le_bytes());
+    }
+    data.extend_from_slice(&hierarchy_offset.to_le_bytes());
+    data.extend_from_slice(&hierarchy_size.to_le_bytes());
+    // GPS time range (points carry none) and reserved words
+    data.resize(INFO_SIZE, 0);
+    las::vlr(COPC_USER_ID, 1, "COPC info", &data)
+}
@@ -0,0 +1,94 @@
+use crate::{mesh::Mesh, point_cloud::PointCloud, random, spatial::KdTree};
+use glam::Vec3;
+use rand::Rng;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Number of surface probes used when none is given
+pub const DEFAULT_PROBES: usize = 10_000;
+
+/// How well a cloud covers the surface of the mesh it was sampled from
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CoverageMetrics {
+    /// Number of area-weighted surface probes measured
+    pub probes: usize,
+
+    /// Distance within which a probe counts as covered (output units)
+    pub radius: f32,
+
+    /// Fraction of the surface area (0.0-1.0) within `radius` of a point
+    pub covered_fraction: f32,
+
+    /// Largest distance from the surface to its nearest point: the radius of the biggest hole
+    pub max_gap: f32,
+
+    /// Mean distance from the surface to its nearest point
+    pub mean_gap: f32,
+}
+
+impl CoverageMetrics {
+    /// Probe the mesh surface (scaled to output units by `scale`) and measure the distance
+    /// from each probe to the nearest cloud point. Without a radius the expected point
+    /// spacing, sqrt(area / points), is used
+    pub fn measure(
+        mesh: &Mesh,
+        point_cloud: &PointCloud,
+        scale: f32,
+        radius: Option<f32>,
+        probes: usize,
+        seed: Option<u64>,
+    ) -> Option<Self> {
+        if mesh.triangle_count() == 0 || point_cloud.points.is_empty() || probes == 0 {
+            return None;
+        }
+
+        let mut cumulative = Vec::with_capacity(mesh.triangle_count());
+        let mut total_area = 0.0f32;
+        for triangle in 0..mesh.triangle_count() {
+            total_area += mesh.triangle_area(triangle);
+            cumulative.push(total_area);
+        }
+        if total_area <= 0.0 {
+            return None;
+        }
+
+        let scale = if scale != 0.0 { scale } else { 1.0 };
+        let radius = radius.unwrap_or_else(|| {
+            (total_area * scale * scale / point_cloud.points.len() as f32).sqrt()
+        });
+        let tree = KdTree::from_points(&point_cloud.points);
+
+        let distances: Vec<f32> = (0..probes)
+            .collect::<Vec<_>>()
+            .par_chunks(random::CHUNK_SIZE)
+            .enumerate()
+            .flat_map_iter(|(chunk, probes)| {
+                let mut rng = random::chunk_rng(seed, random::stream::COVERAGE, chunk as u64);
+                let tree = &tree;
+                let cumulative = &cumulative;
+                probes.iter().map(move |_| {
+                    let target = rng.random::<f32>() * total_area;
+                    let triangle = cumulative
+                        .partition_point(|&area| area < target)
+                        .min(cumulative.len() - 1);
+                    let [v0, v1, v2] = mesh.triangle(triangle);
+
+                    let r1 = rng.random::<f32>().sqrt();
+                    let r2 = rng.random::<f32>();
+                    let probe: Vec3 = (v0 * (1.0 - r1) + v1 * (r1 * (1.0 - r2)) + v2 * (r1 * r2)) * scale;
+
+                    tree.nearest(probe, 1).first().map_or(f32::INFINITY, |&(_, d)| d)
+                })
+            })
+            .collect();
+
+        let covered = distances.iter().filter(|&&d| d <= radius).count();
+        Some(Self {
+            probes,
+            radius,
+            covered_fraction: covered as f32 / probes as f32,
+            max_gap: distances.iter().copied().fold(0.0, f32::max),
+            mean_gap: distances.iter().sum::<f32>() / probes as f32,
+        })
+    }
+}
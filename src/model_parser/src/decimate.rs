@@ -0,0 +1,346 @@
+use crate::mesh::Mesh;
+use glam::{DMat3, DVec3, Vec3};
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+
+/// Weight of the constraint planes that keep open boundaries in place, relative to the
+/// area-weighted face planes
+const BOUNDARY_WEIGHT: f64 = 100.0;
+
+/// Collapses turning an adjacent face by more than this (cosine between old and new
+/// normals) are rejected, so thin features fold less
+const MIN_NORMAL_COSINE: f64 = 0.2;
+
+/// Quadrics whose determinant falls below this fraction of the cubed mean eigenvalue are
+/// treated as singular and placed at the best edge endpoint or midpoint instead
+const MIN_CONDITION: f64 = 1e-3;
+
+/// Symmetric 4x4 error quadric of the squared distance to a set of planes
+#[derive(Debug, Clone, Copy)]
+struct Quadric {
+    a: DMat3,
+    b: DVec3,
+    c: f64,
+}
+
+impl Default for Quadric {
+    // Spelled out because glam's matrix default is the identity, not zero
+    fn default() -> Self {
+        Self {
+            a: DMat3::ZERO,
+            b: DVec3::ZERO,
+            c: 0.0,
+        }
+    }
+}
+
+impl Quadric {
+    /// Quadric of the plane through `point` with unit `normal`, scaled by `weight`
+    fn plane(normal: DVec3, point: DVec3, weight: f64) -> Self {
+        let d = -normal.dot(point);
+        Self {
+            a: DMat3::from_cols(normal * normal.x, normal * normal.y, normal * normal.z) * weight,
+            b: normal * d * weight,
+            c: d * d * weight,
+        }
+    }
+
+    fn add(&self, other: &Self) -> Self {
+        Self {
+            a: self.a + other.a,
+            b: self.b + other.b,
+            c: self.c + other.c,
+        }
+    }
+
+    fn error(&self, p: DVec3) -> f64 {
+        (p.dot(self.a * p) + 2.0 * self.b.dot(p) + self.c).max(0.0)
+    }
+
+    /// Position minimizing the error, when the quadric is well conditioned. Flat or
+    /// cylindrical neighborhoods leave the optimum (nearly) free to slide along the surface,
+    /// so the determinant is compared to the cube of the mean eigenvalue to stay scale free
+    fn optimum(&self) -> Option<DVec3> {
+        let mean = (self.a.x_axis.x + self.a.y_axis.y + self.a.z_axis.z) / 3.0;
+        (mean > 0.0 && self.a.determinant() > MIN_CONDITION * mean * mean * mean)
+            .then(|| -(self.a.inverse() * self.b))
+            .filter(|p| p.is_finite())
+    }
+}
+
+/// Candidate collapse in the priority queue; stale entries are recognized by their versions
+struct Candidate {
+    cost: f64,
+    a: usize,
+    b: usize,
+    versions: (u32, u32),
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cost == other.cost
+    }
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    // Reversed so the max-heap pops the cheapest collapse first
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.cost.total_cmp(&self.cost)
+    }
+}
+
+/// Reduce the mesh to at most `target` triangles by quadric error edge collapses (Garland &
+/// Heckbert), moving each merged vertex to the position of least squared distance to the
+/// original surface. Normals and colors are interpolated along the collapsed edge; edges
+/// between different materials and collapses that would flip a face are skipped, so the
+/// result can stay above `target`
+pub fn decimate(mesh: &mut Mesh, target: usize) {
+    if mesh.triangle_count() <= target || mesh.point_set {
+        return;
+    }
+
+    // Exporters often give every face its own vertices; share vertices at identical positions
+    // (per material, so material borders stay open boundaries) to recover the connectivity
+    // collapses need. Normals and colors of merged duplicates are averaged
+    let mut welded: HashMap<([u32; 3], Option<u32>), usize> = HashMap::new();
+    let mut weld = Vec::with_capacity(mesh.vertices.len());
+    let mut positions: Vec<DVec3> = Vec::new();
+    let mut normals: Vec<Vec3> = Vec::new();
+    let mut colors: Vec<Vec3> = Vec::new();
+    let mut alphas: Vec<f32> = Vec::new();
+    let mut material_ids: Vec<u32> = Vec::new();
+    for (vertex, position) in mesh.vertices.iter().enumerate() {
+        let material = mesh.material_ids.get(vertex).copied();
+        let index = *welded.entry((position.to_array().map(f32::to_bits), material)).or_insert_with(|| {
+            positions.push(position.as_dvec3());
+            normals.extend(mesh.normals.get(vertex).map(|_| Vec3::ZERO));
+            colors.extend(mesh.colors.get(vertex).map(|_| Vec3::ZERO));
+            alphas.extend(mesh.alphas.get(vertex).map(|_| 0.0));
+            material_ids.extend(material);
+            positions.len() - 1
+        });
+        if let Some(normal) = mesh.normals.get(vertex) {
+            normals[index] += *normal;
+        }
+        if let Some(color) = mesh.colors.get(vertex) {
+            colors[index] += *color;
+        }
+        if let Some(alpha) = mesh.alphas.get(vertex) {
+            alphas[index] += *alpha;
+        }
+        weld.push(index);
+    }
+    let mut merged = vec![0u32; positions.len()];
+    weld.iter().for_each(|&index| merged[index] += 1);
+    normals.iter_mut().for_each(|normal| *normal = normal.normalize_or_zero());
+    colors.iter_mut().zip(&merged).for_each(|(color, &count)| *color /= count as f32);
+    alphas.iter_mut().zip(&merged).for_each(|(alpha, &count)| *alpha /= count as f32);
+
+    let mut triangles: Vec<[usize; 3]> = mesh
+        .indices
+        .chunks_exact(3)
+        .map(|t| [weld[t[0]], weld[t[1]], weld[t[2]]])
+        .filter(|[a, b, c]| a != b && b != c && c != a)
+        .collect();
+    let mut alive = vec![true; triangles.len()];
+    let mut faces_of: Vec<Vec<usize>> = vec![Vec::new(); positions.len()];
+    for (face, triangle) in triangles.iter().enumerate() {
+        for &vertex in triangle {
+            faces_of[vertex].push(face);
+        }
+    }
+
+    // Area-weighted face planes, plus a perpendicular plane along every boundary edge
+    let mut quadrics = vec![Quadric::default(); positions.len()];
+    let mut edge_faces: HashMap<(usize, usize), u32> = HashMap::new();
+    for triangle in &triangles {
+        let [p0, p1, p2] = triangle.map(|v| positions[v]);
+        let cross = (p1 - p0).cross(p2 - p0);
+        let area = cross.length() * 0.5;
+        if area > 0.0 {
+            let plane = Quadric::plane(cross.normalize(), p0, area);
+            for &vertex in triangle {
+                quadrics[vertex] = quadrics[vertex].add(&plane);
+            }
+        }
+        for i in 0..3 {
+            let (a, b) = (triangle[i], triangle[(i + 1) % 3]);
+            *edge_faces.entry((a.min(b), a.max(b))).or_default() += 1;
+        }
+    }
+    for triangle in &triangles {
+        let [p0, p1, p2] = triangle.map(|v| positions[v]);
+        let normal = (p1 - p0).cross(p2 - p0);
+        for i in 0..3 {
+            let (a, b) = (triangle[i], triangle[(i + 1) % 3]);
+            if edge_faces.get(&(a.min(b), a.max(b))) == Some(&1) {
+                let edge = positions[b] - positions[a];
+                let side = edge.cross(normal).normalize_or_zero();
+                if side != DVec3::ZERO {
+                    let plane = Quadric::plane(side, positions[a], BOUNDARY_WEIGHT * edge.length_squared());
+                    quadrics[a] = quadrics[a].add(&plane);
+                    quadrics[b] = quadrics[b].add(&plane);
+                }
+            }
+        }
+    }
+
+    let material = |v: usize| material_ids.get(v).copied();
+    let mut versions = vec![0u32; positions.len()];
+    let placement = |quadrics: &[Quadric], positions: &[DVec3], a: usize, b: usize| {
+        let quadric = quadrics[a].add(&quadrics[b]);
+        let (pa, pb) = (positions[a], positions[b]);
+        // An optimum further from the edge than its length is numerical drift, not a better fit
+        let near = |p: &DVec3| p.distance((pa + pb) * 0.5) <= pa.distance(pb);
+        let position = quadric.optimum().filter(near).unwrap_or_else(|| {
+            [pa, pb, (pa + pb) * 0.5]
+                .into_iter()
+                .min_by(|x, y| quadric.error(*x).total_cmp(&quadric.error(*y)))
+                .unwrap_or(pa)
+        });
+        (quadric.error(position), position)
+    };
+
+    let mut heap = BinaryHeap::new();
+    for &(a, b) in edge_faces.keys() {
+        if material(a) == material(b) {
+            let (cost, _) = placement(&quadrics, &positions, a, b);
+            heap.push(Candidate { cost, a, b, versions: (0, 0) });
+        }
+    }
+
+    let mut remaining = triangles.len();
+    while remaining > target {
+        let Some(candidate) = heap.pop() else {
+            break;
+        };
+        let (a, b) = (candidate.a, candidate.b);
+        if (versions[a], versions[b]) != candidate.versions {
+            continue;
+        }
+
+        let (_, position) = placement(&quadrics, &positions, a, b);
+
+        // Reject collapses that would flip or degenerate a surviving face
+        let flips = [a, b].iter().flat_map(|&v| &faces_of[v]).any(|&face| {
+            if !alive[face] {
+                return false;
+            }
+            let triangle = triangles[face];
+            if triangle.contains(&a) && triangle.contains(&b) {
+                return false;
+            }
+            let before = triangle.map(|v| positions[v]);
+            let after = triangle.map(|v| if v == a || v == b { position } else { positions[v] });
+            let old = (before[1] - before[0]).cross(before[2] - before[0]);
+            let new = (after[1] - after[0]).cross(after[2] - after[0]);
+            new.length_squared() == 0.0 || old.normalize_or_zero().dot(new.normalize_or_zero()) < MIN_NORMAL_COSINE
+        });
+        if flips {
+            continue;
+        }
+
+        // Merge b into a, interpolating attributes where the new position projects onto the edge
+        let edge = positions[b] - positions[a];
+        let t = if edge.length_squared() > 0.0 {
+            ((position - positions[a]).dot(edge) / edge.length_squared()).clamp(0.0, 1.0) as f32
+        } else {
+            0.0
+        };
+        if let (Some(&na), Some(&nb)) = (normals.get(a), normals.get(b)) {
+            normals[a] = na.lerp(nb, t).normalize_or_zero();
+        }
+        if let (Some(&ca), Some(&cb)) = (colors.get(a), colors.get(b)) {
+            colors[a] = ca.lerp(cb, t);
+        }
+        if let (Some(&aa), Some(&ab)) = (alphas.get(a), alphas.get(b)) {
+            alphas[a] = aa + (ab - aa) * t;
+        }
+        positions[a] = position;
+        quadrics[a] = quadrics[a].add(&quadrics[b]);
+        versions[a] += 1;
+        versions[b] += 1;
+
+        let moved = std::mem::take(&mut faces_of[b]);
+        for face in moved {
+            if !alive[face] {
+                continue;
+            }
+            if triangles[face].contains(&a) {
+                alive[face] = false;
+                remaining -= 1;
+            } else {
+                for vertex in &mut triangles[face] {
+                    if *vertex == b {
+                        *vertex = a;
+                    }
+                }
+                faces_of[a].push(face);
+            }
+        }
+        faces_of[a].retain(|&face| alive[face]);
+
+        // Re-queue the edges around the merged vertex with their new costs
+        let mut neighbors: Vec<usize> = faces_of[a].iter().flat_map(|&face| triangles[face]).filter(|&v| v != a).collect();
+        neighbors.sort_unstable();
+        neighbors.dedup();
+        for neighbor in neighbors {
+            if material(a) == material(neighbor) {
+                let (cost, _) = placement(&quadrics, &positions, a, neighbor);
+                heap.push(Candidate { cost, a, b: neighbor, versions: (versions[a], versions[neighbor]) });
+            }
+        }
+    }
+
+    // Compact the surviving vertices and faces
+    let mut remap = vec![usize::MAX; positions.len()];
+    let mut order = Vec::new();
+    mesh.indices.clear();
+    for (face, triangle) in triangles.iter().enumerate() {
+        if !alive[face] {
+            continue;
+        }
+        for &vertex in triangle {
+            if remap[vertex] == usize::MAX {
+                remap[vertex] = order.len();
+                order.push(vertex);
+            }
+            mesh.indices.push(remap[vertex]);
+        }
+    }
+
+    // Point and line primitives keep their original vertices, appended after the surface
+    let mut appended: HashMap<usize, usize> = HashMap::new();
+    let mut kept = Vec::new();
+    for index in mesh.points.iter_mut().chain(mesh.lines.iter_mut()) {
+        *index = *appended.entry(*index).or_insert_with(|| {
+            kept.push(*index);
+            order.len() + kept.len() - 1
+        });
+    }
+
+    fn combine<T: Copy>(order: &[usize], kept: &[usize], welded: &[T], original: &[T]) -> Vec<T> {
+        if welded.is_empty() {
+            return Vec::new();
+        }
+        let surface = order.iter().map(|&v| welded[v]);
+        surface.chain(kept.iter().map(|&v| original[v])).collect()
+    }
+    let surface: Vec<Vec3> = positions.iter().map(|p| p.as_vec3()).collect();
+    mesh.vertices = combine(&order, &kept, &surface, &mesh.vertices);
+    mesh.normals = combine(&order, &kept, &normals, &mesh.normals);
+    mesh.colors = combine(&order, &kept, &colors, &mesh.colors);
+    mesh.alphas = combine(&order, &kept, &alphas, &mesh.alphas);
+    if !material_ids.is_empty() {
+        let surface = order.iter().map(|&v| material_ids[v]);
+        mesh.material_ids = surface.chain(kept.iter().map(|&v| mesh.material_ids[v])).collect();
+    }
+}
@@ -0,0 +1,100 @@
+use crate::{
+    error::{ModelParserError, Result},
+    point_cloud::PointCloud,
+};
+use rayon::prelude::*;
+
+/// Built-in dimensions, named as in the EPT schema; each group is kept or dropped whole
+const GROUPS: [&[&str]; 5] = [
+    &["X", "Y", "Z"],
+    &["Red", "Green", "Blue"],
+    &["Alpha"],
+    &["NormalX", "NormalY", "NormalZ"],
+    &["MaterialId"],
+];
+
+/// Names of the dimensions `point_cloud` would be written with
+pub fn available(point_cloud: &PointCloud) -> Vec<String> {
+    let metadata = &point_cloud.metadata;
+    let present = [true, metadata.has_colors, metadata.has_alpha, metadata.has_normals, metadata.has_material_ids];
+    GROUPS
+        .iter()
+        .zip(present)
+        .filter(|(_, present)| *present)
+        .flat_map(|(group, _)| group.iter().map(|name| name.to_string()))
+        .chain(metadata.attributes.iter().map(|attribute| attribute.name.clone()))
+        .collect()
+}
+
+/// Keep only the listed dimensions of `point_cloud`, dropping colors, alpha, normals,
+/// material ids and generic attributes that are not listed. Built-in names match without
+/// regard to case; X, Y and Z are required, and colors and normals need all three of their
+/// components. Listed dimensions the cloud lacks are an error
+pub fn select(point_cloud: &mut PointCloud, names: &[String]) -> Result<()> {
+    let available = available(point_cloud);
+    let builtin = |name: &str| GROUPS.iter().flat_map(|group| group.iter()).find(|b| b.eq_ignore_ascii_case(name));
+    let mut listed: Vec<&str> = Vec::new();
+    for name in names.iter().map(|name| name.trim()) {
+        let canonical = if point_cloud.attribute_index(name).is_some() {
+            name
+        } else {
+            builtin(name).copied().unwrap_or(name)
+        };
+        if !available.iter().any(|available| available == canonical) {
+            return Err(ModelParserError::InvalidData(format!(
+                "unknown dimension '{}'; the cloud has: {}",
+                name,
+                available.join(", ")
+            )));
+        }
+        listed.push(canonical);
+    }
+
+    let mut keep = [false; GROUPS.len()];
+    for (group, keep) in GROUPS.iter().zip(keep.iter_mut()) {
+        let count = group.iter().filter(|name| listed.contains(name)).count();
+        if count != 0 && count != group.len() {
+            return Err(ModelParserError::InvalidData(format!(
+                "dimensions {} must be listed together",
+                group.join(", ")
+            )));
+        }
+        *keep = count != 0;
+    }
+    if !keep[0] {
+        return Err(ModelParserError::InvalidData("dimensions must include X, Y and Z".to_string()));
+    }
+
+    let [_, colors, alpha, normals, material_ids] = keep;
+    point_cloud.points.par_iter_mut().for_each(|point| {
+        if !colors {
+            point.color = None;
+        }
+        if !alpha {
+            point.alpha = None;
+        }
+        if !normals {
+            point.normal = None;
+        }
+        if !material_ids {
+            point.material_id = None;
+        }
+    });
+    if !material_ids {
+        point_cloud.metadata.materials.clear();
+    }
+
+    let dropped: Vec<String> = point_cloud
+        .metadata
+        .attributes
+        .iter()
+        .map(|attribute| attribute.name.clone())
+        .filter(|name| !listed.contains(&name.as_str()))
+        .collect();
+    for name in dropped {
+        point_cloud.remove_attribute(&name);
+    }
+
+    point_cloud.update_metadata();
+    Ok(())
+}
@@ -0,0 +1,217 @@
+use crate::{
+    error::{ModelParserError, Result},
+    manifest,
+};
+use serde::{Deserialize, Serialize};
+use std::ffi::OsString;
+use std::io::{Read, Write};
+use std::path::{Component, Path, PathBuf};
+use std::time::Duration;
+
+/// Directory below the user's cache directory (e.g. `~/.cache`) holding downloaded inputs
+pub const CACHE_DIR: &str = "model_parser";
+
+/// Times a transfer is retried after a connection or server error
+const RETRIES: u32 = 3;
+
+/// Largest file accepted from a server, 4 GiB
+pub const MAX_DOWNLOAD_SIZE: u64 = 4 * 1024 * 1024 * 1024;
+
+/// Cache validators of a downloaded file, sent back to ask whether it changed
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Validators {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    etag: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    last_modified: Option<String>,
+}
+
+/// Whether an input names an `http://` or `https://` URL rather than a local file
+pub fn is_url(input: &str) -> bool {
+    let lower = input.to_ascii_lowercase();
+    lower.starts_with("http://") || lower.starts_with("https://")
+}
+
+/// Download a model into the cache and return its local path. Each URL has its own cache
+/// directory keeping the remote file name (readers pick the format by extension); a cached
+/// copy is only downloaded again when the server reports it modified. For `.gltf` files the
+/// relative buffer and image URIs are fetched beside the document.
+///
+/// Redirects are followed, `HTTPS_PROXY`/`HTTP_PROXY` are honored, failed transfers are
+/// retried and files above `MAX_DOWNLOAD_SIZE` are refused
+pub fn fetch(url: &str) -> Result<PathBuf> {
+    // SHA-256 keeps cache directories stable across toolchains and free of collisions
+    let dir = cache_root()?.join(manifest::sha256_hex(url.as_bytes()));
+    std::fs::create_dir_all(&dir)?;
+
+    let agent = ureq::AgentBuilder::new()
+        .timeout_connect(Duration::from_secs(30))
+        .try_proxy_from_env(true)
+        .build();
+    let (base, name) = split_url(url);
+    let name = Some(name).filter(|name| is_safe_relative(name)).unwrap_or("model");
+    let path = dir.join(name);
+    download(&agent, url, &path)?;
+
+    if name.to_ascii_lowercase().ends_with(".gltf") {
+        let text = std::fs::read_to_string(&path)?;
+        let document: serde_json::Value = serde_json::from_str(&text)?;
+        let uris = ["buffers", "images"]
+            .iter()
+            .filter_map(|list| document.get(list)?.as_array())
+            .flatten()
+            .filter_map(|entry| entry.get("uri")?.as_str())
+            .filter(|uri| !uri.starts_with("data:"));
+        for uri in uris {
+            if !is_safe_relative(uri) {
+                return Err(ModelParserError::DownloadError(format!(
+                    "{}: refusing to fetch '{}' outside the document's directory",
+                    url, uri
+                )));
+            }
+            let target = dir.join(uri);
+            if let Some(parent) = target.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            download(&agent, &format!("{}{}", base, uri), &target)?;
+        }
+    }
+
+    Ok(path)
+}
+
+/// Split a URL into its directory (with trailing '/') and last path segment, ignoring any
+/// query or fragment
+fn split_url(url: &str) -> (&str, &str) {
+    let end = url.find(['?', '#']).unwrap_or(url.len());
+    let path = &url[..end];
+    let scheme_end = path.find("://").map_or(0, |i| i + 3);
+    match path[scheme_end..].rfind('/') {
+        Some(slash) => (&path[..scheme_end + slash + 1], &path[scheme_end + slash + 1..]),
+        None => (path, ""),
+    }
+}
+
+/// A non-empty relative path that stays below its base directory
+fn is_safe_relative(path: &str) -> bool {
+    !path.is_empty()
+        && !path.contains("://")
+        && Path::new(path).components().all(|c| matches!(c, Component::Normal(_)))
+}
+
+/// Per-user cache directory, readable by its owner only, so other local users can neither
+/// read downloads nor plant files for a later run to pick up
+fn cache_root() -> Result<PathBuf> {
+    let root = dirs::cache_dir()
+        .ok_or_else(|| ModelParserError::DownloadError("no user cache directory (is HOME set?)".to_string()))?
+        .join(CACHE_DIR);
+    create_private_dir(&root)?;
+    Ok(root)
+}
+
+#[cfg(unix)]
+fn create_private_dir(dir: &Path) -> Result<()> {
+    use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+
+    std::fs::DirBuilder::new().recursive(true).mode(0o700).create(dir)?;
+    // Narrows a directory left by an earlier version; fails unless it is ours
+    std::fs::set_permissions(dir, std::fs::Permissions::from_mode(0o700))?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn create_private_dir(dir: &Path) -> Result<()> {
+    // The per-user profile directory the cache sits in is already private
+    std::fs::create_dir_all(dir)?;
+    Ok(())
+}
+
+/// `path` with `suffix` appended to its file name
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = OsString::from(path.as_os_str());
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+/// Download `url` to `path`, skipping the transfer when a cached copy is still current.
+/// Data goes to a partial file first so an interrupted download never looks cached
+fn download(agent: &ureq::Agent, url: &str, path: &Path) -> Result<()> {
+    let validators_path = with_suffix(path, ".validators.json");
+    let cached = path
+        .exists()
+        .then(|| std::fs::read(&validators_path).ok())
+        .flatten()
+        .and_then(|bytes| serde_json::from_slice::<Validators>(&bytes).ok());
+
+    let response = request(agent, url, cached.as_ref())?;
+    // 304 Not Modified leaves the cached copy in place
+    if response.status() == 304 {
+        return Ok(());
+    }
+
+    let too_large = || {
+        ModelParserError::DownloadError(format!("{}: larger than the {} byte download limit", url, MAX_DOWNLOAD_SIZE))
+    };
+    let length = response.header("Content-Length").and_then(|length| length.parse::<u64>().ok());
+    if length.is_some_and(|length| length > MAX_DOWNLOAD_SIZE) {
+        return Err(too_large());
+    }
+
+    let validators = Validators {
+        etag: response.header("ETag").map(str::to_string),
+        last_modified: response.header("Last-Modified").map(str::to_string),
+    };
+    let partial = with_suffix(path, ".partial");
+    let mut file = std::fs::File::create(&partial)?;
+    // Bodies without (or with a wrong) Content-Length are cut off one byte past the limit
+    let mut body = response.into_reader().take(MAX_DOWNLOAD_SIZE + 1);
+    let copied = std::io::copy(&mut body, &mut file).and_then(|copied| file.flush().map(|_| copied));
+    match copied {
+        Ok(copied) if copied > MAX_DOWNLOAD_SIZE => {
+            let _ = std::fs::remove_file(&partial);
+            return Err(too_large());
+        }
+        Ok(_) => {}
+        Err(e) => {
+            let _ = std::fs::remove_file(&partial);
+            return Err(ModelParserError::DownloadError(format!("{}: {}", url, e)));
+        }
+    }
+    std::fs::rename(&partial, path)?;
+    std::fs::write(&validators_path, serde_json::to_vec(&validators)?)?;
+    Ok(())
+}
+
+/// GET `url`, conditional on the `cached` validators when there are any. Client errors fail
+/// at once; connection and server errors are retried with a growing delay
+fn request(agent: &ureq::Agent, url: &str, cached: Option<&Validators>) -> Result<ureq::Response> {
+    let mut attempt = 0;
+    loop {
+        let mut request = agent.get(url);
+        if let Some(etag) = cached.and_then(|cached| cached.etag.as_deref()) {
+            request = request.set("If-None-Match", etag);
+        }
+        if let Some(modified) = cached.and_then(|cached| cached.last_modified.as_deref()) {
+            request = request.set("If-Modified-Since", modified);
+        }
+
+        match request.call() {
+            Ok(response) => return Ok(response),
+            Err(ureq::Error::Status(status, response)) if status < 500 || attempt == RETRIES => {
+                return Err(ModelParserError::DownloadError(format!(
+                    "{}: HTTP {} {}",
+                    url,
+                    status,
+                    response.status_text()
+                )));
+            }
+            Err(ureq::Error::Transport(error)) if attempt == RETRIES => {
+                return Err(ModelParserError::DownloadError(format!("{}: {}", url, error)));
+            }
+            Err(_) => {}
+        }
+        attempt += 1;
+        std::thread::sleep(Duration::from_secs(1 << attempt));
+    }
+}
@@ -0,0 +1,316 @@
+use crate::{
+    attributes::{AttributeDescriptor, AttributeType},
+    color,
+    config::PointCloudConfig,
+    error::{ModelParserError, Result},
+    las::SHIFT_THRESHOLD,
+    mesh::Mesh,
+    xml::Element,
+};
+use glam::{DQuat, DVec3, Vec3};
+use std::path::Path;
+
+/// Size of the fixed file header
+const HEADER_SIZE: usize = 48;
+
+/// Load an ASTM E57 file as a point set. Every scan in `data3D` is read from its cartesian or
+/// spherical coordinates, moved into the file's coordinate system by its pose, and merged;
+/// per-point colors and an `intensity` attribute are kept when the scans store them. Points
+/// flagged invalid are skipped, and georeferenced coordinates are shifted near the origin as
+/// for LAS files. 2D images are ignored
+pub fn load(path: &Path, config: &PointCloudConfig) -> Result<Mesh> {
+    let bytes = std::fs::read(path)?;
+    let invalid = |message: &str| ModelParserError::InvalidData(format!("{}: {}", path.display(), message));
+
+    if bytes.len() < HEADER_SIZE || &bytes[..8] != b"ASTM-E57" {
+        return Err(invalid("not an E57 file"));
+    }
+    let u64_at = |at: usize| u64::from_le_bytes(bytes[at..at + 8].try_into().unwrap_or_default());
+    let xml_offset = u64_at(24);
+    let xml_length = u64_at(32) as usize;
+    let page_size = u64_at(40);
+    if page_size <= 4 {
+        return Err(invalid("invalid page size"));
+    }
+
+    // Every page ends with a CRC; strip them so offsets can be followed in one buffer
+    let file = PagedFile::new(&bytes, page_size as usize);
+    let xml = file
+        .get(xml_offset, xml_length)
+        .ok_or_else(|| invalid("truncated XML section"))?;
+    let root = Element::parse(&String::from_utf8_lossy(xml)).map_err(|message| invalid(&message))?;
+
+    let scans: Vec<&Element> = root
+        .child("data3D")
+        .map(|data| data.children("vectorChild").collect())
+        .unwrap_or_default();
+
+    let mut positions: Vec<DVec3> = Vec::new();
+    let mut colors: Vec<Option<[f64; 3]>> = Vec::new();
+    let mut intensity: Vec<f64> = Vec::new();
+    let mut has_colors = false;
+    let mut has_intensity = false;
+
+    for scan in scans {
+        let points = scan.child("points").ok_or_else(|| invalid("scan without points"))?;
+        let prototype = points.child("prototype").ok_or_else(|| invalid("points without prototype"))?;
+        let count: usize = points
+            .attribute("recordCount")
+            .and_then(|c| c.parse().ok())
+            .ok_or_else(|| invalid("points without recordCount"))?;
+        let offset: u64 = points
+            .attribute("fileOffset")
+            .and_then(|o| o.parse().ok())
+            .ok_or_else(|| invalid("points without fileOffset"))?;
+
+        let streams = file
+            .compressed_vector(offset, prototype.children.len())
+            .ok_or_else(|| invalid("truncated compressed vector"))?;
+        let field = |name: &str| -> Option<Vec<f64>> {
+            let index = prototype.children.iter().position(|c| c.name == name)?;
+            decode(&prototype.children[index], &streams[index], count)
+        };
+
+        let cartesian = (field("cartesianX"), field("cartesianY"), field("cartesianZ"));
+        let spherical = (field("sphericalRange"), field("sphericalAzimuth"), field("sphericalElevation"));
+        let (local, state): (Vec<DVec3>, Option<Vec<f64>>) = match (cartesian, spherical) {
+            ((Some(x), Some(y), Some(z)), _) => (
+                (0..count).map(|i| DVec3::new(x[i], y[i], z[i])).collect(),
+                field("cartesianInvalidState"),
+            ),
+            (_, (Some(range), Some(azimuth), Some(elevation))) => (
+                (0..count)
+                    .map(|i| {
+                        let (r, a, e) = (range[i], azimuth[i], elevation[i]);
+                        DVec3::new(r * e.cos() * a.cos(), r * e.cos() * a.sin(), r * e.sin())
+                    })
+                    .collect(),
+                field("sphericalInvalidState"),
+            ),
+            _ => return Err(invalid("scan without cartesian or spherical coordinates")),
+        };
+
+        let pose = scan.child("pose");
+        let rotation = pose
+            .and_then(|p| p.child("rotation"))
+            .map(|r| DQuat::from_xyzw(number(r, "x", 0.0), number(r, "y", 0.0), number(r, "z", 0.0), number(r, "w", 1.0)))
+            .unwrap_or(DQuat::IDENTITY)
+            .normalize();
+        let translation = pose
+            .and_then(|p| p.child("translation"))
+            .map(|t| DVec3::new(number(t, "x", 0.0), number(t, "y", 0.0), number(t, "z", 0.0)))
+            .unwrap_or(DVec3::ZERO);
+
+        // Colors are normalized by the scan's limits, else by the prototype range; intensity stays raw
+        let rgb = [field("colorRed"), field("colorGreen"), field("colorBlue")];
+        let color_ranges = ["Red", "Green", "Blue"].map(|channel| {
+            range(scan.child("colorLimits"), &format!("color{}", channel), prototype.child(&format!("color{}", channel)))
+        });
+        let color_invalid = field("isColorInvalid");
+        let scan_intensity = field("intensity");
+        let intensity_invalid = field("isIntensityInvalid");
+        has_colors |= rgb.iter().all(Option::is_some);
+        has_intensity |= scan_intensity.is_some();
+
+        for i in 0..count {
+            let point_state = state.as_ref().map_or(0.0, |s| s[i]);
+            if point_state != 0.0 || !local[i].is_finite() {
+                continue;
+            }
+
+            positions.push(rotation * local[i] + translation);
+            colors.push(match &rgb {
+                [Some(r), Some(g), Some(b)] if color_invalid.as_ref().is_none_or(|v| v[i] == 0.0) => {
+                    Some([(r, 0), (g, 1), (b, 2)].map(|(values, channel)| {
+                        let (min, max) = color_ranges[channel];
+                        if max > min { (values[i] - min) / (max - min) } else { values[i] }
+                    }))
+                }
+                _ => None,
+            });
+            intensity.push(match &scan_intensity {
+                Some(values) if intensity_invalid.as_ref().is_none_or(|v| v[i] == 0.0) => values[i],
+                _ => 0.0,
+            });
+        }
+    }
+
+    if positions.is_empty() {
+        return Err(ModelParserError::NoMeshData);
+    }
+
+    let (min, max) = positions
+        .iter()
+        .fold((DVec3::MAX, DVec3::MIN), |(min, max), &p| (min.min(p), max.max(p)));
+    let center = (min + max) * 0.5;
+    let shift = (center.abs().max_element() > SHIFT_THRESHOLD).then(|| center.round().to_array());
+    let origin = DVec3::from(shift.unwrap_or([0.0; 3]));
+
+    let mut mesh = Mesh::new();
+    mesh.point_set = true;
+    mesh.offset = shift;
+    mesh.vertices = positions.iter().map(|&p| (p - origin).as_vec3()).collect();
+
+    if config.include_colors {
+        mesh.has_source_colors = has_colors;
+        mesh.colors = colors
+            .iter()
+            .map(|c| match c {
+                Some(c) => Vec3::from(c.map(|v| color::srgb_to_linear(v as f32))),
+                None => Vec3::ONE,
+            })
+            .collect();
+    }
+
+    if has_intensity {
+        mesh.point_attributes = vec![(AttributeDescriptor::new("intensity", AttributeType::F32), intensity)];
+    }
+
+    Ok(mesh)
+}
+
+/// Logical (CRC-free) view of a paged E57 file
+struct PagedFile {
+    data: Vec<u8>,
+    page_size: u64,
+}
+
+impl PagedFile {
+    fn new(bytes: &[u8], page_size: usize) -> Self {
+        let data = bytes
+            .chunks(page_size)
+            .flat_map(|page| &page[..page.len().saturating_sub(4)])
+            .copied()
+            .collect();
+        Self {
+            data,
+            page_size: page_size as u64,
+        }
+    }
+
+    /// Logical offset of a physical file offset
+    fn logical(&self, physical: u64) -> usize {
+        (physical / self.page_size * (self.page_size - 4) + physical % self.page_size) as usize
+    }
+
+    /// `length` logical bytes starting at a physical offset
+    fn get(&self, physical: u64, length: usize) -> Option<&[u8]> {
+        let start = self.logical(physical);
+        self.data.get(start..start.checked_add(length)?)
+    }
+
+    /// Concatenated bytestreams of the compressed vector binary section at a physical offset
+    fn compressed_vector(&self, physical: u64, stream_count: usize) -> Option<Vec<Vec<u8>>> {
+        let header = self.get(physical, 32)?;
+        if header[0] != 1 {
+            return None;
+        }
+        let u64_at = |at: usize| u64::from_le_bytes(header[at..at + 8].try_into().unwrap_or_default());
+        let section_end = self.logical(physical).checked_add(u64_at(8) as usize)?;
+        let mut position = self.logical(u64_at(16));
+
+        let mut streams = vec![Vec::new(); stream_count];
+        while position + 4 <= section_end.min(self.data.len()) {
+            let packet = &self.data[position..];
+            let length = u16::from_le_bytes([packet[2], packet[3]]) as usize + 1;
+            let packet = packet.get(..length)?;
+
+            // Only data packets carry values; index and empty packets are skipped
+            if packet[0] == 1 {
+                let count = u16::from_le_bytes([*packet.get(4)?, *packet.get(5)?]) as usize;
+                let mut at = 6 + count * 2;
+                for (buffer, stream) in streams.iter_mut().enumerate().take(count) {
+                    let size = u16::from_le_bytes([packet[6 + buffer * 2], packet[7 + buffer * 2]]) as usize;
+                    stream.extend_from_slice(packet.get(at..at + size)?);
+                    at += size;
+                }
+            }
+            position += length;
+        }
+        Some(streams)
+    }
+}
+
+/// Decode `count` values of one prototype field from its bytestream
+fn decode(field: &Element, stream: &[u8], count: usize) -> Option<Vec<f64>> {
+    let attribute = |name: &str| field.attribute(name);
+    match field.attribute("type")? {
+        "Float" if attribute("precision") == Some("single") => Some(
+            stream
+                .chunks_exact(4)
+                .take(count)
+                .map(|v| f32::from_le_bytes(v.try_into().unwrap_or_default()) as f64)
+                .collect(),
+        )
+        .filter(|values: &Vec<f64>| values.len() == count),
+        "Float" => Some(
+            stream
+                .chunks_exact(8)
+                .take(count)
+                .map(|v| f64::from_le_bytes(v.try_into().unwrap_or_default()))
+                .collect(),
+        )
+        .filter(|values: &Vec<f64>| values.len() == count),
+        kind @ ("Integer" | "ScaledInteger") => {
+            let minimum: i64 = attribute("minimum").and_then(|v| v.parse().ok()).unwrap_or(i64::MIN);
+            let maximum: i64 = attribute("maximum").and_then(|v| v.parse().ok()).unwrap_or(i64::MAX);
+            let (scale, offset) = match kind {
+                "ScaledInteger" => (
+                    attribute("scale").and_then(|v| v.parse().ok()).unwrap_or(1.0),
+                    attribute("offset").and_then(|v| v.parse().ok()).unwrap_or(0.0),
+                ),
+                _ => (1.0, 0.0),
+            };
+
+            // Values are packed LSB-first in just enough bits for the field's range
+            let range = maximum.wrapping_sub(minimum) as u64;
+            let bits = (u64::BITS - range.leading_zeros()) as usize;
+            if stream.len() * 8 < bits * count {
+                return None;
+            }
+            let mask = if bits == 64 { u64::MAX } else { (1u64 << bits) - 1 };
+            Some(
+                (0..count)
+                    .map(|i| {
+                        let raw = if bits == 0 {
+                            0
+                        } else {
+                            let bit = i * bits;
+                            let mut window = [0u8; 16];
+                            let bytes = &stream[bit / 8..stream.len().min(bit / 8 + 9)];
+                            window[..bytes.len()].copy_from_slice(bytes);
+                            (u128::from_le_bytes(window) >> (bit % 8)) as u64 & mask
+                        };
+                        minimum.wrapping_add(raw as i64) as f64 * scale + offset
+                    })
+                    .collect(),
+            )
+        }
+        _ => None,
+    }
+}
+
+/// Numeric value of a child element such as `<x type="Float">1.5</x>`
+fn number(element: &Element, name: &str, default: f64) -> f64 {
+    element
+        .child(name)
+        .and_then(|child| child.text.trim().parse().ok())
+        .unwrap_or(default)
+}
+
+/// (min, max) of a color channel from the scan's `colorLimits`, else the prototype's range
+fn range(limits: Option<&Element>, name: &str, prototype: Option<&Element>) -> (f64, f64) {
+    if let Some(limits) = limits {
+        let min = number(limits, &format!("{}Minimum", name), f64::NAN);
+        let max = number(limits, &format!("{}Maximum", name), f64::NAN);
+        if min.is_finite() && max.is_finite() {
+            return (min, max);
+        }
+    }
+
+    let bound = |attribute: &str| prototype.and_then(|p| p.attribute(attribute)).and_then(|v| v.parse().ok());
+    match (prototype.and_then(|p| p.attribute("type")), bound("minimum"), bound("maximum")) {
+        (Some("Float"), _, _) | (_, None, _) | (_, _, None) => (0.0, 1.0),
+        (_, Some(min), Some(max)) => (min, max),
+    }
+}
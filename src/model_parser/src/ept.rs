@@ -1,7 +1,17 @@
-use crate::{point_cloud::PointCloud, error::Result};
+use crate::{
+    budget::SizeEstimate,
+    color::{self, ColorDepth, ColorSpace, Dither},
+    error::{ModelParserError, Result},
+    las::SHIFT_THRESHOLD,
+    octahedral::{self, NormalEncoding},
+    point_cloud::{Point, PointCloud},
+    random,
+    storage::Uploader,
+};
 use glam::Vec3;
 use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 use std::path::Path;
 
 /// EPT (Entwine Point Tile) format support
@@ -38,6 +48,18 @@ pub struct EptMetadata {
 
     /// Version
     pub version: String,
+
+    /// Translation removed from the source coordinates by re-centering
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub offset: Option<[f64; 3]>,
+
+    /// Bounds ignoring outliers, for framing the camera: [minx, miny, minz, maxx, maxy, maxz]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub robust_bounds: Option<[f64; 6]>,
+
+    /// Geometric error of the root node in world units; every node's is in `ept-errors.json`
+    #[serde(default, rename = "geometricError", skip_serializing_if = "Option::is_none")]
+    pub geometric_error: Option<f64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -46,6 +68,18 @@ pub struct EptDimension {
     #[serde(rename = "type")]
     pub data_type: String,
     pub size: u32,
+
+    /// How to interpret the stored values when not plain numbers (e.g. "octahedral")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encoding: Option<String>,
+
+    /// Factor applied to stored values: value = stored × scale + offset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scale: Option<f64>,
+
+    /// Added to stored values after scaling
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub offset: Option<f64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -58,7 +92,7 @@ pub struct EptSrs {
 }
 
 /// Octree node key for EPT hierarchy
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
 pub struct OctreeKey {
     pub depth: u32,
     pub x: u32,
@@ -94,10 +128,40 @@ impl OctreeKey {
         ]
     }
 
+    /// Key of the node containing this one, `None` for the root
+    pub fn parent(&self) -> Option<OctreeKey> {
+        (self.depth > 0).then(|| OctreeKey::new(self.depth - 1, self.x / 2, self.y / 2, self.z / 2))
+    }
+
     /// Convert to EPT file path format (D-X-Y-Z.json)
     pub fn to_path_string(&self) -> String {
         format!("{}-{}-{}-{}", self.depth, self.x, self.y, self.z)
     }
+
+    /// Parse an EPT key string ("D-X-Y-Z")
+    pub fn from_path_string(key: &str) -> Option<Self> {
+        let parts: Vec<u32> = key.split('-').map(|p| p.parse().ok()).collect::<Option<_>>()?;
+        match parts.as_slice() {
+            [depth, x, y, z] => Some(Self::new(*depth, *x, *y, *z)),
+            _ => None,
+        }
+    }
+
+    /// Bounds of this node inside the cubic root `bounds` [minx, miny, minz, maxx, maxy, maxz]
+    pub fn bounds(&self, root: &[f64; 6]) -> [f64; 6] {
+        let cells = (1u64 << self.depth) as f64;
+        let size = [
+            (root[3] - root[0]) / cells,
+            (root[4] - root[1]) / cells,
+            (root[5] - root[2]) / cells,
+        ];
+        let min = [
+            root[0] + self.x as f64 * size[0],
+            root[1] + self.y as f64 * size[1],
+            root[2] + self.z as f64 * size[2],
+        ];
+        [min[0], min[1], min[2], min[0] + size[0], min[1] + size[1], min[2] + size[2]]
+    }
 }
 
 /// Binary point data for EPT tiles
@@ -108,16 +172,217 @@ pub struct EptPointData {
     pub normals: Option<Vec<[f32; 3]>>,
 }
 
+/// What to do with points outside bounds set by `EptBuilder::with_bounds`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OutOfBounds {
+    /// Move them onto the nearest face of the bounds
+    #[default]
+    Clamp,
+
+    /// Leave them out of the output
+    Reject,
+}
+
+/// Storage of the X/Y/Z dimensions. Coordinates are stored relative to an origin recorded as
+/// the dimensions' `offset`, chosen automatically when they are far from zero, so large
+/// (georeferenced) coordinates keep their precision
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum PositionEncoding {
+    /// 32-bit floats
+    #[default]
+    Float32,
+
+    /// 32-bit integers at the given resolution, recorded as the dimensions' `scale`
+    Scaled(f64),
+}
+
+/// Summary of a finished EPT build
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct EptBuildReport {
+    /// Points stored in tiles, including tile overlap copies
+    pub points_written: u64,
+
+    /// Input points that were outside the fixed bounds
+    pub outside_bounds: usize,
+
+    /// How those points were handled
+    pub out_of_bounds: OutOfBounds,
+
+    /// Tiles kept from an interrupted build instead of being written again
+    #[serde(default)]
+    pub tiles_resumed: usize,
+}
+
+/// Where a build writes its files
+enum Sink<'a> {
+    /// A local directory; only these builds can be resumed
+    Directory(&'a Path),
+
+    /// Objects below an object storage location, each uploaded as soon as it is encoded
+    Store(&'a Uploader),
+}
+
+impl Sink<'_> {
+    fn directory(&self) -> Option<&Path> {
+        match self {
+            Sink::Directory(dir) => Some(dir),
+            Sink::Store(_) => None,
+        }
+    }
+
+    /// Store `data` at `relative` ('/'-separated). Local files are written then renamed, so
+    /// each is either complete or absent after an interruption
+    fn put(&self, relative: &str, data: Vec<u8>) -> Result<()> {
+        match self {
+            Sink::Directory(dir) => {
+                let path = dir.join(relative);
+                let mut partial = path.clone().into_os_string();
+                partial.push(".partial");
+                std::fs::write(&partial, data)?;
+                std::fs::rename(partial, path)?;
+                Ok(())
+            }
+            Sink::Store(uploader) => uploader.put(relative, data),
+        }
+    }
+}
+
+/// Directory holding the state of an unfinished build, removed once the build completes
+pub const BUILD_STATE_DIR: &str = "ept-build";
+
+/// Partitioning of an unfinished build, persisted so it can be resumed. Tiles are written
+/// atomically, so every tile file present with the expected size is complete
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct BuildState {
+    /// Identifies the input cloud and settings the partitioning belongs to
+    fingerprint: String,
+
+    /// Node keys and point counts, in the order their indices are stored in `partition.bin`
+    nodes: Vec<(String, usize)>,
+}
+
+impl BuildState {
+    fn save(dir: &Path, fingerprint: &str, nodes: &[(OctreeKey, Vec<usize>)]) -> Result<()> {
+        std::fs::create_dir_all(dir)?;
+        let mut partition = Vec::with_capacity(nodes.iter().map(|(_, indices)| indices.len() * 8).sum());
+        for (_, indices) in nodes {
+            for &index in indices {
+                partition.extend_from_slice(&(index as u64).to_le_bytes());
+            }
+        }
+        std::fs::write(dir.join("partition.bin"), partition)?;
+
+        // The state file goes last: its presence marks the partition as complete
+        let state = Self {
+            fingerprint: fingerprint.to_string(),
+            nodes: nodes.iter().map(|(key, indices)| (key.to_path_string(), indices.len())).collect(),
+        };
+        std::fs::write(dir.join("state.json"), serde_json::to_string(&state)?)?;
+        Ok(())
+    }
+
+    /// Saved partitioning, if there is one for this fingerprint
+    fn load(dir: &Path, fingerprint: &str) -> Option<Vec<(OctreeKey, Vec<usize>)>> {
+        let state: Self = serde_json::from_str(&std::fs::read_to_string(dir.join("state.json")).ok()?).ok()?;
+        if state.fingerprint != fingerprint {
+            return None;
+        }
+
+        let partition = std::fs::read(dir.join("partition.bin")).ok()?;
+        let mut indices = partition
+            .chunks_exact(8)
+            .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap_or_default()) as usize);
+        state
+            .nodes
+            .iter()
+            .map(|(key, count)| {
+                let node: Vec<usize> = indices.by_ref().take(*count).collect();
+                let key = OctreeKey::from_path_string(key)?;
+                (node.len() == *count).then_some((key, node))
+            })
+            .collect()
+    }
+}
+
+/// File name of the optional tile index, next to `ept.json`
+pub const TILE_INDEX_FILE: &str = "ept-tiles.json";
+
+/// Every tile of a build with its exact extent and size, so streaming backends can plan
+/// requests without reading the hierarchy or probing tiles
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TileIndex {
+    /// Cubic octree bounds [minx, miny, minz, maxx, maxy, maxz]
+    pub bounds: [f64; 6],
+
+    /// Tiles ordered by depth, then key
+    pub tiles: Vec<TileEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TileEntry {
+    /// Node key ("D-X-Y-Z"), also the tile's file name in `ept-data`
+    pub key: String,
+
+    /// Points stored in the tile
+    pub points: u64,
+
+    /// Tile size in bytes
+    pub bytes: u64,
+
+    /// Extent of the stored points [minx, miny, minz, maxx, maxy, maxz], tighter than the node
+    pub bounds: [f64; 6],
+
+    /// Largest gap in world units between the tile's points and the detail below it
+    #[serde(default)]
+    pub geometric_error: f64,
+}
+
+/// File name of the per-node geometric errors, next to `ept.json`
+pub const GEOMETRIC_ERROR_FILE: &str = "ept-errors.json";
+
+impl TileIndex {
+    pub fn load(dir: &Path) -> Result<Self> {
+        let json = std::fs::read_to_string(dir.join(TILE_INDEX_FILE))?;
+        Ok(serde_json::from_str(&json)?)
+    }
+}
+
 pub struct EptBuilder {
     max_points_per_tile: usize,
+    depth_capacities: Vec<usize>,
     max_depth: u32,
+    color_depth: ColorDepth,
+    dither: Dither,
+    normal_encoding: NormalEncoding,
+    position_encoding: PositionEncoding,
+    origin: Option<[f64; 3]>,
+    seed: Option<u64>,
+    tile_overlap: f32,
+    bounds: Option<[f64; 6]>,
+    out_of_bounds: OutOfBounds,
+    tile_index: bool,
+    resume: bool,
+    lod_averaging: bool,
 }
 
 impl Default for EptBuilder {
     fn default() -> Self {
         Self {
             max_points_per_tile: 100_000, // Standard EPT default
+            depth_capacities: Vec::new(),
             max_depth: 10,
+            color_depth: ColorDepth::Eight,
+            dither: Dither::None,
+            normal_encoding: NormalEncoding::Float32,
+            position_encoding: PositionEncoding::Float32,
+            origin: None,
+            seed: None,
+            tile_overlap: 0.0,
+            bounds: None,
+            out_of_bounds: OutOfBounds::Clamp,
+            tile_index: false,
+            resume: false,
+            lod_averaging: false,
         }
     }
 }
@@ -132,83 +397,166 @@ impl EptBuilder {
         self
     }
 
+    /// Node capacity by depth: nodes at depth `d` hold up to `capacities[d]` points and deeper
+    /// nodes the last entry, e.g. a small root for a fast first frame and larger leaves.
+    /// Replaces `with_max_points_per_tile` when not empty
+    pub fn with_depth_capacities(mut self, capacities: Vec<usize>) -> Self {
+        self.depth_capacities = capacities;
+        self
+    }
+
+    /// Points a node at `depth` holds before the rest is passed down to its children
+    pub fn capacity(&self, depth: u32) -> usize {
+        match self.depth_capacities.last() {
+            Some(&last) => self.depth_capacities.get(depth as usize).copied().unwrap_or(last),
+            None => self.max_points_per_tile,
+        }
+    }
+
     pub fn with_max_depth(mut self, depth: u32) -> Self {
         self.max_depth = depth;
         self
     }
 
+    /// Bit depth of the Red/Green/Blue dimensions
+    pub fn with_color_depth(mut self, color_depth: ColorDepth) -> Self {
+        self.color_depth = color_depth;
+        self
+    }
+
+    /// Dithering of 8-bit colors; 16-bit colors are always rounded to nearest
+    pub fn with_dither(mut self, dither: Dither) -> Self {
+        self.dither = dither;
+        self
+    }
+
+    /// Storage of the normal dimensions
+    pub fn with_normal_encoding(mut self, normal_encoding: NormalEncoding) -> Self {
+        self.normal_encoding = normal_encoding;
+        self
+    }
+
+    /// Storage of the position dimensions
+    pub fn with_position_encoding(mut self, position_encoding: PositionEncoding) -> Self {
+        self.position_encoding = position_encoding;
+        self
+    }
+
+    /// Origin positions are stored relative to. By default the rounded center of the octree
+    /// bounds when they reach far from zero or positions are scaled, otherwise none
+    pub fn with_origin(mut self, origin: Option<[f64; 3]>) -> Self {
+        self.origin = origin;
+        self
+    }
+
+    /// Origin used for the cubic octree `bounds`, if positions are shifted at all
+    pub fn resolve_origin(&self, bounds: &[f64; 6]) -> Option<[f64; 3]> {
+        let far = bounds.iter().any(|v| v.abs() > SHIFT_THRESHOLD);
+        self.origin.or_else(|| {
+            (far || matches!(self.position_encoding, PositionEncoding::Scaled(_)))
+                .then(|| [0, 1, 2].map(|i| ((bounds[i] + bounds[i + 3]) * 0.5).round()))
+        })
+    }
+
+    /// Seed the octree shuffle so identical input produces identical tiles
+    pub fn with_seed(mut self, seed: Option<u64>) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Duplicate points lying within `fraction` of a node's edge length from a child
+    /// boundary into the neighbouring children, hiding seams between tiles
+    pub fn with_tile_overlap(mut self, fraction: f32) -> Self {
+        self.tile_overlap = fraction.max(0.0);
+        self
+    }
+
+    /// Use fixed bounds [minx, miny, minz, maxx, maxy, maxz] (expanded to a cube) instead of
+    /// the cloud's own extent, so separately converted models share one octree frame
+    pub fn with_bounds(mut self, bounds: Option<[f64; 6]>) -> Self {
+        self.bounds = bounds;
+        self
+    }
+
+    /// Handling of points outside the fixed bounds
+    pub fn with_out_of_bounds(mut self, out_of_bounds: OutOfBounds) -> Self {
+        self.out_of_bounds = out_of_bounds;
+        self
+    }
+
+    /// Handling of points outside the fixed bounds
+    pub(crate) fn out_of_bounds(&self) -> OutOfBounds {
+        self.out_of_bounds
+    }
+
+    /// Also write a `TileIndex` to `ept-tiles.json`
+    pub fn with_tile_index(mut self, enabled: bool) -> Self {
+        self.tile_index = enabled;
+        self
+    }
+
+    /// Continue an interrupted build of the same cloud with the same settings in the output
+    /// directory: its saved partitioning is reused and finished tiles are kept. Builds of
+    /// sampled meshes only match when seeded
+    pub fn with_resume(mut self, resume: bool) -> Self {
+        self.resume = resume;
+        self
+    }
+
+    /// Give points of nodes with children the mean color and normal of all points below the
+    /// node in the grid cell they were selected from, so coarse levels show the object's
+    /// overall appearance instead of noisy individual samples. Positions stay unchanged
+    pub fn with_lod_averaging(mut self, enabled: bool) -> Self {
+        self.lod_averaging = enabled;
+        self
+    }
+
     /// Build EPT structure from point cloud
-    pub fn build(&self, point_cloud: &PointCloud, output_dir: &Path) -> Result<()> {
+    pub fn build(&self, point_cloud: &PointCloud, output_dir: &Path) -> Result<EptBuildReport> {
         // Create output directory structure
         std::fs::create_dir_all(output_dir)?;
         std::fs::create_dir_all(output_dir.join("ept-data"))?;
         std::fs::create_dir_all(output_dir.join("ept-hierarchy"))?;
 
-        // Calculate bounds
-        let bounds = self.calculate_bounds(&point_cloud.points);
-        let bounds_conforming = bounds.clone();
+        self.write(point_cloud, &Sink::Directory(output_dir))
+    }
 
-        // Create schema based on available data
-        let mut schema = vec![
-            EptDimension {
-                name: "X".to_string(),
-                data_type: "floating".to_string(),
-                size: 4,
-            },
-            EptDimension {
-                name: "Y".to_string(),
-                data_type: "floating".to_string(),
-                size: 4,
-            },
-            EptDimension {
-                name: "Z".to_string(),
-                data_type: "floating".to_string(),
-                size: 4,
-            },
-        ];
+    /// Build the EPT structure straight into object storage: every tile is uploaded as soon
+    /// as it is encoded and the metadata files after them, without a local copy. Such builds
+    /// cannot be resumed
+    pub fn upload(&self, point_cloud: &PointCloud, uploader: &Uploader) -> Result<EptBuildReport> {
+        self.write(point_cloud, &Sink::Store(uploader))
+    }
 
-        if point_cloud.metadata.has_colors {
-            schema.push(EptDimension {
-                name: "Red".to_string(),
-                data_type: "unsigned".to_string(),
-                size: 1,
-            });
-            schema.push(EptDimension {
-                name: "Green".to_string(),
-                data_type: "unsigned".to_string(),
-                size: 1,
-            });
-            schema.push(EptDimension {
-                name: "Blue".to_string(),
-                data_type: "unsigned".to_string(),
-                size: 1,
-            });
-        }
+    fn write(&self, point_cloud: &PointCloud, sink: &Sink) -> Result<EptBuildReport> {
+        let mut report = EptBuildReport {
+            out_of_bounds: self.out_of_bounds,
+            ..Default::default()
+        };
+        let (point_cloud, bounds_conforming, bounds, outside) = self.frame(point_cloud);
+        report.outside_bounds = outside;
+        let point_cloud = point_cloud.as_ref();
 
-        if point_cloud.metadata.has_normals {
-            schema.push(EptDimension {
-                name: "NormalX".to_string(),
-                data_type: "floating".to_string(),
-                size: 4,
-            });
-            schema.push(EptDimension {
-                name: "NormalY".to_string(),
-                data_type: "floating".to_string(),
-                size: 4,
-            });
-            schema.push(EptDimension {
-                name: "NormalZ".to_string(),
-                data_type: "floating".to_string(),
-                size: 4,
-            });
+        let origin = self.resolve_origin(&bounds);
+        if let PositionEncoding::Scaled(scale) = self.position_encoding {
+            let origin = origin.unwrap_or_default();
+            let reach = (0..3)
+                .map(|i| (bounds[i] - origin[i]).abs().max((bounds[i + 3] - origin[i]).abs()))
+                .fold(0.0, f64::max);
+            if !(scale.is_finite() && scale > 0.0) || reach / scale > i32::MAX as f64 {
+                return Err(ModelParserError::InvalidData(format!(
+                    "coordinates do not fit EPT integers at scale {}; use a coarser scale",
+                    scale
+                )));
+            }
         }
 
         // Create metadata
-        let metadata = EptMetadata {
+        let mut metadata = EptMetadata {
             bounds,
             bounds_conforming,
             points: point_cloud.points.len() as u64,
-            schema,
+            schema: self.schema(point_cloud, origin),
             srs: EptSrs {
                 authority: "EPSG".to_string(),
                 horizontal: "4978".to_string(), // ECEF
@@ -219,16 +567,180 @@ impl EptBuilder {
             hierarchy_type: "json".to_string(),
             span: 128, // Standard span
             version: "1.0.0".to_string(),
+            offset: point_cloud.metadata.offset,
+            robust_bounds: point_cloud.metadata.robust_bounds.map(|b| {
+                [b.min[0], b.min[1], b.min[2], b.max[0], b.max[1], b.max[2]].map(|v| v as f64)
+            }),
+            geometric_error: None,
         };
 
+        // Build octree and write tiles; overlap copies make the stored count differ from the input
+        let (points, tiles_resumed, root_error) = self.build_octree(point_cloud, sink, &metadata)?;
+        metadata.points = points;
+        metadata.geometric_error = root_error;
+        report.tiles_resumed = tiles_resumed;
+
         // Write metadata
         let metadata_json = serde_json::to_string_pretty(&metadata)?;
-        std::fs::write(output_dir.join("ept.json"), metadata_json)?;
+        sink.put("ept.json", metadata_json.into_bytes())?;
 
-        // Build octree and write tiles
-        self.build_octree(point_cloud, output_dir, &metadata)?;
+        report.points_written = metadata.points;
+        Ok(report)
+    }
 
-        Ok(())
+    /// Fit the cloud into the octree: returns the cloud to write (clamped or filtered to fixed
+    /// bounds), its conforming extent, the cubic octree bounds and the number of points that
+    /// were outside fixed bounds
+    pub(crate) fn frame<'a>(&self, point_cloud: &'a PointCloud) -> (Cow<'a, PointCloud>, [f64; 6], [f64; 6], usize) {
+        // The octree needs a cube around the conforming extent
+        match self.bounds {
+            Some(frame) => {
+                let bounds = Self::cubic_bounds(&frame);
+                let (point_cloud, outside) = self.fit_to_bounds(point_cloud, &bounds);
+
+                // The conforming extent never reaches past the frame
+                let actual = self.calculate_bounds(&point_cloud.points);
+                let conforming = [0, 1, 2, 3, 4, 5].map(|i| {
+                    if i < 3 { actual[i].max(bounds[i]) } else { actual[i].min(bounds[i]) }
+                });
+                (point_cloud, conforming, bounds, outside)
+            }
+            None => {
+                let conforming = self.calculate_bounds(&point_cloud.points);
+                (Cow::Borrowed(point_cloud), conforming, Self::cubic_bounds(&conforming), 0)
+            }
+        }
+    }
+
+    /// Split a framed cloud into octree nodes within the cubic `bounds`, parents before
+    /// their children
+    pub(crate) fn octree(&self, point_cloud: &PointCloud, bounds: &[f64; 6]) -> Vec<(OctreeKey, Vec<usize>)> {
+        use rand::seq::SliceRandom;
+
+        // Shuffle once so every node's subset is a random, spatially unbiased sample
+        let mut indices: Vec<usize> = (0..point_cloud.points.len()).collect();
+        indices.shuffle(&mut random::chunk_rng(self.seed, random::stream::OCTREE, 0));
+
+        let min = Vec3::new(bounds[0] as f32, bounds[1] as f32, bounds[2] as f32);
+        let size = (bounds[3] - bounds[0]) as f32;
+        self.partition(&point_cloud.points, OctreeKey::root(), min, size, indices)
+    }
+
+    /// Clamp or drop points outside `bounds`, copying the cloud only when needed.
+    /// Returns the cloud to write and the number of points that were outside
+    fn fit_to_bounds<'a>(&self, point_cloud: &'a PointCloud, bounds: &[f64; 6]) -> (Cow<'a, PointCloud>, usize) {
+        let min = Vec3::new(bounds[0] as f32, bounds[1] as f32, bounds[2] as f32);
+        let max = Vec3::new(bounds[3] as f32, bounds[4] as f32, bounds[5] as f32);
+        let inside: Vec<bool> = point_cloud
+            .points
+            .par_iter()
+            .map(|point| {
+                let p = Vec3::from(point.position);
+                p.cmpge(min).all() && p.cmple(max).all()
+            })
+            .collect();
+
+        let outside = inside.iter().filter(|inside| !**inside).count();
+        if outside == 0 {
+            return (Cow::Borrowed(point_cloud), 0);
+        }
+
+        let mut fitted = point_cloud.clone();
+        match self.out_of_bounds {
+            OutOfBounds::Clamp => {
+                fitted.points.par_iter_mut().for_each(|point| {
+                    point.position = Vec3::from(point.position).clamp(min, max).to_array();
+                });
+                fitted.update_metadata();
+            }
+            OutOfBounds::Reject => fitted.retain_by_mask(&inside),
+        }
+        (Cow::Owned(fitted), outside)
+    }
+
+    /// Estimated output size: one record per point plus metadata and hierarchy files.
+    /// Points duplicated by tile overlap are not included
+    pub fn estimate_size(&self, point_cloud: &PointCloud) -> SizeEstimate {
+        let record_size: u32 = self.schema(point_cloud, None).iter().map(|d| d.size).sum();
+        let smallest = self.depth_capacities.iter().copied().min().unwrap_or(self.max_points_per_tile);
+        let tiles = point_cloud.points.len().div_ceil(smallest.max(1)) as u64;
+        SizeEstimate {
+            bytes_per_point: record_size as f64,
+            overhead: 4096 + tiles * 32,
+        }
+    }
+
+    /// Create schema based on available data, with positions relative to `origin`
+    fn schema(&self, point_cloud: &PointCloud, origin: Option<[f64; 3]>) -> Vec<EptDimension> {
+        fn dimension(name: &str, data_type: &str, size: u32) -> EptDimension {
+            EptDimension {
+                name: name.to_string(),
+                data_type: data_type.to_string(),
+                size,
+                encoding: None,
+                scale: None,
+                offset: None,
+            }
+        }
+
+        let mut schema: Vec<EptDimension> = ["X", "Y", "Z"]
+            .into_iter()
+            .enumerate()
+            .map(|(axis, name)| {
+                let mut position = match self.position_encoding {
+                    PositionEncoding::Float32 => dimension(name, "floating", 4),
+                    PositionEncoding::Scaled(scale) => {
+                        let mut position = dimension(name, "signed", 4);
+                        position.scale = Some(scale);
+                        position
+                    }
+                };
+                position.offset = origin.map(|origin| origin[axis]);
+                position
+            })
+            .collect();
+
+        if point_cloud.metadata.has_colors {
+            let size = self.color_depth.size();
+            schema.push(dimension("Red", "unsigned", size));
+            schema.push(dimension("Green", "unsigned", size));
+            schema.push(dimension("Blue", "unsigned", size));
+        }
+
+        if point_cloud.metadata.has_alpha {
+            schema.push(dimension("Alpha", "unsigned", self.color_depth.size()));
+        }
+
+        if point_cloud.metadata.has_normals {
+            match self.normal_encoding {
+                NormalEncoding::Float32 => {
+                    schema.push(dimension("NormalX", "floating", 4));
+                    schema.push(dimension("NormalY", "floating", 4));
+                    schema.push(dimension("NormalZ", "floating", 4));
+                }
+                NormalEncoding::Octahedral16 => {
+                    for name in ["NormalOctU", "NormalOctV"] {
+                        let mut normal = dimension(name, "unsigned", 2);
+                        normal.encoding = Some("octahedral".to_string());
+                        schema.push(normal);
+                    }
+                }
+            }
+        }
+
+        if point_cloud.metadata.has_material_ids {
+            schema.push(dimension("MaterialId", "unsigned", 4));
+        }
+
+        for attribute in &point_cloud.metadata.attributes {
+            schema.push(dimension(
+                &attribute.name,
+                attribute.data_type.ept_type(),
+                attribute.data_type.size(),
+            ));
+        }
+
+        schema
     }
 
     fn calculate_bounds(&self, points: &[crate::point_cloud::Point]) -> [f64; 6] {
@@ -259,116 +771,448 @@ impl EptBuilder {
         ]
     }
 
+    /// Expand bounds to a cube sharing the same center
+    fn cubic_bounds(bounds: &[f64; 6]) -> [f64; 6] {
+        let center = [
+            (bounds[0] + bounds[3]) * 0.5,
+            (bounds[1] + bounds[4]) * 0.5,
+            (bounds[2] + bounds[5]) * 0.5,
+        ];
+        let half = (bounds[3] - bounds[0])
+            .max(bounds[4] - bounds[1])
+            .max(bounds[5] - bounds[2])
+            .max(f64::EPSILON)
+            * 0.5;
+
+        [
+            center[0] - half, center[1] - half, center[2] - half,
+            center[0] + half, center[1] + half, center[2] + half,
+        ]
+    }
+
+    /// Returns the number of points written, the number of tiles kept from a previous run and
+    /// the geometric error of the root node
     fn build_octree(
         &self,
         point_cloud: &PointCloud,
-        output_dir: &Path,
-        _metadata: &EptMetadata,
-    ) -> Result<()> {
-        use std::collections::HashMap;
+        sink: &Sink,
+        metadata: &EptMetadata,
+    ) -> Result<(u64, usize, Option<f64>)> {
+        use std::collections::BTreeMap;
 
-        // Simple implementation: write all points to root node for now
-        // In production, you'd recursively split into octree tiles
+        let output_dir = sink.directory();
+        let state_dir = output_dir.map(|dir| dir.join(BUILD_STATE_DIR));
+        let fingerprint = self.fingerprint(point_cloud, metadata);
+        let resumed = state_dir
+            .as_deref()
+            .filter(|_| self.resume)
+            .and_then(|dir| BuildState::load(dir, &fingerprint));
+        let is_resumed = resumed.is_some();
 
-        let root_key = OctreeKey::root();
+        let nodes = match resumed {
+            Some(nodes) => nodes,
+            None => {
+                let nodes = self.octree(point_cloud, &metadata.bounds);
+                if let Some(dir) = &state_dir {
+                    BuildState::save(dir, &fingerprint, &nodes)?;
+                }
+                nodes
+            }
+        };
 
-        // Prepare point data in parallel
-        let has_colors = point_cloud.metadata.has_colors;
-        let has_normals = point_cloud.metadata.has_normals;
+        let averages = self.level_averages(point_cloud, &nodes, &metadata.bounds);
+        let parents: std::collections::HashSet<OctreeKey> = nodes.iter().filter_map(|(key, _)| key.parent()).collect();
 
-        let data: Vec<_> = point_cloud
-            .points
-            .par_iter()
-            .map(|point| {
-                let position = point.position;
-
-                let color = if has_colors {
-                    if let Some(color) = point.color {
-                        // Convert from 0-1 float to 0-255 u8
-                        [
-                            (color[0] * 255.0) as u8,
-                            (color[1] * 255.0) as u8,
-                            (color[2] * 255.0) as u8,
-                        ]
-                    } else {
-                        [255, 255, 255]
-                    }
-                } else {
-                    [0, 0, 0] // Dummy value, won't be used
-                };
+        // Write binary tile data, keeping complete tiles of a resumed build
+        let record_size: u64 = metadata.schema.iter().map(|dimension| dimension.size as u64).sum();
+        let kept = std::sync::atomic::AtomicUsize::new(0);
+        nodes.par_iter().try_for_each(|(key, node_indices)| {
+            let tile = format!("ept-data/{}.bin", key.to_path_string());
+            let expected = node_indices.len() as u64 * record_size;
+            let complete = |dir: &Path| std::fs::metadata(dir.join(&tile)).is_ok_and(|m| m.len() == expected);
+            if is_resumed && output_dir.is_some_and(complete) {
+                kept.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                return Ok(());
+            }
+            let level = averages.get(key.depth as usize).filter(|_| parents.contains(key));
+            self.write_binary_tile(sink, &tile, node_indices, point_cloud, &metadata.bounds, level)
+        })?;
 
-                let normal = if has_normals {
-                    point.normal.unwrap_or([0.0, 0.0, 0.0])
-                } else {
-                    [0.0, 0.0, 0.0] // Dummy value, won't be used
-                };
+        // Write hierarchy
+        let hierarchy: BTreeMap<String, i64> = nodes
+            .iter()
+            .map(|(key, node_indices)| (key.to_path_string(), node_indices.len() as i64))
+            .collect();
 
-                (position, color, normal)
-            })
+        let hierarchy_json = serde_json::to_string_pretty(&hierarchy)?;
+        sink.put("ept-hierarchy/0-0-0-0.json", hierarchy_json.into_bytes())?;
+
+        // Write geometric errors, compact like the tile index
+        let size = metadata.bounds[3] - metadata.bounds[0];
+        let errors: BTreeMap<String, f64> = nodes
+            .iter()
+            .map(|(key, _)| (key.to_path_string(), self.geometric_error(key, parents.contains(key), size)))
             .collect();
+        sink.put(GEOMETRIC_ERROR_FILE, serde_json::to_vec(&errors)?)?;
 
-        // Unzip into separate vectors
-        let mut positions = Vec::with_capacity(data.len());
-        let mut color_data = Vec::with_capacity(data.len());
-        let mut normal_data = Vec::with_capacity(data.len());
+        if self.tile_index {
+            let record_size: u64 = metadata.schema.iter().map(|dimension| dimension.size as u64).sum();
+            let mut tiles: Vec<TileEntry> = nodes
+                .par_iter()
+                .map(|(key, node_indices)| {
+                    let (min, max) = node_indices.iter().fold(
+                        (Vec3::splat(f32::MAX), Vec3::splat(f32::MIN)),
+                        |(min, max), &i| {
+                            let position = Vec3::from(point_cloud.points[i].position);
+                            (min.min(position), max.max(position))
+                        },
+                    );
+                    TileEntry {
+                        key: key.to_path_string(),
+                        points: node_indices.len() as u64,
+                        bytes: node_indices.len() as u64 * record_size,
+                        bounds: [min.x, min.y, min.z, max.x, max.y, max.z].map(|v| v as f64),
+                        geometric_error: self.geometric_error(key, parents.contains(key), size),
+                    }
+                })
+                .collect();
+            tiles.sort_by_key(|tile| {
+                let key = OctreeKey::from_path_string(&tile.key).unwrap_or_default();
+                (key.depth, key.x, key.y, key.z)
+            });
 
-        for (pos, col, norm) in data {
-            positions.push(pos);
-            color_data.push(col);
-            normal_data.push(norm);
+            // Compact on purpose: the index is fetched whole by clients
+            let index = TileIndex { bounds: metadata.bounds, tiles };
+            sink.put(TILE_INDEX_FILE, serde_json::to_vec(&index)?)?;
         }
 
-        let colors = if has_colors { Some(color_data) } else { None };
-        let normals = if has_normals { Some(normal_data) } else { None };
+        if let Some(dir) = &state_dir {
+            std::fs::remove_dir_all(dir)?;
+        }
 
-        // Write binary tile data
-        let tile_path = output_dir.join("ept-data").join(format!("{}.bin", root_key.to_path_string()));
-        self.write_binary_tile(&tile_path, &positions, colors.as_ref(), normals.as_ref())?;
+        let points = nodes.iter().map(|(_, node_indices)| node_indices.len() as u64).sum();
+        let root_error = errors.get(&OctreeKey::root().to_path_string()).copied();
+        Ok((points, kept.into_inner(), root_error))
+    }
 
-        // Write hierarchy
-        let mut hierarchy = HashMap::new();
-        hierarchy.insert(root_key.to_path_string(), point_cloud.points.len() as i64);
+    /// Geometric error of a node in an octree of edge length `size`: the spacing of the grid
+    /// its points were selected on, as each cell keeps about one point of everything below.
+    /// Leaves hold all their points and have no error
+    pub fn geometric_error(&self, key: &OctreeKey, has_children: bool, size: f64) -> f64 {
+        if !has_children {
+            return 0.0;
+        }
+        size / (1u64 << key.depth) as f64 / Self::grid(self.capacity(key.depth)) as f64
+    }
 
-        let hierarchy_json = serde_json::to_string_pretty(&hierarchy)?;
-        let hierarchy_path = output_dir.join("ept-hierarchy").join("0-0-0-0.json");
-        std::fs::write(hierarchy_path, hierarchy_json)?;
+    /// Identity of a build: the cloud's layout plus a sample of its positions, and every
+    /// setting that affects partitioning or the tile format
+    fn fingerprint(&self, point_cloud: &PointCloud, metadata: &EptMetadata) -> String {
+        use std::hash::{Hash, Hasher};
 
-        Ok(())
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        let stride = (point_cloud.points.len() / 4096).max(1);
+        for point in point_cloud.points.iter().step_by(stride) {
+            point.position.map(f32::to_bits).hash(&mut hasher);
+        }
+        metadata.bounds.map(f64::to_bits).hash(&mut hasher);
+        serde_json::to_string(&metadata.schema).unwrap_or_default().hash(&mut hasher);
+        (self.max_points_per_tile, &self.depth_capacities, self.max_depth, self.seed, self.tile_overlap.to_bits(), self.dither)
+            .hash(&mut hasher);
+        self.lod_averaging.hash(&mut hasher);
+        format!("{}-{:016x}", point_cloud.points.len(), hasher.finish())
     }
 
-    fn write_binary_tile(
+    /// Recursively split points into octree nodes. Every node keeps a representative
+    /// subset of its points and passes the remainder down, so coarse levels are a
+    /// thinned version of the whole node rather than arbitrary overflow
+    fn partition(
         &self,
-        path: &Path,
-        positions: &[[f32; 3]],
-        colors: Option<&Vec<[u8; 3]>>,
-        normals: Option<&Vec<[f32; 3]>>,
-    ) -> Result<()> {
-        use std::io::Write;
+        points: &[Point],
+        key: OctreeKey,
+        min: Vec3,
+        size: f32,
+        indices: Vec<usize>,
+    ) -> Vec<(OctreeKey, Vec<usize>)> {
+        if indices.is_empty() {
+            return Vec::new();
+        }
+
+        let capacity = self.capacity(key.depth);
+        if indices.len() <= capacity || key.depth >= self.max_depth {
+            return vec![(key, indices)];
+        }
+
+        let (selected, remainder) = self.select_representatives(points, indices, capacity, min, size);
+
+        // Bucket the remainder into children, matching OctreeKey::children ordering
+        let half = size * 0.5;
+        let center = min + Vec3::splat(half);
+        let margin = size * self.tile_overlap;
+        let mut buckets: [Vec<usize>; 8] = Default::default();
+        for index in remainder {
+            let p = Vec3::from(points[index].position);
+            let child = (p.x >= center.x) as usize
+                | ((p.y >= center.y) as usize) << 1
+                | ((p.z >= center.z) as usize) << 2;
+            buckets[child].push(index);
+
+            if margin > 0.0 {
+                // Duplicate into neighbours across every split plane within the margin
+                let near = (p - center).abs().cmplt(Vec3::splat(margin));
+                let axes = near.x as usize | (near.y as usize) << 1 | (near.z as usize) << 2;
+                for flip in 1..8 {
+                    if flip & !axes == 0 {
+                        buckets[child ^ flip].push(index);
+                    }
+                }
+            }
+        }
+
+        let children = key.children();
+        let mut nodes = vec![(key, selected)];
+        nodes.par_extend(buckets.into_par_iter().enumerate().flat_map(|(child, bucket)| {
+            let offset = Vec3::new(
+                (child & 1) as f32,
+                ((child >> 1) & 1) as f32,
+                ((child >> 2) & 1) as f32,
+            ) * half;
+            self.partition(points, children[child], min + offset, half, bucket)
+        }));
+
+        nodes
+    }
+
+    /// Cells per axis of the grid representatives are picked from. Surfaces occupy roughly
+    /// grid^2 cells, so this grid yields about `capacity` winners
+    fn grid(capacity: usize) -> usize {
+        ((capacity as f32).sqrt().ceil() as usize).clamp(1, 1024)
+    }
+
+    /// Mean colors and normals per representative grid cell for every depth that has nodes
+    /// with children, indexed by depth; empty without LOD averaging. A cell of a node at depth
+    /// `d` averages every point stored at depth `d` or deeper inside it, which is all the points
+    /// the node was selected from
+    pub(crate) fn level_averages(
+        &self,
+        point_cloud: &PointCloud,
+        nodes: &[(OctreeKey, Vec<usize>)],
+        bounds: &[f64; 6],
+    ) -> Vec<LevelAverages> {
+        if !self.lod_averaging {
+            return Vec::new();
+        }
+
+        let metadata = &point_cloud.metadata;
+        let deepest = nodes.iter().map(|(key, _)| key.depth).max().unwrap_or(0);
+        let origin = Vec3::new(bounds[0] as f32, bounds[1] as f32, bounds[2] as f32);
+        let size = (bounds[3] - bounds[0]) as f32;
+
+        (0..deepest)
+            .into_par_iter()
+            .map(|depth| {
+                // Node edges are whole multiples of the cell size, so cells can be indexed globally
+                let cell_size = size / (1u64 << depth) as f32 / Self::grid(self.capacity(depth)) as f32;
+                let mut cells: std::collections::HashMap<glam::IVec3, CellAverage> = std::collections::HashMap::new();
+                for (_, indices) in nodes.iter().filter(|(key, _)| key.depth >= depth) {
+                    for &index in indices {
+                        let point = &point_cloud.points[index];
+                        let cell = ((Vec3::from(point.position) - origin) / cell_size).floor().as_ivec3();
+                        let average = cells.entry(cell).or_default();
+                        if let Some(c) = point.color {
+                            // Colors are averaged in linear space so mixes keep their brightness
+                            average.color += color::convert(Vec3::from(c), metadata.color_space, ColorSpace::Linear);
+                            average.colors += 1;
+                        }
+                        if let Some(n) = point.normal {
+                            average.normal += Vec3::from(n);
+                        }
+                    }
+                }
+                LevelAverages { origin, cell_size, color_space: metadata.color_space, cells }
+            })
+            .collect()
+    }
+
+    /// Pick up to `capacity` points spread evenly over the node: the first point (in
+    /// shuffled order) of each grid cell wins, then the quota is topped up
+    fn select_representatives(
+        &self,
+        points: &[Point],
+        indices: Vec<usize>,
+        capacity: usize,
+        min: Vec3,
+        size: f32,
+    ) -> (Vec<usize>, Vec<usize>) {
+        use std::collections::HashSet;
 
-        let mut file = std::fs::File::create(path)?;
+        let grid = Self::grid(capacity);
+        let cell_size = size / grid as f32;
 
-        // Write point data in binary format
-        for (i, pos) in positions.iter().enumerate() {
-            // Write position (3 x f32)
-            file.write_all(&pos[0].to_le_bytes())?;
-            file.write_all(&pos[1].to_le_bytes())?;
-            file.write_all(&pos[2].to_le_bytes())?;
+        let mut occupied = HashSet::new();
+        let mut selected = Vec::with_capacity(capacity);
+        let mut remainder = Vec::with_capacity(indices.len().saturating_sub(capacity));
 
-            // Write color if present (3 x u8)
-            if let Some(color_vec) = colors {
-                let color = color_vec[i];
-                file.write_all(&[color[0], color[1], color[2]])?;
+        for index in indices {
+            let cell = ((Vec3::from(points[index].position) - min) / cell_size)
+                .floor()
+                .as_ivec3()
+                .clamp(glam::IVec3::ZERO, glam::IVec3::splat(grid as i32 - 1));
+            if selected.len() < capacity && occupied.insert(cell) {
+                selected.push(index);
+            } else {
+                remainder.push(index);
             }
+        }
+
+        // Top up sparse nodes with the next points in shuffled order
+        let missing = capacity.saturating_sub(selected.len()).min(remainder.len());
+        selected.extend(remainder.drain(..missing));
+
+        (selected, remainder)
+    }
 
-            // Write normal if present (3 x f32)
-            if let Some(normal_vec) = normals {
-                let normal = normal_vec[i];
-                file.write_all(&normal[0].to_le_bytes())?;
-                file.write_all(&normal[1].to_le_bytes())?;
-                file.write_all(&normal[2].to_le_bytes())?;
+    /// Encode a single point following the schema produced by [`EptBuilder::schema`]
+    fn encode_point(&self, point: &Point, point_cloud: &PointCloud, origin: [f64; 3], dither_cell: f32, out: &mut Vec<u8>) {
+        let metadata = &point_cloud.metadata;
+
+        // Write position relative to the origin (3 x f32 or 3 x i32)
+        for (component, origin) in point.position.into_iter().zip(origin) {
+            let residual = component as f64 - origin;
+            match self.position_encoding {
+                PositionEncoding::Float32 => out.extend_from_slice(&(residual as f32).to_le_bytes()),
+                PositionEncoding::Scaled(scale) => out.extend_from_slice(&((residual / scale).round() as i32).to_le_bytes()),
             }
         }
 
-        Ok(())
+        // Write color if present (3 x u8 or 3 x u16), white when a point has none
+        if metadata.has_colors {
+            let color = point.color.unwrap_or([1.0, 1.0, 1.0]);
+            match self.color_depth {
+                ColorDepth::Eight if self.dither != Dither::None => {
+                    let cell = point.position.map(|c| (c / dither_cell).floor() as i64);
+                    out.extend_from_slice(&color::to_rgb8_dithered(color, self.dither.offset(cell)))
+                }
+                ColorDepth::Eight => out.extend_from_slice(&color::to_rgb8(color)),
+                ColorDepth::Sixteen => {
+                    for channel in color::to_rgb16(color) {
+                        out.extend_from_slice(&channel.to_le_bytes());
+                    }
+                }
+            }
+        }
+
+        // Write alpha if present (u8 or u16), opaque when a point has none
+        if metadata.has_alpha {
+            let alpha = point.alpha.unwrap_or(1.0);
+            match self.color_depth {
+                ColorDepth::Eight => out.push(color::to_u8(alpha)),
+                ColorDepth::Sixteen => out.extend_from_slice(&color::to_u16(alpha).to_le_bytes()),
+            }
+        }
+
+        // Write normal if present (3 x f32 or 2 x u16 octahedral)
+        if metadata.has_normals {
+            let normal = point.normal.unwrap_or([0.0, 0.0, 0.0]);
+            match self.normal_encoding {
+                NormalEncoding::Float32 => {
+                    for component in normal {
+                        out.extend_from_slice(&component.to_le_bytes());
+                    }
+                }
+                NormalEncoding::Octahedral16 => {
+                    for component in octahedral::encode(Vec3::from(normal)) {
+                        out.extend_from_slice(&component.to_le_bytes());
+                    }
+                }
+            }
+        }
+
+        // Write material id if present (u32)
+        if metadata.has_material_ids {
+            out.extend_from_slice(&point.material_id.unwrap_or(0).to_le_bytes());
+        }
+
+        // Write generic attributes in descriptor order
+        for (i, attribute) in metadata.attributes.iter().enumerate() {
+            let value = point.attributes.get(i).copied().unwrap_or(0.0);
+            attribute.data_type.encode(value, out);
+        }
+    }
+
+    fn write_binary_tile(
+        &self,
+        sink: &Sink,
+        tile: &str,
+        indices: &[usize],
+        point_cloud: &PointCloud,
+        bounds: &[f64; 6],
+        averages: Option<&LevelAverages>,
+    ) -> Result<()> {
+        // Dither patterns repeat over cells about one average point spacing wide, estimated
+        // from the cloud's extent as if its points covered the three bounding planes
+        let metadata = &point_cloud.metadata;
+        let [dx, dy, dz] = [0, 1, 2].map(|i| (metadata.bounds_max[i] - metadata.bounds_min[i]).max(0.0));
+        let area = dx * dy + dy * dz + dz * dx;
+        let dither_cell = (area / point_cloud.points.len().max(1) as f32).sqrt().max(f32::MIN_POSITIVE);
+        let origin = self.resolve_origin(bounds).unwrap_or_default();
+
+        // Encode point data in parallel, then write it in one go
+        let data: Vec<u8> = indices
+            .par_chunks(4096)
+            .map(|chunk| {
+                let mut buffer = Vec::new();
+                for &index in chunk {
+                    let point = &point_cloud.points[index];
+                    let point = averages.map_or(Cow::Borrowed(point), |averages| averages.apply(point));
+                    self.encode_point(&point, point_cloud, origin, dither_cell, &mut buffer);
+                }
+                buffer
+            })
+            .flatten_iter()
+            .collect();
+
+        sink.put(tile, data)
+    }
+}
+
+/// Sums of the colors and normals in one grid cell
+#[derive(Debug, Clone, Copy, Default)]
+struct CellAverage {
+    /// Sum of the linear colors
+    color: Vec3,
+
+    /// Number of points with a color
+    colors: u32,
+
+    /// Sum of the normals
+    normal: Vec3,
+}
+
+/// Averages of one octree depth, keyed by global grid cell
+pub(crate) struct LevelAverages {
+    origin: Vec3,
+    cell_size: f32,
+    color_space: ColorSpace,
+    cells: std::collections::HashMap<glam::IVec3, CellAverage>,
+}
+
+impl LevelAverages {
+    /// `point` with the mean color and normal of its cell
+    pub(crate) fn apply<'a>(&self, point: &'a Point) -> Cow<'a, Point> {
+        let cell = ((Vec3::from(point.position) - self.origin) / self.cell_size).floor().as_ivec3();
+        let Some(average) = self.cells.get(&cell) else {
+            return Cow::Borrowed(point);
+        };
+
+        let mut averaged = point.clone();
+        if point.color.is_some() && average.colors > 0 {
+            let mean = average.color / average.colors as f32;
+            averaged.color = Some(color::convert(mean, ColorSpace::Linear, self.color_space).to_array());
+        }
+        if point.normal.is_some() && average.normal.length_squared() > 0.0 {
+            averaged.normal = Some(average.normal.normalize().to_array());
+        }
+        Cow::Owned(averaged)
     }
 }
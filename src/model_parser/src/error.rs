@@ -17,6 +17,15 @@ pub enum ModelParserError {
     #[error("Invalid point count: {0}")]
     InvalidPointCount(usize),
 
+    #[error("Invalid data: {0}")]
+    InvalidData(String),
+
+    #[error("Upload failed: {0}")]
+    UploadError(String),
+
+    #[error("Download failed: {0}")]
+    DownloadError(String),
+
     #[error("Serialization error: {0}")]
     SerializationError(#[from] serde_json::Error),
 }
@@ -0,0 +1,352 @@
+use crate::{
+    error::{ModelParserError, Result},
+    point_cloud::{Point, PointCloud},
+};
+use rayon::prelude::*;
+
+/// A per-point expression such as `classification != 7 && z > 10 && density > 0.5`.
+///
+/// Values are numbers; comparisons and logical operators yield 1 or 0, and any non-zero
+/// value counts as true. Names refer to `x`/`y`/`z`, `nx`/`ny`/`nz`, `red`/`green`/`blue`,
+/// `material_id` or any generic attribute. Values a point lacks (e.g. a normal) are NaN,
+/// which makes every comparison false.
+///
+/// Precedence from loosest to tightest: `||`, `&&`, comparisons
+/// (`== != < <= > >=`), `+ -`, `* /`, unary `!` and `-`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expression {
+    source: String,
+    root: Node,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Number(f64),
+    Variable(String),
+    Not(Box<Node>),
+    Negate(Box<Node>),
+    Binary(Operator, Box<Node>, Box<Node>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operator {
+    Or,
+    And,
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+}
+
+impl Operator {
+    fn apply(self, a: f64, b: f64) -> f64 {
+        let truth = |value: bool| if value { 1.0 } else { 0.0 };
+        match self {
+            Self::Or => truth(a != 0.0 || b != 0.0),
+            Self::And => truth(a != 0.0 && b != 0.0),
+            Self::Equal => truth(a == b),
+            Self::NotEqual => truth(a != b && !a.is_nan() && !b.is_nan()),
+            Self::Less => truth(a < b),
+            Self::LessEqual => truth(a <= b),
+            Self::Greater => truth(a > b),
+            Self::GreaterEqual => truth(a >= b),
+            Self::Add => a + b,
+            Self::Subtract => a - b,
+            Self::Multiply => a * b,
+            Self::Divide => a / b,
+        }
+    }
+}
+
+/// A name resolved against a cloud's layout
+#[derive(Debug, Clone, Copy)]
+enum Variable {
+    Position(usize),
+    Normal(usize),
+    Color(usize),
+    Alpha,
+    MaterialId,
+    Attribute(usize),
+}
+
+impl Variable {
+    fn value(self, point: &Point) -> f64 {
+        match self {
+            Self::Position(axis) => point.position[axis] as f64,
+            Self::Normal(axis) => point.normal.map_or(f64::NAN, |n| n[axis] as f64),
+            Self::Color(channel) => point.color.map_or(f64::NAN, |c| c[channel] as f64),
+            Self::Alpha => point.alpha.map_or(f64::NAN, |a| a as f64),
+            Self::MaterialId => point.material_id.map_or(f64::NAN, |id| id as f64),
+            Self::Attribute(index) => point.attributes.get(index).copied().unwrap_or(f64::NAN),
+        }
+    }
+}
+
+/// Expression tree with names replaced by accessors
+enum Bound {
+    Number(f64),
+    Variable(Variable),
+    Not(Box<Bound>),
+    Negate(Box<Bound>),
+    Binary(Operator, Box<Bound>, Box<Bound>),
+}
+
+impl Bound {
+    fn evaluate(&self, point: &Point) -> f64 {
+        match self {
+            Self::Number(value) => *value,
+            Self::Variable(variable) => variable.value(point),
+            Self::Not(inner) => if inner.evaluate(point) != 0.0 { 0.0 } else { 1.0 },
+            Self::Negate(inner) => -inner.evaluate(point),
+            Self::Binary(operator, a, b) => operator.apply(a.evaluate(point), b.evaluate(point)),
+        }
+    }
+}
+
+impl Expression {
+    pub fn parse(source: &str) -> Result<Self> {
+        let tokens = tokenize(source).map_err(|message| syntax_error(source, &message))?;
+        let mut parser = Parser { tokens, position: 0 };
+        let root = parser.or().map_err(|message| syntax_error(source, &message))?;
+        if let Some(token) = parser.tokens.get(parser.position) {
+            return Err(syntax_error(source, &format!("unexpected '{}'", token)));
+        }
+        Ok(Self {
+            source: source.to_string(),
+            root,
+        })
+    }
+
+    /// The expression text as given
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Evaluate the expression for every point
+    pub fn evaluate(&self, point_cloud: &PointCloud) -> Result<Vec<f64>> {
+        let bound = self.bind(&self.root, point_cloud)?;
+        Ok(point_cloud.points.par_iter().map(|point| bound.evaluate(point)).collect())
+    }
+
+    /// Which points the expression holds for
+    pub fn select(&self, point_cloud: &PointCloud) -> Result<Vec<bool>> {
+        Ok(self.evaluate(point_cloud)?.into_iter().map(|value| value != 0.0 && !value.is_nan()).collect())
+    }
+
+    fn bind(&self, node: &Node, point_cloud: &PointCloud) -> Result<Bound> {
+        Ok(match node {
+            Node::Number(value) => Bound::Number(*value),
+            Node::Variable(name) => Bound::Variable(resolve(name, point_cloud).ok_or_else(|| {
+                let mut available = vec!["x", "y", "z", "nx", "ny", "nz", "red", "green", "blue", "material_id"];
+                available.extend(point_cloud.metadata.attributes.iter().map(|a| a.name.as_str()));
+                ModelParserError::InvalidData(format!(
+                    "unknown name '{}' in '{}' (available: {})",
+                    name,
+                    self.source,
+                    available.join(", ")
+                ))
+            })?),
+            Node::Not(inner) => Bound::Not(Box::new(self.bind(inner, point_cloud)?)),
+            Node::Negate(inner) => Bound::Negate(Box::new(self.bind(inner, point_cloud)?)),
+            Node::Binary(operator, a, b) => Bound::Binary(
+                *operator,
+                Box::new(self.bind(a, point_cloud)?),
+                Box::new(self.bind(b, point_cloud)?),
+            ),
+        })
+    }
+}
+
+impl std::str::FromStr for Expression {
+    type Err = ModelParserError;
+
+    fn from_str(source: &str) -> Result<Self> {
+        Self::parse(source)
+    }
+}
+
+/// Generic attributes take precedence so clouds can override the built-in names
+fn resolve(name: &str, point_cloud: &PointCloud) -> Option<Variable> {
+    if let Some(index) = point_cloud.attribute_index(name) {
+        return Some(Variable::Attribute(index));
+    }
+    Some(match name {
+        "x" => Variable::Position(0),
+        "y" => Variable::Position(1),
+        "z" => Variable::Position(2),
+        "nx" => Variable::Normal(0),
+        "ny" => Variable::Normal(1),
+        "nz" => Variable::Normal(2),
+        "red" | "r" => Variable::Color(0),
+        "green" | "g" => Variable::Color(1),
+        "blue" | "b" => Variable::Color(2),
+        "alpha" | "a" => Variable::Alpha,
+        "material_id" => Variable::MaterialId,
+        _ => return None,
+    })
+}
+
+fn syntax_error(source: &str, message: &str) -> ModelParserError {
+    ModelParserError::InvalidData(format!("invalid expression '{}': {}", source, message))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Name(String),
+    Symbol(&'static str),
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Number(value) => write!(f, "{}", value),
+            Self::Name(name) => write!(f, "{}", name),
+            Self::Symbol(symbol) => write!(f, "{}", symbol),
+        }
+    }
+}
+
+/// Longest symbols first so "<=" is not read as "<"
+const SYMBOLS: &[&str] = &[
+    "||", "&&", "==", "!=", "<=", ">=", "<", ">", "+", "-", "*", "/", "!", "(", ")",
+];
+
+fn tokenize(source: &str) -> std::result::Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut rest = source.trim_start();
+
+    while let Some(c) = rest.chars().next() {
+        if c.is_ascii_digit() || c == '.' {
+            let end = rest
+                .char_indices()
+                .find(|&(i, c)| {
+                    let exponent_sign = (c == '-' || c == '+') && i > 0 && rest[..i].ends_with(['e', 'E']);
+                    !(c.is_ascii_digit() || c == '.' || c == 'e' || c == 'E' || exponent_sign)
+                })
+                .map_or(rest.len(), |(i, _)| i);
+            let number = rest[..end].parse().map_err(|_| format!("invalid number '{}'", &rest[..end]))?;
+            tokens.push(Token::Number(number));
+            rest = &rest[end..];
+        } else if c.is_alphabetic() || c == '_' {
+            let end = rest
+                .char_indices()
+                .find(|&(_, c)| !(c.is_alphanumeric() || c == '_'))
+                .map_or(rest.len(), |(i, _)| i);
+            let name = &rest[..end];
+            tokens.push(match name {
+                "and" => Token::Symbol("&&"),
+                "or" => Token::Symbol("||"),
+                "not" => Token::Symbol("!"),
+                _ => Token::Name(name.to_string()),
+            });
+            rest = &rest[end..];
+        } else {
+            let symbol = SYMBOLS
+                .iter()
+                .find(|symbol| rest.starts_with(**symbol))
+                .ok_or_else(|| format!("unexpected character '{}'", c))?;
+            tokens.push(Token::Symbol(symbol));
+            rest = &rest[symbol.len()..];
+        }
+        rest = rest.trim_start();
+    }
+
+    Ok(tokens)
+}
+
+/// Recursive descent, one method per precedence level
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek_symbol(&self, symbols: &[&'static str]) -> Option<&'static str> {
+        match self.tokens.get(self.position) {
+            Some(Token::Symbol(symbol)) if symbols.contains(symbol) => Some(symbol),
+            _ => None,
+        }
+    }
+
+    fn binary_level(
+        &mut self,
+        operators: &[(&'static str, Operator)],
+        next: fn(&mut Self) -> std::result::Result<Node, String>,
+    ) -> std::result::Result<Node, String> {
+        let mut node = next(self)?;
+        while let Some(&(_, operator)) = operators.iter().find(|(symbol, _)| self.peek_symbol(&[symbol]).is_some()) {
+            self.position += 1;
+            node = Node::Binary(operator, Box::new(node), Box::new(next(self)?));
+        }
+        Ok(node)
+    }
+
+    fn or(&mut self) -> std::result::Result<Node, String> {
+        self.binary_level(&[("||", Operator::Or)], Self::and)
+    }
+
+    fn and(&mut self) -> std::result::Result<Node, String> {
+        self.binary_level(&[("&&", Operator::And)], Self::comparison)
+    }
+
+    fn comparison(&mut self) -> std::result::Result<Node, String> {
+        self.binary_level(
+            &[
+                ("==", Operator::Equal),
+                ("!=", Operator::NotEqual),
+                ("<=", Operator::LessEqual),
+                (">=", Operator::GreaterEqual),
+                ("<", Operator::Less),
+                (">", Operator::Greater),
+            ],
+            Self::sum,
+        )
+    }
+
+    fn sum(&mut self) -> std::result::Result<Node, String> {
+        self.binary_level(&[("+", Operator::Add), ("-", Operator::Subtract)], Self::product)
+    }
+
+    fn product(&mut self) -> std::result::Result<Node, String> {
+        self.binary_level(&[("*", Operator::Multiply), ("/", Operator::Divide)], Self::unary)
+    }
+
+    fn unary(&mut self) -> std::result::Result<Node, String> {
+        match self.peek_symbol(&["!", "-"]) {
+            Some("!") => {
+                self.position += 1;
+                Ok(Node::Not(Box::new(self.unary()?)))
+            }
+            Some(_) => {
+                self.position += 1;
+                Ok(Node::Negate(Box::new(self.unary()?)))
+            }
+            None => self.primary(),
+        }
+    }
+
+    fn primary(&mut self) -> std::result::Result<Node, String> {
+        let token = self.tokens.get(self.position).cloned().ok_or("unexpected end")?;
+        self.position += 1;
+        match token {
+            Token::Number(value) => Ok(Node::Number(value)),
+            Token::Name(name) => Ok(Node::Variable(name)),
+            Token::Symbol("(") => {
+                let node = self.or()?;
+                if self.peek_symbol(&[")"]).is_none() {
+                    return Err("missing ')'".to_string());
+                }
+                self.position += 1;
+                Ok(node)
+            }
+            token => Err(format!("unexpected '{}'", token)),
+        }
+    }
+}
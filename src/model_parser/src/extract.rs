@@ -0,0 +1,135 @@
+use crate::{
+    ept::{EptMetadata, GEOMETRIC_ERROR_FILE, OctreeKey, TILE_INDEX_FILE, TileIndex},
+    error::{ModelParserError, Result},
+    lod::EptHierarchy,
+};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Nodes of an EPT dataset to copy into a smaller one; nodes must pass every limit that is set
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct EptExtraction {
+    /// Deepest octree level kept (0 keeps the root node only)
+    pub max_depth: Option<u32>,
+
+    /// Keep nodes intersecting these bounds: [minx, miny, minz, maxx, maxy, maxz]
+    pub bounds: Option<[f64; 6]>,
+}
+
+/// Summary of an extraction
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct ExtractionReport {
+    /// Nodes copied, and the nodes of the source dataset
+    pub nodes: usize,
+    pub source_nodes: usize,
+
+    /// Points in the copied nodes, and in the source dataset
+    pub points: u64,
+    pub source_points: u64,
+}
+
+impl EptExtraction {
+    pub fn is_empty(&self) -> bool {
+        self.max_depth.is_none() && self.bounds.is_none()
+    }
+
+    /// Whether the node with `node_bounds` at `key` is kept
+    fn keeps(&self, key: &OctreeKey, node_bounds: &[f64; 6]) -> bool {
+        self.max_depth.is_none_or(|depth| key.depth <= depth)
+            && self.bounds.is_none_or(|bounds| intersects(&bounds, node_bounds))
+    }
+}
+
+fn intersects(a: &[f64; 6], b: &[f64; 6]) -> bool {
+    (0..3).all(|i| a[i] <= b[i + 3] && b[i] <= a[i + 3])
+}
+
+const EMPTY: [f64; 6] = [f64::MAX, f64::MAX, f64::MAX, f64::MIN, f64::MIN, f64::MIN];
+
+fn union(a: [f64; 6], b: [f64; 6]) -> [f64; 6] {
+    [0, 1, 2, 3, 4, 5].map(|i| if i < 3 { a[i].min(b[i]) } else { a[i].max(b[i]) })
+}
+
+/// Part of `bounds` inside `region`
+fn clip(bounds: [f64; 6], region: &[f64; 6]) -> [f64; 6] {
+    [0, 1, 2, 3, 4, 5].map(|i| if i < 3 { bounds[i].max(region[i]) } else { bounds[i].min(region[i]) })
+}
+
+/// Copy the selected nodes of the EPT dataset in `input` into a new dataset in `output`.
+/// Tiles are copied whole, so a region keeps every point of the nodes it touches; nodes keep
+/// their keys and the octree its bounds. Parents of kept nodes are always kept, since a node
+/// lies within its parent and is deeper than it
+pub fn extract(input: &Path, output: &Path, extraction: &EptExtraction) -> Result<ExtractionReport> {
+    if std::path::absolute(input)? == std::path::absolute(output)? {
+        return Err(ModelParserError::InvalidData("extraction output must differ from the input dataset".to_string()));
+    }
+
+    let hierarchy = EptHierarchy::load(input)?;
+    let mut metadata: EptMetadata = serde_json::from_str(&std::fs::read_to_string(input.join("ept.json"))?)?;
+
+    let nodes: BTreeMap<String, u64> = hierarchy
+        .nodes
+        .iter()
+        .filter(|(key, _)| extraction.keeps(key, &key.bounds(&hierarchy.bounds)))
+        .map(|(key, &count)| (key.to_path_string(), count))
+        .collect();
+    if nodes.is_empty() {
+        return Err(ModelParserError::InvalidData(format!(
+            "no nodes of {:?} match the extraction limits",
+            input
+        )));
+    }
+
+    let extension = match metadata.data_type.as_str() {
+        "laszip" => "laz",
+        "zstandard" => "zst",
+        _ => "bin",
+    };
+    std::fs::create_dir_all(output.join("ept-data"))?;
+    std::fs::create_dir_all(output.join("ept-hierarchy"))?;
+    nodes.par_iter().try_for_each(|(key, _)| -> Result<()> {
+        let name = format!("{}.{}", key, extension);
+        std::fs::copy(input.join("ept-data").join(&name), output.join("ept-data").join(&name))?;
+        Ok(())
+    })?;
+
+    let counts: BTreeMap<&String, i64> = nodes.iter().map(|(key, &count)| (key, count as i64)).collect();
+    std::fs::write(output.join("ept-hierarchy").join("0-0-0-0.json"), serde_json::to_string_pretty(&counts)?)?;
+    if !hierarchy.errors.is_empty() {
+        let errors: BTreeMap<String, f64> = hierarchy
+            .errors
+            .iter()
+            .map(|(key, &error)| (key.to_path_string(), error))
+            .filter(|(key, _)| nodes.contains_key(key))
+            .collect();
+        std::fs::write(output.join(GEOMETRIC_ERROR_FILE), serde_json::to_string(&errors)?)?;
+    }
+
+    let report = ExtractionReport {
+        nodes: nodes.len(),
+        source_nodes: hierarchy.nodes.len(),
+        points: nodes.values().sum(),
+        source_points: metadata.points,
+    };
+    metadata.points = report.points;
+
+    // Kept points lie within the kept nodes; the tile index, when there is one, gives their
+    // exact extent
+    let kept = nodes
+        .keys()
+        .filter_map(|key| OctreeKey::from_path_string(key))
+        .map(|key| key.bounds(&hierarchy.bounds))
+        .fold(EMPTY, union);
+    metadata.bounds_conforming = clip(metadata.bounds_conforming, &kept);
+    metadata.robust_bounds = metadata.robust_bounds.map(|bounds| clip(bounds, &kept));
+    if let Ok(mut index) = TileIndex::load(input) {
+        index.tiles.retain(|tile| nodes.contains_key(&tile.key));
+        metadata.bounds_conforming = index.tiles.iter().map(|tile| tile.bounds).fold(EMPTY, union);
+        std::fs::write(output.join(TILE_INDEX_FILE), serde_json::to_string(&index)?)?;
+    }
+    std::fs::write(output.join("ept.json"), serde_json::to_string_pretty(&metadata)?)?;
+
+    Ok(report)
+}
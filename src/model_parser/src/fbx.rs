@@ -0,0 +1,335 @@
+use crate::{
+    config::PointCloudConfig,
+    error::{ModelParserError, Result},
+    mesh::Mesh,
+};
+use flate2::read::ZlibDecoder;
+use glam::Vec3;
+use std::io::Read;
+use std::path::Path;
+
+const MAGIC: &[u8] = b"Kaydara FBX Binary  \0";
+
+/// Property value of an FBX node record
+#[derive(Debug, Clone)]
+enum Property {
+    Integer(i64),
+    Float(f64),
+    String(String),
+    Floats(Vec<f64>),
+    Integers(Vec<i64>),
+    Other,
+}
+
+impl Property {
+    fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    fn as_floats(&self) -> Option<Vec<f64>> {
+        match self {
+            Self::Floats(values) => Some(values.clone()),
+            Self::Integers(values) => Some(values.iter().map(|&v| v as f64).collect()),
+            Self::Float(value) => Some(vec![*value]),
+            _ => None,
+        }
+    }
+
+    fn as_integers(&self) -> Option<Vec<i64>> {
+        match self {
+            Self::Integers(values) => Some(values.clone()),
+            Self::Integer(value) => Some(vec![*value]),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct Node {
+    name: String,
+    properties: Vec<Property>,
+    children: Vec<Node>,
+}
+
+impl Node {
+    fn child(&self, name: &str) -> Option<&Node> {
+        self.children.iter().find(|child| child.name == name)
+    }
+
+    fn children<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Node> {
+        self.children.iter().filter(move |child| child.name == name)
+    }
+
+    /// First property of a child node, e.g. the array of `Vertices`
+    fn value(&self, name: &str) -> Option<&Property> {
+        self.child(name)?.properties.first()
+    }
+}
+
+/// Per-corner layer data (normals or colors) with its FBX mapping
+struct Layer {
+    values: Vec<f64>,
+    components: usize,
+    indices: Option<Vec<i64>>,
+    by_polygon_vertex: bool,
+}
+
+impl Layer {
+    fn read(element: &Node, values: &str, indices: &str, components: usize) -> Option<Self> {
+        let mapping = element.value("MappingInformationType").and_then(Property::as_str).unwrap_or("");
+        let reference = element.value("ReferenceInformationType").and_then(Property::as_str).unwrap_or("");
+        let by_polygon_vertex = match mapping {
+            "ByPolygonVertex" => true,
+            "ByVertice" | "ByVertex" | "ByControlPoint" => false,
+            _ => return None,
+        };
+        Some(Self {
+            values: element.value(values)?.as_floats()?,
+            components,
+            indices: if reference == "IndexToDirect" {
+                element.value(indices)?.as_integers()
+            } else {
+                None
+            },
+            by_polygon_vertex,
+        })
+    }
+
+    /// Value for a polygon corner (`corner`) of control point `vertex`
+    fn get(&self, corner: usize, vertex: usize) -> Option<&[f64]> {
+        let slot = if self.by_polygon_vertex { corner } else { vertex };
+        let index = match &self.indices {
+            Some(indices) => usize::try_from(*indices.get(slot)?).ok()?,
+            None => slot,
+        };
+        self.values.get(index * self.components..(index + 1) * self.components)
+    }
+}
+
+/// Load the geometry of a binary FBX file (version 7.x): control points, normals and
+/// vertex colors of every mesh. Geometry stays in the space of each mesh; model transforms
+/// and materials are not applied. ASCII FBX files should be re-exported as binary
+pub fn load(path: &Path, config: &PointCloudConfig) -> Result<Mesh> {
+    let bytes = std::fs::read(path)?;
+    let invalid = |message: &str| ModelParserError::InvalidData(format!("{}: {}", path.display(), message));
+
+    if !bytes.starts_with(MAGIC) || bytes.len() < 27 {
+        return Err(ModelParserError::UnsupportedFormat(
+            "fbx (only binary FBX files are supported; re-export ASCII files as binary)".to_string(),
+        ));
+    }
+    let version = u32::from_le_bytes([bytes[23], bytes[24], bytes[25], bytes[26]]);
+
+    let mut reader = Reader {
+        bytes: &bytes,
+        position: 27,
+        wide: version >= 7500,
+    };
+    let mut root = Node::default();
+    while let Some(node) = reader.node().map_err(|message| invalid(&message))? {
+        root.children.push(node);
+    }
+
+    let objects = root.child("Objects").ok_or_else(|| invalid("missing Objects section"))?;
+    let mut mesh = Mesh::new();
+    let mut has_colors = false;
+
+    let geometries = objects
+        .children("Geometry")
+        .filter(|geometry| geometry.properties.get(2).and_then(Property::as_str) == Some("Mesh"));
+    for geometry in geometries {
+        let positions: Vec<Vec3> = geometry
+            .value("Vertices")
+            .and_then(Property::as_floats)
+            .unwrap_or_default()
+            .chunks_exact(3)
+            .map(|p| Vec3::new(p[0] as f32, p[1] as f32, p[2] as f32))
+            .collect();
+        let polygon_indices = geometry
+            .value("PolygonVertexIndex")
+            .and_then(Property::as_integers)
+            .unwrap_or_default();
+        let normals = geometry
+            .child("LayerElementNormal")
+            .and_then(|element| Layer::read(element, "Normals", "NormalsIndex", 3));
+        let colors = geometry
+            .child("LayerElementColor")
+            .and_then(|element| Layer::read(element, "Colors", "ColorIndex", 4));
+        has_colors |= colors.is_some();
+
+        // A negative index (stored as -index - 1) closes a polygon
+        let mut polygon: Vec<(usize, usize)> = Vec::new();
+        for (corner, &index) in polygon_indices.iter().enumerate() {
+            let vertex = if index < 0 { -index - 1 } else { index } as usize;
+            if vertex >= positions.len() {
+                return Err(invalid("polygon vertex index out of range"));
+            }
+            polygon.push((corner, vertex));
+            if index >= 0 {
+                continue;
+            }
+
+            if polygon.len() >= 3 {
+                let [a, b, c] = [0, 1, 2].map(|i| positions[polygon[i].1]);
+                let face_normal = (b - a).cross(c - a).normalize_or_zero();
+
+                let first = mesh.vertices.len();
+                for &(corner, vertex) in &polygon {
+                    mesh.vertices.push(positions[vertex]);
+                    if config.include_normals {
+                        let normal = normals
+                            .as_ref()
+                            .and_then(|layer| layer.get(corner, vertex))
+                            .map_or(face_normal, |n| Vec3::new(n[0] as f32, n[1] as f32, n[2] as f32));
+                        mesh.normals.push(normal);
+                    }
+                    if config.include_colors {
+                        let color = colors
+                            .as_ref()
+                            .and_then(|layer| layer.get(corner, vertex))
+                            .map_or(Vec3::ONE, |c| Vec3::new(c[0] as f32, c[1] as f32, c[2] as f32));
+                        mesh.colors.push(color);
+                    }
+                }
+                for i in 1..polygon.len() - 1 {
+                    mesh.indices.extend([first, first + i, first + i + 1]);
+                }
+            }
+            polygon.clear();
+        }
+    }
+
+    if mesh.indices.is_empty() {
+        return Err(ModelParserError::NoMeshData);
+    }
+    mesh.has_source_colors = has_colors && config.include_colors;
+
+    Ok(mesh)
+}
+
+/// Reads node records; offsets and counts are 64-bit from version 7.5
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+    wide: bool,
+}
+
+impl Reader<'_> {
+    fn take(&mut self, count: usize) -> std::result::Result<&[u8], String> {
+        let slice = self
+            .bytes
+            .get(self.position..self.position + count)
+            .ok_or("unexpected end of file")?;
+        self.position += count;
+        Ok(slice)
+    }
+
+    fn u8(&mut self) -> std::result::Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> std::result::Result<u32, String> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap_or_default()))
+    }
+
+    fn offset(&mut self) -> std::result::Result<u64, String> {
+        if self.wide {
+            Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap_or_default()))
+        } else {
+            self.u32().map(u64::from)
+        }
+    }
+
+    /// Next node record, `None` at a null record ending a node list
+    fn node(&mut self) -> std::result::Result<Option<Node>, String> {
+        if self.position >= self.bytes.len() {
+            return Ok(None);
+        }
+        let end = self.offset()? as usize;
+        let property_count = self.offset()?;
+        let _property_bytes = self.offset()?;
+        let name_length = self.u8()? as usize;
+        if end == 0 {
+            return Ok(None);
+        }
+        if end > self.bytes.len() || end < self.position {
+            return Err("invalid node record".to_string());
+        }
+
+        let name = String::from_utf8_lossy(self.take(name_length)?).into_owned();
+        let properties = (0..property_count).map(|_| self.property()).collect::<std::result::Result<_, _>>()?;
+        let mut children = Vec::new();
+        while self.position < end {
+            match self.node()? {
+                Some(child) => children.push(child),
+                None => break,
+            }
+        }
+        self.position = end;
+
+        Ok(Some(Node {
+            name,
+            properties,
+            children,
+        }))
+    }
+
+    fn property(&mut self) -> std::result::Result<Property, String> {
+        let code = self.u8()?;
+        Ok(match code {
+            b'C' => Property::Integer(self.u8()? as i64),
+            b'Y' => Property::Integer(i16::from_le_bytes(self.take(2)?.try_into().unwrap_or_default()) as i64),
+            b'I' => Property::Integer(self.u32()? as i32 as i64),
+            b'L' => Property::Integer(i64::from_le_bytes(self.take(8)?.try_into().unwrap_or_default())),
+            b'F' => Property::Float(f32::from_le_bytes(self.take(4)?.try_into().unwrap_or_default()) as f64),
+            b'D' => Property::Float(f64::from_le_bytes(self.take(8)?.try_into().unwrap_or_default())),
+            b'S' | b'R' => {
+                let length = self.u32()? as usize;
+                let data = self.take(length)?;
+                if code == b'S' {
+                    // Names are stored as "Name\0\x01Class"; the class is not needed
+                    let text = String::from_utf8_lossy(data);
+                    Property::String(text.split("\0\u{1}").next().unwrap_or_default().to_string())
+                } else {
+                    Property::Other
+                }
+            }
+            b'f' | b'd' | b'i' | b'l' | b'b' => {
+                let length = self.u32()? as usize;
+                let encoding = self.u32()?;
+                let stored = self.u32()? as usize;
+                let raw = self.take(stored)?;
+                let element_size = match code {
+                    b'f' | b'i' => 4,
+                    b'd' | b'l' => 8,
+                    _ => 1,
+                };
+                let data = if encoding == 1 {
+                    let mut data = Vec::with_capacity(length * element_size);
+                    ZlibDecoder::new(raw)
+                        .read_to_end(&mut data)
+                        .map_err(|e| format!("corrupt compressed array: {}", e))?;
+                    data
+                } else {
+                    raw.to_vec()
+                };
+                if data.len() < length * element_size {
+                    return Err("truncated array property".to_string());
+                }
+
+                let elements = data.chunks_exact(element_size).take(length);
+                match code {
+                    b'f' => Property::Floats(elements.map(|e| f32::from_le_bytes(e.try_into().unwrap_or_default()) as f64).collect()),
+                    b'd' => Property::Floats(elements.map(|e| f64::from_le_bytes(e.try_into().unwrap_or_default())).collect()),
+                    b'i' => Property::Integers(elements.map(|e| i32::from_le_bytes(e.try_into().unwrap_or_default()) as i64).collect()),
+                    b'l' => Property::Integers(elements.map(|e| i64::from_le_bytes(e.try_into().unwrap_or_default())).collect()),
+                    _ => Property::Integers(elements.map(|e| e[0] as i64).collect()),
+                }
+            }
+            other => return Err(format!("unknown property type '{}'", other as char)),
+        })
+    }
+}
@@ -0,0 +1,332 @@
+use crate::{
+    error::{ModelParserError, Result},
+    expression::Expression,
+    point_cloud::PointCloud,
+    spatial::KdTree,
+};
+use glam::Vec3;
+use rayon::prelude::*;
+use std::collections::{BTreeMap, HashSet};
+
+/// A processing stage applied to a finished point cloud
+pub trait PointFilter: Send + Sync {
+    /// Name used in pipeline specs and logs
+    fn name(&self) -> &str;
+
+    fn apply(&self, point_cloud: &mut PointCloud) -> Result<()>;
+}
+
+/// Builds a filter from the argument part of a spec ("0.05" in "voxel:0.05")
+pub type FilterFactory = fn(&str) -> Result<Box<dyn PointFilter>>;
+
+/// Filters available by name to `--filter` specs
+pub struct FilterRegistry {
+    factories: BTreeMap<String, FilterFactory>,
+}
+
+impl Default for FilterRegistry {
+    /// Registry with the built-in filters
+    fn default() -> Self {
+        let mut registry = Self::empty();
+        registry.register("voxel", VoxelFilter::from_args);
+        registry.register("sor", OutlierFilter::from_args);
+        registry.register("crop", CropFilter::from_args);
+        registry.register("cylinder", CylinderFilter::from_args);
+        registry.register("expr", ExpressionFilter::from_args);
+        registry
+    }
+}
+
+impl FilterRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registry without any filters
+    pub fn empty() -> Self {
+        Self {
+            factories: BTreeMap::new(),
+        }
+    }
+
+    /// Add or replace a filter
+    pub fn register(&mut self, name: &str, factory: FilterFactory) {
+        self.factories.insert(name.to_lowercase(), factory);
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.factories.keys().map(String::as_str)
+    }
+
+    /// Create a filter from a "name:args" spec
+    pub fn create(&self, spec: &str) -> Result<Box<dyn PointFilter>> {
+        let (name, args) = spec.split_once(':').unwrap_or((spec, ""));
+        let factory = self.factories.get(&name.trim().to_lowercase()).ok_or_else(|| {
+            ModelParserError::InvalidData(format!(
+                "unknown filter '{}' (available: {})",
+                name,
+                self.names().collect::<Vec<_>>().join(", ")
+            ))
+        })?;
+        factory(args.trim())
+    }
+
+    /// Create a pipeline from specs, in order
+    pub fn pipeline<S: AsRef<str>>(&self, specs: &[S]) -> Result<FilterPipeline> {
+        let filters = specs.iter().map(|spec| self.create(spec.as_ref())).collect::<Result<_>>()?;
+        Ok(FilterPipeline { filters })
+    }
+}
+
+/// Filters applied one after another
+#[derive(Default)]
+pub struct FilterPipeline {
+    filters: Vec<Box<dyn PointFilter>>,
+}
+
+impl FilterPipeline {
+    pub fn push(&mut self, filter: Box<dyn PointFilter>) {
+        self.filters.push(filter);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.filters.is_empty()
+    }
+
+    /// Apply every filter, returning (name, points before, points after) per stage
+    pub fn apply(&self, point_cloud: &mut PointCloud) -> Result<Vec<(String, usize, usize)>> {
+        let mut stages = Vec::with_capacity(self.filters.len());
+        for filter in &self.filters {
+            let before = point_cloud.points.len();
+            filter.apply(point_cloud)?;
+            point_cloud.update_metadata();
+            stages.push((filter.name().to_string(), before, point_cloud.points.len()));
+        }
+        Ok(stages)
+    }
+}
+
+/// Parse comma-separated numbers, requiring `count` of them
+pub(crate) fn parse_numbers(filter: &str, args: &str, count: usize) -> Result<Vec<f32>> {
+    let values: Vec<f32> = args
+        .split(',')
+        .map(|v| v.trim().parse::<f32>())
+        .collect::<std::result::Result<_, _>>()
+        .map_err(|_| ModelParserError::InvalidData(format!("{}: invalid arguments '{}'", filter, args)))?;
+
+    if values.len() != count {
+        return Err(ModelParserError::InvalidData(format!(
+            "{}: expected {} values but got '{}'",
+            filter, count, args
+        )));
+    }
+    Ok(values)
+}
+
+/// Keep the first point in every cubic voxel of the given edge length
+pub struct VoxelFilter {
+    pub size: f32,
+}
+
+impl VoxelFilter {
+    fn from_args(args: &str) -> Result<Box<dyn PointFilter>> {
+        let size = parse_numbers("voxel", args, 1)?[0];
+        if size <= 0.0 {
+            return Err(ModelParserError::InvalidData("voxel: size must be positive".to_string()));
+        }
+        Ok(Box::new(Self { size }))
+    }
+}
+
+impl PointFilter for VoxelFilter {
+    fn name(&self) -> &str {
+        "voxel"
+    }
+
+    fn apply(&self, point_cloud: &mut PointCloud) -> Result<()> {
+        let mut occupied = HashSet::new();
+        let keep: Vec<bool> = point_cloud
+            .points
+            .iter()
+            .map(|point| {
+                let cell = (Vec3::from(point.position) / self.size).floor().as_ivec3();
+                occupied.insert(cell)
+            })
+            .collect();
+        point_cloud.retain_by_mask(&keep);
+        Ok(())
+    }
+}
+
+/// Statistical outlier removal: drop points whose mean distance to their `k` nearest
+/// neighbours exceeds the global mean by more than `std_multiplier` standard deviations
+pub struct OutlierFilter {
+    pub k: usize,
+    pub std_multiplier: f32,
+}
+
+impl OutlierFilter {
+    fn from_args(args: &str) -> Result<Box<dyn PointFilter>> {
+        let values = parse_numbers("sor", args, 2)?;
+        if values[0] < 1.0 {
+            return Err(ModelParserError::InvalidData("sor: k must be at least 1".to_string()));
+        }
+        Ok(Box::new(Self {
+            k: values[0] as usize,
+            std_multiplier: values[1],
+        }))
+    }
+}
+
+impl PointFilter for OutlierFilter {
+    fn name(&self) -> &str {
+        "sor"
+    }
+
+    fn apply(&self, point_cloud: &mut PointCloud) -> Result<()> {
+        if point_cloud.points.len() <= self.k {
+            return Ok(());
+        }
+
+        let tree = KdTree::from_points(&point_cloud.points);
+        let mean_distances: Vec<f32> = point_cloud
+            .points
+            .par_iter()
+            .map(|point| {
+                // The nearest hit is the point itself
+                let neighbours = tree.nearest(Vec3::from(point.position), self.k + 1);
+                neighbours.iter().skip(1).map(|&(_, d)| d).sum::<f32>() / self.k as f32
+            })
+            .collect();
+
+        let n = mean_distances.len() as f32;
+        let mean = mean_distances.iter().sum::<f32>() / n;
+        let std_dev = (mean_distances.iter().map(|d| (d - mean).powi(2)).sum::<f32>() / n).sqrt();
+        let threshold = mean + self.std_multiplier * std_dev;
+
+        let keep: Vec<bool> = mean_distances.iter().map(|&d| d <= threshold).collect();
+        point_cloud.retain_by_mask(&keep);
+        Ok(())
+    }
+}
+
+/// Keep points inside an axis-aligned box
+pub struct CropFilter {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl CropFilter {
+    fn from_args(args: &str) -> Result<Box<dyn PointFilter>> {
+        let v = parse_numbers("crop", args, 6)?;
+        let (a, b) = (Vec3::new(v[0], v[1], v[2]), Vec3::new(v[3], v[4], v[5]));
+        Ok(Box::new(Self {
+            min: a.min(b),
+            max: a.max(b),
+        }))
+    }
+}
+
+impl PointFilter for CropFilter {
+    fn name(&self) -> &str {
+        "crop"
+    }
+
+    fn apply(&self, point_cloud: &mut PointCloud) -> Result<()> {
+        let keep: Vec<bool> = point_cloud
+            .points
+            .par_iter()
+            .map(|point| {
+                let p = Vec3::from(point.position);
+                p.cmpge(self.min).all() && p.cmple(self.max).all()
+            })
+            .collect();
+        point_cloud.retain_by_mask(&keep);
+        Ok(())
+    }
+}
+
+/// Keep points inside a cylinder: within `radius` of the axis through `base` along `axis`,
+/// and between `min_height` and `max_height` measured along the axis from `base`
+pub struct CylinderFilter {
+    pub base: Vec3,
+    pub axis: Vec3,
+    pub radius: f32,
+    pub min_height: f32,
+    pub max_height: f32,
+}
+
+impl CylinderFilter {
+    /// "x,y,z,radius,min_height,max_height" for a vertical (Y) axis, or
+    /// "x,y,z,ax,ay,az,radius,min_height,max_height" for any axis direction
+    fn from_args(args: &str) -> Result<Box<dyn PointFilter>> {
+        let count = if args.split(',').count() == 9 { 9 } else { 6 };
+        let v = parse_numbers("cylinder", args, count)?;
+        let (axis, rest) = if count == 9 {
+            (Vec3::new(v[3], v[4], v[5]), &v[6..])
+        } else {
+            (Vec3::Y, &v[3..])
+        };
+
+        if axis.length_squared() == 0.0 {
+            return Err(ModelParserError::InvalidData("cylinder: axis must not be zero".to_string()));
+        }
+        if rest[0] <= 0.0 {
+            return Err(ModelParserError::InvalidData("cylinder: radius must be positive".to_string()));
+        }
+        Ok(Box::new(Self {
+            base: Vec3::new(v[0], v[1], v[2]),
+            axis: axis.normalize(),
+            radius: rest[0],
+            min_height: rest[1].min(rest[2]),
+            max_height: rest[1].max(rest[2]),
+        }))
+    }
+}
+
+impl PointFilter for CylinderFilter {
+    fn name(&self) -> &str {
+        "cylinder"
+    }
+
+    fn apply(&self, point_cloud: &mut PointCloud) -> Result<()> {
+        let radius_squared = self.radius * self.radius;
+        let keep: Vec<bool> = point_cloud
+            .points
+            .par_iter()
+            .map(|point| {
+                let relative = Vec3::from(point.position) - self.base;
+                let height = relative.dot(self.axis);
+                let radial = relative - self.axis * height;
+                (self.min_height..=self.max_height).contains(&height) && radial.length_squared() <= radius_squared
+            })
+            .collect();
+        point_cloud.retain_by_mask(&keep);
+        Ok(())
+    }
+}
+
+/// Keep points for which an `Expression` holds, e.g. "classification != 7 && z > 10"
+pub struct ExpressionFilter {
+    pub expression: Expression,
+}
+
+impl ExpressionFilter {
+    fn from_args(args: &str) -> Result<Box<dyn PointFilter>> {
+        Ok(Box::new(Self {
+            expression: Expression::parse(args)?,
+        }))
+    }
+}
+
+impl PointFilter for ExpressionFilter {
+    fn name(&self) -> &str {
+        "expr"
+    }
+
+    fn apply(&self, point_cloud: &mut PointCloud) -> Result<()> {
+        let keep = self.expression.select(point_cloud)?;
+        point_cloud.retain_by_mask(&keep);
+        Ok(())
+    }
+}
@@ -0,0 +1,256 @@
+use crate::{
+    config::PointCloudConfig,
+    error::Result,
+    mesh::Mesh,
+    parser::ModelParser,
+};
+use glam::Vec3;
+use serde_json::json;
+use std::path::{Path, PathBuf};
+
+/// Procedural test models for golden-file regression tests
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fixture {
+    /// UV sphere of radius 1 with smooth normals, colored by normal
+    Sphere,
+    /// Axis-aligned cube from -1 to 1 with flat normals and one color per face
+    Cube,
+    /// High-poly sphere displaced by layered sine noise, similar in density to scanned models
+    Blob,
+    /// Zero-area, repeated-index and sliver triangles plus an unreferenced vertex
+    Degenerate,
+}
+
+impl Fixture {
+    pub const ALL: [Fixture; 4] = [Fixture::Sphere, Fixture::Cube, Fixture::Blob, Fixture::Degenerate];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Fixture::Sphere => "sphere",
+            Fixture::Cube => "cube",
+            Fixture::Blob => "blob",
+            Fixture::Degenerate => "degenerate",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|f| f.name() == name)
+    }
+
+    /// Build the fixture geometry
+    pub fn mesh(self) -> Mesh {
+        match self {
+            Fixture::Sphere => sphere(32, 16, |_| 1.0),
+            Fixture::Cube => cube(),
+            Fixture::Blob => sphere(256, 128, |n| {
+                1.0 + 0.08 * (5.0 * n.x).sin() * (4.0 * n.y).cos()
+                    + 0.04 * (11.0 * n.z + 3.0 * n.x).sin()
+                    + 0.02 * (23.0 * n.y).sin()
+            }),
+            Fixture::Degenerate => degenerate(),
+        }
+    }
+}
+
+/// Write each fixture as `<name>.gltf`/`<name>.bin` into `dir`, together with a reference
+/// cloud `<name>.expected.json` sampled from the written file with `config` (which should be seeded)
+pub fn generate(dir: &Path, fixtures: &[Fixture], config: &PointCloudConfig) -> Result<Vec<PathBuf>> {
+    std::fs::create_dir_all(dir)?;
+
+    let mut written = Vec::new();
+    for &fixture in fixtures {
+        let gltf_path = dir.join(format!("{}.gltf", fixture.name()));
+        written.extend(write_gltf(&fixture.mesh(), &gltf_path)?);
+
+        // Read back through the normal loader so the reference covers the whole pipeline
+        let expected_path = dir.join(format!("{}.expected.json", fixture.name()));
+        ModelParser::parse_file(&gltf_path, config)?.save_to_file(&expected_path)?;
+        written.push(expected_path);
+    }
+    Ok(written)
+}
+
+/// Write a mesh as a glTF 2.0 file with an external binary buffer next to it.
+/// Returns the .gltf and .bin paths
+pub fn write_gltf(mesh: &Mesh, path: &Path) -> Result<Vec<PathBuf>> {
+    let bin_path = path.with_extension("bin");
+    let bin_name = bin_path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("buffer.bin")
+        .to_string();
+
+    let mut buffer = Vec::new();
+    let mut views = Vec::new();
+    let mut accessors = Vec::new();
+    let mut attributes = serde_json::Map::new();
+
+    let mut push_vec3 = |name: &str, values: &[Vec3], buffer: &mut Vec<u8>| {
+        let offset = buffer.len();
+        for value in values {
+            for component in value.to_array() {
+                buffer.extend_from_slice(&component.to_le_bytes());
+            }
+        }
+        let (min, max) = values.iter().fold(
+            (Vec3::splat(f32::MAX), Vec3::splat(f32::MIN)),
+            |(min, max), v| (min.min(*v), max.max(*v)),
+        );
+
+        attributes.insert(name.to_string(), json!(accessors.len()));
+        accessors.push(json!({
+            "bufferView": views.len(),
+            "componentType": 5126,
+            "count": values.len(),
+            "type": "VEC3",
+            "min": min.to_array(),
+            "max": max.to_array(),
+        }));
+        views.push(json!({
+            "buffer": 0,
+            "byteOffset": offset,
+            "byteLength": buffer.len() - offset,
+            "target": 34962,
+        }));
+    };
+
+    push_vec3("POSITION", &mesh.vertices, &mut buffer);
+    if mesh.normals.len() == mesh.vertices.len() {
+        push_vec3("NORMAL", &mesh.normals, &mut buffer);
+    }
+    if mesh.colors.len() == mesh.vertices.len() {
+        push_vec3("COLOR_0", &mesh.colors, &mut buffer);
+    }
+
+    let index_offset = buffer.len();
+    for &index in &mesh.indices {
+        buffer.extend_from_slice(&(index as u32).to_le_bytes());
+    }
+    let indices_accessor = accessors.len();
+    accessors.push(json!({
+        "bufferView": views.len(),
+        "componentType": 5125,
+        "count": mesh.indices.len(),
+        "type": "SCALAR",
+    }));
+    views.push(json!({
+        "buffer": 0,
+        "byteOffset": index_offset,
+        "byteLength": buffer.len() - index_offset,
+        "target": 34963,
+    }));
+
+    let name = path.file_stem().and_then(|n| n.to_str()).unwrap_or("fixture");
+    let document = json!({
+        "asset": { "version": "2.0", "generator": "model_parser fixtures" },
+        "scene": 0,
+        "scenes": [{ "nodes": [0] }],
+        "nodes": [{ "mesh": 0, "name": name }],
+        "meshes": [{
+            "name": name,
+            "primitives": [{ "attributes": attributes, "indices": indices_accessor, "material": 0 }],
+        }],
+        "materials": [{ "name": format!("{}_material", name) }],
+        "buffers": [{ "uri": bin_name, "byteLength": buffer.len() }],
+        "bufferViews": views,
+        "accessors": accessors,
+    });
+
+    std::fs::write(&bin_path, &buffer)?;
+    std::fs::write(path, serde_json::to_string_pretty(&document)?)?;
+    Ok(vec![path.to_path_buf(), bin_path])
+}
+
+/// UV sphere with `radius(direction)` giving the distance from the center per vertex
+fn sphere(segments: usize, rings: usize, radius: impl Fn(Vec3) -> f32) -> Mesh {
+    let mut mesh = Mesh::new();
+
+    for ring in 0..=rings {
+        let theta = std::f32::consts::PI * ring as f32 / rings as f32;
+        for segment in 0..=segments {
+            let phi = std::f32::consts::TAU * segment as f32 / segments as f32;
+            let direction = Vec3::new(theta.sin() * phi.cos(), theta.cos(), theta.sin() * phi.sin());
+            mesh.vertices.push(direction * radius(direction));
+            mesh.colors.push(direction * 0.5 + Vec3::splat(0.5));
+        }
+    }
+
+    let stride = segments + 1;
+    for ring in 0..rings {
+        for segment in 0..segments {
+            let a = ring * stride + segment;
+            let b = a + stride;
+            mesh.indices.extend([a, a + 1, b, a + 1, b + 1, b]);
+        }
+    }
+
+    mesh.normals = vertex_normals(&mesh);
+    mesh
+}
+
+/// Area-weighted vertex normals accumulated from the faces
+fn vertex_normals(mesh: &Mesh) -> Vec<Vec3> {
+    let mut normals = vec![Vec3::ZERO; mesh.vertices.len()];
+    for triangle in 0..mesh.triangle_count() {
+        let [v0, v1, v2] = mesh.triangle(triangle);
+        let weighted = (v1 - v0).cross(v2 - v0);
+        for corner in 0..3 {
+            normals[mesh.indices[triangle * 3 + corner]] += weighted;
+        }
+    }
+    normals.iter().map(|n| n.normalize_or_zero()).collect()
+}
+
+fn cube() -> Mesh {
+    let mut mesh = Mesh::new();
+    let faces = [
+        (Vec3::X, Vec3::Y, Vec3::new(1.0, 0.2, 0.2)),
+        (Vec3::NEG_X, Vec3::Y, Vec3::new(0.2, 1.0, 1.0)),
+        (Vec3::Y, Vec3::Z, Vec3::new(0.2, 1.0, 0.2)),
+        (Vec3::NEG_Y, Vec3::Z, Vec3::new(1.0, 0.2, 1.0)),
+        (Vec3::Z, Vec3::X, Vec3::new(0.2, 0.2, 1.0)),
+        (Vec3::NEG_Z, Vec3::X, Vec3::new(1.0, 1.0, 0.2)),
+    ];
+
+    for (normal, up, color) in faces {
+        let right = up.cross(normal);
+        let base = mesh.vertices.len();
+        for (u, v) in [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)] {
+            mesh.vertices.push(normal + right * u + up * v);
+            mesh.normals.push(normal);
+            mesh.colors.push(color);
+        }
+        mesh.indices.extend([base, base + 1, base + 2, base, base + 2, base + 3]);
+    }
+    mesh
+}
+
+fn degenerate() -> Mesh {
+    let mut mesh = Mesh::new();
+    mesh.vertices = vec![
+        // Unit quad
+        Vec3::new(0.0, 0.0, 0.0),
+        Vec3::new(1.0, 0.0, 0.0),
+        Vec3::new(1.0, 1.0, 0.0),
+        Vec3::new(0.0, 1.0, 0.0),
+        // Collinear points
+        Vec3::new(2.0, 0.0, 0.0),
+        Vec3::new(3.0, 0.0, 0.0),
+        Vec3::new(4.0, 0.0, 0.0),
+        // Sliver
+        Vec3::new(0.0, 2.0, 0.0),
+        Vec3::new(1.0, 2.0, 0.0),
+        Vec3::new(0.5, 2.0 + 1e-6, 0.0),
+        // Unreferenced
+        Vec3::new(-5.0, -5.0, -5.0),
+    ];
+    mesh.indices = vec![
+        0, 1, 2, 0, 2, 3, // quad
+        4, 5, 6, // zero area
+        1, 1, 2, // repeated index
+        7, 8, 9, // sliver
+    ];
+    mesh.normals = vec![Vec3::Z; mesh.vertices.len()];
+    mesh.colors = vec![Vec3::new(0.5, 0.5, 0.5); mesh.vertices.len()];
+    mesh
+}
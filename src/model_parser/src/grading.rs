@@ -0,0 +1,49 @@
+use crate::{color, point_cloud::PointCloud};
+use glam::Vec3;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Export-time color grading, so clouds match the brightness of the target viewer
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ColorGrading {
+    /// Brightness change in photographic stops; each stop doubles the light
+    pub exposure: f32,
+
+    /// Midtone gamma; values above 1.0 brighten and below 1.0 darken the midtones
+    pub gamma: f32,
+
+    /// Saturation factor; 0.0 gives grayscale, values above 1.0 more vivid colors
+    pub saturation: f32,
+}
+
+impl Default for ColorGrading {
+    fn default() -> Self {
+        Self {
+            exposure: 0.0,
+            gamma: 1.0,
+            saturation: 1.0,
+        }
+    }
+}
+
+impl ColorGrading {
+    pub fn is_identity(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Grade point colors; colors must still be linear. Exposure and saturation act on the
+/// linear light, gamma on the result, which is clamped to 0.0-1.0
+pub fn grade(point_cloud: &mut PointCloud, grading: &ColorGrading) {
+    let exposure = grading.exposure.exp2();
+    let inverse_gamma = 1.0 / grading.gamma.max(f32::EPSILON);
+
+    point_cloud.points.par_iter_mut().for_each(|point| {
+        if let Some(c) = point.color {
+            let exposed = Vec3::from(c) * exposure;
+            let gray = Vec3::splat(color::luminance(exposed));
+            let saturated = (gray + (exposed - gray) * grading.saturation).max(Vec3::ZERO);
+            point.color = Some(saturated.powf(inverse_gamma).clamp(Vec3::ZERO, Vec3::ONE).to_array());
+        }
+    });
+}
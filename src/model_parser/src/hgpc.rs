@@ -0,0 +1,471 @@
+use crate::{
+    attributes::{AttributeDescriptor, AttributeType},
+    budget::SizeEstimate,
+    error::{ModelParserError, Result},
+    point_cloud::{LoadLimits, Point, PointCloud, PointCloudMetadata},
+};
+use flate2::{Compression, read::ZlibDecoder, write::ZlibEncoder};
+use glam::Vec3;
+use rayon::prelude::*;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+/// File signature
+const MAGIC: &[u8; 4] = b"HGPC";
+
+/// Container version written; readers accept it and every older version
+pub const VERSION: u16 = 1;
+
+/// Header flag: the point records are one zlib stream
+const FLAG_COMPRESSED: u16 = 1;
+
+/// Points encoded or decoded at a time
+const BATCH_POINTS: usize = 65_536;
+
+/// What a schema field holds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+enum Kind {
+    Position = 0,
+    Normal = 1,
+    Color = 2,
+    Alpha = 3,
+    MaterialId = 4,
+    Attribute = 5,
+}
+
+impl Kind {
+    fn from_code(code: u8) -> Option<Self> {
+        [Kind::Position, Kind::Normal, Kind::Color, Kind::Alpha, Kind::MaterialId, Kind::Attribute]
+            .into_iter()
+            .find(|kind| *kind as u8 == code)
+    }
+
+    fn components(&self) -> u8 {
+        match self {
+            Kind::Position | Kind::Normal | Kind::Color => 3,
+            Kind::Alpha | Kind::MaterialId | Kind::Attribute => 1,
+        }
+    }
+}
+
+/// One field of the point records
+#[derive(Debug, Clone, PartialEq)]
+struct Field {
+    kind: Kind,
+    name: String,
+    data_type: AttributeType,
+}
+
+impl Field {
+    fn new(kind: Kind, name: &str, data_type: AttributeType) -> Self {
+        Self { kind, name: name.to_string(), data_type }
+    }
+
+    fn size(&self) -> usize {
+        self.data_type.size() as usize * self.kind.components() as usize
+    }
+
+    /// Integer value of 1.0 for normalized fields (colors and alpha), 1 otherwise
+    fn scale(&self) -> f64 {
+        match (self.kind, self.data_type) {
+            (Kind::Color | Kind::Alpha, AttributeType::U8) => u8::MAX as f64,
+            (Kind::Color | Kind::Alpha, AttributeType::U16) => u16::MAX as f64,
+            _ => 1.0,
+        }
+    }
+}
+
+/// Type code of an attribute type in the schema
+fn type_code(data_type: AttributeType) -> u8 {
+    match data_type {
+        AttributeType::U8 => 0,
+        AttributeType::U16 => 1,
+        AttributeType::U32 => 2,
+        AttributeType::I32 => 3,
+        AttributeType::F32 => 4,
+        AttributeType::F64 => 5,
+    }
+}
+
+fn type_from_code(code: u8) -> Option<AttributeType> {
+    [AttributeType::U8, AttributeType::U16, AttributeType::U32, AttributeType::I32, AttributeType::F32, AttributeType::F64]
+        .into_iter()
+        .find(|data_type| type_code(*data_type) == code)
+}
+
+/// Fields written for a cloud: float positions and normals, 16-bit normalized colors and
+/// alpha, material ids and every generic attribute in its own type, each only when the
+/// cloud has it
+fn schema(metadata: &PointCloudMetadata) -> Vec<Field> {
+    let mut fields = vec![Field::new(Kind::Position, "position", AttributeType::F32)];
+    if metadata.has_normals {
+        fields.push(Field::new(Kind::Normal, "normal", AttributeType::F32));
+    }
+    if metadata.has_colors {
+        fields.push(Field::new(Kind::Color, "color", AttributeType::U16));
+    }
+    if metadata.has_alpha {
+        fields.push(Field::new(Kind::Alpha, "alpha", AttributeType::U16));
+    }
+    if metadata.has_material_ids {
+        fields.push(Field::new(Kind::MaterialId, "material_id", AttributeType::U32));
+    }
+    fields.extend(
+        metadata
+            .attributes
+            .iter()
+            .map(|attribute| Field::new(Kind::Attribute, &attribute.name, attribute.data_type)),
+    );
+    fields
+}
+
+fn record_size(fields: &[Field]) -> usize {
+    fields.iter().map(Field::size).sum()
+}
+
+/// Header of a cloud: signature, version, flags, point count, bounds, record size, the schema
+/// and the metadata as compact JSON
+fn header(point_cloud: &PointCloud, fields: &[Field], compress: bool) -> Result<Vec<u8>> {
+    let metadata = serde_json::to_vec(&point_cloud.metadata)?;
+    let mut header = Vec::with_capacity(64 + fields.len() * 16 + metadata.len());
+    header.extend_from_slice(MAGIC);
+    header.extend_from_slice(&VERSION.to_le_bytes());
+    header.extend_from_slice(&(if compress { FLAG_COMPRESSED } else { 0 }).to_le_bytes());
+    header.extend_from_slice(&(point_cloud.points.len() as u64).to_le_bytes());
+    for value in point_cloud.metadata.bounds_min.iter().chain(&point_cloud.metadata.bounds_max) {
+        header.extend_from_slice(&value.to_le_bytes());
+    }
+    header.extend_from_slice(&(record_size(fields) as u32).to_le_bytes());
+
+    header.extend_from_slice(&(fields.len() as u16).to_le_bytes());
+    for field in fields {
+        header.push(field.kind as u8);
+        header.push(type_code(field.data_type));
+        header.push(field.kind.components());
+        header.extend_from_slice(&(field.name.len() as u16).to_le_bytes());
+        header.extend_from_slice(field.name.as_bytes());
+    }
+
+    header.extend_from_slice(&(metadata.len() as u32).to_le_bytes());
+    header.extend_from_slice(&metadata);
+    Ok(header)
+}
+
+/// Append the record of `point` to `out`
+fn encode(point: &Point, fields: &[Field], out: &mut Vec<u8>) {
+    let mut attribute = 0;
+    for field in fields {
+        let scale = field.scale();
+        let mut put = |value: f64| field.data_type.encode(value * scale, out);
+        match field.kind {
+            Kind::Position => point.position.iter().for_each(|&value| put(value as f64)),
+            Kind::Normal => point.normal.unwrap_or([0.0; 3]).iter().for_each(|&value| put(value as f64)),
+            Kind::Color => point.color.unwrap_or([1.0; 3]).iter().for_each(|&value| put(value as f64)),
+            Kind::Alpha => put(point.alpha.unwrap_or(1.0) as f64),
+            Kind::MaterialId => put(point.material_id.unwrap_or(0) as f64),
+            Kind::Attribute => {
+                put(point.attributes.get(attribute).copied().unwrap_or(0.0));
+                attribute += 1;
+            }
+        }
+    }
+}
+
+/// Point of a record written by [`encode`]
+fn decode(record: &[u8], fields: &[Field], attributes: usize) -> Point {
+    let mut point = Point::new(Vec3::ZERO);
+    point.attributes.reserve_exact(attributes);
+    let mut offset = 0;
+    for field in fields {
+        let size = field.data_type.size() as usize;
+        let scale = field.scale();
+        let mut values = [0.0; 3];
+        for value in values.iter_mut().take(field.kind.components() as usize) {
+            *value = field.data_type.decode(&record[offset..offset + size]) / scale;
+            offset += size;
+        }
+        let vector = values.map(|value| value as f32);
+        match field.kind {
+            Kind::Position => point.position = vector,
+            Kind::Normal => point.normal = Some(vector),
+            Kind::Color => point.color = Some(vector),
+            Kind::Alpha => point.alpha = Some(vector[0]),
+            Kind::MaterialId => point.material_id = Some(values[0] as u32),
+            Kind::Attribute => point.attributes.push(values[0]),
+        }
+    }
+    point
+}
+
+/// Write `point_cloud` as an `.hgpc` container: a header with the bounds, point count, record
+/// schema and metadata, then one packed little-endian record per point, zlib-compressed when
+/// `compress` is set. Colors and alpha are stored as 16-bit normalized integers; everything
+/// else keeps its exact value
+pub fn write(point_cloud: &PointCloud, path: &Path, compress: bool) -> Result<()> {
+    let fields = schema(&point_cloud.metadata);
+    let mut writer = BufWriter::new(std::fs::File::create(path)?);
+    writer.write_all(&header(point_cloud, &fields, compress)?)?;
+    if compress {
+        let mut encoder = ZlibEncoder::new(writer, Compression::default());
+        write_records(point_cloud, &fields, &mut encoder)?;
+        encoder.finish()?.flush()?;
+    } else {
+        write_records(point_cloud, &fields, &mut writer)?;
+        writer.flush()?;
+    }
+    Ok(())
+}
+
+fn write_records(point_cloud: &PointCloud, fields: &[Field], writer: &mut impl Write) -> Result<()> {
+    let record_size = record_size(fields);
+    for batch in point_cloud.points.chunks(BATCH_POINTS) {
+        let bytes: Vec<u8> = batch
+            .par_iter()
+            .flat_map_iter(|point| {
+                let mut record = Vec::with_capacity(record_size);
+                encode(point, fields, &mut record);
+                record
+            })
+            .collect();
+        writer.write_all(&bytes)?;
+    }
+    Ok(())
+}
+
+/// Size of the `.hgpc` output: the header plus one record per point. Compression usually
+/// shrinks the records well below this
+pub fn estimate(point_cloud: &PointCloud) -> Result<SizeEstimate> {
+    let fields = schema(&point_cloud.metadata);
+    Ok(SizeEstimate {
+        bytes_per_point: record_size(&fields) as f64,
+        // Room for a point count with more digits once the cloud is thinned or grows
+        overhead: header(point_cloud, &fields, false)?.len() as u64 + 32,
+    })
+}
+
+/// Header fields needed to read the records
+struct Header {
+    compressed: bool,
+    count: u64,
+    record_size: usize,
+    fields: Vec<Field>,
+    metadata: PointCloudMetadata,
+}
+
+/// Read an `.hgpc` container written by [`write`] by this or an earlier version, failing as
+/// soon as the file or its point count exceeds `limits`. The header's bounds, point count and
+/// schema take precedence over the stored metadata
+pub fn read(path: &Path, limits: &LoadLimits) -> Result<PointCloud> {
+    let file = std::fs::File::open(path)?;
+    let size = file.metadata()?.len();
+    if let Some(max) = limits.max_file_size
+        && size > max
+    {
+        return Err(ModelParserError::InvalidData(format!(
+            "{:?} is {} bytes, above the limit of {} bytes",
+            path, size, max
+        )));
+    }
+
+    let mut reader = BufReader::new(file);
+    let header = read_header(&mut reader)?;
+    if let Some(max) = limits.max_points
+        && header.count > max as u64
+    {
+        return Err(ModelParserError::InvalidData(format!(
+            "{:?} has {} points, above the limit of {} points",
+            path, header.count, max
+        )));
+    }
+
+    let points = if header.compressed {
+        read_records(&mut ZlibDecoder::new(reader), &header)?
+    } else {
+        read_records(&mut reader, &header)?
+    };
+
+    let has = |kind: Kind| header.fields.iter().any(|field| field.kind == kind);
+    let mut metadata = header.metadata;
+    metadata.point_count = points.len();
+    metadata.has_normals = has(Kind::Normal);
+    metadata.has_colors = has(Kind::Color);
+    metadata.has_alpha = has(Kind::Alpha);
+    metadata.has_material_ids = has(Kind::MaterialId);
+    metadata.attributes = header
+        .fields
+        .iter()
+        .filter(|field| field.kind == Kind::Attribute)
+        .map(|field| AttributeDescriptor::new(field.name.clone(), field.data_type))
+        .collect();
+    Ok(PointCloud { points, metadata })
+}
+
+fn read_header(reader: &mut impl Read) -> Result<Header> {
+    if &read_array::<4>(reader)? != MAGIC {
+        return Err(ModelParserError::UnsupportedFormat("not an HGPC file".to_string()));
+    }
+    let version = u16::from_le_bytes(read_array(reader)?);
+    if version == 0 || version > VERSION {
+        return Err(ModelParserError::UnsupportedFormat(format!(
+            "HGPC version {} (supported up to {})",
+            version, VERSION
+        )));
+    }
+    let flags = u16::from_le_bytes(read_array(reader)?);
+    if flags & !FLAG_COMPRESSED != 0 {
+        return Err(ModelParserError::UnsupportedFormat(format!("HGPC flags {:#06x}", flags)));
+    }
+    let count = u64::from_le_bytes(read_array(reader)?);
+    let mut bounds = [0.0f32; 6];
+    for value in &mut bounds {
+        *value = f32::from_le_bytes(read_array(reader)?);
+    }
+    let record_length = u32::from_le_bytes(read_array(reader)?) as usize;
+
+    let field_count = u16::from_le_bytes(read_array(reader)?);
+    let mut fields: Vec<Field> = Vec::with_capacity(field_count as usize);
+    for _ in 0..field_count {
+        let [kind, data_type, components] = read_array(reader)?;
+        let name_length = u16::from_le_bytes(read_array(reader)?) as usize;
+        let mut name = vec![0; name_length];
+        read_exact(reader, &mut name)?;
+        let name = String::from_utf8(name)
+            .map_err(|_| ModelParserError::InvalidData("HGPC field name is not UTF-8".to_string()))?;
+
+        let field = match (Kind::from_code(kind), type_from_code(data_type)) {
+            (Some(kind), Some(data_type)) if components == kind.components() => Field { kind, name, data_type },
+            _ => {
+                return Err(ModelParserError::InvalidData(format!(
+                    "HGPC field '{}' has an unknown layout (kind {}, type {}, {} components)",
+                    name, kind, data_type, components
+                )));
+            }
+        };
+        let normalized = matches!(field.kind, Kind::Color | Kind::Alpha);
+        if normalized && matches!(field.data_type, AttributeType::U32 | AttributeType::I32) {
+            return Err(ModelParserError::InvalidData(format!(
+                "HGPC field '{}' cannot be stored as {:?}",
+                field.name, field.data_type
+            )));
+        }
+        if field.kind != Kind::Attribute && fields.iter().any(|other| other.kind == field.kind) {
+            return Err(ModelParserError::InvalidData(format!("HGPC field '{}' appears twice", field.name)));
+        }
+        fields.push(field);
+    }
+    if !fields.iter().any(|field| field.kind == Kind::Position) {
+        return Err(ModelParserError::InvalidData("HGPC schema has no position field".to_string()));
+    }
+    if record_size(&fields) != record_length {
+        return Err(ModelParserError::InvalidData(format!(
+            "HGPC records are {} bytes but the schema describes {}",
+            record_length,
+            record_size(&fields)
+        )));
+    }
+
+    let metadata_length = u32::from_le_bytes(read_array(reader)?) as usize;
+    let mut metadata = Vec::new();
+    reader.by_ref().take(metadata_length as u64).read_to_end(&mut metadata)?;
+    if metadata.len() != metadata_length {
+        return Err(truncated());
+    }
+    let mut metadata: PointCloudMetadata = serde_json::from_slice(&metadata)?;
+    metadata.bounds_min = [bounds[0], bounds[1], bounds[2]];
+    metadata.bounds_max = [bounds[3], bounds[4], bounds[5]];
+
+    Ok(Header {
+        compressed: flags & FLAG_COMPRESSED != 0,
+        count,
+        record_size: record_length,
+        fields,
+        metadata,
+    })
+}
+
+/// Records of `header`, decoded a batch at a time; the point count is not trusted for
+/// allocation, so a corrupt header fails at the end of the data instead
+fn read_records(reader: &mut impl Read, header: &Header) -> Result<Vec<Point>> {
+    let attributes = header.fields.iter().filter(|field| field.kind == Kind::Attribute).count();
+    let mut points = Vec::with_capacity(header.count.min(BATCH_POINTS as u64) as usize);
+    let mut buffer = Vec::new();
+    let mut remaining = header.count;
+    while remaining > 0 {
+        let batch = remaining.min(BATCH_POINTS as u64) as usize;
+        buffer.resize(batch * header.record_size, 0);
+        read_exact(reader, &mut buffer)?;
+        points.par_extend(
+            buffer
+                .par_chunks(header.record_size)
+                .map(|record| decode(record, &header.fields, attributes)),
+        );
+        remaining -= batch as u64;
+    }
+    Ok(points)
+}
+
+fn read_array<const N: usize>(reader: &mut impl Read) -> Result<[u8; N]> {
+    let mut bytes = [0; N];
+    read_exact(reader, &mut bytes)?;
+    Ok(bytes)
+}
+
+fn read_exact(reader: &mut impl Read, buffer: &mut [u8]) -> Result<()> {
+    reader.read_exact(buffer).map_err(|e| match e.kind() {
+        std::io::ErrorKind::UnexpectedEof => truncated(),
+        _ => e.into(),
+    })
+}
+
+fn truncated() -> ModelParserError {
+    ModelParserError::InvalidData("HGPC file is truncated".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::point_cloud::test_cloud;
+
+    fn round_trip(compress: bool) {
+        let cloud = test_cloud();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cloud.hgpc");
+        write(&cloud, &path, compress).unwrap();
+        let loaded = read(&path, &LoadLimits::default()).unwrap();
+
+        assert_eq!(loaded.points.len(), cloud.points.len());
+        assert_eq!(loaded.metadata.point_count, cloud.metadata.point_count);
+        assert_eq!(loaded.metadata.bounds_min, cloud.metadata.bounds_min);
+        assert_eq!(loaded.metadata.bounds_max, cloud.metadata.bounds_max);
+        assert_eq!(loaded.metadata.attributes.len(), 1);
+        assert_eq!(loaded.metadata.attributes[0].name, "intensity");
+        for (i, (loaded, point)) in loaded.points.iter().zip(&cloud.points).enumerate() {
+            assert_eq!(loaded.position, point.position, "point {}", i);
+            assert_eq!(loaded.normal, point.normal, "point {}", i);
+            // Colors are stored as normalized 16-bit integers
+            let color = Vec3::from(loaded.color.unwrap()) - Vec3::from(point.color.unwrap());
+            assert!(color.abs().max_element() < 1e-4, "point {}", i);
+            assert_eq!(loaded.attributes, point.attributes, "point {}", i);
+        }
+    }
+
+    #[test]
+    fn round_trip_uncompressed() {
+        round_trip(false);
+    }
+
+    #[test]
+    fn round_trip_compressed() {
+        round_trip(true);
+    }
+
+    #[test]
+    fn limits_reject_too_many_points() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cloud.hgpc");
+        write(&test_cloud(), &path, false).unwrap();
+        let limits = LoadLimits { max_file_size: None, max_points: Some(10) };
+        assert!(read(&path, &limits).is_err());
+    }
+}
@@ -0,0 +1,75 @@
+use crate::point_cloud::{Point, PointCloud};
+use glam::Vec3;
+use rayon::prelude::*;
+
+/// Bits per axis of the curve grid; three axes fill a 63-bit key
+pub const BITS: u32 = 21;
+
+/// Distance along a 3D Hilbert curve of the grid cell `cell` (each axis below 2^`bits`), using
+/// Skilling's transpose form: neighbouring keys are always neighbouring cells, so runs of
+/// keys stay spatially compact
+pub fn index(cell: [u32; 3], bits: u32) -> u64 {
+    let mut x = cell;
+    let top = 1u32 << (bits - 1);
+
+    // Undo the excess rotations and reflections of each level
+    let mut q = top;
+    while q > 1 {
+        let p = q - 1;
+        for i in 0..3 {
+            if x[i] & q != 0 {
+                x[0] ^= p;
+            } else {
+                let t = (x[0] ^ x[i]) & p;
+                x[0] ^= t;
+                x[i] ^= t;
+            }
+        }
+        q >>= 1;
+    }
+
+    // Gray encode
+    x[1] ^= x[0];
+    x[2] ^= x[1];
+    let mut t = 0;
+    let mut q = top;
+    while q > 1 {
+        if x[2] & q != 0 {
+            t ^= q - 1;
+        }
+        q >>= 1;
+    }
+    x.iter_mut().for_each(|v| *v ^= t);
+
+    // Interleave the transposed bits, most significant level first
+    (0..bits).rev().fold(0u64, |key, bit| {
+        x.iter().fold(key, |key, &v| (key << 1) | ((v >> bit) & 1) as u64)
+    })
+}
+
+/// Curve keys of points on a grid of 2^`BITS` cells per axis spanning `min`..`max`
+fn keys(points: &[Point], min: Vec3, max: Vec3) -> Vec<u64> {
+    let cells = ((1u32 << BITS) - 1) as f32;
+    let scale = (max - min).max(Vec3::splat(f32::MIN_POSITIVE)).recip() * cells;
+    points
+        .par_iter()
+        .map(|point| {
+            let cell = ((Vec3::from(point.position) - min) * scale).clamp(Vec3::ZERO, Vec3::splat(cells));
+            index(cell.to_array().map(|v| v as u32), BITS)
+        })
+        .collect()
+}
+
+/// Reorder points along a Hilbert curve through the cloud's bounds, so sequential formats
+/// compress better and byte ranges of the file cover compact regions. Points in the same
+/// grid cell keep their relative order
+pub fn sort(point_cloud: &mut PointCloud) {
+    point_cloud.update_metadata();
+    let min = Vec3::from(point_cloud.metadata.bounds_min);
+    let max = Vec3::from(point_cloud.metadata.bounds_max);
+    let keys = keys(&point_cloud.points, min, max);
+
+    let mut keyed: Vec<(u64, Point)> = keys.into_iter().zip(std::mem::take(&mut point_cloud.points)).collect();
+    keyed.par_sort_by_key(|(key, _)| *key);
+    point_cloud.points = keyed.into_iter().map(|(_, point)| point).collect();
+}
@@ -0,0 +1,197 @@
+use crate::{error::Result, mesh::Mesh, point_cloud::PointCloud, random, spatial::KdTree};
+use glam::{IVec3, Vec3};
+use rand::Rng;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Multiple of the expected point spacing beyond which the surface counts as a hole. Random
+/// sampling leaves a spot this far from every point with probability exp(-4π) ≈ 3.5e-6
+pub const DEFAULT_RADIUS_FACTOR: f32 = 2.0;
+
+/// Surface regions of a mesh that no cloud point landed near
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HoleReport {
+    /// Number of surface probes measured
+    pub probes: usize,
+
+    /// Distance from the nearest point beyond which a probe is part of a hole (output units)
+    pub radius: f32,
+
+    /// Total surface area of all holes (output units squared)
+    pub total_area: f32,
+
+    /// Holes, largest area first
+    pub holes: Vec<Hole>,
+}
+
+/// One connected uncovered region
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Hole {
+    /// Bounds of the uncovered probes (output units)
+    pub bounds_min: [f32; 3],
+
+    pub bounds_max: [f32; 3],
+
+    /// Estimated uncovered surface area (output units squared)
+    pub area: f32,
+
+    /// Largest distance from a probe in the hole to its nearest point
+    pub max_gap: f32,
+
+    /// Number of uncovered probes in the hole
+    pub probes: usize,
+
+    /// Names of the materials the hole lies on
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub materials: Vec<String>,
+}
+
+/// A surface sample standing for `area` of its triangle
+struct Probe {
+    position: Vec3,
+    area: f32,
+    triangle: usize,
+}
+
+impl HoleReport {
+    /// Probe the mesh surface (scaled to output units by `scale`) and group the probes
+    /// further than `radius` from every point into holes. Each triangle gets at least one
+    /// probe so small features are checked however few probes their area would earn; the
+    /// rest of `probes` is spread by area. Without a radius, [`DEFAULT_RADIUS_FACTOR`] times
+    /// the expected point spacing sqrt(area / points) is used
+    pub fn detect(
+        mesh: &Mesh,
+        point_cloud: &PointCloud,
+        scale: f32,
+        radius: Option<f32>,
+        probes: usize,
+        seed: Option<u64>,
+    ) -> Option<Self> {
+        if mesh.triangle_count() == 0 || point_cloud.points.is_empty() {
+            return None;
+        }
+
+        let scale = if scale != 0.0 { scale } else { 1.0 };
+        let surface_area = mesh.surface_area() * scale * scale;
+        if surface_area <= 0.0 {
+            return None;
+        }
+        let radius = radius.unwrap_or_else(|| {
+            DEFAULT_RADIUS_FACTOR * (surface_area / point_cloud.points.len() as f32).sqrt()
+        });
+        let tree = KdTree::from_points(&point_cloud.points);
+        let probe_count = |area: f32| ((area / surface_area * probes as f32).round() as usize).max(1);
+
+        // Measure every probe, keeping the uncovered ones with their gap
+        let triangles: Vec<usize> = (0..mesh.triangle_count()).collect();
+        let uncovered: Vec<(Probe, f32)> = triangles
+            .par_chunks(random::CHUNK_SIZE)
+            .enumerate()
+            .flat_map_iter(|(chunk, triangles)| {
+                let mut rng = random::chunk_rng(seed, random::stream::HOLES, chunk as u64);
+                let tree = &tree;
+                let mut found = Vec::new();
+                for &triangle in triangles {
+                    let area = mesh.triangle_area(triangle) * scale * scale;
+                    let count = probe_count(area);
+                    let [v0, v1, v2] = mesh.triangle(triangle);
+                    for _ in 0..count {
+                        let r1 = rng.random::<f32>().sqrt();
+                        let r2 = rng.random::<f32>();
+                        let position = (v0 * (1.0 - r1) + v1 * (r1 * (1.0 - r2)) + v2 * (r1 * r2)) * scale;
+                        let gap = tree.nearest(position, 1).first().map_or(f32::INFINITY, |&(_, d)| d);
+                        if gap > radius {
+                            let probe = Probe { position, area: area / count as f32, triangle };
+                            found.push((probe, gap));
+                        }
+                    }
+                }
+                found
+            })
+            .collect();
+        let measured: usize = triangles
+            .iter()
+            .map(|&t| probe_count(mesh.triangle_area(t) * scale * scale))
+            .sum();
+
+        // Probes within linking distance belong to the same hole: union-find over a grid of
+        // cells as wide as that distance, so only neighboring cells need comparing
+        let spacing = (surface_area / measured as f32).sqrt();
+        let link = 2.0 * radius.max(spacing);
+        let cell = |p: Vec3| (p / link).floor().as_ivec3();
+        let mut grid: HashMap<IVec3, Vec<usize>> = HashMap::new();
+        for (index, (probe, _)) in uncovered.iter().enumerate() {
+            grid.entry(cell(probe.position)).or_default().push(index);
+        }
+
+        let mut parent: Vec<usize> = (0..uncovered.len()).collect();
+        fn root(parent: &mut [usize], mut i: usize) -> usize {
+            while parent[i] != i {
+                parent[i] = parent[parent[i]];
+                i = parent[i];
+            }
+            i
+        }
+        for (index, (probe, _)) in uncovered.iter().enumerate() {
+            let home = cell(probe.position);
+            for offset in (-1..=1).flat_map(|x| (-1..=1).flat_map(move |y| (-1..=1).map(move |z| IVec3::new(x, y, z)))) {
+                for &other in grid.get(&(home + offset)).into_iter().flatten() {
+                    if other < index && probe.position.distance(uncovered[other].0.position) <= link {
+                        let (a, b) = (root(&mut parent, index), root(&mut parent, other));
+                        parent[a] = b;
+                    }
+                }
+            }
+        }
+
+        let mut groups: HashMap<usize, Vec<usize>> = HashMap::new();
+        for index in 0..uncovered.len() {
+            groups.entry(root(&mut parent, index)).or_default().push(index);
+        }
+
+        let mut holes: Vec<Hole> = groups
+            .into_values()
+            .map(|members| {
+                let (min, max) = members.iter().fold((Vec3::MAX, Vec3::MIN), |(min, max), &i| {
+                    let p = uncovered[i].0.position;
+                    (min.min(p), max.max(p))
+                });
+                let mut materials: Vec<String> = members
+                    .iter()
+                    .filter_map(|&i| {
+                        let triangle = uncovered[i].0.triangle;
+                        let id = *mesh.material_ids.get(mesh.indices[triangle * 3])?;
+                        mesh.materials.get(id as usize).cloned()
+                    })
+                    .collect();
+                materials.sort();
+                materials.dedup();
+
+                Hole {
+                    bounds_min: min.to_array(),
+                    bounds_max: max.to_array(),
+                    area: members.iter().map(|&i| uncovered[i].0.area).sum(),
+                    max_gap: members.iter().map(|&i| uncovered[i].1).fold(0.0, f32::max),
+                    probes: members.len(),
+                    materials,
+                }
+            })
+            .collect();
+        // Ties are broken by position so seeded runs list holes in a stable order
+        holes.sort_by(|a, b| b.area.total_cmp(&a.area).then(a.bounds_min[0].total_cmp(&b.bounds_min[0])));
+
+        Some(Self {
+            probes: measured,
+            radius,
+            total_area: holes.iter().fold(0.0, |total, hole| total + hole.area),
+            holes,
+        })
+    }
+
+    pub fn save_to_file(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,94 @@
+use crate::{
+    error::Result,
+    mesh::Mesh,
+    point_cloud::PointCloud,
+};
+use std::sync::Arc;
+
+/// Transform of a loaded mesh
+pub type MeshHook = Arc<dyn Fn(&mut Mesh) -> Result<()> + Send + Sync>;
+
+/// Transform of a point cloud
+pub type CloudHook = Arc<dyn Fn(&mut PointCloud) -> Result<()> + Send + Sync>;
+
+/// Caller code run at fixed points of the conversion pipeline, e.g. to compute proprietary
+/// attributes without forking the crate. Hooks of a stage run in registration order and the
+/// first error aborts the conversion
+#[derive(Clone, Default)]
+pub struct PipelineHooks {
+    post_parse: Vec<MeshHook>,
+    post_sample: Vec<CloudHook>,
+    pre_write: Vec<CloudHook>,
+}
+
+impl PipelineHooks {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Run `hook` on the mesh once it is loaded (after welding and decimation), before sampling
+    pub fn on_post_parse<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&mut Mesh) -> Result<()> + Send + Sync + 'static,
+    {
+        self.post_parse.push(Arc::new(hook));
+        self
+    }
+
+    /// Run `hook` on the cloud once it is sampled and post-processed
+    pub fn on_post_sample<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&mut PointCloud) -> Result<()> + Send + Sync + 'static,
+    {
+        self.post_sample.push(Arc::new(hook));
+        self
+    }
+
+    /// Run `hook` on the cloud right before it is written, after filtering and budgeting
+    pub fn on_pre_write<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&mut PointCloud) -> Result<()> + Send + Sync + 'static,
+    {
+        self.pre_write.push(Arc::new(hook));
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.post_parse.is_empty() && self.post_sample.is_empty() && self.pre_write.is_empty()
+    }
+
+    pub fn run_post_parse(&self, mesh: &mut Mesh) -> Result<()> {
+        self.post_parse.iter().try_for_each(|hook| hook(mesh))
+    }
+
+    /// Run the post-sample hooks, refreshing the metadata after them
+    pub fn run_post_sample(&self, point_cloud: &mut PointCloud) -> Result<()> {
+        Self::run(&self.post_sample, point_cloud)
+    }
+
+    /// Run the pre-write hooks, refreshing the metadata after them. Writers do not call this
+    /// themselves; callers run it before handing the cloud to one
+    pub fn run_pre_write(&self, point_cloud: &mut PointCloud) -> Result<()> {
+        Self::run(&self.pre_write, point_cloud)
+    }
+
+    fn run(hooks: &[CloudHook], point_cloud: &mut PointCloud) -> Result<()> {
+        if hooks.is_empty() {
+            return Ok(());
+        }
+        hooks.iter().try_for_each(|hook| hook(point_cloud))?;
+        point_cloud.update_metadata();
+        Ok(())
+    }
+}
+
+impl std::fmt::Debug for PipelineHooks {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter
+            .debug_struct("PipelineHooks")
+            .field("post_parse", &self.post_parse.len())
+            .field("post_sample", &self.post_sample.len())
+            .field("pre_write", &self.pre_write.len())
+            .finish()
+    }
+}
@@ -0,0 +1,109 @@
+use crate::{
+    attributes::{AttributeDescriptor, AttributeType},
+    error::{ModelParserError, Result},
+    point_cloud::{Point, PointCloud},
+};
+use glam::{Mat3, Mat4, Quat, Vec3};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Placements file: one transform per instance of the input model
+///
+/// ```json
+/// { "instances": [
+///     { "translation": [10, 0, 5], "rotation": [0, 0.707, 0, 0.707], "scale": [1, 1, 1] },
+///     { "matrix": [1,0,0,0, 0,1,0,0, 0,0,1,0, 20,0,0,1] }
+/// ] }
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Placements {
+    pub instances: Vec<Placement>,
+}
+
+/// A single instance transform, either as TRS components or a column-major 4x4 matrix
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Placement {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub translation: Option<[f32; 3]>,
+
+    /// Rotation quaternion [x, y, z, w]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rotation: Option<[f32; 4]>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scale: Option<[f32; 3]>,
+
+    /// Column-major matrix; takes precedence over TRS components
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub matrix: Option<Vec<f32>>,
+}
+
+impl Placement {
+    pub fn to_matrix(&self) -> Result<Mat4> {
+        if let Some(matrix) = &self.matrix {
+            let columns: [f32; 16] = matrix.as_slice().try_into().map_err(|_| {
+                ModelParserError::InvalidData(format!(
+                    "placement matrix needs 16 values, got {}",
+                    matrix.len()
+                ))
+            })?;
+            return Ok(Mat4::from_cols_array(&columns));
+        }
+
+        Ok(Mat4::from_scale_rotation_translation(
+            self.scale.map(Vec3::from).unwrap_or(Vec3::ONE),
+            self.rotation.map(Quat::from_array).unwrap_or(Quat::IDENTITY).normalize(),
+            self.translation.map(Vec3::from).unwrap_or(Vec3::ZERO),
+        ))
+    }
+}
+
+impl Placements {
+    pub fn load_from_file(path: &Path) -> Result<Self> {
+        let json = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&json)?)
+    }
+
+    pub fn matrices(&self) -> Result<Vec<Mat4>> {
+        self.instances.iter().map(Placement::to_matrix).collect()
+    }
+}
+
+/// Replicate the cloud once per transform, tagging copies with an "instance_id" attribute
+pub fn instantiate(point_cloud: &PointCloud, transforms: &[Mat4]) -> PointCloud {
+    let normal_matrices: Vec<Mat3> = transforms
+        .iter()
+        .map(|m| Mat3::from_mat4(*m).inverse().transpose())
+        .collect();
+    let attribute_count = point_cloud.metadata.attributes.len();
+
+    let points: Vec<Point> = transforms
+        .par_iter()
+        .zip(normal_matrices.par_iter())
+        .enumerate()
+        .flat_map_iter(|(instance, (transform, normal_matrix))| {
+            point_cloud.points.iter().map(move |point| {
+                let mut copy = point.clone();
+                copy.position = transform.transform_point3(Vec3::from(point.position)).to_array();
+                copy.normal = point
+                    .normal
+                    .map(|n| (*normal_matrix * Vec3::from(n)).normalize_or_zero().to_array());
+                copy.attributes.resize(attribute_count, 0.0);
+                copy.attributes.push(instance as f64);
+                copy
+            })
+        })
+        .collect();
+
+    let mut result = PointCloud::new(points, point_cloud.metadata.source_file.clone());
+    let mut metadata = point_cloud.metadata.clone();
+    metadata
+        .attributes
+        .push(AttributeDescriptor::new("instance_id", AttributeType::U32));
+    metadata.point_count = result.metadata.point_count;
+    metadata.bounds_min = result.metadata.bounds_min;
+    metadata.bounds_max = result.metadata.bounds_max;
+    result.metadata = metadata;
+    result
+}
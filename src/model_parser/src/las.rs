@@ -0,0 +1,680 @@
+use crate::{
+    attributes::{AttributeDescriptor, AttributeType},
+    budget::SizeEstimate,
+    color::{self, ColorSpace},
+    config::PointCloudConfig,
+    error::{ModelParserError, Result},
+    mesh::Mesh,
+    point_cloud::{Point, PointCloud},
+};
+use glam::Vec3;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// Coordinates further from the origin than this lose sub-millimetre precision as f32,
+/// so the cloud is shifted by the (rounded) center of the header bounds
+pub(crate) const SHIFT_THRESHOLD: f64 = 10_000.0;
+
+/// Load a LAS or LASzip-compressed LAZ file (versions 1.0-1.4, point formats 0-10) as a point
+/// set: positions, RGB colors where the format has them, and `intensity`, `classification` and
+/// `return_number` attributes. Georeferenced coordinates are shifted near the origin and the
+/// shift recorded as the cloud's offset
+pub fn load(path: &Path, config: &PointCloudConfig) -> Result<Mesh> {
+    let bytes = std::fs::read(path)?;
+    let invalid = |message: &str| ModelParserError::InvalidData(format!("{}: {}", path.display(), message));
+
+    if bytes.len() < 227 || &bytes[..4] != b"LASF" {
+        return Err(invalid("not a LAS file"));
+    }
+
+    let u16_at = |at: usize| u16::from_le_bytes([bytes[at], bytes[at + 1]]);
+    let u32_at = |at: usize| u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap_or_default());
+    let f64_at = |at: usize| f64::from_le_bytes(bytes[at..at + 8].try_into().unwrap_or_default());
+
+    let version = (bytes[24], bytes[25]);
+    let header_size = u16_at(94) as usize;
+    let point_offset = u32_at(96) as usize;
+    let vlr_count = u32_at(100) as usize;
+    // The two high bits of the format byte flag LAZ compression
+    let format_byte = bytes[104];
+    let compressed = format_byte & 0xC0 != 0;
+    let format = format_byte & 0x3F;
+    let record_length = u16_at(105) as usize;
+    let legacy_count = u32_at(107) as u64;
+    let count = if version >= (1, 4) && bytes.len() >= 255 && legacy_count == 0 {
+        u64::from_le_bytes(bytes[247..255].try_into().unwrap_or_default())
+    } else {
+        legacy_count
+    } as usize;
+
+    let scale = [f64_at(131), f64_at(139), f64_at(147)];
+    let offset = [f64_at(155), f64_at(163), f64_at(171)];
+    // Header bounds are stored as max x, min x, max y, min y, max z, min z
+    let center = [(f64_at(179) + f64_at(187)) * 0.5, (f64_at(195) + f64_at(203)) * 0.5, (f64_at(211) + f64_at(219)) * 0.5];
+
+    let rgb_offset = match format {
+        0 | 1 | 4 | 6 | 9 => None,
+        2 => Some(20),
+        3 | 5 => Some(28),
+        7 | 8 | 10 => Some(30),
+        _ => return Err(invalid(&format!("unsupported point format {}", format))),
+    };
+    let minimum_length = match format {
+        0 => 20,
+        1 => 28,
+        2 => 26,
+        3 => 34,
+        4 => 57,
+        5 => 63,
+        6 => 30,
+        7 => 36,
+        8 => 38,
+        9 => 59,
+        _ => 67,
+    };
+    if record_length < minimum_length {
+        return Err(invalid("point record too short for its format"));
+    }
+    let data_length = count.checked_mul(record_length).ok_or_else(|| invalid("point count too large"))?;
+    let decompressed;
+    let records = if compressed {
+        decompressed = decompress(&bytes, header_size, vlr_count, point_offset, data_length).map_err(|e| invalid(&e))?;
+        &decompressed[..]
+    } else {
+        point_offset
+            .checked_add(data_length)
+            .and_then(|end| bytes.get(point_offset..end))
+            .ok_or_else(|| invalid("truncated point data"))?
+    };
+
+    let shift = if center.iter().any(|c| c.abs() > SHIFT_THRESHOLD) {
+        Some(center.map(f64::round))
+    } else {
+        None
+    };
+    let origin = shift.unwrap_or([0.0; 3]);
+
+    let mut mesh = Mesh::new();
+    mesh.point_set = true;
+    mesh.offset = shift;
+    mesh.vertices.reserve(count);
+    let mut colors = Vec::new();
+    let mut intensity = Vec::with_capacity(count);
+    let mut classification = Vec::with_capacity(count);
+    let mut return_number = Vec::with_capacity(count);
+
+    for record in records.chunks_exact(record_length) {
+        let coordinate = |axis: usize| {
+            let raw = i32::from_le_bytes(record[axis * 4..axis * 4 + 4].try_into().unwrap_or_default());
+            (raw as f64 * scale[axis] + offset[axis] - origin[axis]) as f32
+        };
+        mesh.vertices.push(Vec3::new(coordinate(0), coordinate(1), coordinate(2)));
+        intensity.push(u16::from_le_bytes([record[12], record[13]]) as f64);
+
+        // Formats 6+ widen the return and classification fields
+        if format >= 6 {
+            return_number.push((record[14] & 0x0F) as f64);
+            classification.push(record[16] as f64);
+        } else {
+            return_number.push((record[14] & 0x07) as f64);
+            classification.push((record[15] & 0x1F) as f64);
+        }
+
+        if let Some(at) = rgb_offset {
+            let channel = |i: usize| u16::from_le_bytes([record[at + i * 2], record[at + i * 2 + 1]]);
+            colors.push([channel(0), channel(1), channel(2)]);
+        }
+    }
+
+    if mesh.vertices.is_empty() {
+        return Err(ModelParserError::NoMeshData);
+    }
+
+    if config.include_colors {
+        // The spec asks for 16-bit colors, but many writers store 8-bit values unscaled
+        let max = if colors.iter().flatten().all(|&c| c <= 255) { 255.0 } else { 65535.0 };
+        mesh.has_source_colors = !colors.is_empty();
+        mesh.colors = if mesh.has_source_colors {
+            colors
+                .iter()
+                .map(|c| Vec3::from(c.map(|v| color::srgb_to_linear(v as f32 / max))))
+                .collect()
+        } else {
+            vec![Vec3::ONE; mesh.vertices.len()]
+        };
+    }
+
+    mesh.point_attributes = vec![
+        (AttributeDescriptor::new("intensity", AttributeType::U16), intensity),
+        (AttributeDescriptor::new("classification", AttributeType::U8), classification),
+        (AttributeDescriptor::new("return_number", AttributeType::U8), return_number),
+    ];
+
+    Ok(mesh)
+}
+
+/// Largest accepted ratio of decompressed to compressed point data. LASzip typically reaches
+/// 5-20x, so a header claiming far more comes from a corrupt or crafted point count
+const MAX_LAZ_RATIO: usize = 1024;
+
+/// Point records of a LAZ file, `length` bytes decompressed with the LASzip parameters from
+/// its VLRs
+fn decompress(
+    bytes: &[u8],
+    header_size: usize,
+    vlr_count: usize,
+    point_offset: usize,
+    length: usize,
+) -> std::result::Result<Vec<u8>, String> {
+    let mut at = header_size;
+    let mut laz_vlr = None;
+    for _ in 0..vlr_count {
+        let header = bytes.get(at..at + VLR_HEADER_SIZE).ok_or("truncated VLR")?;
+        let user_id = header[2..18].split(|&b| b == 0).next().unwrap_or_default();
+        let record_id = u16::from_le_bytes([header[18], header[19]]);
+        let data_length = u16::from_le_bytes([header[20], header[21]]) as usize;
+        let data = bytes
+            .get(at + VLR_HEADER_SIZE..at + VLR_HEADER_SIZE + data_length)
+            .ok_or("truncated VLR")?;
+        if user_id == laz::LazVlr::USER_ID.as_bytes() && record_id == laz::LazVlr::RECORD_ID {
+            laz_vlr = Some(laz::LazVlr::from_buffer(data).map_err(|e| format!("LASzip VLR: {}", e))?);
+            break;
+        }
+        at += VLR_HEADER_SIZE + data_length;
+    }
+    let laz_vlr = laz_vlr.ok_or("compressed points without a LASzip VLR")?;
+
+    // Checked before allocating the output, which the header alone could make huge
+    let compressed = bytes.len().saturating_sub(point_offset);
+    if length > compressed.saturating_mul(MAX_LAZ_RATIO) {
+        return Err(format!(
+            "header claims {} bytes of point records, too many for {} compressed bytes",
+            length, compressed
+        ));
+    }
+
+    // The chunk table offset is absolute, so the decompressor reads the whole file
+    let mut source = std::io::Cursor::new(bytes);
+    source.set_position(point_offset as u64);
+    let mut decompressor =
+        laz::LasZipDecompressor::new(source, laz_vlr).map_err(|e| format!("LASzip: {}", e))?;
+    let mut records = vec![0; length];
+    decompressor
+        .decompress_many(&mut records)
+        .map_err(|e| format!("LASzip: {}", e))?;
+    Ok(records)
+}
+
+/// Size of the LAS 1.4 public header block
+pub(crate) const HEADER_SIZE: usize = 375;
+
+/// Size of a variable length record header
+pub(crate) const VLR_HEADER_SIZE: usize = 54;
+
+/// Size of an extended variable length record header
+pub(crate) const EVLR_HEADER_SIZE: usize = 60;
+
+/// Size of one Extra Bytes descriptor
+const EXTRA_BYTES_DESCRIPTOR_SIZE: usize = 192;
+
+/// Attributes stored in the standard point record fields instead of as extra bytes
+const STANDARD_ATTRIBUTES: [&str; 3] = ["intensity", "classification", "return_number"];
+
+/// Writes clouds as LAS 1.4 (point format 6, or 7 with colors), optionally LASzip compressed.
+/// Coordinates are stored as integers times a scale plus an offset; normals, alpha, material
+/// ids and generic attributes other than `intensity`, `classification` and `return_number`
+/// (which fill the standard fields) are described in an Extra Bytes VLR
+#[derive(Debug, Clone, PartialEq)]
+pub struct LasWriter {
+    scale: [f64; 3],
+    offset: Option<[f64; 3]>,
+    compress: bool,
+    creation_time: Option<u64>,
+}
+
+impl Default for LasWriter {
+    fn default() -> Self {
+        Self {
+            scale: [0.001; 3],
+            offset: None,
+            compress: false,
+            creation_time: None,
+        }
+    }
+}
+
+impl LasWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Coordinate resolution per axis in output units (default 0.001)
+    pub fn with_scale(mut self, scale: [f64; 3]) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    /// Header offset, in the cloud's real coordinates (its offset added back). By default the
+    /// rounded center of the cloud's bounds
+    pub fn with_offset(mut self, offset: Option<[f64; 3]>) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    /// Compress the point records with LASzip (.laz)
+    pub fn with_compression(mut self, compress: bool) -> Self {
+        self.compress = compress;
+        self
+    }
+
+    /// Creation date stored in the header, in seconds since the Unix epoch. By default the
+    /// current date; fix it for byte-identical output across runs
+    pub fn with_creation_time(mut self, seconds: Option<u64>) -> Self {
+        self.creation_time = seconds;
+        self
+    }
+
+    /// Header offset used for `point_cloud`
+    pub fn resolve_offset(&self, point_cloud: &PointCloud) -> [f64; 3] {
+        self.offset.unwrap_or_else(|| {
+            let metadata = &point_cloud.metadata;
+            let shift = metadata.offset.unwrap_or([0.0; 3]);
+            std::array::from_fn(|i| {
+                ((metadata.bounds_min[i] as f64 + metadata.bounds_max[i] as f64) * 0.5 + shift[i]).round()
+            })
+        })
+    }
+
+    /// Size of the output: the header and VLRs plus one record per point. LASzip output is
+    /// usually several times smaller, so this is an upper bound for it
+    pub fn estimate(&self, point_cloud: &PointCloud) -> SizeEstimate {
+        let extra = extra_dimensions(point_cloud);
+        let base_length = if point_cloud.metadata.has_colors { 36 } else { 30 };
+        let extra_length: usize = extra.iter().map(|dimension| dimension.data_type.size() as usize).sum();
+        let mut overhead = HEADER_SIZE;
+        if !extra.is_empty() {
+            overhead += VLR_HEADER_SIZE + extra.len() * EXTRA_BYTES_DESCRIPTOR_SIZE;
+        }
+        if self.compress {
+            // LASzip parameters and the chunk table
+            overhead += VLR_HEADER_SIZE + 256;
+        }
+        SizeEstimate {
+            bytes_per_point: (base_length + extra_length) as f64,
+            overhead: overhead as u64,
+        }
+    }
+
+    pub fn write(&self, point_cloud: &PointCloud, path: &Path) -> Result<()> {
+        let records = self.records(point_cloud)?;
+
+        // Variable length records: the Extra Bytes descriptors and the LASzip parameters
+        let mut vlrs: Vec<Vec<u8>> = records.extra_bytes_vlr().into_iter().collect();
+        let laz = if self.compress {
+            let (laz, record) = records.laz_vlr(false)?;
+            vlrs.push(record);
+            Some(laz)
+        } else {
+            None
+        };
+
+        let mut writer = BufWriter::new(std::fs::File::create(path)?);
+        writer.write_all(&records.header(&vlrs, self.compress, None))?;
+        vlrs.iter().try_for_each(|vlr| writer.write_all(vlr))?;
+
+        let mut record = Vec::with_capacity(records.record_length);
+        match laz {
+            Some(laz) => {
+                let mut compressor = laz::LasZipCompressor::new(&mut writer, laz)
+                    .map_err(|e| ModelParserError::InvalidData(format!("LASzip: {}", e)))?;
+                for index in 0..point_cloud.points.len() {
+                    records.encode(index, &mut record);
+                    compressor.compress_one(&record)?;
+                }
+                compressor.done()?;
+            }
+            None => {
+                for index in 0..point_cloud.points.len() {
+                    records.encode(index, &mut record);
+                    writer.write_all(&record)?;
+                }
+            }
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Quantize the points of `point_cloud` into LAS records
+    pub(crate) fn records<'a>(&self, point_cloud: &'a PointCloud) -> Result<LasRecords<'a>> {
+        if self.scale.iter().any(|&scale| !(scale.is_finite() && scale > 0.0)) {
+            return Err(ModelParserError::InvalidData(format!("LAS scale must be positive, got {:?}", self.scale)));
+        }
+
+        let metadata = &point_cloud.metadata;
+        let shift = metadata.offset.unwrap_or([0.0; 3]);
+        let offset = self.resolve_offset(point_cloud);
+        let extra = extra_dimensions(point_cloud);
+        let standard = STANDARD_ATTRIBUTES.map(|name| point_cloud.attribute_index(name));
+        let format: u8 = if metadata.has_colors { 7 } else { 6 };
+        let base_length = if metadata.has_colors { 36 } else { 30 };
+        let extra_length: usize = extra.iter().map(|dimension| dimension.data_type.size() as usize).sum();
+
+        // Quantize first: coordinates outside the i32 range are an error, and the header needs
+        // the exact bounds and the return counts
+        let mut coordinates = Vec::with_capacity(point_cloud.points.len());
+        let mut min = [f64::MAX; 3];
+        let mut max = [f64::MIN; 3];
+        let mut returns = [0u64; 15];
+        for point in &point_cloud.points {
+            let mut quantized = [0i32; 3];
+            for axis in 0..3 {
+                let value = ((point.position[axis] as f64 + shift[axis] - offset[axis]) / self.scale[axis]).round();
+                if !(i32::MIN as f64..=i32::MAX as f64).contains(&value) {
+                    return Err(ModelParserError::InvalidData(format!(
+                        "coordinates do not fit LAS integers at scale {:?}; use a coarser scale or an offset nearer the data",
+                        self.scale
+                    )));
+                }
+                quantized[axis] = value as i32;
+                let real = value * self.scale[axis] + offset[axis];
+                min[axis] = min[axis].min(real);
+                max[axis] = max[axis].max(real);
+            }
+            coordinates.push(quantized);
+            returns[return_number(point, standard[2]) as usize - 1] += 1;
+        }
+        if point_cloud.points.is_empty() {
+            (min, max) = ([0.0; 3], [0.0; 3]);
+        }
+
+        Ok(LasRecords {
+            point_cloud,
+            scale: self.scale,
+            offset,
+            extra,
+            standard,
+            format,
+            extra_length,
+            record_length: base_length + extra_length,
+            coordinates,
+            min,
+            max,
+            returns,
+        })
+    }
+}
+
+/// Points of a cloud laid out as LAS 1.4 records, with the header fields derived from them
+pub(crate) struct LasRecords<'a> {
+    point_cloud: &'a PointCloud,
+    scale: [f64; 3],
+    offset: [f64; 3],
+    extra: Vec<ExtraDimension>,
+    standard: [Option<usize>; 3],
+    format: u8,
+    extra_length: usize,
+    record_length: usize,
+    coordinates: Vec<[i32; 3]>,
+    min: [f64; 3],
+    max: [f64; 3],
+    returns: [u64; 15],
+}
+
+impl LasRecords<'_> {
+    /// Extra Bytes VLR describing the extra dimensions, if there are any
+    pub(crate) fn extra_bytes_vlr(&self) -> Option<Vec<u8>> {
+        if self.extra.is_empty() {
+            return None;
+        }
+        let mut descriptors = Vec::with_capacity(self.extra.len() * EXTRA_BYTES_DESCRIPTOR_SIZE);
+        for dimension in &self.extra {
+            let mut descriptor = [0u8; EXTRA_BYTES_DESCRIPTOR_SIZE];
+            descriptor[2] = extra_bytes_type(dimension.data_type);
+            write_text(&mut descriptor[4..36], &dimension.name);
+            write_text(&mut descriptor[160..192], &dimension.name);
+            descriptors.extend_from_slice(&descriptor);
+        }
+        Some(vlr("LASF_Spec", 4, "Extra Bytes", &descriptors))
+    }
+
+    /// LASzip parameters for these records and their VLR. Chunks hold 50,000 points, or as
+    /// many as the writer puts in each with `variable_chunks`
+    pub(crate) fn laz_vlr(&self, variable_chunks: bool) -> Result<(laz::LazVlr, Vec<u8>)> {
+        let items = laz::LazItemRecordBuilder::default_for_point_format_id(self.format, self.extra_length as u16)
+            .map_err(|e| ModelParserError::InvalidData(format!("LASzip: {}", e)))?;
+        let mut builder = laz::LazVlrBuilder::new(items);
+        if variable_chunks {
+            builder = builder.with_variable_chunk_size();
+        }
+        let laz = builder.build();
+        let mut data = Vec::new();
+        laz.write_to(&mut data)?;
+        let record = vlr(laz::LazVlr::USER_ID, laz::LazVlr::RECORD_ID, "LASzip compression", &data);
+        Ok((laz, record))
+    }
+
+    /// Public header block for a file with `vlrs`, and `evlrs` as (start, count)
+    pub(crate) fn header(&self, vlrs: &[Vec<u8>], compressed: bool, evlrs: Option<(u64, u32)>) -> Vec<u8> {
+        let point_offset = HEADER_SIZE + vlrs.iter().map(Vec::len).sum::<usize>();
+        let mut header = vec![0u8; HEADER_SIZE];
+        header[0..4].copy_from_slice(b"LASF");
+        // Global encoding: coordinate reference systems of formats 6+ are WKT
+        header[6..8].copy_from_slice(&0x10u16.to_le_bytes());
+        header[24] = 1;
+        header[25] = 4;
+        write_text(&mut header[26..58], "OTHER");
+        write_text(&mut header[58..90], concat!("model_parser ", env!("CARGO_PKG_VERSION")));
+        let (day, year) = creation_date(self.creation_time.unwrap_or_else(|| {
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_secs())
+        }));
+        header[90..92].copy_from_slice(&day.to_le_bytes());
+        header[92..94].copy_from_slice(&year.to_le_bytes());
+        header[94..96].copy_from_slice(&(HEADER_SIZE as u16).to_le_bytes());
+        header[96..100].copy_from_slice(&(point_offset as u32).to_le_bytes());
+        header[100..104].copy_from_slice(&(vlrs.len() as u32).to_le_bytes());
+        // The two high bits of the format byte flag LAZ compression
+        header[104] = if compressed { self.format | 0x80 } else { self.format };
+        header[105..107].copy_from_slice(&(self.record_length as u16).to_le_bytes());
+        // The legacy point counts stay 0, as formats 6+ require
+        for axis in 0..3 {
+            header[131 + axis * 8..139 + axis * 8].copy_from_slice(&self.scale[axis].to_le_bytes());
+            header[155 + axis * 8..163 + axis * 8].copy_from_slice(&self.offset[axis].to_le_bytes());
+            header[179 + axis * 16..187 + axis * 16].copy_from_slice(&self.max[axis].to_le_bytes());
+            header[187 + axis * 16..195 + axis * 16].copy_from_slice(&self.min[axis].to_le_bytes());
+        }
+        if let Some((start, count)) = evlrs {
+            header[235..243].copy_from_slice(&start.to_le_bytes());
+            header[243..247].copy_from_slice(&count.to_le_bytes());
+        }
+        header[247..255].copy_from_slice(&(self.coordinates.len() as u64).to_le_bytes());
+        for (i, count) in self.returns.iter().enumerate() {
+            header[255 + i * 8..263 + i * 8].copy_from_slice(&count.to_le_bytes());
+        }
+        header
+    }
+
+    /// Encode the record of the point at `index` into `record`
+    pub(crate) fn encode(&self, index: usize, record: &mut Vec<u8>) {
+        let point = &self.point_cloud.points[index];
+        let metadata = &self.point_cloud.metadata;
+        record.clear();
+        self.coordinates[index].iter().for_each(|c| record.extend_from_slice(&c.to_le_bytes()));
+        let attribute = |index: Option<usize>| index.and_then(|i| point.attributes.get(i).copied()).unwrap_or(0.0);
+        let intensity = attribute(self.standard[0]).round().clamp(0.0, u16::MAX as f64) as u16;
+        record.extend_from_slice(&intensity.to_le_bytes());
+        // Return number of number of returns, both the same as a sampled point is its only return
+        let returns = return_number(point, self.standard[2]);
+        record.push(returns | (returns << 4));
+        record.push(0);
+        record.push(attribute(self.standard[1]).round().clamp(0.0, u8::MAX as f64) as u8);
+        // User data, scan angle, point source id and GPS time
+        record.extend_from_slice(&[0; 13]);
+        if metadata.has_colors {
+            let mut rgb = point.color.unwrap_or_default();
+            if metadata.color_space == ColorSpace::Linear {
+                rgb = rgb.map(color::linear_to_srgb);
+            }
+            color::to_rgb16(rgb).iter().for_each(|c| record.extend_from_slice(&c.to_le_bytes()));
+        }
+        for dimension in &self.extra {
+            dimension.data_type.encode((dimension.value)(point), record);
+        }
+    }
+
+    /// Size of one record
+    pub(crate) fn record_length(&self) -> usize {
+        self.record_length
+    }
+}
+
+/// A dimension written as extra bytes
+struct ExtraDimension {
+    name: String,
+    data_type: AttributeType,
+    value: Box<dyn Fn(&Point) -> f64>,
+}
+
+/// Extra bytes dimensions of `point_cloud`, named as in the EPT schema
+fn extra_dimensions(point_cloud: &PointCloud) -> Vec<ExtraDimension> {
+    let metadata = &point_cloud.metadata;
+    let mut dimensions = Vec::new();
+    if metadata.has_normals {
+        for (axis, name) in ["NormalX", "NormalY", "NormalZ"].into_iter().enumerate() {
+            dimensions.push(ExtraDimension {
+                name: name.to_string(),
+                data_type: AttributeType::F32,
+                value: Box::new(move |point| point.normal.map_or(0.0, |n| n[axis] as f64)),
+            });
+        }
+    }
+    if metadata.has_alpha {
+        dimensions.push(ExtraDimension {
+            name: "Alpha".to_string(),
+            data_type: AttributeType::U8,
+            value: Box::new(|point| color::to_u8(point.alpha.unwrap_or(1.0)) as f64),
+        });
+    }
+    if metadata.has_material_ids {
+        dimensions.push(ExtraDimension {
+            name: "MaterialId".to_string(),
+            data_type: AttributeType::U32,
+            value: Box::new(|point| point.material_id.unwrap_or(0) as f64),
+        });
+    }
+    for (index, attribute) in metadata.attributes.iter().enumerate() {
+        if !STANDARD_ATTRIBUTES.contains(&attribute.name.as_str()) {
+            dimensions.push(ExtraDimension {
+                name: attribute.name.clone(),
+                data_type: attribute.data_type,
+                value: Box::new(move |point| point.attributes.get(index).copied().unwrap_or(0.0)),
+            });
+        }
+    }
+    dimensions
+}
+
+/// Return number of a point (1-15), 1 unless the cloud carries `return_number`
+fn return_number(point: &Point, index: Option<usize>) -> u8 {
+    index
+        .and_then(|i| point.attributes.get(i))
+        .map_or(1, |&value| value.round().clamp(1.0, 15.0) as u8)
+}
+
+/// Extra Bytes data type code of an attribute type
+fn extra_bytes_type(data_type: AttributeType) -> u8 {
+    match data_type {
+        AttributeType::U8 => 1,
+        AttributeType::U16 => 3,
+        AttributeType::U32 => 5,
+        AttributeType::I32 => 6,
+        AttributeType::F32 => 9,
+        AttributeType::F64 => 10,
+    }
+}
+
+/// A variable length record: header and data
+pub(crate) fn vlr(user_id: &str, record_id: u16, description: &str, data: &[u8]) -> Vec<u8> {
+    let mut record = vec![0u8; VLR_HEADER_SIZE];
+    write_text(&mut record[2..18], user_id);
+    record[18..20].copy_from_slice(&record_id.to_le_bytes());
+    record[20..22].copy_from_slice(&(data.len() as u16).to_le_bytes());
+    write_text(&mut record[22..54], description);
+    record.extend_from_slice(data);
+    record
+}
+
+/// An extended variable length record, for data longer than a VLR can hold: header and data
+pub(crate) fn evlr(user_id: &str, record_id: u16, description: &str, data: &[u8]) -> Vec<u8> {
+    let mut record = vec![0u8; EVLR_HEADER_SIZE];
+    write_text(&mut record[2..18], user_id);
+    record[18..20].copy_from_slice(&record_id.to_le_bytes());
+    record[20..28].copy_from_slice(&(data.len() as u64).to_le_bytes());
+    write_text(&mut record[28..60], description);
+    record.extend_from_slice(data);
+    record
+}
+
+/// Copy `text` into a fixed-size, zero-padded field, truncating it to fit
+fn write_text(field: &mut [u8], text: &str) {
+    let length = text.len().min(field.len());
+    field[..length].copy_from_slice(&text.as_bytes()[..length]);
+}
+
+/// (day of year, year) in UTC of a Unix time, for the header's creation date
+fn creation_date(seconds: u64) -> (u16, u16) {
+    let mut days = seconds / 86_400;
+    let mut year = 1970u64;
+    loop {
+        let length = if year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400)) { 366 } else { 365 };
+        if days < length {
+            return (days as u16 + 1, year as u16);
+        }
+        days -= length;
+        year += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::point_cloud::test_cloud;
+
+    fn round_trip(writer: LasWriter) {
+        let cloud = test_cloud();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cloud.las");
+        writer.write(&cloud, &path).unwrap();
+        let mesh = load(&path, &PointCloudConfig::default()).unwrap();
+
+        assert_eq!(mesh.vertices.len(), cloud.points.len());
+        assert!(mesh.has_source_colors);
+        let (_, intensity) = mesh.point_attributes.iter().find(|(a, _)| a.name == "intensity").unwrap();
+        for (i, point) in cloud.points.iter().enumerate() {
+            // Coordinates are quantized to the default 0.001 scale
+            let position = Vec3::from(point.position);
+            assert!((mesh.vertices[i] - position).abs().max_element() < 6e-4, "point {}", i);
+            let color = Vec3::from(point.color.unwrap());
+            assert!((mesh.colors[i] - color).abs().max_element() < 1e-3, "point {}", i);
+            assert_eq!(intensity[i], point.attributes[0], "point {}", i);
+        }
+    }
+
+    #[test]
+    fn las_round_trip() {
+        round_trip(LasWriter::new());
+    }
+
+    #[test]
+    fn laz_round_trip() {
+        round_trip(LasWriter::new().with_compression(true));
+    }
+
+    #[test]
+    fn fixed_creation_date() {
+        assert_eq!(creation_date(0), (1, 1970));
+        // 2024-12-31, the last day of a leap year
+        assert_eq!(creation_date(1_735_603_200), (366, 2024));
+    }
+}
@@ -1,11 +1,103 @@
+pub mod animation;
+pub mod attributes;
+pub mod budget;
+pub mod buffers;
+pub mod bvh;
+pub mod collada;
+pub mod bundle;
+pub mod color;
+pub mod colormap;
+pub mod composite;
+pub mod copc;
+pub mod coverage;
+pub mod decimate;
+pub mod dimensions;
+pub mod download;
+pub mod e57;
 pub mod error;
+pub mod expression;
+pub mod extract;
+pub mod fbx;
+pub mod filters;
+pub mod fixtures;
+pub mod grading;
+pub mod hgpc;
+pub mod hilbert;
+pub mod holes;
+pub mod hooks;
+pub mod instances;
+pub mod las;
+pub mod lidar;
+pub mod lighting;
+pub mod lod;
+pub mod manifest;
+pub mod mesh;
+pub mod obj;
+pub mod octahedral;
+pub mod palette;
+pub mod pcd;
+pub mod ply;
 pub mod point_cloud;
+pub mod profiling;
+pub mod random;
+pub mod slicing;
+pub mod report;
+pub mod selection;
+pub mod sequence;
+pub mod spatial;
+pub mod storage;
+pub mod stylize;
+pub mod stl;
+pub mod texture;
+pub mod tiles3d;
+pub mod validation;
+pub mod viewer;
+pub mod weld;
+pub mod xyz;
+mod xml;
 pub mod parser;
 pub mod config;
 pub mod ept;
 
+pub use animation::AnimationPose;
+pub use attributes::{AttributeDescriptor, AttributeType};
+pub use buffers::{BufferArray, BufferDescriptor};
+pub use bvh::{Bvh, SurfacePoint};
+pub use color::{ColorDepth, ColorSpace, Dither};
+pub use colormap::Colormap;
+pub use composite::{Composition, CompositeDataset, CompositeReport, EptSource};
+pub use copc::CopcWriter;
+pub use coverage::CoverageMetrics;
 pub use error::ModelParserError;
-pub use point_cloud::{PointCloud, Point};
+pub use expression::Expression;
+pub use extract::{EptExtraction, ExtractionReport};
+pub use grading::ColorGrading;
+pub use holes::{Hole, HoleReport};
+pub use hooks::PipelineHooks;
+pub use filters::{FilterPipeline, FilterRegistry, PointFilter};
+pub use instances::{Placement, Placements};
+pub use las::LasWriter;
+pub use lidar::{LidarNoise, VirtualScanner};
+pub use lighting::LightBaking;
+pub use lod::{EptHierarchy, Frustum, LodQuery};
+pub use manifest::Manifest;
+pub use mesh::Mesh;
+pub use octahedral::NormalEncoding;
+pub use point_cloud::{Centering, JsonLayout, LoadLimits, PointCloud, PointCloudArrays, Point, RobustBounds};
+pub use palette::Palette;
 pub use parser::ModelParser;
-pub use config::{PointCloudConfig, SamplingStrategy};
-pub use ept::{EptBuilder, EptMetadata, OctreeKey};
+pub use pcd::PcdData;
+pub use profiling::{Profiler, StageTiming};
+pub use report::RunReport;
+pub use selection::NameFilter;
+pub use sequence::{Sequence, SequenceFrame};
+pub use slicing::{Section, Slab};
+pub use spatial::KdTree;
+pub use storage::{ObjectStore, Staging, Uploader};
+pub use stylize::Stylize;
+pub use tiles3d::TilesetWriter;
+pub use validation::{ValidationCounts, ValidationPolicy};
+pub use viewer::ViewerConfig;
+pub use xyz::ColumnMapping;
+pub use config::{AmbientOcclusion, JitterMode, NormalOrientation, PointCloudConfig, SamplingStrategy, ScalePivot, SplatRadius, UpAxis};
+pub use ept::{EptBuildReport, EptBuilder, EptMetadata, OctreeKey, OutOfBounds, PositionEncoding, TileEntry, TileIndex};
@@ -0,0 +1,247 @@
+use crate::{
+    attributes::AttributeType,
+    bvh::Bvh,
+    color,
+    config::PointCloudConfig,
+    mesh::Mesh,
+    point_cloud::{Point, PointCloud},
+    random,
+};
+use glam::Vec3;
+use rand::Rng;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Scanner noise model applied to generated points
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LidarNoise {
+    /// Standard deviation of the range error along the beam (output units)
+    pub range_sigma: f32,
+
+    /// Probability (0.0-1.0) that a return is dropped
+    pub dropout: f32,
+
+    /// Sensor position; beams run from here to each point. Without a sensor the
+    /// range error is applied along the point normal and intensity ignores range
+    pub sensor: Option<[f32; 3]>,
+
+    /// Exponential intensity falloff per unit of range
+    pub intensity_falloff: f32,
+}
+
+impl Default for LidarNoise {
+    fn default() -> Self {
+        Self {
+            range_sigma: 0.005,
+            dropout: 0.0,
+            sensor: None,
+            intensity_falloff: 0.0,
+        }
+    }
+}
+
+/// Standard normal sample (Box-Muller)
+fn gaussian(rng: &mut impl Rng) -> f32 {
+    let u1 = rng.random::<f32>().max(f32::MIN_POSITIVE);
+    let u2 = rng.random::<f32>();
+    (-2.0 * u1.ln()).sqrt() * (std::f32::consts::TAU * u2).cos()
+}
+
+/// Perturb positions, drop returns and store an "intensity" attribute (0-65535)
+pub fn apply_noise(point_cloud: &mut PointCloud, noise: &LidarNoise, seed: Option<u64>) {
+    let sensor = noise.sensor.map(Vec3::from);
+    let dropout = noise.dropout.clamp(0.0, 1.0);
+
+    let kept: Vec<Option<f64>> = point_cloud
+        .points
+        .par_chunks_mut(random::CHUNK_SIZE)
+        .enumerate()
+        .flat_map_iter(|(chunk, points)| {
+            let mut rng = random::chunk_rng(seed, random::stream::LIDAR_NOISE, chunk as u64);
+            points.iter_mut().map(move |point| {
+                if dropout > 0.0 && rng.random::<f32>() < dropout {
+                    return None;
+                }
+
+                let position = Vec3::from(point.position);
+                let normal = point
+                    .normal
+                    .map(Vec3::from)
+                    .filter(|n| n.is_finite() && n.length_squared() > 0.0)
+                    .map(|n| n.normalize());
+
+                // Beam direction and range from the sensor, or along the normal without one
+                let (beam, range) = match sensor {
+                    Some(sensor) => {
+                        let to_point = position - sensor;
+                        (to_point.normalize_or_zero(), to_point.length())
+                    }
+                    None => (normal.map(|n| -n).unwrap_or(Vec3::ZERO), 0.0),
+                };
+
+                if noise.range_sigma > 0.0 {
+                    let error = gaussian(&mut rng) * noise.range_sigma;
+                    point.position = (position + beam * error).to_array();
+                }
+
+                let incidence = match (normal, sensor) {
+                    (Some(n), Some(_)) => n.dot(-beam).abs(),
+                    _ => 1.0,
+                };
+                let intensity = incidence * (-noise.intensity_falloff * range).exp();
+
+                Some((intensity.clamp(0.0, 1.0) * 65535.0).round() as f64)
+            })
+        })
+        .collect();
+
+    let mut intensities = Vec::with_capacity(kept.len());
+    let mut keep = kept.iter();
+    point_cloud.points.retain(|_| match keep.next() {
+        Some(Some(intensity)) => {
+            intensities.push(*intensity);
+            true
+        }
+        _ => false,
+    });
+
+    point_cloud.set_attribute_values("intensity", AttributeType::U16, &intensities);
+    point_cloud.update_metadata();
+}
+
+/// Store a plausible "intensity" attribute (0-65535) for sampled meshes from each point's
+/// albedo luminance and material: rough surfaces scatter the beam back like a diffuse
+/// reflector, while smooth metals mirror it away. An existing intensity from
+/// [`apply_noise`] is scaled by this reflectance, so incidence and range falloff still apply
+pub fn synthesize_intensity(point_cloud: &mut PointCloud, mesh: &Mesh) {
+    let existing = point_cloud.attribute_values("intensity");
+    let reflectance: Vec<f64> = point_cloud
+        .points
+        .par_iter()
+        .enumerate()
+        .map(|(index, point)| {
+            // Colors are still linear here; points without one count as white
+            let albedo = point.color.map_or(1.0, |c| color::luminance(Vec3::from(c)));
+            let [metallic, roughness] = point
+                .material_id
+                .map_or([0.0, 1.0], |id| mesh.metallic_roughness_of(id))
+                .map(|f| f.clamp(0.0, 1.0));
+            let reflectance = (albedo * (1.0 - metallic * (1.0 - roughness))).clamp(0.0, 1.0) as f64;
+            match &existing {
+                Some(values) => (values[index] * reflectance).round(),
+                None => (reflectance * 65535.0).round(),
+            }
+        })
+        .collect();
+
+    point_cloud.set_attribute_values("intensity", AttributeType::U16, &reflectance);
+}
+
+/// Terrestrial scanner placement and beam pattern for `SamplingStrategy::Scanner`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VirtualScanner {
+    /// Sensor positions in output coordinates; the mesh bounds center is used when empty
+    pub positions: Vec<[f32; 3]>,
+
+    /// Angle between neighbouring beams around the vertical axis, in degrees
+    pub horizontal_resolution: f32,
+
+    /// Angle between neighbouring beams in elevation, in degrees
+    pub vertical_resolution: f32,
+
+    /// Elevation range of the beams [min, max] in degrees (0 = horizontal)
+    pub vertical_fov: [f32; 2],
+
+    /// Maximum beam range in output units
+    pub max_range: f32,
+}
+
+impl Default for VirtualScanner {
+    fn default() -> Self {
+        Self {
+            positions: Vec::new(),
+            horizontal_resolution: 0.5,
+            vertical_resolution: 0.5,
+            vertical_fov: [-60.0, 90.0],
+            max_range: f32::MAX,
+        }
+    }
+}
+
+/// Cast the scanner beam pattern from every sensor and keep the first hit of each beam.
+/// Hits record the sensor index in the "scan_id" attribute
+pub fn scan(mesh: &Mesh, config: &PointCloudConfig, scanner: &VirtualScanner) -> Vec<Point> {
+    let scale = if config.scale != 0.0 { config.scale } else { 1.0 };
+    let has_normals = config.include_normals && !mesh.normals.is_empty();
+    let has_colors = config.include_colors && !mesh.colors.is_empty();
+    let has_material_ids = !mesh.material_ids.is_empty();
+
+    let sensors: Vec<Vec3> = if scanner.positions.is_empty() {
+        let (min, max) = mesh.bounds();
+        vec![(min + max) * 0.5]
+    } else {
+        scanner.positions.iter().map(|p| Vec3::from(*p) / scale).collect()
+    };
+    let max_range = scanner.max_range / scale.abs();
+    let bvh = Bvh::new(mesh);
+
+    // Beam directions, with Y as the vertical axis
+    let h_step = scanner.horizontal_resolution.max(0.01).to_radians();
+    let v_step = scanner.vertical_resolution.max(0.01).to_radians();
+    let [v_min, v_max] = scanner.vertical_fov.map(f32::to_radians);
+    let h_count = (std::f32::consts::TAU / h_step).round() as usize;
+    let v_count = ((v_max - v_min) / v_step).floor() as usize + 1;
+    let directions: Vec<Vec3> = (0..v_count)
+        .flat_map(|v| {
+            let elevation = v_min + v as f32 * v_step;
+            (0..h_count).map(move |h| {
+                let azimuth = h as f32 * h_step;
+                Vec3::new(
+                    elevation.cos() * azimuth.cos(),
+                    elevation.sin(),
+                    elevation.cos() * azimuth.sin(),
+                )
+            })
+        })
+        .collect();
+
+    sensors
+        .iter()
+        .enumerate()
+        .flat_map(|(scan_id, &sensor)| {
+            directions
+                .par_iter()
+                .filter_map(|&direction| {
+                    let (triangle, distance) = bvh.intersect_ray(sensor, direction, max_range)?;
+                    let hit = sensor + direction * distance;
+                    let weights = mesh.barycentric(triangle, hit);
+                    let corners = [
+                        mesh.indices[triangle * 3],
+                        mesh.indices[triangle * 3 + 1],
+                        mesh.indices[triangle * 3 + 2],
+                    ];
+                    let interpolate = |values: &[Vec3]| {
+                        values[corners[0]] * weights.x + values[corners[1]] * weights.y + values[corners[2]] * weights.z
+                    };
+
+                    let mut point = Point::new(hit * scale);
+                    if has_normals {
+                        point = point.with_normal(interpolate(&mesh.normals).normalize_or_zero());
+                    }
+                    if has_colors {
+                        point = point.with_color(interpolate(&mesh.colors));
+                    }
+                    if has_colors && !mesh.alphas.is_empty() {
+                        let alpha = &mesh.alphas;
+                        point = point.with_alpha(alpha[corners[0]] * weights.x + alpha[corners[1]] * weights.y + alpha[corners[2]] * weights.z);
+                    }
+                    if has_material_ids {
+                        point = point.with_material_id(mesh.material_ids[corners[0]]);
+                    }
+                    point.attributes = vec![scan_id as f64];
+                    Some(point)
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
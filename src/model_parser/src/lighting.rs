@@ -0,0 +1,53 @@
+use crate::point_cloud::PointCloud;
+use glam::Vec3;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Directional plus ambient lighting baked into point colors
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LightBaking {
+    /// Direction pointing toward the light
+    pub direction: [f32; 3],
+
+    /// Strength of the directional (Lambertian) term
+    pub intensity: f32,
+
+    /// Strength of the ambient term, attenuated by the "occlusion" attribute when present
+    pub ambient: f32,
+}
+
+impl Default for LightBaking {
+    fn default() -> Self {
+        Self {
+            direction: [0.3, 1.0, 0.5],
+            intensity: 0.8,
+            ambient: 0.3,
+        }
+    }
+}
+
+/// Multiply point colors by the lighting term; colors must still be linear.
+/// Points without a color are lit as white, points without a normal get the ambient term only
+pub fn bake(point_cloud: &mut PointCloud, lighting: &LightBaking) {
+    let light = Vec3::from(lighting.direction).normalize_or_zero();
+    let occlusion_index = point_cloud.attribute_index("occlusion");
+
+    point_cloud.points.par_iter_mut().for_each(|point| {
+        let occlusion = occlusion_index
+            .and_then(|i| point.attributes.get(i).copied())
+            .unwrap_or(0.0) as f32;
+
+        let diffuse = point
+            .normal
+            .map(Vec3::from)
+            .filter(|n| n.is_finite())
+            .map(|n| n.normalize_or_zero().dot(light).max(0.0))
+            .unwrap_or(0.0);
+
+        let shade = lighting.ambient * (1.0 - occlusion) + lighting.intensity * diffuse;
+        let base = point.color.map(Vec3::from).unwrap_or(Vec3::ONE);
+        point.color = Some((base * shade).clamp(Vec3::ZERO, Vec3::ONE).to_array());
+    });
+
+    point_cloud.metadata.has_colors = true;
+}
@@ -0,0 +1,183 @@
+use crate::{
+    ept::{EptMetadata, GEOMETRIC_ERROR_FILE, OctreeKey},
+    error::Result,
+};
+use glam::{DVec3, DVec4, Mat4};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Node point counts of an EPT octree, for choosing which tiles to stream
+#[derive(Debug, Clone)]
+pub struct EptHierarchy {
+    /// Cubic root bounds [minx, miny, minz, maxx, maxy, maxz]
+    pub bounds: [f64; 6],
+
+    /// Grid resolution of a node; its geometric error is edge length / span
+    pub span: u32,
+
+    /// Points stored per node
+    pub nodes: HashMap<OctreeKey, u64>,
+
+    /// Geometric error per node, from `ept-errors.json` when the dataset has one
+    pub errors: HashMap<OctreeKey, f64>,
+}
+
+impl EptHierarchy {
+    /// Read ept.json and the root hierarchy file from an EPT directory
+    pub fn load(dir: &Path) -> Result<Self> {
+        let metadata: EptMetadata = serde_json::from_str(&std::fs::read_to_string(dir.join("ept.json"))?)?;
+        let counts: HashMap<String, i64> = serde_json::from_str(&std::fs::read_to_string(
+            dir.join("ept-hierarchy").join("0-0-0-0.json"),
+        )?)?;
+
+        let nodes = counts
+            .into_iter()
+            .filter(|(_, count)| *count > 0)
+            .filter_map(|(key, count)| Some((OctreeKey::from_path_string(&key)?, count as u64)))
+            .collect();
+
+        // Datasets written before errors were stored, or by other tools, estimate them from the span
+        let errors = match std::fs::read_to_string(dir.join(GEOMETRIC_ERROR_FILE)) {
+            Ok(json) => serde_json::from_str::<HashMap<String, f64>>(&json)?
+                .into_iter()
+                .filter_map(|(key, error)| Some((OctreeKey::from_path_string(&key)?, error)))
+                .collect(),
+            Err(_) => HashMap::new(),
+        };
+
+        Ok(Self {
+            bounds: metadata.bounds,
+            span: metadata.span,
+            nodes,
+            errors,
+        })
+    }
+
+    /// Distance in world units below which points of `key` are not distinguishable: the
+    /// stored error, or edge length / span when there is none
+    pub fn geometric_error(&self, key: &OctreeKey) -> f64 {
+        if let Some(&error) = self.errors.get(key) {
+            return error;
+        }
+        let bounds = key.bounds(&self.bounds);
+        (bounds[3] - bounds[0]) / self.span.max(1) as f64
+    }
+
+    /// Nodes to load for a view: every visible node is refined into its children while
+    /// its projected geometric error exceeds the query threshold. Parents precede children
+    pub fn select(&self, query: &LodQuery) -> Vec<OctreeKey> {
+        let mut selected = Vec::new();
+        let mut budget = query.point_budget;
+        let mut queue = std::collections::VecDeque::from([OctreeKey::root()]);
+
+        // Breadth-first so coarse nodes claim the point budget before finer ones
+        while let Some(key) = queue.pop_front() {
+            let Some(&count) = self.nodes.get(&key) else {
+                continue;
+            };
+            let bounds = key.bounds(&self.bounds);
+            if !query.frustum.intersects_box(&bounds) {
+                continue;
+            }
+            if count > budget {
+                break;
+            }
+            budget -= count;
+            selected.push(key);
+
+            if query.screen_space_error(self.geometric_error(&key), &bounds) > query.max_error {
+                queue.extend(key.children());
+            }
+        }
+
+        selected
+    }
+}
+
+/// View frustum as six inward-facing planes (a, b, c, d): inside where a*x + b*y + c*z + d >= 0
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Frustum {
+    pub planes: [DVec4; 6],
+}
+
+impl Frustum {
+    /// Extract the planes from a view-projection matrix (OpenGL clip space, -w <= z <= w)
+    pub fn from_matrix(view_projection: Mat4) -> Self {
+        let m = view_projection.as_dmat4().transpose();
+        let (r0, r1, r2, r3) = (m.x_axis, m.y_axis, m.z_axis, m.w_axis);
+        let planes = [r3 + r0, r3 - r0, r3 + r1, r3 - r1, r3 + r2, r3 - r2].map(|p| {
+            let length = p.truncate().length();
+            if length > 0.0 { p / length } else { p }
+        });
+        Self { planes }
+    }
+
+    /// Whether an axis-aligned box [minx, miny, minz, maxx, maxy, maxz] is at least partly inside
+    pub fn intersects_box(&self, bounds: &[f64; 6]) -> bool {
+        self.planes.iter().all(|plane| {
+            // Corner furthest along the plane normal
+            let corner = DVec3::new(
+                if plane.x >= 0.0 { bounds[3] } else { bounds[0] },
+                if plane.y >= 0.0 { bounds[4] } else { bounds[1] },
+                if plane.z >= 0.0 { bounds[5] } else { bounds[2] },
+            );
+            plane.truncate().dot(corner) + plane.w >= 0.0
+        })
+    }
+}
+
+/// Camera parameters for `EptHierarchy::select`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LodQuery {
+    pub frustum: Frustum,
+
+    /// Camera position in world units
+    pub camera_position: DVec3,
+
+    /// Vertical field of view in radians
+    pub fov_y: f64,
+
+    /// Viewport height in pixels
+    pub screen_height: f64,
+
+    /// Largest acceptable projected error in pixels
+    pub max_error: f64,
+
+    /// Stop selecting once this many points are chosen
+    pub point_budget: u64,
+}
+
+impl LodQuery {
+    /// Query for a perspective camera; the frustum comes from `view_projection`
+    pub fn new(view_projection: Mat4, camera_position: DVec3, fov_y: f64, screen_height: f64) -> Self {
+        Self {
+            frustum: Frustum::from_matrix(view_projection),
+            camera_position,
+            fov_y,
+            screen_height,
+            max_error: 1.0,
+            point_budget: u64::MAX,
+        }
+    }
+
+    pub fn with_max_error(mut self, pixels: f64) -> Self {
+        self.max_error = pixels;
+        self
+    }
+
+    pub fn with_point_budget(mut self, points: u64) -> Self {
+        self.point_budget = points;
+        self
+    }
+
+    /// Projected size in pixels of `error` at the nearest point of `bounds`
+    pub fn screen_space_error(&self, error: f64, bounds: &[f64; 6]) -> f64 {
+        let min = DVec3::new(bounds[0], bounds[1], bounds[2]);
+        let max = DVec3::new(bounds[3], bounds[4], bounds[5]);
+        let distance = self.camera_position.clamp(min, max).distance(self.camera_position);
+        if distance <= f64::EPSILON {
+            return f64::INFINITY;
+        }
+        error * self.screen_height / (2.0 * (self.fov_y * 0.5).tan() * distance)
+    }
+}
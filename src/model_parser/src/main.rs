@@ -1,28 +1,56 @@
 use clap::Parser;
-use model_parser::{ModelParser, PointCloudConfig, SamplingStrategy, EptBuilder};
+use model_parser::{AmbientOcclusion, AnimationPose, ColorGrading, EptExtraction, JsonLayout, Sequence, ObjectStore, Staging, Uploader, Palette, Centering, ColorDepth, Colormap, ColorSpace, ColumnMapping, Dither, JitterMode, CoverageMetrics, HoleReport, FilterRegistry, LasWriter, LidarNoise, LightBaking, Manifest, Placements, ViewerConfig, VirtualScanner, ModelParser, NormalEncoding, NormalOrientation, PcdData, Profiler, TilesetWriter, CopcWriter, RunReport, PointCloudConfig, SamplingStrategy, ScalePivot, SplatRadius, UpAxis, Stylize, EptBuilder, OutOfBounds, PositionEncoding, ValidationPolicy};
 use std::path::PathBuf;
 
+#[cfg(feature = "alloc-tracking")]
+#[global_allocator]
+static ALLOCATOR: model_parser::profiling::TrackingAllocator = model_parser::profiling::TrackingAllocator;
+
 #[derive(Parser, Debug)]
 #[command(name = "model_parser")]
 #[command(about = "Convert 3D models to point cloud data for visualization", long_about = None)]
 struct Args {
-    /// Input 3D model file (GLTF/GLB)
+    /// Input 3D model file, or an http(s):// URL to download it from
     #[arg(short, long)]
     input: PathBuf,
 
-    /// Output file or directory
+    /// Output file or directory, or an object storage URL (s3://bucket/prefix, gs://bucket/prefix)
     #[arg(short, long)]
     output: PathBuf,
 
-    /// Output format: json or ept
+    /// Output format: json, bin (JSON descriptor plus packed binary arrays), ply, pcd, las, laz, copc, hgpc, ept or 3dtiles
     #[arg(short, long, default_value = "json")]
     format: String,
 
+    /// Write --format ply as ASCII text instead of binary
+    #[arg(long, default_value_t = false)]
+    ply_ascii: bool,
+
+    /// Data encoding of --format pcd: ascii, binary or binary_compressed
+    #[arg(long, default_value = "binary")]
+    pcd_data: String,
+
+    /// Compress the point records of --format hgpc with zlib
+    #[arg(long, default_value_t = false)]
+    hgpc_compress: bool,
+
+    /// Coordinate resolution of --format las/laz/copc: one value or x,y,z (default 0.001)
+    #[arg(long, value_parser = parse_scale, default_value = "0.001")]
+    las_scale: [f64; 3],
+
+    /// Header offset x,y,z of --format las/laz/copc (default: rounded center of the cloud)
+    #[arg(long, value_parser = parse_dvec3)]
+    las_offset: Option<[f64; 3]>,
+
+    /// JSON layout: points (one object per point) or arrays (flat typed-array-friendly arrays)
+    #[arg(long, default_value = "points")]
+    json_layout: String,
+
     /// Number of points to generate
     #[arg(short = 'n', long, default_value_t = 2000)]
     point_count: usize,
 
-    /// Sampling strategy: uniform, area-weighted, or vertices
+    /// Sampling strategy: uniform, area-weighted, vertices, or scanner
     #[arg(short, long, default_value = "area-weighted")]
     strategy: String,
 
@@ -34,17 +62,479 @@ struct Args {
     #[arg(long, default_value_t = true)]
     colors: bool,
 
-    /// Scale factor for the model
-    #[arg(long, default_value_t = 1.0)]
-    scale: f32,
+    /// Record the source material index per point
+    #[arg(long, default_value_t = false)]
+    material_ids: bool,
+
+    /// Scale factor for the model: uniform ("2.0") or per axis ("1,1,3")
+    #[arg(long, default_value = "1.0")]
+    scale: String,
+
+    /// Point kept fixed when scaling: origin, centroid, center, min or base (bottom center)
+    #[arg(long, default_value = "origin")]
+    scale_pivot: String,
+
+    /// Up axis of the model: auto (declared by the file or detected), y or z; Z-up models
+    /// are turned Y-up
+    #[arg(long, default_value = "auto")]
+    up_axis: String,
 
-    /// Jitter amount (0.0-1.0)
+    /// Jitter amount, measured as --jitter-mode says
     #[arg(short, long, default_value_t = 0.0)]
     jitter: f32,
+
+    /// Jitter measure: absolute (output units along each axis), diagonal (fraction of the
+    /// bounding box diagonal, up to 1) or normal (as absolute, along the surface normal)
+    #[arg(long, default_value = "absolute")]
+    jitter_mode: String,
+
+    /// Flip all normals
+    #[arg(long, default_value_t = false, conflicts_with = "orient_normals")]
+    flip_normals: bool,
+
+    /// Orient normals toward a viewpoint given as x,y,z
+    #[arg(long, value_parser = parse_vec3)]
+    orient_normals: Option<[f32; 3]>,
+
+    /// Output color space: srgb or linear
+    #[arg(long, default_value = "srgb")]
+    color_space: String,
+
+    /// Bits per color channel in binary outputs: 8 or 16
+    #[arg(long, default_value_t = 8)]
+    color_depth: u8,
+
+    /// Columns of XYZ/CSV/TXT inputs, e.g. x,y,z,_,r,g,b (_ skips a column, other names become attributes)
+    #[arg(long)]
+    columns: Option<ColumnMapping>,
+
+    /// Dithering of 8-bit colors in binary outputs: none, ordered or blue-noise
+    #[arg(long, default_value = "none")]
+    dither: String,
+
+    /// Export a per-point splat radius: area or knn[:k]
+    #[arg(long)]
+    splat_radius: Option<String>,
+
+    /// Bake ambient occlusion with this many rays per point
+    #[arg(long)]
+    ao_samples: Option<usize>,
+
+    /// Maximum occluder distance for ambient occlusion baking
+    #[arg(long, default_value_t = 1.0)]
+    ao_distance: f32,
+
+    /// Bake lighting into colors from a light direction given as x,y,z
+    #[arg(long, value_parser = parse_vec3)]
+    bake_light: Option<[f32; 3]>,
+
+    /// Directional light intensity for --bake-light
+    #[arg(long, default_value_t = 0.8)]
+    light_intensity: f32,
+
+    /// Ambient light intensity for --bake-light
+    #[arg(long, default_value_t = 0.3)]
+    ambient_light: f32,
+
+    /// Exposure adjustment of point colors in stops (+1 doubles the brightness)
+    #[arg(long, default_value_t = 0.0, allow_negative_numbers = true)]
+    exposure: f32,
+
+    /// Gamma of point colors; above 1 brightens and below 1 darkens midtones
+    #[arg(long, default_value_t = 1.0)]
+    gamma: f32,
+
+    /// Saturation factor of point colors (0 = grayscale, 1 = unchanged)
+    #[arg(long, default_value_t = 1.0)]
+    saturation: f32,
+
+    /// Tilt normals randomly by up to this many degrees for a stylized look
+    #[arg(long, default_value_t = 0.0)]
+    normal_jitter: f32,
+
+    /// Shift point hues randomly by up to this fraction of the color wheel (0.0-0.5)
+    #[arg(long, default_value_t = 0.0)]
+    hue_jitter: f32,
+
+    /// Scale point brightness randomly by up to this fraction (0.0-1.0)
+    #[arg(long, default_value_t = 0.0)]
+    value_jitter: f32,
+
+    /// Quantize colors to this many palette colors (1-256) and store each point's
+    /// "palette_index"; the palette is saved in the metadata
+    #[arg(long)]
+    palette: Option<usize>,
+
+    /// Virtual scanner position x,y,z (repeatable) for the scanner strategy
+    #[arg(long = "scanner-position", value_parser = parse_vec3)]
+    scanner_positions: Vec<[f32; 3]>,
+
+    /// Angular resolution of the virtual scanner in degrees
+    #[arg(long, default_value_t = 0.5)]
+    scan_resolution: f32,
+
+    /// Maximum beam range of the virtual scanner
+    #[arg(long)]
+    scan_max_range: Option<f32>,
+
+    /// Simulate LiDAR range noise with this standard deviation
+    #[arg(long)]
+    lidar_noise: Option<f32>,
+
+    /// Probability of dropping a return when simulating LiDAR noise
+    #[arg(long, default_value_t = 0.0)]
+    lidar_dropout: f32,
+
+    /// Sensor position x,y,z for LiDAR noise beams and intensity
+    #[arg(long, value_parser = parse_vec3)]
+    lidar_sensor: Option<[f32; 3]>,
+
+    /// Exponential intensity falloff per unit of range
+    #[arg(long, default_value_t = 0.0)]
+    lidar_falloff: f32,
+
+    /// Synthesize a LiDAR-like intensity attribute from material albedo, metallic and roughness
+    #[arg(long, default_value_t = false)]
+    synthesize_intensity: bool,
+
+    /// Add "metallic", "roughness" and "emissive_r/g/b" attributes from each point's material
+    #[arg(long, default_value_t = false)]
+    material_attributes: bool,
+
+    /// Add a "random_key" attribute for stable percentage-based display in viewers
+    #[arg(long, default_value_t = false)]
+    random_key: bool,
+
+    /// Fix random seeds and parallel ordering so identical inputs produce identical outputs
+    #[arg(long, default_value_t = false)]
+    deterministic: bool,
+
+    /// Random seed (implies --deterministic)
+    #[arg(long)]
+    seed: Option<u64>,
+
+    /// Random generator behind every random stream: std (ChaCha) or pcg (PCG with jump-ahead)
+    #[arg(long, default_value = "std")]
+    rng: String,
+
+    /// Placements file (JSON) replicating the sampled cloud at each listed transform
+    #[arg(long)]
+    instances: Option<PathBuf>,
+
+    /// Duplicate points this close to EPT tile boundaries (fraction of node size) into neighbouring tiles
+    #[arg(long, default_value_t = 0.0)]
+    tile_overlap: f32,
+
+    /// Points per EPT node, by depth from the root; deeper nodes use the last value (default: 100000)
+    #[arg(long, value_delimiter = ',')]
+    tile_capacity: Vec<usize>,
+
+    /// With EPT output, add index.html, hosting headers and gzip-precompressed assets
+    #[arg(long, default_value_t = false)]
+    bundle: bool,
+
+    /// Also write a standalone HTML viewer with the cloud embedded (opens without a server)
+    #[arg(long)]
+    viewer: Option<PathBuf>,
+
+    /// Write a JSON run report (point count, bounds, surface coverage)
+    #[arg(long)]
+    report: Option<PathBuf>,
+
+    /// Distance within which the surface counts as covered in the report (default: expected point spacing)
+    #[arg(long)]
+    coverage_radius: Option<f32>,
+
+    /// Write a JSON report of surface regions no point landed near (bounds, area, materials)
+    #[arg(long)]
+    holes: Option<PathBuf>,
+
+    /// Distance from the nearest point beyond which the surface counts as a hole (default: twice the expected point spacing)
+    #[arg(long)]
+    hole_radius: Option<f32>,
+
+    /// glTF morph target weights, e.g. "0,1,0.5", replacing the weights stored in the file
+    #[arg(long, value_delimiter = ',', num_args = 1..)]
+    morph_weights: Option<Vec<f32>>,
+
+    /// Convert only glTF meshes whose node, ancestor node or mesh name matches one of these globs
+    #[arg(long, value_delimiter = ',')]
+    include_mesh: Vec<String>,
+
+    /// Skip glTF meshes whose node, ancestor node or mesh name matches one of these globs
+    #[arg(long, value_delimiter = ',')]
+    exclude_mesh: Vec<String>,
+
+    /// glTF scene (name or index) to convert instead of the default scene
+    #[arg(long)]
+    scene: Option<String>,
+
+    /// glTF material variant (name or index) to convert, e.g. one color option of a product
+    #[arg(long)]
+    material_variant: Option<String>,
+
+    /// glTF animation (name or index) to pose nodes and skinned meshes with
+    #[arg(long)]
+    animation: Option<String>,
+
+    /// Time in seconds within --animation
+    #[arg(long, default_value_t = 0.0)]
+    animation_time: f32,
+
+    /// Sample --animation at this many evenly spaced frames, writing one numbered cloud per frame
+    #[arg(long)]
+    frames: Option<usize>,
+
+    /// Points per unit of output length sampled along line primitives (default: the surface point spacing)
+    #[arg(long)]
+    line_density: Option<f32>,
+
+    /// Merge mesh vertices closer than this distance before sampling (0 merges identical positions only)
+    #[arg(long)]
+    weld: Option<f32>,
+
+    /// Largest angle in degrees between the normals of vertices merged by --weld
+    #[arg(long, default_value_t = model_parser::weld::DEFAULT_NORMAL_ANGLE)]
+    weld_angle: f32,
+
+    /// Simplify the mesh to at most this many triangles before sampling (quadric decimation)
+    #[arg(long)]
+    decimate: Option<usize>,
+
+    /// Recolor points by a generic attribute (e.g. intensity, occlusion, instance_id)
+    #[arg(long)]
+    color_by: Option<String>,
+
+    /// Colormap for --color-by: viridis, turbo, terrain, grayscale or custom stops ("#000000,#ff0000" or "0:#000000,1:#ffffff")
+    #[arg(long, default_value = "viridis")]
+    colormap: String,
+
+    /// Store EPT normals as two 16-bit octahedral values instead of three floats
+    #[arg(long, default_value_t = false)]
+    octahedral_normals: bool,
+
+    /// Move the cloud so its bbox center or centroid sits at the origin (none, bbox, centroid)
+    #[arg(long, default_value = "none")]
+    center: String,
+
+    /// Fixed EPT octree bounds "minx,miny,minz,maxx,maxy,maxz" shared across conversions
+    #[arg(long, value_parser = parse_bounds)]
+    ept_bounds: Option<[f64; 6]>,
+
+    /// Store EPT positions as 32-bit integers at this resolution instead of floats
+    #[arg(long)]
+    ept_scale: Option<f64>,
+
+    /// Origin x,y,z EPT positions are stored relative to (default: rounded center of the
+    /// octree when coordinates are large or --ept-scale is set)
+    #[arg(long, value_parser = parse_dvec3)]
+    ept_origin: Option<[f64; 3]>,
+
+    /// Points outside --ept-bounds: clamp (move onto the bounds) or reject
+    #[arg(long, default_value = "clamp")]
+    out_of_bounds: String,
+
+    /// Points with NaN/Inf positions, degenerate normals or out-of-range colors: drop, fix or error
+    #[arg(long, default_value = "fix")]
+    invalid_points: String,
+
+    /// Also compute bounds ignoring this percentage of points at each end of every axis,
+    /// used to frame viewers while the exact bounds are kept
+    #[arg(long)]
+    robust_bounds: Option<f32>,
+
+    /// Sort points along a Hilbert curve before writing json/bin output, so the file
+    /// compresses better and byte ranges cover compact regions
+    #[arg(long, default_value_t = false)]
+    hilbert_order: bool,
+
+    /// Write only these dimensions, named as in the EPT schema (e.g. "X,Y,Z,Red,Green,Blue"),
+    /// dropping normals, alpha, material ids or attributes that are not listed
+    #[arg(long, value_delimiter = ',')]
+    dims: Option<Vec<String>>,
+
+    /// With --format ept, also write ept-tiles.json listing every tile with its exact bounds,
+    /// point count and size
+    #[arg(long, default_value_t = false)]
+    tile_index: bool,
+
+    /// Resume an interrupted EPT build in the output directory, keeping finished tiles
+    /// (requires the same input and settings; use --seed for sampled models)
+    #[arg(long, default_value_t = false)]
+    resume: bool,
+
+    /// With --format ept, give points of coarse nodes the mean color and normal of the points
+    /// they stand for, so zoomed-out views look like the object rather than noise
+    #[arg(long, default_value_t = false)]
+    average_lods: bool,
+
+    /// Concurrent file uploads when --output is an s3:// or gs:// URL
+    #[arg(long, default_value_t = 16)]
+    upload_jobs: usize,
+
+    /// Filter stage applied before output, repeatable and run in order: voxel:<size>,
+    /// sor:<k>,<std-multiplier>, crop:<minx>,<miny>,<minz>,<maxx>,<maxy>,<maxz>,
+    /// cylinder:<x>,<y>,<z>,[<ax>,<ay>,<az>,]<radius>,<hmin>,<hmax>, expr:<expression>
+    #[arg(long = "filter")]
+    filters: Vec<String>,
+
+    /// Color models without vertex colors or textures by `elevation` (through --colormap) or `normal` direction
+    #[arg(long)]
+    fallback_color: Option<String>,
+
+    /// Maximum output size (e.g. 20MB, 512KiB); the cloud is quantized and thinned to fit
+    #[arg(long)]
+    max_size: Option<String>,
+
+    /// When --max-size thins the cloud, keep points matching an expression with this relative
+    /// weight, e.g. "0.1:classification == 2" or "all:edge > 0.5"; first match wins, others weigh 1
+    #[arg(long = "budget-weight")]
+    budget_weights: Vec<String>,
+
+    /// Print wall time and peak memory after each stage
+    #[arg(short, long, default_value_t = false)]
+    verbose: bool,
+
+    /// Write a manifest with sizes and SHA-256 checksums of all produced files
+    #[arg(long, default_value_t = false)]
+    manifest: bool,
+
+    /// Emit back-facing samples for doubleSided materials
+    #[arg(long, default_value_t = false)]
+    two_sided: bool,
+
+    /// Treat the input as a composition file listing built EPT/3D Tiles datasets with their
+    /// transforms, and write a parent ept-sources list and tileset.json into the output directory
+    #[arg(long, default_value_t = false)]
+    compose: bool,
+
+    /// Treat the input as an EPT directory and copy the nodes within --extract-depth and
+    /// --extract-bounds into a smaller EPT dataset in the output directory
+    #[arg(long, default_value_t = false)]
+    extract: bool,
+
+    /// Deepest octree level kept by --extract (0 keeps the root node only)
+    #[arg(long)]
+    extract_depth: Option<u32>,
+
+    /// Region "minx,miny,minz,maxx,maxy,maxz" whose intersecting nodes --extract keeps
+    #[arg(long, value_parser = parse_bounds)]
+    extract_bounds: Option<[f64; 6]>,
+}
+
+/// Parse a comma-separated "x,y,z" triple
+fn parse_vec3(value: &str) -> Result<[f32; 3], String> {
+    let parts: Vec<f32> = value
+        .split(',')
+        .map(|part| part.trim().parse::<f32>())
+        .collect::<Result<_, _>>()
+        .map_err(|e| format!("invalid number in '{}': {}", value, e))?;
+
+    match parts.as_slice() {
+        [x, y, z] => Ok([*x, *y, *z]),
+        _ => Err(format!("expected x,y,z but got '{}'", value)),
+    }
+}
+
+/// Parse a comma-separated "x,y,z" triple at full precision
+fn parse_dvec3(value: &str) -> Result<[f64; 3], String> {
+    let parts: Vec<f64> = value
+        .split(',')
+        .map(|part| part.trim().parse::<f64>())
+        .collect::<Result<_, _>>()
+        .map_err(|e| format!("invalid number in '{}': {}", value, e))?;
+
+    <[f64; 3]>::try_from(parts).map_err(|_| format!("expected x,y,z but got '{}'", value))
+}
+
+/// Parse a per-axis scale: one value for all axes or "x,y,z"
+fn parse_scale(value: &str) -> Result<[f64; 3], String> {
+    match value.trim().parse::<f64>() {
+        Ok(scale) => Ok([scale; 3]),
+        Err(_) => parse_dvec3(value),
+    }
+}
+
+/// Parse comma-separated "minx,miny,minz,maxx,maxy,maxz" bounds
+fn parse_bounds(value: &str) -> Result<[f64; 6], String> {
+    let parts: Vec<f64> = value
+        .split(',')
+        .map(|part| part.trim().parse::<f64>())
+        .collect::<Result<_, _>>()
+        .map_err(|e| format!("invalid number in '{}': {}", value, e))?;
+
+    <[f64; 6]>::try_from(parts).map_err(|_| format!("expected minx,miny,minz,maxx,maxy,maxz but got '{}'", value))
+}
+
+/// Whether `format` writes files beside the output path rather than into an output directory
+fn writes_file(format: &str) -> bool {
+    ["json", "bin", "ply", "pcd", "las", "laz", "copc", "hgpc"].iter().any(|file_format| format.eq_ignore_ascii_case(file_format))
+}
+
+/// Write the SHA-256 manifest of the output of `format`: `manifest.json` inside directory
+/// outputs, `<name>.manifest.json` beside single files. Returns its path and the number of files
+fn write_manifest(output: &std::path::Path, format: &str) -> Result<(PathBuf, usize), Box<dyn std::error::Error>> {
+    let (manifest, manifest_path) = if writes_file(format) {
+        let base = output.parent().unwrap_or_else(|| std::path::Path::new(""));
+        let mut files = vec![output.to_path_buf()];
+        // Binary buffers are a descriptor and the `.bin` beside it
+        if format.eq_ignore_ascii_case("bin") && output.with_extension("bin") != output {
+            files.push(output.with_extension("bin"));
+        }
+        (Manifest::from_files(base, &files)?, output.with_extension("manifest.json"))
+    } else {
+        (Manifest::from_directory(output)?, output.join(model_parser::manifest::MANIFEST_FILE))
+    };
+    manifest.save_to_file(&manifest_path)?;
+    Ok((manifest_path, manifest.files.len()))
+}
+
+/// EPT builder configured from the command line
+fn ept_builder(
+    args: &Args,
+    config: &PointCloudConfig,
+    color_depth: ColorDepth,
+    dither: Dither,
+    out_of_bounds: OutOfBounds,
+) -> EptBuilder {
+    EptBuilder::new()
+        .with_color_depth(color_depth)
+        .with_dither(dither)
+        .with_seed(config.seed)
+        .with_tile_overlap(args.tile_overlap)
+        .with_depth_capacities(args.tile_capacity.clone())
+        .with_normal_encoding(if args.octahedral_normals {
+            NormalEncoding::Octahedral16
+        } else {
+            NormalEncoding::Float32
+        })
+        .with_position_encoding(args.ept_scale.map_or(PositionEncoding::Float32, PositionEncoding::Scaled))
+        .with_origin(args.ept_origin)
+        .with_bounds(args.ept_bounds)
+        .with_out_of_bounds(out_of_bounds)
+        .with_tile_index(args.tile_index)
+        .with_resume(args.resume)
+        .with_lod_averaging(args.average_lods)
+}
+
+fn las_writer(args: &Args) -> LasWriter {
+    LasWriter::new()
+        .with_scale(args.las_scale)
+        .with_offset(args.las_offset)
+        .with_compression(args.format.eq_ignore_ascii_case("laz"))
+        // Reproducible runs date files at the epoch instead of today
+        .with_creation_time((args.deterministic || args.seed.is_some()).then_some(0))
 }
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let args = Args::parse();
+    let mut args = Args::parse();
+
+    // URL inputs are downloaded (or reused from the cache) and read from there
+    if model_parser::download::is_url(&args.input.to_string_lossy()) {
+        let url = args.input.to_string_lossy().into_owned();
+        println!("Fetching input: {}", url);
+        args.input = model_parser::download::fetch(&url)?;
+    }
 
     // Validate input file
     if !args.input.exists() {
@@ -52,27 +542,335 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         std::process::exit(1);
     }
 
+    // Compositions only index datasets that were already built, nothing is sampled
+    if args.compose {
+        if ObjectStore::parse(&args.output.to_string_lossy()).is_some() {
+            eprintln!("Error: --compose writes to a local directory; upload it together with the datasets");
+            std::process::exit(1);
+        }
+
+        let report = model_parser::composite::compose(&args.input, &args.output)?;
+        println!("✓ Composed {} datasets ({} EPT points)", report.datasets, report.points);
+        for path in report.ept_sources.iter().chain(&report.tileset) {
+            println!("  - {:?}", path);
+        }
+        return Ok(());
+    }
+
+    // Extraction copies whole tiles of an existing EPT dataset, nothing is sampled
+    if args.extract {
+        let extraction = EptExtraction { max_depth: args.extract_depth, bounds: args.extract_bounds };
+        if extraction.is_empty() {
+            eprintln!("Error: --extract requires --extract-depth and/or --extract-bounds");
+            std::process::exit(1);
+        }
+        if ObjectStore::parse(&args.output.to_string_lossy()).is_some() {
+            eprintln!("Error: --extract writes to a local directory");
+            std::process::exit(1);
+        }
+
+        let report = model_parser::extract::extract(&args.input, &args.output, &extraction)?;
+        println!(
+            "✓ Extracted {} of {} nodes ({} of {} points) into {:?}",
+            report.nodes, report.source_nodes, report.points, report.source_points, args.output
+        );
+        return Ok(());
+    }
+
     // Parse sampling strategy
     let strategy = match args.strategy.to_lowercase().as_str() {
         "uniform" => SamplingStrategy::Uniform,
         "area-weighted" | "area_weighted" => SamplingStrategy::AreaWeighted,
         "vertices" => SamplingStrategy::Vertices,
+        "scanner" => SamplingStrategy::Scanner,
         _ => {
             eprintln!(
-                "Error: Invalid sampling strategy '{}'. Use: uniform, area-weighted, or vertices",
+                "Error: Invalid sampling strategy '{}'. Use: uniform, area-weighted, vertices, or scanner",
                 args.strategy
             );
             std::process::exit(1);
         }
     };
 
+    match model_parser::random::builtin(&args.rng) {
+        Some(generator) => model_parser::random::set_generator(generator),
+        None => {
+            eprintln!("Error: Invalid random generator '{}'. Use: std or pcg", args.rng);
+            std::process::exit(1);
+        }
+    }
+
+    let color_space = match args.color_space.to_lowercase().as_str() {
+        "srgb" => ColorSpace::Srgb,
+        "linear" => ColorSpace::Linear,
+        _ => {
+            eprintln!("Error: Invalid color space '{}'. Use: srgb or linear", args.color_space);
+            std::process::exit(1);
+        }
+    };
+
+    let mut color_depth = match args.color_depth {
+        8 => ColorDepth::Eight,
+        16 => ColorDepth::Sixteen,
+        _ => {
+            eprintln!("Error: Invalid color depth '{}'. Use: 8 or 16", args.color_depth);
+            std::process::exit(1);
+        }
+    };
+
+    let dither = match args.dither.to_lowercase().as_str() {
+        "none" => Dither::None,
+        "ordered" => Dither::Ordered,
+        "blue-noise" => Dither::BlueNoise,
+        _ => {
+            eprintln!("Error: Invalid dither '{}'. Use: none, ordered or blue-noise", args.dither);
+            std::process::exit(1);
+        }
+    };
+
+    let splat_radius = match args.splat_radius.as_deref().map(str::to_lowercase) {
+        None => None,
+        Some(method) if method == "area" => Some(SplatRadius::SurfaceArea),
+        Some(method) if method == "knn" => Some(SplatRadius::KNearest(8)),
+        Some(method) => match method.strip_prefix("knn:").and_then(|k| k.parse().ok()) {
+            Some(k) => Some(SplatRadius::KNearest(k)),
+            None => {
+                eprintln!("Error: Invalid splat radius '{}'. Use: area, knn or knn:<k>", method);
+                std::process::exit(1);
+            }
+        },
+    };
+
+    let scale_pivot = match args.scale_pivot.to_lowercase().as_str() {
+        "origin" => ScalePivot::Origin,
+        "centroid" => ScalePivot::Centroid,
+        "center" => ScalePivot::BoundsCenter,
+        "min" => ScalePivot::BoundsMin,
+        "base" => ScalePivot::Base,
+        _ => {
+            eprintln!("Error: Invalid scale pivot '{}'. Use: origin, centroid, center, min or base", args.scale_pivot);
+            std::process::exit(1);
+        }
+    };
+
+    let up_axis = match args.up_axis.to_lowercase().as_str() {
+        "auto" => UpAxis::Auto,
+        "y" => UpAxis::Y,
+        "z" => UpAxis::Z,
+        _ => {
+            eprintln!("Error: Invalid up axis '{}'. Use: auto, y or z", args.up_axis);
+            std::process::exit(1);
+        }
+    };
+
+    let jitter_mode = match args.jitter_mode.to_lowercase().as_str() {
+        "diagonal" => JitterMode::BoundsFraction,
+        "absolute" => JitterMode::Absolute,
+        "normal" => JitterMode::Normal,
+        _ => {
+            eprintln!("Error: Invalid jitter mode '{}'. Use: absolute, diagonal or normal", args.jitter_mode);
+            std::process::exit(1);
+        }
+    };
+
+    if let Some(colors) = args.palette
+        && !(1..=model_parser::palette::MAX_COLORS).contains(&colors)
+    {
+        eprintln!("Error: --palette must be between 1 and {}", model_parser::palette::MAX_COLORS);
+        std::process::exit(1);
+    }
+
+    // A uniform scale about the origin keeps using the sampling-time scale
+    let (scale, axis_scale) = match (args.scale.parse::<f32>(), parse_vec3(&args.scale)) {
+        (Ok(scale), _) if scale_pivot == ScalePivot::Origin => (scale, [1.0, 1.0, 1.0]),
+        (Ok(scale), _) => (1.0, [scale; 3]),
+        (_, Ok(factors)) => (1.0, factors),
+        _ => {
+            eprintln!("Error: Invalid scale '{}'. Use a factor (2.0) or per-axis factors (1,1,3)", args.scale);
+            std::process::exit(1);
+        }
+    };
+
+    let normal_orientation = match (args.flip_normals, args.orient_normals) {
+        (true, _) => NormalOrientation::Flipped,
+        (false, Some(viewpoint)) => NormalOrientation::TowardViewpoint(viewpoint),
+        (false, None) => NormalOrientation::AsAuthored,
+    };
+
+    let fallback_color = args.fallback_color.as_deref().map(str::to_lowercase);
+    if let Some(mode) = fallback_color.as_deref()
+        && mode != "elevation"
+        && mode != "normal"
+    {
+        eprintln!("Error: Invalid fallback color '{}'. Use: elevation or normal", mode);
+        std::process::exit(1);
+    }
+
+    let max_size = match args.max_size.as_deref().map(model_parser::budget::parse_size) {
+        None => None,
+        Some(Ok(bytes)) => Some(bytes),
+        Some(Err(e)) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let budget_weights = match model_parser::budget::BudgetWeights::parse(&args.budget_weights) {
+        Ok(weights) => weights,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    if let Some(percentile) = args.robust_bounds
+        && !(0.0..50.0).contains(&percentile)
+    {
+        eprintln!("Error: --robust-bounds must be a percentage in [0, 50), got {}", percentile);
+        std::process::exit(1);
+    }
+
+    if args.gamma <= 0.0 || args.saturation < 0.0 {
+        eprintln!("Error: --gamma must be positive and --saturation not negative");
+        std::process::exit(1);
+    }
+
+    if args.tile_capacity.contains(&0) {
+        eprintln!("Error: --tile-capacity values must be at least 1");
+        std::process::exit(1);
+    }
+
+    let validation = match args.invalid_points.to_lowercase().as_str() {
+        "drop" => ValidationPolicy::Drop,
+        "fix" => ValidationPolicy::Fix,
+        "error" => ValidationPolicy::Error,
+        _ => {
+            eprintln!("Error: Invalid policy '{}'. Use: drop, fix or error", args.invalid_points);
+            std::process::exit(1);
+        }
+    };
+
+    let out_of_bounds = match args.out_of_bounds.to_lowercase().as_str() {
+        "clamp" => OutOfBounds::Clamp,
+        "reject" => OutOfBounds::Reject,
+        _ => {
+            eprintln!("Error: Invalid out-of-bounds policy '{}'. Use: clamp or reject", args.out_of_bounds);
+            std::process::exit(1);
+        }
+    };
+
+    let pcd_data = match args.pcd_data.to_lowercase().as_str() {
+        "ascii" => PcdData::Ascii,
+        "binary" => PcdData::Binary,
+        "binary_compressed" => PcdData::BinaryCompressed,
+        _ => {
+            eprintln!("Error: Invalid PCD data '{}'. Use: ascii, binary or binary_compressed", args.pcd_data);
+            std::process::exit(1);
+        }
+    };
+
+    let json_layout = match args.json_layout.to_lowercase().as_str() {
+        "points" => JsonLayout::Points,
+        "arrays" => JsonLayout::Arrays,
+        _ => {
+            eprintln!("Error: Invalid JSON layout '{}'. Use: points or arrays", args.json_layout);
+            std::process::exit(1);
+        }
+    };
+
+    let centering = match args.center.to_lowercase().as_str() {
+        "none" => Centering::None,
+        "bbox" => Centering::BoundsCenter,
+        "centroid" => Centering::Centroid,
+        _ => {
+            eprintln!("Error: Invalid center '{}'. Use: none, bbox or centroid", args.center);
+            std::process::exit(1);
+        }
+    };
+
+    let filters = match FilterRegistry::new().pipeline(&args.filters) {
+        Ok(filters) => filters,
+        Err(e) => {
+            eprintln!("Error: Invalid filter: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let colormap = match Colormap::parse(&args.colormap) {
+        Ok(colormap) => colormap,
+        Err(e) => {
+            eprintln!("Error: Invalid colormap '{}': {}", args.colormap, e);
+            std::process::exit(1);
+        }
+    };
+
+    let seed = args.seed.or(args.deterministic.then_some(0));
+
     // Create configuration
     let config = PointCloudConfig::new(args.point_count)
         .with_strategy(strategy)
         .with_normals(args.normals)
         .with_colors(args.colors)
-        .with_scale(args.scale)
-        .with_jitter(args.jitter);
+        .with_material_ids(args.material_ids)
+        .with_scale(scale)
+        .with_up_axis(up_axis)
+        .with_axis_scale(axis_scale, scale_pivot)
+        .with_jitter(args.jitter)
+        .with_jitter_mode(jitter_mode)
+        .with_normal_orientation(normal_orientation)
+        .with_two_sided(args.two_sided)
+        .with_color_space(color_space)
+        .with_splat_radius(splat_radius)
+        .with_seed(seed)
+        .with_validation(validation)
+        .with_text_columns(args.columns.clone())
+        .with_synthesized_intensity(args.synthesize_intensity)
+        .with_material_attributes(args.material_attributes)
+        .with_random_key(args.random_key)
+        .with_welding(args.weld, args.weld_angle)
+        .with_line_density(args.line_density)
+        .with_morph_weights(args.morph_weights.clone())
+        .with_scene(args.scene.clone())
+        .with_material_variant(args.material_variant.clone())
+        .with_mesh_filter(args.include_mesh.clone(), args.exclude_mesh.clone())
+        .with_pose(args.animation.clone().map(|animation| AnimationPose {
+            animation,
+            time: args.animation_time,
+        }))
+        .with_decimation(args.decimate)
+        .with_ambient_occlusion(args.ao_samples.map(|samples| AmbientOcclusion {
+            samples,
+            max_distance: args.ao_distance,
+        }))
+        .with_scanner(VirtualScanner {
+            positions: args.scanner_positions.clone(),
+            horizontal_resolution: args.scan_resolution,
+            vertical_resolution: args.scan_resolution,
+            max_range: args.scan_max_range.unwrap_or(f32::MAX),
+            ..Default::default()
+        })
+        .with_lidar_noise(args.lidar_noise.map(|range_sigma| LidarNoise {
+            range_sigma,
+            dropout: args.lidar_dropout,
+            sensor: args.lidar_sensor,
+            intensity_falloff: args.lidar_falloff,
+        }))
+        .with_stylize(Some(Stylize {
+            normal_jitter: args.normal_jitter,
+            hue_jitter: args.hue_jitter,
+            value_jitter: args.value_jitter,
+        }))
+        .with_color_grading(Some(ColorGrading {
+            exposure: args.exposure,
+            gamma: args.gamma,
+            saturation: args.saturation,
+        }))
+        .with_palette(args.palette.map(|colors| Palette { colors, ..Default::default() }))
+        .with_light_baking(args.bake_light.map(|direction| LightBaking {
+            direction,
+            intensity: args.light_intensity,
+            ambient: args.ambient_light,
+        }));
 
     println!("Parsing 3D model: {:?}", args.input);
     println!("Configuration:");
@@ -80,41 +878,532 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("  - Strategy: {:?}", config.sampling_strategy);
     println!("  - Include normals: {}", config.include_normals);
     println!("  - Include colors: {}", config.include_colors);
+    println!("  - Include material ids: {}", config.include_material_ids);
     println!("  - Scale: {}", config.scale);
-    println!("  - Jitter: {}", config.jitter);
+    if config.up_axis != UpAxis::Auto {
+        println!("  - Up axis: {:?}", config.up_axis);
+    }
+    if config.axis_scale != [1.0, 1.0, 1.0] {
+        println!("  - Axis scale: {:?} about {:?}", config.axis_scale, config.scale_pivot);
+    }
+    println!("  - Jitter: {} ({:?})", config.jitter, config.jitter_mode);
+    println!("  - Normal orientation: {:?}", config.normal_orientation);
+    println!("  - Two-sided: {}", config.two_sided);
+    println!("  - Color space: {:?}", config.color_space);
+    if let Some(seed) = config.seed {
+        println!("  - Deterministic: seed {} ({} generator)", seed, model_parser::random::generator().name());
+    }
+    if let Some(ao) = config.ambient_occlusion {
+        println!("  - Ambient occlusion: {} rays, max distance {}", ao.samples, ao.max_distance);
+    }
+    if let Some(noise) = config.lidar_noise {
+        println!("  - LiDAR noise: sigma {}, dropout {}", noise.range_sigma, noise.dropout);
+    }
+    if let Some(weights) = &config.morph_weights {
+        println!("  - Morph weights: {:?}", weights);
+    }
+    if !config.mesh_filter.is_empty() {
+        println!("  - Meshes: include {:?}, exclude {:?}", config.mesh_filter.include, config.mesh_filter.exclude);
+    }
+    if let Some(scene) = &config.scene {
+        println!("  - Scene: {}", scene);
+    }
+    if let Some(variant) = &config.material_variant {
+        println!("  - Material variant: {}", variant);
+    }
+    if let Some(pose) = &config.pose {
+        println!("  - Pose: animation '{}' at {}s", pose.animation, pose.time);
+    }
+    if let Some(density) = config.line_density {
+        println!("  - Line density: {} points per unit", density);
+    }
+    if let Some(tolerance) = config.weld {
+        println!("  - Welding: within {} and {}°", tolerance, config.weld_normal_angle);
+    }
+    if let Some(target) = config.decimate {
+        println!("  - Decimation: down to {} triangles", target);
+    }
+    if config.synthesize_intensity {
+        println!("  - Intensity: synthesized from materials");
+    }
+    if config.material_attributes {
+        println!("  - Material attributes: metallic, roughness, emissive");
+    }
+    if config.random_key {
+        println!("  - Random key: hashed from positions");
+    }
+    if let Some(style) = config.stylize {
+        println!("  - Stylize: normals ±{}°, hue ±{}, value ±{}", style.normal_jitter, style.hue_jitter, style.value_jitter);
+    }
+    if let Some(light) = config.light_baking {
+        println!("  - Light baking: direction {:?}, intensity {}, ambient {}", light.direction, light.intensity, light.ambient);
+    }
+    if let Some(grading) = config.color_grading {
+        println!("  - Color grading: exposure {:+}, gamma {}, saturation {}", grading.exposure, grading.gamma, grading.saturation);
+    }
+    if let Some(palette) = config.palette {
+        println!("  - Palette: {} colors (k-means)", palette.colors);
+    }
+    if let Some(method) = config.splat_radius {
+        println!("  - Splat radius: {:?}", method);
+    }
+
+    // EPT datasets for object storage are uploaded tile by tile while they are built. Other
+    // outputs, and EPT that --bundle post-processes, are written to a staging directory and
+    // uploaded at the end; it is created after option parsing and removed on drop, so no
+    // exit path leaves it behind
+    let destination = args.output.clone();
+    let remote = ObjectStore::parse(&destination.to_string_lossy());
+    if remote.is_some() && args.resume {
+        eprintln!("Error: --resume needs a local --output directory");
+        std::process::exit(1);
+    }
+    let streamed = args.format.eq_ignore_ascii_case("ept") && !args.bundle && args.frames.is_none();
+    let uploader = remote
+        .as_ref()
+        .filter(|_| streamed)
+        .map(|store| Uploader::new(store, args.upload_jobs))
+        .transpose()?;
+    let staging = remote.as_ref().filter(|_| !streamed).map(|_| Staging::new()).transpose()?;
+    if let (Some(store), Some(staging)) = (&remote, &staging) {
+        args.output = if writes_file(&args.format) {
+            let name = Some(store.name()).filter(|name| !name.is_empty()).unwrap_or("pointcloud.json");
+            staging.path().join(name)
+        } else {
+            staging.path().to_path_buf()
+        };
+    }
+
+    // Animation sequences sample every frame with the same settings and seed, so seeded
+    // points stay on the same spot of the surface from frame to frame
+    if let Some(frames) = args.frames {
+        let Some(animation) = config.pose.as_ref().map(|pose| pose.animation.clone()) else {
+            eprintln!("Error: --frames requires --animation");
+            drop(staging);
+            std::process::exit(1);
+        };
+        let format = args.format.to_lowercase();
+        let ept = match format.as_str() {
+            "json" | "bin" | "ply" | "pcd" | "las" | "laz" | "copc" | "hgpc" => false,
+            "ept" | "3dtiles" => true,
+            _ => {
+                eprintln!("Error: Invalid format '{}' for --frames. Use: json, bin, ply, pcd, las, laz, copc, hgpc, ept or 3dtiles", args.format);
+                drop(staging);
+                std::process::exit(1);
+            }
+        };
+        if frames == 0 {
+            eprintln!("Error: --frames must be at least 1");
+            drop(staging);
+            std::process::exit(1);
+        }
+        // Frames only run filters, --hilbert-order and --dims; the other per-cloud steps and side
+        // outputs of the single-cloud path below would be silently skipped
+        let unsupported: Vec<&str> = [
+            ("--center", centering != Centering::None),
+            ("--max-size", max_size.is_some()),
+            ("--instances", args.instances.is_some()),
+            ("--fallback-color", fallback_color.is_some()),
+            ("--color-by", args.color_by.is_some()),
+            ("--robust-bounds", args.robust_bounds.is_some()),
+            ("--holes", args.holes.is_some()),
+            ("--manifest", args.manifest),
+            ("--report", args.report.is_some()),
+            ("--viewer", args.viewer.is_some()),
+            ("--bundle", args.bundle),
+        ]
+        .into_iter()
+        .filter(|(_, set)| *set)
+        .map(|(flag, _)| flag)
+        .collect();
+        if !unsupported.is_empty() {
+            eprintln!("Error: --frames does not support {}", unsupported.join(", "));
+            drop(staging);
+            std::process::exit(1);
+        }
+
+        let sequence = Sequence::plan(&args.input, &animation, frames, &args.output, ept)?;
+        println!("\nSampling {} frames of '{}'", frames, animation);
+        if ept {
+            std::fs::create_dir_all(&args.output)?;
+        }
+        for (frame, entry) in sequence.frames.iter().enumerate() {
+            let config = config.clone().with_pose(Some(AnimationPose { animation: animation.clone(), time: entry.time }));
+            let mesh = ModelParser::load_mesh(&args.input, &config)?;
+            let mut point_cloud = ModelParser::sample_mesh(&mesh, &args.input, &config)?;
+            filters.apply(&mut point_cloud)?;
+            if args.hilbert_order && !ept {
+                model_parser::hilbert::sort(&mut point_cloud);
+            }
+            if let Some(dims) = &args.dims {
+                model_parser::dimensions::select(&mut point_cloud, dims)?;
+            }
+
+            let path = sequence.frame_path(&args.output, frame, ept);
+            if format == "3dtiles" {
+                TilesetWriter::new(ept_builder(&args, &config, color_depth, dither, out_of_bounds)).write(&point_cloud, &path)?;
+            } else if ept {
+                ept_builder(&args, &config, color_depth, dither, out_of_bounds).build(&point_cloud, &path)?;
+            } else if format == "bin" {
+                model_parser::buffers::write_buffers(&point_cloud, &path)?;
+            } else if format == "ply" {
+                model_parser::ply::write(&point_cloud, &path, args.ply_ascii)?;
+            } else if format == "pcd" {
+                model_parser::pcd::write(&point_cloud, &path, pcd_data)?;
+            } else if format == "las" || format == "laz" {
+                las_writer(&args).write(&point_cloud, &path)?;
+            } else if format == "copc" {
+                let octree = ept_builder(&args, &config, color_depth, dither, out_of_bounds);
+                CopcWriter::new(octree, las_writer(&args)).write(&point_cloud, &path)?;
+            } else if format == "hgpc" {
+                point_cloud.save_hgpc(&path, args.hgpc_compress)?;
+            } else {
+                point_cloud.save_to_file_with_layout(&path, json_layout)?;
+            }
+            println!("  - {:.3}s: {} points -> {:?}", entry.time, point_cloud.points.len(), path);
+        }
+
+        let index = Sequence::index_path(&args.output, ept);
+        sequence.save_to_file(&index)?;
+        println!("✓ Sequence written: {:?}", index);
+
+        if let (Some(store), Some(dir)) = (&remote, &staging) {
+            let target = if ept { store.clone() } else { store.parent() };
+            println!("\nUploading to {}", target.url(""));
+            let urls = target.upload_directory(dir.path(), args.upload_jobs)?;
+            println!("✓ Uploaded {} files", urls.len());
+        }
+        return Ok(());
+    }
 
     // Parse the model
-    let point_cloud = ModelParser::parse_file(&args.input, &config)?;
+    let mut profiler = Profiler::new(args.verbose);
+    let mesh = profiler.stage("load", || ModelParser::load_mesh(&args.input, &config))?;
+    for warning in &mesh.warnings {
+        eprintln!("Warning: {}", warning);
+    }
+    let mut point_cloud = profiler.stage("sample", || ModelParser::sample_mesh(&mesh, &args.input, &config))?;
+
+    let coverage = match args.report {
+        Some(_) => profiler.stage("coverage", || {
+            CoverageMetrics::measure(
+                &mesh,
+                &point_cloud,
+                config.scale,
+                args.coverage_radius,
+                model_parser::coverage::DEFAULT_PROBES,
+                config.seed,
+            )
+        }),
+        None => None,
+    };
+
+    if let Some(path) = &args.holes {
+        let report = profiler.stage("holes", || {
+            HoleReport::detect(
+                &mesh,
+                &point_cloud,
+                config.scale,
+                args.hole_radius,
+                model_parser::coverage::DEFAULT_PROBES,
+                config.seed,
+            )
+        });
+        match report {
+            Some(report) => {
+                report.save_to_file(path)?;
+                println!(
+                    "✓ Hole report written: {:?} ({} holes, {:.4} uncovered area beyond {:.4})",
+                    path,
+                    report.holes.len(),
+                    report.total_area,
+                    report.radius
+                );
+            }
+            None => println!("  - Hole report skipped: the model has no surface to probe"),
+        }
+    }
+
+    if let Some(path) = &args.instances {
+        let transforms = Placements::load_from_file(path)?.matrices()?;
+        println!("\nReplicating cloud at {} placements from {:?}", transforms.len(), path);
+        point_cloud = profiler.stage("instances", || model_parser::instances::instantiate(&point_cloud, &transforms));
+    }
+
+    if !filters.is_empty() {
+        let stages = profiler.stage("filters", || filters.apply(&mut point_cloud))?;
+        println!("\nFilters:");
+        for (name, before, after) in stages {
+            println!("  - {}: {} -> {} points", name, before, after);
+        }
+    }
+
+    if centering != Centering::None {
+        let offset = point_cloud.recenter(centering);
+        println!("\nRe-centered cloud, offset {:?} recorded in metadata", offset);
+    }
+
+    if let Some(mode) = fallback_color.as_deref().filter(|_| config.include_colors && !mesh.has_source_colors) {
+        if mode == "normal" && point_cloud.metadata.has_normals {
+            point_cloud.color_by_normal();
+        } else {
+            point_cloud.color_by_elevation(&colormap);
+        }
+        println!("\nModel has no colors; colored points by {}", mode);
+    }
+
+    if let Some(name) = &args.color_by {
+        let Some(values) = point_cloud.attribute_values(name) else {
+            let available: Vec<_> = point_cloud.metadata.attributes.iter().map(|a| a.name.as_str()).collect();
+            eprintln!("Error: Unknown attribute '{}' for --color-by. Available: {:?}", name, available);
+            drop(staging);
+            std::process::exit(1);
+        };
+        point_cloud.color_by_values(&values, &colormap, None);
+        println!("\nColored points by '{}' using {}", name, args.colormap);
+    }
+
+    if let Some(dims) = &args.dims {
+        let before = model_parser::dimensions::available(&point_cloud).len();
+        profiler.stage("dims", || model_parser::dimensions::select(&mut point_cloud, dims))?;
+        println!("\nKept {} of {} dimensions", model_parser::dimensions::available(&point_cloud).len(), before);
+    }
+
+    if let Some(max_bytes) = max_size {
+        let format = args.format.to_lowercase();
+        let estimate = |point_cloud: &model_parser::PointCloud, color_depth| match format.as_str() {
+            "ept" => Ok(ept_builder(&args, &config, color_depth, dither, out_of_bounds).estimate_size(point_cloud)),
+            "3dtiles" => {
+                let octree = ept_builder(&args, &config, color_depth, dither, out_of_bounds);
+                Ok(TilesetWriter::new(octree).estimate_size(point_cloud))
+            }
+            "bin" => model_parser::buffers::estimate(point_cloud),
+            "ply" => Ok(model_parser::ply::estimate(point_cloud, args.ply_ascii)),
+            "pcd" => Ok(model_parser::pcd::estimate(point_cloud, pcd_data)),
+            "las" | "laz" => Ok(las_writer(&args).estimate(point_cloud)),
+            // The same octree settings as the written file, so the layout matches
+            "copc" => {
+                let octree = ept_builder(&args, &config, color_depth, dither, out_of_bounds);
+                Ok(CopcWriter::new(octree, las_writer(&args)).estimate_size(point_cloud))
+            }
+            "hgpc" => model_parser::hgpc::estimate(point_cloud),
+            _ => model_parser::budget::estimate_json(point_cloud, json_layout),
+        };
+
+        let before = estimate(&point_cloud, color_depth)?.total(point_cloud.points.len());
+        if before > max_bytes {
+            // Reduce precision first since it keeps every point, then thin what still does not fit
+            match format.as_str() {
+                "ept" => color_depth = ColorDepth::Eight,
+                "bin" | "ply" | "pcd" | "las" | "laz" | "copc" | "hgpc" | "3dtiles" => {}
+                _ => model_parser::budget::quantize_for_json(&mut point_cloud),
+            }
+
+            let estimate = estimate(&point_cloud, color_depth)?;
+            let count = estimate.points_within(max_bytes);
+            if budget_weights.is_empty() {
+                profiler.stage("thin", || model_parser::budget::thin(&mut point_cloud, count, config.seed));
+            } else {
+                let weights = budget_weights.evaluate(&point_cloud)?;
+                profiler.stage("thin", || model_parser::budget::thin_weighted(&mut point_cloud, count, &weights, config.seed));
+            }
+            println!(
+                "\nFitting output into {} bytes: estimated {} -> {} bytes, {} points kept",
+                max_bytes,
+                before,
+                estimate.total(point_cloud.points.len()),
+                point_cloud.points.len()
+            );
+        }
+    }
+
+    if let Some(percentile) = args.robust_bounds {
+        point_cloud.set_robust_bounds(percentile);
+    }
+
+    // Octree formats shuffle points into tiles, so a curve order would be lost
+    if args.hilbert_order && !["ept", "3dtiles", "copc"].iter().any(|octree| args.format.eq_ignore_ascii_case(octree)) {
+        profiler.stage("order", || model_parser::hilbert::sort(&mut point_cloud));
+    }
 
     println!("\nPoint cloud generated:");
     println!("  - Total points: {}", point_cloud.metadata.point_count);
     println!("  - Bounds min: {:?}", point_cloud.metadata.bounds_min);
     println!("  - Bounds max: {:?}", point_cloud.metadata.bounds_max);
+    if let Some(robust) = point_cloud.metadata.robust_bounds {
+        println!("  - Robust bounds ({}%): {:?} - {:?}", robust.percentile, robust.min, robust.max);
+    }
     println!("  - Has normals: {}", point_cloud.metadata.has_normals);
     println!("  - Has colors: {}", point_cloud.metadata.has_colors);
+    if point_cloud.metadata.has_alpha {
+        println!("  - Has alpha: true");
+    }
+    if point_cloud.metadata.has_material_ids {
+        println!("  - Materials: {}", point_cloud.metadata.materials.len());
+    }
+    if let Some(counts) = point_cloud.metadata.validation {
+        println!(
+            "  - Invalid values: {} positions, {} normals, {} colors, {} attributes ({} points dropped)",
+            counts.invalid_positions, counts.invalid_normals, counts.invalid_colors, counts.invalid_attributes, counts.dropped
+        );
+    }
+    if let Some(coverage) = coverage {
+        println!(
+            "  - Coverage: {:.1}% within {:.4}, max gap {:.4}",
+            coverage.covered_fraction * 100.0,
+            coverage.radius,
+            coverage.max_gap
+        );
+    }
+
+    if let Some(path) = &args.viewer {
+        let viewer_config = ViewerConfig::for_metadata(&point_cloud.metadata);
+        profiler.stage("viewer", || {
+            std::fs::write(path, model_parser::viewer::render_embedded_viewer(&viewer_config, &point_cloud))
+        })?;
+        println!("\n✓ Viewer written: {:?}", path);
+    }
 
     // Save based on format
+    let mut ept_report = None;
     match args.format.to_lowercase().as_str() {
         "json" => {
             println!("\nSaving to JSON: {:?}", args.output);
-            point_cloud.save_to_file(&args.output)?;
+            profiler.stage("write", || point_cloud.save_to_file_with_layout(&args.output, json_layout))?;
+            println!("✓ Point cloud saved successfully!");
+        }
+        "bin" => {
+            println!("\nSaving binary buffers: {:?}", args.output);
+            let descriptor = profiler.stage("write", || model_parser::buffers::write_buffers(&point_cloud, &args.output))?;
+            println!("✓ Point cloud saved successfully!");
+            println!("  - {} ({} bytes, {} arrays)", descriptor.buffer, descriptor.byte_length, descriptor.arrays.len());
+        }
+        "ply" => {
+            println!("\nSaving to PLY ({}): {:?}", if args.ply_ascii { "ASCII" } else { "binary" }, args.output);
+            profiler.stage("write", || model_parser::ply::write(&point_cloud, &args.output, args.ply_ascii))?;
+            println!("✓ Point cloud saved successfully!");
+        }
+        "pcd" => {
+            println!("\nSaving to PCD ({}): {:?}", args.pcd_data.to_lowercase(), args.output);
+            profiler.stage("write", || model_parser::pcd::write(&point_cloud, &args.output, pcd_data))?;
+            println!("✓ Point cloud saved successfully!");
+        }
+        "las" | "laz" => {
+            let las_writer = las_writer(&args);
+            let [x, y, z] = las_writer.resolve_offset(&point_cloud);
+            println!("\nSaving to {}: {:?}", args.format.to_uppercase(), args.output);
+            profiler.stage("write", || las_writer.write(&point_cloud, &args.output))?;
+            println!("✓ Point cloud saved successfully!");
+            println!("  - Scale {:?}, offset [{}, {}, {}]", args.las_scale, x, y, z);
+        }
+        "copc" => {
+            let las_writer = las_writer(&args);
+            let [x, y, z] = las_writer.resolve_offset(&point_cloud);
+            println!("\nBuilding COPC file: {:?}", args.output);
+            let writer = CopcWriter::new(ept_builder(&args, &config, color_depth, dither, out_of_bounds), las_writer);
+            let report = profiler.stage("write", || writer.write(&point_cloud, &args.output))?;
+            println!("✓ COPC file created successfully!");
+            println!("  - Scale {:?}, offset [{}, {}, {}]", args.las_scale, x, y, z);
+            if report.outside_bounds > 0 {
+                println!(
+                    "  {} points outside --ept-bounds were handled with policy {:?}",
+                    report.outside_bounds, report.out_of_bounds
+                );
+            }
+            ept_report = Some(report);
+        }
+        "hgpc" => {
+            println!("\nSaving to HGPC ({}): {:?}", if args.hgpc_compress { "compressed" } else { "raw" }, args.output);
+            profiler.stage("write", || point_cloud.save_hgpc(&args.output, args.hgpc_compress))?;
             println!("✓ Point cloud saved successfully!");
         }
         "ept" => {
             println!("\nBuilding EPT structure: {:?}", args.output);
-            let ept_builder = EptBuilder::new();
-            ept_builder.build(&point_cloud, &args.output)?;
+            let ept_builder = ept_builder(&args, &config, color_depth, dither, out_of_bounds);
+            let report = match &uploader {
+                Some(uploader) => profiler.stage("write", || ept_builder.upload(&point_cloud, uploader))?,
+                None => profiler.stage("write", || ept_builder.build(&point_cloud, &args.output))?,
+            };
             println!("✓ EPT structure created successfully!");
+            if report.tiles_resumed > 0 {
+                println!("  Resumed: kept {} tiles from the interrupted build", report.tiles_resumed);
+            }
+            if report.outside_bounds > 0 {
+                println!(
+                    "  {} points outside --ept-bounds were handled with policy {:?}",
+                    report.outside_bounds, report.out_of_bounds
+                );
+            }
+            ept_report = Some(report);
             println!("\nEPT files created:");
             println!("  - ept.json (metadata)");
             println!("  - ept-data/ (binary tiles)");
             println!("  - ept-hierarchy/ (octree structure)");
+
+            if args.bundle {
+                let viewer_config = ViewerConfig::for_metadata(&point_cloud.metadata);
+                let files = profiler.stage("bundle", || model_parser::bundle::write_bundle(&args.output, &viewer_config))?;
+                println!("  - index.html, _headers and gzip assets ({} files)", files.len());
+            }
+        }
+        "3dtiles" => {
+            println!("\nBuilding 3D Tiles tileset: {:?}", args.output);
+            let writer = TilesetWriter::new(ept_builder(&args, &config, color_depth, dither, out_of_bounds));
+            let report = profiler.stage("write", || writer.write(&point_cloud, &args.output))?;
+            println!("✓ 3D Tiles tileset created successfully!");
+            if report.outside_bounds > 0 {
+                println!(
+                    "  {} points outside --ept-bounds were handled with policy {:?}",
+                    report.outside_bounds, report.out_of_bounds
+                );
+            }
+            ept_report = Some(report);
+            println!("\n3D Tiles files created:");
+            println!("  - tileset.json (tile tree)");
+            println!("  - tiles/ (.pnts point tiles)");
         }
         _ => {
-            eprintln!("Error: Invalid format '{}'. Use: json or ept", args.format);
+            eprintln!("Error: Invalid format '{}'. Use: json, bin, ply, pcd, las, laz, copc, hgpc, ept or 3dtiles", args.format);
+            drop(staging);
             std::process::exit(1);
         }
     }
 
+    if args.manifest {
+        match &uploader {
+            // Streamed datasets have no local files; the uploader hashed every object it wrote
+            Some(uploader) => {
+                let manifest = uploader.manifest();
+                uploader.put(model_parser::manifest::MANIFEST_FILE, serde_json::to_vec_pretty(&manifest)?)?;
+                let url = uploader.store().url(model_parser::manifest::MANIFEST_FILE);
+                println!("✓ Manifest written: {} ({} files with SHA-256)", url, manifest.files.len());
+            }
+            None => {
+                let (manifest_path, files) = profiler.stage("manifest", || write_manifest(&args.output, &args.format))?;
+                println!("✓ Manifest written: {:?} ({} files with SHA-256)", manifest_path, files);
+            }
+        }
+    }
+
+    if let (Some(store), Some(dir)) = (&remote, &staging) {
+        // A JSON, binary, PLY, PCD, LAS, COPC or HGPC destination names the object itself; other files go next to it
+        let target = if writes_file(&args.format) { store.parent() } else { store.clone() };
+        println!("\nUploading to {}", target.url(""));
+        let urls = profiler.stage("upload", || target.upload_directory(dir.path(), args.upload_jobs))?;
+        println!("✓ Uploaded {} files", urls.len());
+    }
+    if let Some(uploader) = &uploader {
+        println!("\n✓ Uploaded {} files to {}", uploader.len(), uploader.store().url(""));
+    }
+
+    if let Some(path) = &args.report {
+        RunReport::new(&args.input, &destination, &args.format.to_lowercase(), &point_cloud)
+            .with_coverage(coverage)
+            .with_ept(ept_report)
+            .with_stages(profiler.stages())
+            .save_to_file(path)?;
+        println!("\n✓ Report written: {:?}", path);
+    }
+
     Ok(())
 }
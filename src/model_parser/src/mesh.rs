@@ -0,0 +1,415 @@
+use crate::{
+    attributes::AttributeDescriptor,
+    config::{ScalePivot, UpAxis},
+    texture::{self, Texture},
+};
+use glam::{Vec2, Vec3};
+use std::sync::Arc;
+
+/// Values of the "primitive_type" point attribute, telling which kind of primitive a point
+/// was sampled from
+pub mod primitive_type {
+    pub const TRIANGLE: u8 = 0;
+    pub const LINE: u8 = 1;
+    pub const POINT: u8 = 2;
+}
+
+/// Smallest difference between how the two up-axis candidates compare to X (as a fraction of
+/// the surface area) that the surfaces decide on
+const UP_AXIS_MIN_CONTRAST: f32 = 0.1;
+
+/// How much more one up-axis candidate must stand apart from X than the other
+const UP_AXIS_CONFIDENCE: f32 = 2.0;
+
+/// A bounding box extent below this fraction of both others marks a flat scene's up axis
+const FLAT_RATIO: f32 = 0.5;
+
+/// Triangle mesh data extracted from a source model, ready for sampling
+#[derive(Debug, Clone, Default)]
+pub struct Mesh {
+    /// Vertex positions
+    pub vertices: Vec<Vec3>,
+
+    /// Per-vertex normals (empty when normals were not requested)
+    pub normals: Vec<Vec3>,
+
+    /// Per-vertex colors (empty when colors were not requested)
+    pub colors: Vec<Vec3>,
+
+    /// Per-vertex opacity (empty when the source is fully opaque or colors were not requested)
+    pub alphas: Vec<f32>,
+
+    /// Per-vertex texture coordinates into the material's base color texture (empty when no
+    /// material is textured; NaN for vertices without them)
+    pub uvs: Vec<Vec2>,
+
+    /// Base color texture of each material, indexed by material id. Surface samples multiply
+    /// their interpolated vertex color by the texel at their interpolated UV
+    pub base_color_textures: Vec<Option<Arc<Texture>>>,
+
+    /// Whether the colors come from the source (vertex colors or material textures)
+    /// rather than white padding
+    pub has_source_colors: bool,
+
+    /// Triangle indices into `vertices`
+    pub indices: Vec<usize>,
+
+    /// Vertices of point primitives, passed through to the cloud as they are
+    pub points: Vec<usize>,
+
+    /// Line segment index pairs into `vertices`, sampled along their length
+    pub lines: Vec<usize>,
+
+    /// Per-vertex material index into `materials`
+    pub material_ids: Vec<u32>,
+
+    /// Material names, indexed by material id
+    pub materials: Vec<String>,
+
+    /// Whether each material is double-sided, indexed by material id
+    pub double_sided: Vec<bool>,
+
+    /// Alpha cutoff of each material using `alphaMode: MASK`, indexed by material id; surface
+    /// samples whose alpha falls below it are discarded
+    pub alpha_cutoffs: Vec<Option<f32>>,
+
+    /// PBR (metallic, roughness) factors, indexed by material id; empty for formats without them
+    pub metallic_roughness: Vec<[f32; 2]>,
+
+    /// Linear emitted light (factor times strength), indexed by material id; empty for
+    /// formats without it
+    pub emissive: Vec<[f32; 3]>,
+
+    /// Whether the vertices are measured points (e.g. a scan) without faces, passed through
+    /// to the cloud as they are instead of being sampled
+    pub point_set: bool,
+
+    /// Per-vertex generic attributes of point sets (e.g. LiDAR intensity), copied to the cloud
+    pub point_attributes: Vec<(AttributeDescriptor, Vec<f64>)>,
+
+    /// Problems found while loading that did not prevent it, for the caller to report
+    pub warnings: Vec<String>,
+
+    /// Translation already subtracted from the source coordinates to keep f32 precision,
+    /// recorded as the cloud's offset
+    pub offset: Option<[f64; 3]>,
+
+    /// Up axis declared by the source file, for formats that record one
+    pub up_axis: Option<UpAxis>,
+}
+
+impl Mesh {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.vertices.is_empty()
+    }
+
+    /// Whether the given material should be treated as double-sided
+    pub fn is_double_sided(&self, material_id: u32) -> bool {
+        self.double_sided.get(material_id as usize).copied().unwrap_or(false)
+    }
+
+    /// Alpha cutoff of a material, or None when it is not alpha-masked
+    pub fn alpha_cutoff(&self, material_id: u32) -> Option<f32> {
+        self.alpha_cutoffs.get(material_id as usize).copied().flatten()
+    }
+
+    /// (metallic, roughness) of a material, treating unknown materials as rough dielectrics
+    pub fn metallic_roughness_of(&self, material_id: u32) -> [f32; 2] {
+        self.metallic_roughness.get(material_id as usize).copied().unwrap_or([0.0, 1.0])
+    }
+
+    /// Emitted light of a material, treating unknown materials as not emissive
+    pub fn emissive_of(&self, material_id: u32) -> [f32; 3] {
+        self.emissive.get(material_id as usize).copied().unwrap_or([0.0; 3])
+    }
+
+    /// Whether any vertex is colored by a base color texture
+    pub fn is_textured(&self) -> bool {
+        !self.uvs.is_empty() && self.base_color_textures.iter().any(Option::is_some)
+    }
+
+    /// Base color texture of a material
+    pub fn base_color_texture(&self, material_id: u32) -> Option<&Texture> {
+        self.base_color_textures.get(material_id as usize)?.as_deref()
+    }
+
+    /// Multiply the textures into the vertex colors and drop the UVs, for uses that only see
+    /// vertices. Each texture is sampled at the mip level matching the area a vertex color
+    /// covers, so large textures on coarse or sparsely sampled surfaces are averaged, not aliased
+    pub fn bake_textures(&mut self, sample_area: f32) {
+        if self.is_textured() && self.colors.len() == self.vertices.len() {
+            let footprints = texture::uv_footprints(&self.vertices, &self.uvs, &self.indices, 0, sample_area);
+            for (i, (color, footprint)) in self.colors.iter_mut().zip(footprints).enumerate() {
+                let uv = self.uvs[i];
+                let texture = self.material_ids.get(i).and_then(|&id| self.base_color_textures.get(id as usize));
+                if let Some(Some(texture)) = texture
+                    && uv.is_finite()
+                {
+                    *color *= texture.sample_level(uv, texture.lod(footprint));
+                }
+            }
+        }
+
+        self.uvs.clear();
+        self.base_color_textures.clear();
+    }
+
+    /// Position of a scale pivot for this mesh
+    pub fn pivot(&self, pivot: ScalePivot) -> Vec3 {
+        if self.vertices.is_empty() {
+            return Vec3::ZERO;
+        }
+
+        let (min, max) = self.bounds();
+        match pivot {
+            ScalePivot::Origin => Vec3::ZERO,
+            ScalePivot::Centroid => self.vertices.iter().sum::<Vec3>() / self.vertices.len() as f32,
+            ScalePivot::BoundsCenter => (min + max) * 0.5,
+            ScalePivot::BoundsMin => min,
+            ScalePivot::Base => Vec3::new((min.x + max.x) * 0.5, min.y, (min.z + max.z) * 0.5),
+        }
+    }
+
+    /// Scale each axis by `factors` about `pivot`. Normals use the inverse transpose, and
+    /// winding is reversed for mirroring scales so faces keep pointing outward
+    pub fn scale(&mut self, factors: Vec3, pivot: Vec3) {
+        for vertex in &mut self.vertices {
+            *vertex = pivot + (*vertex - pivot) * factors;
+        }
+
+        let inverse = Vec3::ONE / factors;
+        if inverse.is_finite() {
+            for normal in &mut self.normals {
+                *normal = (*normal * inverse).normalize_or_zero();
+            }
+        }
+
+        if factors.x * factors.y * factors.z < 0.0 {
+            for triangle in self.indices.chunks_exact_mut(3) {
+                triangle.swap(1, 2);
+            }
+        }
+    }
+
+    /// Turn a Z-up mesh Y-up, a quarter turn about X: +Z becomes +Y and +Y becomes -Z. The
+    /// offset turns with it, so restored coordinates stay consistent
+    pub fn rotate_z_up(&mut self) {
+        let rotate = |v: Vec3| Vec3::new(v.x, v.z, -v.y);
+        self.vertices.iter_mut().for_each(|vertex| *vertex = rotate(*vertex));
+        self.normals.iter_mut().for_each(|normal| *normal = rotate(*normal));
+        if let Some([x, y, z]) = self.offset {
+            self.offset = Some([x, z, -y]);
+        }
+        self.up_axis = Some(UpAxis::Y);
+    }
+
+    /// Guess whether the mesh is Y-up or Z-up from its shape; `None` when unsure. X is
+    /// horizontal in both conventions, so the other horizontal axis is the one whose surfaces
+    /// face it about as often as they face X: walls, sides and fronts are spread over the two
+    /// horizontal axes, while gravity-aligned planes (ground, floors, table tops) and the lack
+    /// of them (towers, figures) set the up axis apart. Meshes without triangles, or whose
+    /// surfaces don't tell, fall back to the bounding box: flat scenes such as terrain and
+    /// floor plans are thinnest along up
+    pub fn detect_up_axis(&self) -> Option<UpAxis> {
+        // Area-weighted mean of the squared normal components, summing to 1
+        let mut moments = Vec3::ZERO;
+        let mut total = 0.0;
+        for triangle in 0..self.triangle_count() {
+            let [v0, v1, v2] = self.triangle(triangle);
+            let cross = (v1 - v0).cross(v2 - v0);
+            let area = cross.length();
+            if area > 0.0 && area.is_finite() {
+                let normal = cross / area;
+                moments += normal * normal * area;
+                total += area;
+            }
+        }
+        if total > 0.0 {
+            let moments = moments / total;
+            let like_y = (moments.y - moments.x).abs();
+            let like_z = (moments.z - moments.x).abs();
+            if like_y.max(like_z) >= UP_AXIS_MIN_CONTRAST {
+                if like_z > like_y * UP_AXIS_CONFIDENCE {
+                    return Some(UpAxis::Z);
+                }
+                if like_y > like_z * UP_AXIS_CONFIDENCE {
+                    return Some(UpAxis::Y);
+                }
+            }
+        }
+
+        let (min, max) = self.bounds();
+        let extent = max - min;
+        if extent.z < FLAT_RATIO * extent.x.min(extent.y) {
+            Some(UpAxis::Z)
+        } else if extent.y < FLAT_RATIO * extent.x.min(extent.z) {
+            Some(UpAxis::Y)
+        } else {
+            None
+        }
+    }
+
+    /// Number of complete triangles described by the index buffer
+    pub fn triangle_count(&self) -> usize {
+        self.indices.len() / 3
+    }
+
+    /// Area of a single triangle
+    pub fn triangle_area(&self, triangle: usize) -> f32 {
+        let i = triangle * 3;
+        let v0 = self.vertices[self.indices[i]];
+        let v1 = self.vertices[self.indices[i + 1]];
+        let v2 = self.vertices[self.indices[i + 2]];
+        (v1 - v0).cross(v2 - v0).length() * 0.5
+    }
+
+    /// Corner positions of a triangle
+    pub fn triangle(&self, triangle: usize) -> [Vec3; 3] {
+        let i = triangle * 3;
+        [
+            self.vertices[self.indices[i]],
+            self.vertices[self.indices[i + 1]],
+            self.vertices[self.indices[i + 2]],
+        ]
+    }
+
+    /// Total length of all line segments
+    pub fn line_length(&self) -> f32 {
+        self.lines
+            .chunks_exact(2)
+            .map(|segment| self.vertices[segment[0]].distance(self.vertices[segment[1]]))
+            .sum()
+    }
+
+    /// Axis-aligned bounds of all vertices as (min, max)
+    pub fn bounds(&self) -> (Vec3, Vec3) {
+        self.vertices.iter().fold(
+            (Vec3::splat(f32::MAX), Vec3::splat(f32::MIN)),
+            |(min, max), &v| (min.min(v), max.max(v)),
+        )
+    }
+
+    /// Distance to the closest triangle hit by the ray within `max_distance`.
+    /// Tests every triangle; build a `Bvh` for more than a handful of rays
+    pub fn intersect_ray(&self, origin: Vec3, direction: Vec3, max_distance: f32) -> Option<f32> {
+        self.intersect_ray_triangle(origin, direction, max_distance)
+            .map(|(_, distance)| distance)
+    }
+
+    /// Closest triangle hit by the ray within `max_distance` as (triangle, distance)
+    pub fn intersect_ray_triangle(
+        &self,
+        origin: Vec3,
+        direction: Vec3,
+        max_distance: f32,
+    ) -> Option<(usize, f32)> {
+        (0..self.triangle_count())
+            .filter_map(|t| ray_triangle(origin, direction, self.triangle(t)).map(|d| (t, d)))
+            .filter(|&(_, distance)| distance <= max_distance)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+    }
+
+    /// Barycentric weights of `point` with respect to a triangle
+    pub fn barycentric(&self, triangle: usize, point: Vec3) -> Vec3 {
+        let [v0, v1, v2] = self.triangle(triangle);
+        let e0 = v1 - v0;
+        let e1 = v2 - v0;
+        let e2 = point - v0;
+        let d00 = e0.dot(e0);
+        let d01 = e0.dot(e1);
+        let d11 = e1.dot(e1);
+        let d20 = e2.dot(e0);
+        let d21 = e2.dot(e1);
+        let denom = d00 * d11 - d01 * d01;
+        if denom.abs() < f32::EPSILON {
+            return Vec3::new(1.0, 0.0, 0.0);
+        }
+        let v = (d11 * d20 - d01 * d21) / denom;
+        let w = (d00 * d21 - d01 * d20) / denom;
+        Vec3::new(1.0 - v - w, v, w)
+    }
+
+    /// Geometric (face) normal of a triangle
+    pub fn face_normal(&self, triangle: usize) -> Vec3 {
+        let [v0, v1, v2] = self.triangle(triangle);
+        (v1 - v0).cross(v2 - v0).normalize_or_zero()
+    }
+
+    /// Smooth per-vertex normals: the area-weighted sum of the adjacent face normals
+    pub fn compute_vertex_normals(&mut self) {
+        let mut normals = vec![Vec3::ZERO; self.vertices.len()];
+        for triangle in self.indices.chunks_exact(3) {
+            let [v0, v1, v2] = [0, 1, 2].map(|i| self.vertices[triangle[i]]);
+            // The unnormalized cross product is weighted by twice the triangle area
+            let normal = (v1 - v0).cross(v2 - v0);
+            for &index in triangle {
+                normals[index] += normal;
+            }
+        }
+        self.normals = normals.into_iter().map(Vec3::normalize_or_zero).collect();
+    }
+
+    /// Whether the mesh is an unindexed triangle soup: faceless vertices, three per triangle,
+    /// that are not a scan and carry no points or lines
+    pub fn is_triangle_soup(&self) -> bool {
+        !self.point_set
+            && self.indices.is_empty()
+            && self.lines.is_empty()
+            && self.points.is_empty()
+            && self.vertices.len() >= 3
+            && self.vertices.len().is_multiple_of(3)
+    }
+
+    /// Index a triangle soup as consecutive vertex triplets, so its surface is sampled instead
+    /// of its vertices, and give it face normals when it has none. Returns whether it was a soup
+    pub fn index_triangle_soup(&mut self) -> bool {
+        if !self.is_triangle_soup() {
+            return false;
+        }
+
+        self.indices = (0..self.vertices.len()).collect();
+        if self.normals.is_empty() {
+            // Every vertex belongs to a single triangle, so these are the face normals
+            self.compute_vertex_normals();
+        }
+        true
+    }
+
+    /// Total surface area of all triangles
+    pub fn surface_area(&self) -> f32 {
+        (0..self.triangle_count()).map(|t| self.triangle_area(t)).sum()
+    }
+}
+
+/// Möller–Trumbore ray/triangle intersection, returning the hit distance along `direction`
+pub fn ray_triangle(origin: Vec3, direction: Vec3, [v0, v1, v2]: [Vec3; 3]) -> Option<f32> {
+    const EPSILON: f32 = 1e-8;
+
+    let edge1 = v1 - v0;
+    let edge2 = v2 - v0;
+    let p = direction.cross(edge2);
+    let det = edge1.dot(p);
+    if det.abs() < EPSILON {
+        return None;
+    }
+
+    let inv_det = 1.0 / det;
+    let s = origin - v0;
+    let u = s.dot(p) * inv_det;
+    if !(0.0..=1.0).contains(&u) {
+        return None;
+    }
+
+    let q = s.cross(edge1);
+    let v = direction.dot(q) * inv_det;
+    if v < 0.0 || u + v > 1.0 {
+        return None;
+    }
+
+    let t = edge2.dot(q) * inv_det;
+    (t > EPSILON).then_some(t)
+}
@@ -0,0 +1,197 @@
+use crate::{
+    config::PointCloudConfig,
+    error::{ModelParserError, Result},
+    mesh::Mesh,
+};
+use glam::Vec3;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Diffuse color of an MTL material
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct MtlMaterial {
+    diffuse: Vec3,
+}
+
+/// Load a Wavefront OBJ file, with diffuse colors (Kd) from the MTL libraries it references.
+/// Polygons are fan-triangulated; `v x y z r g b` vertex colors are supported
+pub fn load(path: &Path, config: &PointCloudConfig) -> Result<Mesh> {
+    let text = std::fs::read_to_string(path)?;
+    let base_dir = path.parent().unwrap_or_else(|| Path::new(""));
+
+    let mut positions: Vec<Vec3> = Vec::new();
+    let mut vertex_colors: Vec<Option<Vec3>> = Vec::new();
+    let mut normals: Vec<Vec3> = Vec::new();
+    let mut library: HashMap<String, MtlMaterial> = HashMap::new();
+
+    let mut mesh = Mesh::new();
+    let mut material_index: HashMap<String, u32> = HashMap::new();
+    let mut current_material: Option<u32> = None;
+    let mut uses_default_material = false;
+
+    // Output vertices are unique (position, normal, material) combinations
+    let mut vertex_lookup: HashMap<(usize, Option<usize>, Option<u32>), usize> = HashMap::new();
+    let mut material_ids: Vec<Option<u32>> = Vec::new();
+    let mut corner_normals: Vec<Option<Vec3>> = Vec::new();
+    let mut corner_colors: Vec<Option<Vec3>> = Vec::new();
+
+    for (line_number, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        let mut parts = line.split_whitespace();
+        let Some(keyword) = parts.next() else {
+            continue;
+        };
+        let invalid = |what: &str| {
+            ModelParserError::InvalidData(format!("{}:{}: invalid {}", path.display(), line_number + 1, what))
+        };
+
+        match keyword {
+            "v" => {
+                let values: Vec<f32> = parts.map(|p| p.parse::<f32>()).collect::<std::result::Result<_, _>>().map_err(|_| invalid("vertex"))?;
+                if values.len() < 3 {
+                    return Err(invalid("vertex"));
+                }
+                positions.push(Vec3::new(values[0], values[1], values[2]));
+                vertex_colors.push((values.len() >= 6).then(|| Vec3::new(values[3], values[4], values[5])));
+            }
+            "vn" => {
+                let values: Vec<f32> = parts.map(|p| p.parse::<f32>()).collect::<std::result::Result<_, _>>().map_err(|_| invalid("normal"))?;
+                if values.len() < 3 {
+                    return Err(invalid("normal"));
+                }
+                normals.push(Vec3::new(values[0], values[1], values[2]));
+            }
+            "mtllib" => {
+                // Missing libraries only cost the colors
+                for file in parts {
+                    if let Ok(materials) = load_mtl(&base_dir.join(file)) {
+                        library.extend(materials);
+                    }
+                }
+            }
+            "usemtl" => {
+                let name = parts.collect::<Vec<_>>().join(" ");
+                let next = mesh.materials.len() as u32;
+                let id = *material_index.entry(name.clone()).or_insert_with(|| {
+                    mesh.materials.push(name);
+                    next
+                });
+                current_material = Some(id);
+            }
+            "f" => {
+                let mut corners = Vec::new();
+                for corner in parts {
+                    let mut refs = corner.split('/');
+                    let position = resolve(refs.next(), positions.len()).ok_or_else(|| invalid("face"))?;
+                    let _texcoord = refs.next();
+                    let normal = resolve(refs.next(), normals.len());
+                    corners.push((position, normal));
+                }
+                if corners.len() < 3 {
+                    return Err(invalid("face"));
+                }
+                if current_material.is_none() {
+                    uses_default_material = true;
+                }
+
+                // Corners without a normal get the face normal and are never shared
+                let face_normal = (positions[corners[1].0] - positions[corners[0].0])
+                    .cross(positions[corners[2].0] - positions[corners[0].0])
+                    .normalize_or_zero();
+
+                let mut indices = Vec::with_capacity(corners.len());
+                for (position, normal) in corners {
+                    let mut new_vertex = || {
+                        mesh.vertices.push(positions[position]);
+                        corner_normals.push(Some(normal.map_or(face_normal, |n| normals[n])));
+                        corner_colors.push(vertex_colors[position]);
+                        material_ids.push(current_material);
+                        mesh.vertices.len() - 1
+                    };
+                    let index = match normal {
+                        Some(_) => *vertex_lookup
+                            .entry((position, normal, current_material))
+                            .or_insert_with(&mut new_vertex),
+                        None => new_vertex(),
+                    };
+                    indices.push(index);
+                }
+
+                for i in 1..indices.len() - 1 {
+                    mesh.indices.extend([indices[0], indices[i], indices[i + 1]]);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if mesh.vertices.is_empty() {
+        return Err(ModelParserError::NoMeshData);
+    }
+
+    let default_material = mesh.materials.len() as u32;
+    if uses_default_material {
+        mesh.materials.push("default".to_string());
+    }
+    mesh.double_sided = vec![false; mesh.materials.len()];
+    mesh.material_ids = material_ids.iter().map(|id| id.unwrap_or(default_material)).collect();
+
+    if config.include_normals {
+        mesh.normals = corner_normals.iter().map(|n| n.unwrap_or(Vec3::ZERO)).collect();
+    }
+
+    if config.include_colors {
+        mesh.has_source_colors = corner_colors.iter().any(Option::is_some)
+            || mesh.materials.iter().any(|name| library.contains_key(name));
+        mesh.colors = corner_colors
+            .iter()
+            .zip(&mesh.material_ids)
+            .map(|(color, &id)| {
+                let diffuse = mesh
+                    .materials
+                    .get(id as usize)
+                    .and_then(|name| library.get(name))
+                    .map_or(Vec3::ONE, |m| m.diffuse);
+                color.unwrap_or(Vec3::ONE) * diffuse
+            })
+            .collect();
+    }
+
+    Ok(mesh)
+}
+
+/// Resolve a 1-based (or negative, relative) OBJ index
+fn resolve(reference: Option<&str>, count: usize) -> Option<usize> {
+    let index: i64 = reference.filter(|r| !r.is_empty())?.parse().ok()?;
+    let resolved = if index < 0 { count as i64 + index } else { index - 1 };
+    (0..count as i64).contains(&resolved).then_some(resolved as usize)
+}
+
+fn load_mtl(path: &Path) -> Result<HashMap<String, MtlMaterial>> {
+    let text = std::fs::read_to_string(path)?;
+    let mut materials = HashMap::new();
+    let mut current: Option<String> = None;
+
+    for line in text.lines() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        let mut parts = line.split_whitespace();
+        match parts.next() {
+            Some("newmtl") => {
+                let name = parts.collect::<Vec<_>>().join(" ");
+                materials.insert(name.clone(), MtlMaterial { diffuse: Vec3::ONE });
+                current = Some(name);
+            }
+            Some("Kd") => {
+                let values: Vec<f32> = parts.filter_map(|p| p.parse().ok()).collect();
+                if let (Some(name), [r, g, b, ..]) = (&current, values.as_slice())
+                    && let Some(material) = materials.get_mut(name)
+                {
+                    material.diffuse = Vec3::new(*r, *g, *b);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(materials)
+}
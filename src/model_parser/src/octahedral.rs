@@ -0,0 +1,45 @@
+use glam::{Vec2, Vec3};
+use serde::{Deserialize, Serialize};
+
+/// Storage of normals in binary outputs
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NormalEncoding {
+    /// Three 32-bit floats (12 bytes)
+    #[default]
+    Float32,
+
+    /// Octahedral projection quantized to two 16-bit integers (4 bytes, under 0.05° error)
+    Octahedral16,
+}
+
+/// Map a unit vector onto the [-1, 1]² octahedral square
+fn encode_unit(n: Vec3) -> Vec2 {
+    let n = n / (n.x.abs() + n.y.abs() + n.z.abs());
+    let p = Vec2::new(n.x, n.y);
+    if n.z >= 0.0 {
+        p
+    } else {
+        // Fold the lower hemisphere over the diagonals
+        (Vec2::ONE - Vec2::new(p.y.abs(), p.x.abs())) * Vec2::new(p.x.signum(), p.y.signum())
+    }
+}
+
+/// Encode a normal as two 16-bit octahedral coordinates; zero-length normals map to +Z
+pub fn encode(normal: Vec3) -> [u16; 2] {
+    let normal = normal.normalize_or_zero();
+    let p = if normal == Vec3::ZERO { Vec2::ZERO } else { encode_unit(normal) };
+    let quantize = |v: f32| ((v.clamp(-1.0, 1.0) * 0.5 + 0.5) * 65535.0).round() as u16;
+    [quantize(p.x), quantize(p.y)]
+}
+
+/// Decode two 16-bit octahedral coordinates back to a unit normal
+pub fn decode(encoded: [u16; 2]) -> Vec3 {
+    let p = Vec2::new(encoded[0] as f32, encoded[1] as f32) / 65535.0 * 2.0 - Vec2::ONE;
+    let z = 1.0 - p.x.abs() - p.y.abs();
+    let (x, y) = if z >= 0.0 {
+        (p.x, p.y)
+    } else {
+        ((1.0 - p.y.abs()) * p.x.signum(), (1.0 - p.x.abs()) * p.y.signum())
+    };
+    Vec3::new(x, y, z).normalize_or_zero()
+}
@@ -0,0 +1,137 @@
+use crate::{
+    attributes::AttributeType,
+    color::{self, ColorSpace},
+    point_cloud::PointCloud,
+    random,
+};
+use glam::Vec3;
+use rand::Rng;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Attribute holding each point's palette entry
+pub const INDEX_ATTRIBUTE: &str = "palette_index";
+
+/// Most colors a palette can have, so indices fit in a byte
+pub const MAX_COLORS: usize = 256;
+
+/// Colors the palette is fitted to; larger clouds are subsampled, then every point is assigned
+const SAMPLE_SIZE: usize = 65_536;
+
+/// Quantization of point colors to a small palette, for retro-style renders and tiny outputs
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Palette {
+    /// Number of palette colors (1-256)
+    pub colors: usize,
+
+    /// Most k-means refinement rounds; refinement stops early once the palette settles
+    pub iterations: usize,
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Self {
+            colors: 16,
+            iterations: 16,
+        }
+    }
+}
+
+/// Quantize point colors to a palette found by k-means clustering in sRGB, seeded with
+/// k-means++. Each color is replaced by its palette entry and the entry's index stored as the
+/// "palette_index" attribute; the palette, sorted from dark to light, is saved in the
+/// metadata in the cloud's color space. Clouds with fewer distinct colors get a smaller
+/// palette. Alpha is left as is
+pub fn quantize(point_cloud: &mut PointCloud, palette: &Palette, seed: Option<u64>) {
+    if !point_cloud.metadata.has_colors || point_cloud.points.is_empty() {
+        return;
+    }
+
+    let color_space = point_cloud.metadata.color_space;
+    let colors: Vec<Vec3> = point_cloud
+        .points
+        .par_iter()
+        .map(|point| color::convert(Vec3::from(point.color.unwrap_or([1.0; 3])), color_space, ColorSpace::Srgb))
+        .collect();
+
+    let mut rng = random::chunk_rng(seed, random::stream::PALETTE, 0);
+    let sample: Vec<Vec3> = if colors.len() <= SAMPLE_SIZE {
+        colors.clone()
+    } else {
+        (0..SAMPLE_SIZE).map(|_| colors[rng.random_range(0..colors.len())]).collect()
+    };
+
+    let mut centers = initial_centers(&sample, palette.colors.clamp(1, MAX_COLORS), &mut rng);
+    for _ in 0..palette.iterations {
+        let assignments: Vec<usize> = sample.par_iter().map(|&color| nearest(&centers, color)).collect();
+        let mut sums = vec![(Vec3::ZERO, 0usize); centers.len()];
+        for (&color, &center) in sample.iter().zip(&assignments) {
+            sums[center].0 += color;
+            sums[center].1 += 1;
+        }
+
+        // Centers without colors keep their place
+        let mut moved = false;
+        for (center, (sum, count)) in centers.iter_mut().zip(sums) {
+            if count > 0 {
+                let mean = sum / count as f32;
+                moved |= mean != *center;
+                *center = mean;
+            }
+        }
+        if !moved {
+            break;
+        }
+    }
+    centers.sort_by(|a, b| color::luminance(*a).total_cmp(&color::luminance(*b)));
+
+    let indices: Vec<usize> = colors.par_iter().map(|&color| nearest(&centers, color)).collect();
+    let entries: Vec<[f32; 3]> = centers
+        .iter()
+        .map(|&center| color::convert(center, ColorSpace::Srgb, color_space).to_array())
+        .collect();
+    point_cloud.points.par_iter_mut().zip(&indices).for_each(|(point, &index)| {
+        point.color = Some(entries[index]);
+    });
+    let values: Vec<f64> = indices.iter().map(|&index| index as f64).collect();
+    point_cloud.set_attribute_values(INDEX_ATTRIBUTE, AttributeType::U8, &values);
+    point_cloud.metadata.palette = entries;
+}
+
+/// Up to `count` k-means++ centers: each further center is drawn with probability
+/// proportional to its squared distance from the nearest center so far
+fn initial_centers(sample: &[Vec3], count: usize, rng: &mut impl Rng) -> Vec<Vec3> {
+    let mut centers = vec![sample[rng.random_range(0..sample.len())]];
+    let mut distances: Vec<f32> = sample.iter().map(|color| color.distance_squared(centers[0])).collect();
+    while centers.len() < count {
+        let total: f32 = distances.iter().sum();
+        if total <= 0.0 {
+            // Every color is already a center
+            break;
+        }
+        let mut target = rng.random::<f32>() * total;
+        let index = distances
+            .iter()
+            .position(|&distance| {
+                target -= distance;
+                target <= 0.0 && distance > 0.0
+            })
+            .unwrap_or_else(|| distances.iter().rposition(|&distance| distance > 0.0).unwrap_or(0));
+        let center = sample[index];
+        centers.push(center);
+        distances
+            .par_iter_mut()
+            .zip(sample)
+            .for_each(|(distance, color)| *distance = distance.min(color.distance_squared(center)));
+    }
+    centers
+}
+
+/// Index of the center closest to `color`
+fn nearest(centers: &[Vec3], color: Vec3) -> usize {
+    centers
+        .iter()
+        .enumerate()
+        .min_by(|(_, a), (_, b)| a.distance_squared(color).total_cmp(&b.distance_squared(color)))
+        .map_or(0, |(index, _)| index)
+}
@@ -1,135 +1,816 @@
 use crate::{
-    config::{PointCloudConfig, SamplingStrategy},
+    animation,
+    attributes::{AttributeDescriptor, AttributeType},
+    bvh::Bvh,
+    collada, decimate, e57,
+    config::{AmbientOcclusion, JitterMode, NormalOrientation, PointCloudConfig, SamplingStrategy, SplatRadius, UpAxis},
     error::{ModelParserError, Result},
+    fbx, grading,
+    hooks::PipelineHooks,
+    las, lidar, lighting,
+    random,
+    mesh::{Mesh, primitive_type},
+    obj, palette, pcd, ply,
     point_cloud::{Point, PointCloud},
+    spatial::KdTree,
+    stl, stylize,
+    texture::{self, TextureCache},
+    validation, weld, xyz,
+};
+use glam::{Mat3, Mat4, Quat, Vec2, Vec3};
+use gltf::{
+    accessor::{DataType, Dimensions},
+    mesh::Mode,
 };
-use glam::Vec3;
 use rand::Rng;
 use rayon::prelude::*;
+use std::ops::Range;
 use std::path::Path;
 
+/// glTF extension for Draco-compressed primitives, which is not decoded
+const DRACO_EXTENSION: &str = "KHR_draco_mesh_compression";
+
+/// glTF extension replicating a node's mesh at per-instance transforms
+const GPU_INSTANCING_EXTENSION: &str = "EXT_mesh_gpu_instancing";
+
+/// Basis Universal (KTX2) textures, decoded by `import_images`
+const BASISU_EXTENSION: &str = "KHR_texture_basisu";
+
+/// Draws per surface sample before one falling on alpha-masked cut-outs is dropped
+const ALPHA_MASK_ATTEMPTS: usize = 32;
+
 pub struct ModelParser;
 
 impl ModelParser {
     /// Parse a 3D model file and generate a point cloud
     pub fn parse_file(path: &Path, config: &PointCloudConfig) -> Result<PointCloud> {
+        let mesh = Self::load_mesh(path, config)?;
+        Self::sample_mesh(&mesh, path, config)
+    }
+
+    /// Parse a 3D model file like `parse_file`, running the post-parse hooks on the loaded mesh
+    /// and the post-sample hooks on the generated cloud. Pre-write hooks are left to the caller
+    pub fn parse_file_with_hooks(path: &Path, config: &PointCloudConfig, hooks: &PipelineHooks) -> Result<PointCloud> {
+        let mut mesh = Self::load_mesh(path, config)?;
+        hooks.run_post_parse(&mut mesh)?;
+        let mut point_cloud = Self::sample_mesh(&mesh, path, config)?;
+        hooks.run_post_sample(&mut point_cloud)?;
+        Ok(point_cloud)
+    }
+
+    /// Load the mesh of a 3D model file, choosing the reader by extension
+    pub fn load_mesh(path: &Path, config: &PointCloudConfig) -> Result<Mesh> {
         let extension = path
             .extension()
             .and_then(|e| e.to_str())
             .ok_or_else(|| ModelParserError::UnsupportedFormat("no extension".to_string()))?;
 
-        match extension.to_lowercase().as_str() {
-            "gltf" | "glb" => Self::parse_gltf(path, config),
-            ext => Err(ModelParserError::UnsupportedFormat(format!(
-                "{} (currently only GLTF/GLB supported)",
-                ext
-            ))),
+        let mut mesh = match extension.to_lowercase().as_str() {
+            "gltf" | "glb" => Self::load_gltf_mesh(path, config)?,
+            "las" | "laz" => las::load(path, config)?,
+            "dae" => collada::load(path, config)?,
+            "e57" => e57::load(path, config)?,
+            "fbx" => fbx::load(path, config)?,
+            "obj" => obj::load(path, config)?,
+            "pcd" => pcd::load(path, config)?,
+            "ply" => ply::load(path, config)?,
+            "stl" => stl::load(path, config)?,
+            "xyz" | "csv" | "txt" => xyz::load(path, config)?,
+            ext => {
+                return Err(ModelParserError::UnsupportedFormat(format!(
+                    "{} (supported: gltf, glb, dae, e57, fbx, las, obj, pcd, ply, stl, xyz, csv, txt)",
+                    ext
+                )));
+            }
+        };
+
+        mesh.index_triangle_soup();
+
+        // Clouds are Y-up: turn Z-up models, as declared by the file or detected from its shape
+        let up_axis = match (config.up_axis, mesh.up_axis) {
+            (UpAxis::Auto, Some(declared)) => declared,
+            (UpAxis::Auto, None) if !mesh.point_set => {
+                let detected = mesh.detect_up_axis().unwrap_or(UpAxis::Y);
+                if detected == UpAxis::Z {
+                    mesh.warnings.push(
+                        "the model looks Z-up and was turned Y-up; use --up-axis y to keep it as authored".to_string(),
+                    );
+                }
+                detected
+            }
+            (UpAxis::Auto, None) => UpAxis::Y,
+            (up_axis, _) => up_axis,
+        };
+        if up_axis == UpAxis::Z {
+            mesh.rotate_z_up();
         }
+
+        if config.axis_scale != [1.0, 1.0, 1.0] {
+            let pivot = mesh.pivot(config.scale_pivot);
+            mesh.scale(Vec3::from(config.axis_scale), pivot);
+        }
+
+        // Welding and decimation move vertices across UV seams, so they see baked colors
+        if config.weld.is_some() || config.decimate.is_some() {
+            mesh.bake_textures(mesh.surface_area() / config.point_count.max(1) as f32);
+        }
+
+        if let Some(tolerance) = config.weld {
+            weld::weld(&mut mesh, tolerance, config.weld_normal_angle);
+        }
+
+        if let Some(target) = config.decimate {
+            decimate::decimate(&mut mesh, target);
+        }
+
+        Ok(mesh)
     }
 
-    /// Parse GLTF/GLB file
-    fn parse_gltf(path: &Path, config: &PointCloudConfig) -> Result<PointCloud> {
-        let (document, buffers, _) = gltf::import(path)?;
+    /// Generate a point cloud from already extracted mesh data
+    pub fn sample_mesh(mesh: &Mesh, path: &Path, config: &PointCloudConfig) -> Result<PointCloud> {
+        if mesh.is_empty() {
+            return Err(ModelParserError::NoMeshData);
+        }
+
+        // Meshes that did not come through `load_mesh` may still be unindexed triangle soups
+        let indexed = mesh.is_triangle_soup().then(|| {
+            let mut indexed = mesh.clone();
+            indexed.index_triangle_soup();
+            indexed
+        });
+        let mesh = indexed.as_ref().unwrap_or(mesh);
+
+        // Triangle surfaces sample base color textures per point; vertex, line and scanner
+        // samples take colors with the textures baked in at the vertices
+        let baked = mesh.is_textured().then(|| {
+            let mut baked = mesh.clone();
+            baked.bake_textures(mesh.surface_area() / config.point_count.max(1) as f32);
+            baked
+        });
+        let vertex_mesh = baked.as_ref().unwrap_or(mesh);
+
+        // Generate point cloud based on sampling strategy
+        let mut points = Self::generate_point_cloud(mesh, vertex_mesh, config);
+        Self::orient_normals(&mut points, mesh, config);
+
+        let source_file = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("unknown")
+            .to_string();
+
+        let mut point_cloud = PointCloud::new(points, source_file);
+        point_cloud.metadata.offset = mesh.offset;
+        if mesh.point_set {
+            for (descriptor, values) in &mesh.point_attributes {
+                point_cloud.set_attribute_values(&descriptor.name, descriptor.data_type, values);
+            }
+        }
+        if matches!(config.sampling_strategy, SamplingStrategy::Scanner) {
+            point_cloud
+                .metadata
+                .attributes
+                .push(AttributeDescriptor::new("scan_id", AttributeType::U16));
+        }
+        if Self::has_primitive_types(mesh, config) {
+            point_cloud
+                .metadata
+                .attributes
+                .push(AttributeDescriptor::new("primitive_type", AttributeType::U8));
+        }
+
+        if let Some(settings) = config.ambient_occlusion {
+            Self::bake_ambient_occlusion(&mut point_cloud, mesh, config, settings);
+        }
+
+        if let Some(method) = config.splat_radius {
+            Self::estimate_splat_radius(&mut point_cloud, mesh, config, method);
+        }
+
+        if let Some(noise) = config.lidar_noise {
+            lidar::apply_noise(&mut point_cloud, &noise, config.seed);
+        }
+
+        if config.synthesize_intensity && !mesh.point_set {
+            lidar::synthesize_intensity(&mut point_cloud, mesh);
+        }
+
+        if config.material_attributes && !mesh.point_set {
+            Self::store_material_attributes(&mut point_cloud, mesh);
+        }
+
+        if let Some(style) = config.stylize {
+            stylize::apply(&mut point_cloud, &style, config.seed);
+        }
+
+        if let Some(lighting) = config.light_baking {
+            lighting::bake(&mut point_cloud, &lighting);
+        }
+
+        if let Some(grading) = config.color_grading {
+            grading::grade(&mut point_cloud, &grading);
+        }
+
+        // Mesh colors are linear; convert to the requested output space last
+        point_cloud.convert_color_space(config.color_space);
+
+        if let Some(settings) = config.palette {
+            palette::quantize(&mut point_cloud, &settings, config.seed);
+        }
+
+        validation::validate(&mut point_cloud, config.validation)?;
+
+        if config.seed.is_some() {
+            point_cloud.canonicalize_floats();
+        }
+
+        if config.random_key {
+            point_cloud.assign_random_keys(config.seed);
+        }
+
+        // Material ids are always tracked during sampling; drop them unless requested
+        if config.include_material_ids {
+            point_cloud.metadata.materials = mesh.materials.clone();
+        } else {
+            point_cloud.points.par_iter_mut().for_each(|point| point.material_id = None);
+            point_cloud.metadata.has_material_ids = false;
+        }
+
+        Ok(point_cloud)
+    }
+
+    /// Extract mesh data from a GLTF/GLB file
+    pub fn load_gltf_mesh(path: &Path, config: &PointCloudConfig) -> Result<Mesh> {
+        let (document, buffers, images, image_warnings) = import_gltf(path)?;
+
+        let textures = TextureCache::new(if config.include_colors { &images } else { &[] });
+
+        let mut mesh = Mesh::new();
+        mesh.up_axis = Some(UpAxis::Y);
+        if config.include_colors {
+            mesh.warnings.extend(image_warnings);
+        }
+
+        // Material lookup table; primitives without a material get a trailing "default" entry
+        mesh.materials = document
+            .materials()
+            .map(|m| {
+                m.name()
+                    .map(str::to_string)
+                    .unwrap_or_else(|| format!("material_{}", m.index().unwrap_or(0)))
+            })
+            .collect();
+        mesh.double_sided = document.materials().map(|m| m.double_sided()).collect();
+        mesh.alpha_cutoffs = document
+            .materials()
+            .map(|m| (m.alpha_mode() == gltf::material::AlphaMode::Mask).then(|| m.alpha_cutoff().unwrap_or(0.5)))
+            .collect();
+        mesh.metallic_roughness = document
+            .materials()
+            .map(|m| {
+                let pbr = m.pbr_metallic_roughness();
+                [pbr.metallic_factor(), pbr.roughness_factor()]
+            })
+            .collect();
+        mesh.emissive = document
+            .materials()
+            .map(|m| m.emissive_factor().map(|f| f * m.emissive_strength().unwrap_or(1.0)))
+            .collect();
+        mesh.base_color_textures = vec![None; mesh.materials.len()];
+        let default_material = mesh.materials.len() as u32;
+        let mut uses_default_material = false;
 
-        let mut all_vertices = Vec::new();
-        let mut all_normals = Vec::new();
-        let mut all_colors = Vec::new();
-        let mut all_indices = Vec::new();
+        // Which optional attributes each primitive provided, with its index range
+        let mut primitives = Vec::new();
+
+        // Mesh instances of the requested scene's node tree (else the default or first scene)
+        // with their world transforms; files without scenes use every mesh untransformed. Nodes are posed by the requested animation, or
+        // at the rest pose stored in the file
+        let state = animation::evaluate(&document, &buffers, config.pose.as_ref())?;
+        let scene = match config.scene.as_deref() {
+            Some(name) => Some(find_scene(&document, name)?),
+            None => document.default_scene().or_else(|| document.scenes().next()),
+        };
+        let instances: Vec<MeshInstance> = match scene {
+            Some(scene) => {
+                let mut instances = Vec::new();
+                for node in scene.nodes() {
+                    collect_mesh_instances(node, &state.world, &document, &buffers, &[], &mut instances);
+                }
+                instances
+            }
+            None => document
+                .meshes()
+                .map(|mesh| MeshInstance {
+                    names: mesh.name().into_iter().collect(),
+                    mesh,
+                    transform: Mat4::IDENTITY,
+                    node: None,
+                })
+                .collect(),
+        };
+
+        // Meshes are selected by their node, ancestor node and mesh names
+        let found = instances.len();
+        let instances: Vec<MeshInstance> =
+            instances.into_iter().filter(|instance| config.mesh_filter.accepts(&instance.names)).collect();
+        if found > 0 && instances.is_empty() {
+            return Err(ModelParserError::InvalidData(format!(
+                "none of the {} meshes match the name patterns (include {:?}, exclude {:?})",
+                found, config.mesh_filter.include, config.mesh_filter.exclude
+            )));
+        }
+
+        let variant = match config.material_variant.as_deref() {
+            Some(name) => Some(find_variant(&document, name)?),
+            None => None,
+        };
 
         // Extract mesh data
-        for mesh in document.meshes() {
-            for primitive in mesh.primitives() {
-                let reader = primitive.reader(|buffer| Some(&buffers[buffer.index()]));
+        for MeshInstance { mesh: gltf_mesh, transform, node, .. } in instances {
+            // Normals use the inverse transpose; mirroring transforms reverse the winding
+            let normal_matrix = Mat3::from_mat4(transform).inverse().transpose();
+            let mirrored = transform.determinant() < 0.0;
+
+            // Morph target weights: requested ones, else the animated, node's or mesh's ones
+            let weights = config
+                .morph_weights
+                .as_deref()
+                .or_else(|| node.as_ref().and_then(|node| state.weights[node.index()].as_deref()))
+                .or_else(|| node.as_ref().and_then(|node| node.weights()))
+                .or_else(|| gltf_mesh.weights())
+                .unwrap_or_default();
+
+            // Skinned meshes are placed by their joints, which already include the node transforms
+            let joints = node
+                .as_ref()
+                .and_then(|node| node.skin())
+                .map(|skin| animation::joint_matrices(&skin, &buffers, &state.world));
+
+            for primitive in gltf_mesh.primitives() {
+                let reader = primitive.reader(|buffer| buffers.get(buffer.index()).map(|data| &data.0[..]));
+
+                // The requested material variant replaces the material of primitives it maps
+                let material = variant
+                    .and_then(|variant| primitive.mappings().find(|mapping| mapping.variants().contains(&variant)))
+                    .map(|mapping| mapping.material())
+                    .unwrap_or_else(|| primitive.material());
 
                 // Read positions
                 if let Some(positions) = reader.read_positions() {
-                    let base_index = all_vertices.len();
-                    all_vertices.extend(positions.map(Vec3::from));
+                    let base_index = mesh.vertices.len();
+                    let mut texture = None;
+                    let mut positions: Vec<Vec3> = positions.map(Vec3::from).collect();
 
-                    // Read normals if available and requested
+                    // Blend in the weighted morph target displacements before placing the mesh
+                    let mut normal_deltas = Vec::new();
+                    for ((target_positions, target_normals, _), &weight) in reader.read_morph_targets().zip(weights) {
+                        if weight == 0.0 {
+                            continue;
+                        }
+                        for (position, delta) in positions.iter_mut().zip(target_positions.into_iter().flatten()) {
+                            *position += Vec3::from(delta) * weight;
+                        }
+                        if let Some(deltas) = target_normals {
+                            normal_deltas.resize(positions.len(), Vec3::ZERO);
+                            for (sum, delta) in normal_deltas.iter_mut().zip(deltas) {
+                                *sum += Vec3::from(delta) * weight;
+                            }
+                        }
+                    }
+                    let skinning = joints.as_deref().and_then(|joints| skin_transforms(&reader, joints, positions.len()));
+                    let vertex_transform = |i: usize| skinning.as_ref().map_or(transform, |matrices| matrices[i]);
+                    mesh.vertices.extend(positions.into_iter().enumerate().map(|(i, p)| vertex_transform(i).transform_point3(p)));
+
+                    let vertex_count = mesh.vertices.len() - base_index;
+                    let mut present = PrimitiveAttributes {
+                        vertices: base_index..mesh.vertices.len(),
+                        normals: false,
+                        colors: false,
+                    };
+
+                    // Read normals if available and requested; missing ones are computed once
+                    // the primitive's triangles are known
                     if config.include_normals {
-                        if let Some(normals) = reader.read_normals() {
-                            all_normals.extend(normals.map(Vec3::from));
+                        let normals: Vec<Vec3> = reader
+                            .read_normals()
+                            .map(|normals| {
+                                normals
+                                    .enumerate()
+                                    .map(|(i, n)| {
+                                        let n = Vec3::from(n) + normal_deltas.get(i).copied().unwrap_or(Vec3::ZERO);
+                                        let normal_matrix = match &skinning {
+                                            Some(matrices) => Mat3::from_mat4(matrices[i]).inverse().transpose(),
+                                            None => normal_matrix,
+                                        };
+                                        (normal_matrix * n).normalize_or_zero()
+                                    })
+                                    .collect()
+                            })
+                            .unwrap_or_default();
+                        present.normals = normals.len() == vertex_count;
+                        if present.normals {
+                            mesh.normals.extend(normals);
                         } else {
-                            // Pad with zero normals if not available
-                            all_normals.resize(all_vertices.len(), Vec3::ZERO);
+                            mesh.normals.extend(std::iter::repeat_n(Vec3::ZERO, vertex_count));
                         }
                     }
 
-                    // Read colors if available and requested
+                    // Read colors if available and requested, white otherwise
                     if config.include_colors {
-                        if let Some(colors) = reader.read_colors(0) {
-                            all_colors.extend(colors.into_rgb_f32().map(Vec3::from));
+                        let colors: Vec<[f32; 4]> = reader
+                            .read_colors(0)
+                            .map(|colors| colors.into_rgba_f32().collect())
+                            .unwrap_or_default();
+                        present.colors = colors.len() == vertex_count;
+                        let mut alphas: Vec<f32> = colors.iter().map(|&[_, _, _, a]| a).collect();
+                        if present.colors {
+                            mesh.colors.extend(colors.iter().map(|&[r, g, b, _]| Vec3::new(r, g, b)));
+                            mesh.has_source_colors = true;
                         } else {
-                            // Default white color
-                            all_colors.resize(all_vertices.len(), Vec3::ONE);
+                            // Without vertex colors the material's flat base color is used, so
+                            // multi-material models keep their per-material coloring
+                            let [r, g, b, _] = material.pbr_metallic_roughness().base_color_factor();
+                            let factor = Vec3::new(r, g, b);
+                            mesh.colors.extend(std::iter::repeat_n(factor, vertex_count));
+                            alphas = vec![1.0; vertex_count];
+                            if factor != Vec3::ONE {
+                                present.colors = true;
+                                mesh.has_source_colors = true;
+                            }
+                        }
+
+                        // Legacy specular-glossiness materials tint by their diffuse factor; the
+                        // diffuse or base color texture is sampled per surface point at its UVs
+                        let (texture_info, opacity) = match material.pbr_specular_glossiness() {
+                            Some(spec_gloss) => {
+                                let [r, g, b, a] = spec_gloss.diffuse_factor();
+                                let factor = Vec3::new(r, g, b);
+                                mesh.has_source_colors = true;
+                                mesh.colors[base_index..].iter_mut().for_each(|color| *color *= factor);
+                                (spec_gloss.diffuse_texture(), a)
+                            }
+                            None => {
+                                let metallic_roughness = material.pbr_metallic_roughness();
+                                (metallic_roughness.base_color_texture(), metallic_roughness.base_color_factor()[3])
+                            }
+                        };
+
+                        // Vertex alpha times the factor's alpha; the mesh only carries alpha
+                        // once some vertex is translucent, with earlier vertices opaque
+                        alphas.iter_mut().for_each(|alpha| *alpha *= opacity);
+                        if !mesh.alphas.is_empty() || alphas.iter().any(|&alpha| alpha < 1.0) {
+                            mesh.alphas.resize(base_index, 1.0);
+                            mesh.alphas.extend(alphas);
+                        }
+
+                        // UVs are stored with the texture's `KHR_texture_transform` applied, so
+                        // atlased and tiled materials are sampled where the renderer would
+                        if let Some(info) = texture_info
+                            && let Some(uvs) = reader.read_tex_coords(texture_tex_coord(&info))
+                            && let Some(source) = texture_image(&info.texture()).and_then(|image| textures.get(image))
+                        {
+                            let transform = texture_transform(&info);
+                            let uvs: Vec<Vec2> =
+                                uvs.into_f32().map(|uv| transform.transform_point2(Vec2::from(uv))).collect();
+                            if uvs.len() == vertex_count {
+                                mesh.uvs.resize(base_index, Vec2::NAN);
+                                mesh.uvs.extend(uvs);
+                                mesh.has_source_colors = true;
+                                texture = Some(source);
+                            }
                         }
                     }
 
-                    // Read indices for triangle-based sampling
-                    if let Some(indices) = reader.read_indices() {
-                        all_indices.extend(indices.into_u32().map(|i| (i as usize) + base_index));
+                    // Tag every vertex of the primitive with its material
+                    let material_id = match material.index() {
+                        Some(index) => index as u32,
+                        None => {
+                            uses_default_material = true;
+                            default_material
+                        }
+                    };
+                    mesh.material_ids.resize(mesh.vertices.len(), material_id);
+                    if let Some(texture) = texture {
+                        mesh.base_color_textures[material_id as usize] = Some(texture);
+                    }
+
+                    // Non-indexed primitives use their vertices in order
+                    let indices: Vec<usize> = match reader.read_indices() {
+                        Some(indices) => indices.into_u32().map(|i| (i as usize) + base_index).collect(),
+                        None => (base_index..mesh.vertices.len()).collect(),
+                    };
+
+                    // Triangles are sampled on their surface, lines along their length and
+                    // points passed through; strips, fans and loops are expanded first
+                    let index_start = mesh.indices.len();
+                    match primitive.mode() {
+                        Mode::Triangles => mesh.indices.extend(indices.chunks_exact(3).flatten()),
+                        Mode::TriangleStrip => {
+                            for (i, window) in indices.windows(3).enumerate() {
+                                // Every other triangle is flipped to keep a consistent winding
+                                let [a, b, c] = [window[0], window[1], window[2]];
+                                mesh.indices.extend(if i % 2 == 0 { [a, b, c] } else { [b, a, c] });
+                            }
+                        }
+                        Mode::TriangleFan => {
+                            for pair in indices.get(1..).unwrap_or_default().windows(2) {
+                                mesh.indices.extend([indices[0], pair[0], pair[1]]);
+                            }
+                        }
+                        Mode::Points => mesh.points.extend(indices),
+                        Mode::Lines => mesh.lines.extend(indices.chunks_exact(2).flatten()),
+                        Mode::LineStrip => mesh.lines.extend(indices.windows(2).flatten()),
+                        Mode::LineLoop => {
+                            mesh.lines.extend(indices.windows(2).flatten());
+                            if let (Some(&first), Some(&last)) = (indices.first(), indices.last())
+                                && indices.len() > 2
+                            {
+                                mesh.lines.extend([last, first]);
+                            }
+                        }
+                    }
+                    if mirrored {
+                        mesh.indices[index_start..].chunks_exact_mut(3).for_each(|t| t.swap(1, 2));
                     }
+
+                    primitives.push((present, index_start..mesh.indices.len()));
                 }
             }
         }
 
-        if all_vertices.is_empty() {
-            return Err(ModelParserError::NoMeshData);
+        Self::fill_missing_attributes(&mut mesh, &primitives, config);
+
+        if uses_default_material {
+            mesh.materials.push("default".to_string());
+            mesh.double_sided.push(false);
+            mesh.alpha_cutoffs.push(None);
+            mesh.metallic_roughness.push([0.0, 1.0]);
+            mesh.emissive.push([0.0; 3]);
+            mesh.base_color_textures.push(None);
         }
 
-        // Generate point cloud based on sampling strategy
-        let points = Self::generate_point_cloud(
-            &all_vertices,
-            &all_normals,
-            &all_colors,
-            &all_indices,
-            config,
-        );
+        if !mesh.alphas.is_empty() {
+            mesh.alphas.resize(mesh.vertices.len(), 1.0);
+        }
+        if !mesh.uvs.is_empty() {
+            mesh.uvs.resize(mesh.vertices.len(), Vec2::NAN);
+        }
 
-        let source_file = path
-            .file_name()
-            .and_then(|n| n.to_str())
-            .unwrap_or("unknown")
-            .to_string();
+        Ok(mesh)
+    }
+
+    /// Give primitives without normals area-weighted normals from their own triangles, and
+    /// warn when only some primitives carry normals or colors
+    fn fill_missing_attributes(
+        mesh: &mut Mesh,
+        primitives: &[(PrimitiveAttributes, Range<usize>)],
+        config: &PointCloudConfig,
+    ) {
+        let without_normals = primitives.iter().filter(|(p, _)| !p.normals).count();
+        if config.include_normals && without_normals > 0 {
+            for (present, triangles) in primitives.iter().filter(|(p, _)| !p.normals) {
+                for triangle in mesh.indices[triangles.clone()].chunks_exact(3) {
+                    let [v0, v1, v2] = [0, 1, 2].map(|i| mesh.vertices[triangle[i]]);
+                    let normal = (v1 - v0).cross(v2 - v0);
+                    for &index in triangle {
+                        mesh.normals[index] += normal;
+                    }
+                }
+                for normal in &mut mesh.normals[present.vertices.clone()] {
+                    *normal = normal.normalize_or_zero();
+                }
+            }
+            mesh.warnings.push(format!(
+                "{} of {} primitives have no normals; computed them from the triangles",
+                without_normals,
+                primitives.len()
+            ));
+        }
+
+        let without_colors = primitives.iter().filter(|(p, _)| !p.colors).count();
+        if config.include_colors && without_colors > 0 && without_colors < primitives.len() {
+            mesh.warnings.push(format!(
+                "{} of {} primitives have no vertex colors or base color; they are white",
+                without_colors,
+                primitives.len()
+            ));
+        }
+    }
 
-        Ok(PointCloud::new(points, source_file))
+    /// Store each point's material factors as "metallic", "roughness" and linear
+    /// "emissive_r/g/b" attributes, for physically based shading in the viewer
+    fn store_material_attributes(point_cloud: &mut PointCloud, mesh: &Mesh) {
+        let mut columns: [Vec<f64>; 5] = Default::default();
+        for point in &point_cloud.points {
+            let (metallic_roughness, emissive) = match point.material_id {
+                Some(id) => (mesh.metallic_roughness_of(id), mesh.emissive_of(id)),
+                None => ([0.0, 1.0], [0.0; 3]),
+            };
+            for (column, value) in columns.iter_mut().zip(metallic_roughness.into_iter().chain(emissive)) {
+                column.push(value as f64);
+            }
+        }
+
+        let names = ["metallic", "roughness", "emissive_r", "emissive_g", "emissive_b"];
+        for (name, values) in names.into_iter().zip(&columns) {
+            point_cloud.set_attribute_values(name, AttributeType::F32, values);
+        }
     }
 
-    fn generate_point_cloud(
-        vertices: &[Vec3],
-        normals: &[Vec3],
-        colors: &[Vec3],
-        indices: &[usize],
+    /// Store the fraction of occluded hemisphere rays as the "occlusion" attribute (0 = open, 1 = fully occluded)
+    fn bake_ambient_occlusion(
+        point_cloud: &mut PointCloud,
+        mesh: &Mesh,
         config: &PointCloudConfig,
-    ) -> Vec<Point> {
-        let mut rng = rand::rng();
-        let has_normals = !normals.is_empty();
-        let has_colors = !colors.is_empty();
+        settings: AmbientOcclusion,
+    ) {
+        if mesh.triangle_count() == 0 || settings.samples == 0 {
+            return;
+        }
 
-        match config.sampling_strategy {
-            SamplingStrategy::Vertices => {
-                // Use existing vertices in parallel
-                vertices
-                    .par_iter()
-                    .take(config.point_count)
-                    .enumerate()
-                    .map(|(i, &pos)| {
-                        let scaled_pos = pos * config.scale;
-                        let mut point = Point::new(scaled_pos);
+        // Rays are cast in mesh space, so undo the output scale
+        let scale = if config.scale != 0.0 { config.scale } else { 1.0 };
+        let max_distance = settings.max_distance / scale.abs();
+        let (min, max) = mesh.bounds();
+        let offset = (max - min).length() * 1e-4;
+        let bvh = &Bvh::new(mesh);
 
-                        if has_normals && config.include_normals && i < normals.len() {
-                            point = point.with_normal(normals[i]);
-                        }
+        // Small chunks: every point casts many rays, so parallelism matters more than RNG setup
+        const AO_CHUNK_SIZE: usize = 64;
+
+        let occlusion: Vec<f64> = point_cloud
+            .points
+            .par_chunks(AO_CHUNK_SIZE)
+            .enumerate()
+            .flat_map_iter(|(chunk, points)| {
+                let mut rng = random::chunk_rng(config.seed, random::stream::AMBIENT_OCCLUSION, chunk as u64);
+                points.iter().map(move |point| {
+                    let position = Vec3::from(point.position) / scale;
+                    let normal = point
+                        .normal
+                        .map(Vec3::from)
+                        .filter(|n| n.is_finite() && n.length_squared() > 0.0)
+                        .map(|n| n.normalize());
 
-                        if has_colors && config.include_colors && i < colors.len() {
-                            point = point.with_color(colors[i]);
+                    let mut hits = 0;
+                    for _ in 0..settings.samples {
+                        let direction = match normal {
+                            Some(n) => cosine_hemisphere(&mut rng, n),
+                            None => uniform_sphere(&mut rng),
+                        };
+                        let origin = position + normal.unwrap_or(direction) * offset;
+                        if bvh.occluded(origin, direction, max_distance) {
+                            hits += 1;
                         }
+                    }
+
+                    hits as f64 / settings.samples as f64
+                })
+            })
+            .collect();
+
+        point_cloud.set_attribute_values("occlusion", AttributeType::F32, &occlusion);
+    }
+
+    /// Store a per-point splat radius as the "radius" attribute
+    fn estimate_splat_radius(
+        point_cloud: &mut PointCloud,
+        mesh: &Mesh,
+        config: &PointCloudConfig,
+        method: SplatRadius,
+    ) {
+        let point_count = point_cloud.points.len().max(1) as f32;
 
-                        point
+        match method {
+            SplatRadius::SurfaceArea => {
+                let area = mesh.surface_area() * config.scale * config.scale;
+                let radius = (area / (point_count * std::f32::consts::PI)).sqrt() as f64;
+                point_cloud.set_attribute("radius", AttributeType::F32, |_| radius);
+            }
+            SplatRadius::KNearest(k) => {
+                let tree = KdTree::from_points(&point_cloud.points);
+                let radii: Vec<f64> = point_cloud
+                    .points
+                    .par_iter()
+                    .map(|point| {
+                        // The first neighbour is the point itself
+                        let neighbours = tree.nearest(Vec3::from(point.position), k + 1);
+                        let distances: Vec<f32> = neighbours.iter().skip(1).map(|(_, d)| *d).collect();
+                        if distances.is_empty() {
+                            0.0
+                        } else {
+                            (distances.iter().sum::<f32>() / distances.len() as f32) as f64
+                        }
                     })
-                    .collect()
+                    .collect();
+                point_cloud.set_attribute_values("radius", AttributeType::F32, &radii);
+            }
+        }
+    }
+
+    /// Apply the configured normal orientation and two-sided duplication
+    fn orient_normals(points: &mut Vec<Point>, mesh: &Mesh, config: &PointCloudConfig) {
+        match config.normal_orientation {
+            NormalOrientation::AsAuthored => {}
+            NormalOrientation::Flipped => {
+                points.par_iter_mut().for_each(|point| {
+                    if let Some(normal) = point.normal.as_mut() {
+                        *normal = (-Vec3::from(*normal)).to_array();
+                    }
+                });
             }
+            NormalOrientation::TowardViewpoint(viewpoint) => {
+                let viewpoint = Vec3::from(viewpoint);
+                points.par_iter_mut().for_each(|point| {
+                    if let Some(normal) = point.normal.as_mut() {
+                        let to_viewpoint = viewpoint - Vec3::from(point.position);
+                        if Vec3::from(*normal).dot(to_viewpoint) < 0.0 {
+                            *normal = (-Vec3::from(*normal)).to_array();
+                        }
+                    }
+                });
+            }
+        }
+
+        if config.two_sided {
+            // Back-facing copies for points sampled on doubleSided materials
+            let back_faces: Vec<Point> = points
+                .par_iter()
+                .filter(|point| point.normal.is_some())
+                .filter(|point| point.material_id.is_some_and(|id| mesh.is_double_sided(id)))
+                .map(|point| {
+                    let mut back = point.clone();
+                    back.normal = point.normal.map(|n| (-Vec3::from(n)).to_array());
+                    back
+                })
+                .collect();
+            points.extend(back_faces);
+        }
+    }
+
+    /// One point per mesh vertex, up to `limit`
+    fn vertex_points(mesh: &Mesh, config: &PointCloudConfig, limit: usize) -> Vec<Point> {
+        (0..mesh.vertices.len().min(limit))
+            .into_par_iter()
+            .map(|i| Self::vertex_point(mesh, config, i))
+            .collect()
+    }
+
+    /// The point of a single mesh vertex with its normal, color and material
+    fn vertex_point(mesh: &Mesh, config: &PointCloudConfig, i: usize) -> Point {
+        let mut point = Point::new(mesh.vertices[i] * config.scale);
+
+        if config.include_normals && let Some(&normal) = mesh.normals.get(i) {
+            point = point.with_normal(normal);
+        }
+
+        if config.include_colors && let Some(&color) = mesh.colors.get(i) {
+            point = point.with_color(color);
+        }
+
+        if config.include_colors && let Some(&alpha) = mesh.alphas.get(i) {
+            point = point.with_alpha(alpha);
+        }
+
+        if let Some(&material_id) = mesh.material_ids.get(i) {
+            point = point.with_material_id(material_id);
+        }
+
+        point
+    }
+
+    /// Sample `mesh`, whose surface colors are textured per point; `vertex_mesh` is the same
+    /// mesh with the textures baked into its vertex colors
+    fn generate_point_cloud(mesh: &Mesh, vertex_mesh: &Mesh, config: &PointCloudConfig) -> Vec<Point> {
+        // Scanned points are passed through as they are
+        if mesh.point_set {
+            return Self::vertex_points(vertex_mesh, config, usize::MAX);
+        }
+
+        let mut rng = random::chunk_rng(config.seed, random::stream::SAMPLING, 0);
+        let vertices = &mesh.vertices;
+        let normals = &mesh.normals;
+        let colors = &mesh.colors;
+        let alphas = &mesh.alphas;
+        let indices = &mesh.indices;
+        let material_ids = &mesh.material_ids;
+        let has_normals = !normals.is_empty();
+        let has_colors = !colors.is_empty();
+        let has_material_ids = !material_ids.is_empty();
+        let uvs = &mesh.uvs;
+        let textured = mesh.is_textured();
+        let diagonal = if config.jitter > 0.0 {
+            let (min, max) = mesh.bounds();
+            (max - min).length() * config.scale.abs()
+        } else {
+            0.0
+        };
+
+        match config.sampling_strategy {
+            SamplingStrategy::Scanner => lidar::scan(vertex_mesh, config, &config.scanner),
+
+            SamplingStrategy::Vertices => Self::vertex_points(vertex_mesh, config, config.point_count),
 
             SamplingStrategy::Uniform | SamplingStrategy::AreaWeighted => {
                 let mut points = Vec::with_capacity(config.point_count);
@@ -160,67 +841,116 @@ impl ModelParser {
 
                     let total_weight: f32 = triangle_weights.iter().sum();
 
-                    // Generate points in parallel chunks
-                    let chunk_size = (config.point_count / rayon::current_num_threads()).max(1000);
+                    // Base color texture, UV and mip level of a surface sample
+                    let surface_texture = |selected_tri: usize, [a, b, c]: [f32; 3]| {
+                        let tri = triangles[selected_tri];
+                        let texture = material_ids
+                            .get(tri[0])
+                            .and_then(|&id| mesh.base_color_texture(id))
+                            .filter(|_| textured)?;
+                        let [t0, t1, t2] = [0, 1, 2].map(|i| uvs[tri[i]]);
+                        let uv = t0 * a + t1 * b + t2 * c;
+                        if !uv.is_finite() {
+                            return None;
+                        }
+
+                        // Each point stands for total_weight / (weight * point_count)
+                        // of its triangle, so for that share of the triangle's UV area
+                        let uv_area = (t1 - t0).perp_dot(t2 - t0).abs() * 0.5;
+                        let weight = triangle_weights[selected_tri];
+                        let footprint = if weight > 0.0 {
+                            uv_area * total_weight / (weight * config.point_count as f32)
+                        } else {
+                            0.0
+                        };
+                        Some((texture, uv, texture.lod(footprint)))
+                    };
+
+                    // Generate points in fixed-size parallel chunks, one random stream per chunk
                     let chunks: Vec<_> = (0..config.point_count)
                         .collect::<Vec<_>>()
-                        .chunks(chunk_size)
+                        .chunks(random::CHUNK_SIZE)
                         .map(|chunk| chunk.to_vec())
                         .collect();
 
                     points = chunks
                         .par_iter()
-                        .flat_map(|chunk_indices| {
-                            let mut local_rng = rand::rng();
+                        .enumerate()
+                        .flat_map(|(chunk, chunk_indices)| {
+                            let mut local_rng =
+                                random::chunk_rng(config.seed, random::stream::SAMPLING, chunk as u64 + 1);
                             chunk_indices
                                 .iter()
                                 .filter_map(|_| {
-                                    // Select random triangle (weighted by area if needed)
-                                    let mut weight_select = local_rng.random::<f32>() * total_weight;
-                                    let mut selected_tri = 0;
-
-                                    for (i, &weight) in triangle_weights.iter().enumerate() {
-                                        weight_select -= weight;
-                                        if weight_select <= 0.0 {
-                                            selected_tri = i;
-                                            break;
+                                    // Samples on alpha-masked materials that fall below the
+                                    // cutoff are drawn again, a bounded number of times, so
+                                    // cut-out surfaces such as foliage cards keep their point count
+                                    let mut attempts = 0;
+                                    let (selected_tri, [a, b, c], surface) = loop {
+                                        // Select random triangle (weighted by area if needed)
+                                        let mut weight_select = local_rng.random::<f32>() * total_weight;
+                                        let mut selected_tri = 0;
+
+                                        for (i, &weight) in triangle_weights.iter().enumerate() {
+                                            weight_select -= weight;
+                                            if weight_select <= 0.0 {
+                                                selected_tri = i;
+                                                break;
+                                            }
                                         }
-                                    }
 
-                                    let tri = triangles[selected_tri];
-                                    if tri.len() != 3 {
-                                        return None;
-                                    }
+                                        let tri = triangles[selected_tri];
+                                        if tri.len() != 3 {
+                                            return None;
+                                        }
+
+                                        // Random barycentric coordinates
+                                        let r1 = local_rng.random::<f32>().sqrt();
+                                        let r2 = local_rng.random::<f32>();
+                                        let weights = [1.0 - r1, r1 * (1.0 - r2), r1 * r2];
 
-                                    // Random barycentric coordinates
-                                    let r1 = local_rng.random::<f32>().sqrt();
-                                    let r2 = local_rng.random::<f32>();
-                                    let a = 1.0 - r1;
-                                    let b = r1 * (1.0 - r2);
-                                    let c = r1 * r2;
+                                        let surface = surface_texture(selected_tri, weights);
+                                        let cutoff = material_ids.get(tri[0]).and_then(|&id| mesh.alpha_cutoff(id));
+                                        let masked = cutoff.is_some_and(|cutoff| {
+                                            let alpha = if alphas.is_empty() {
+                                                1.0
+                                            } else {
+                                                (0..3).map(|i| alphas[tri[i]] * weights[i]).sum()
+                                            };
+                                            let texel = surface.map_or(1.0, |(texture, uv, lod)| texture.sample_alpha(uv, lod));
+                                            alpha * texel < cutoff
+                                        });
+                                        if !masked {
+                                            break (selected_tri, weights, surface);
+                                        }
+                                        attempts += 1;
+                                        if attempts == ALPHA_MASK_ATTEMPTS {
+                                            return None;
+                                        }
+                                    };
+                                    let tri = triangles[selected_tri];
 
                                     let v0 = vertices[tri[0]];
                                     let v1 = vertices[tri[1]];
                                     let v2 = vertices[tri[2]];
 
-                                    let mut pos = v0 * a + v1 * b + v2 * c;
+                                    let pos = v0 * a + v1 * b + v2 * c;
+                                    let normal = has_normals.then(|| {
+                                        let n0 = normals[tri[0]];
+                                        let n1 = normals[tri[1]];
+                                        let n2 = normals[tri[2]];
+                                        (n0 * a + n1 * b + n2 * c).normalize()
+                                    });
 
-                                    // Apply jitter
+                                    let mut scaled_pos = pos * config.scale;
                                     if config.jitter > 0.0 {
-                                        let jitter_amount = config.jitter * 0.1;
-                                        pos.x += local_rng.random_range(-jitter_amount..jitter_amount);
-                                        pos.y += local_rng.random_range(-jitter_amount..jitter_amount);
-                                        pos.z += local_rng.random_range(-jitter_amount..jitter_amount);
+                                        scaled_pos += Self::jitter_offset(config, normal, diagonal, &mut local_rng);
                                     }
-
-                                    let scaled_pos = pos * config.scale;
                                     let mut point = Point::new(scaled_pos);
 
-                                    if has_normals && config.include_normals {
-                                        let n0 = normals[tri[0]];
-                                        let n1 = normals[tri[1]];
-                                        let n2 = normals[tri[2]];
-                                        let normal = (n0 * a + n1 * b + n2 * c).normalize();
+                                    if let Some(normal) = normal
+                                        && config.include_normals
+                                    {
                                         point = point.with_normal(normal);
                                     }
 
@@ -228,30 +958,37 @@ impl ModelParser {
                                         let c0 = colors[tri[0]];
                                         let c1 = colors[tri[1]];
                                         let c2 = colors[tri[2]];
-                                        let color = c0 * a + c1 * b + c2 * c;
+                                        let mut color = c0 * a + c1 * b + c2 * c;
+
+                                        if let Some((texture, uv, lod)) = surface {
+                                            color *= texture.sample_level(uv, lod);
+                                        }
                                         point = point.with_color(color);
                                     }
 
+                                    if !alphas.is_empty() && config.include_colors {
+                                        let [a0, a1, a2] = [0, 1, 2].map(|i| alphas[tri[i]]);
+                                        point = point.with_alpha(a0 * a + a1 * b + a2 * c);
+                                    }
+
+                                    if has_material_ids {
+                                        point = point.with_material_id(material_ids[tri[0]]);
+                                    }
+
                                     Some(point)
                                 })
                                 .collect::<Vec<_>>()
                         })
                         .collect();
-                } else {
+                } else if mesh.lines.is_empty() && mesh.points.is_empty() {
                     // Fallback to vertex sampling
                     for _ in 0..config.point_count {
                         let idx = rng.random_range(0..vertices.len());
-                        let mut pos = vertices[idx];
-
-                        // Apply jitter
+                        let mut scaled_pos = vertices[idx] * config.scale;
                         if config.jitter > 0.0 {
-                            let jitter_amount = config.jitter * 0.1;
-                            pos.x += rng.random_range(-jitter_amount..jitter_amount);
-                            pos.y += rng.random_range(-jitter_amount..jitter_amount);
-                            pos.z += rng.random_range(-jitter_amount..jitter_amount);
+                            let normal = normals.get(idx).copied();
+                            scaled_pos += Self::jitter_offset(config, normal, diagonal, &mut rng);
                         }
-
-                        let scaled_pos = pos * config.scale;
                         let mut point = Point::new(scaled_pos);
 
                         if has_normals && config.include_normals && idx < normals.len() {
@@ -259,15 +996,455 @@ impl ModelParser {
                         }
 
                         if has_colors && config.include_colors && idx < colors.len() {
-                            point = point.with_color(colors[idx]);
+                            point = point.with_color(vertex_mesh.colors[idx]);
+                        }
+
+                        if config.include_colors && let Some(&alpha) = alphas.get(idx) {
+                            point = point.with_alpha(alpha);
+                        }
+
+                        if has_material_ids && idx < material_ids.len() {
+                            point = point.with_material_id(material_ids[idx]);
                         }
 
                         points.push(point);
                     }
                 }
 
+                // Mixed geometry tags every point with the kind of primitive it came from
+                if Self::has_primitive_types(mesh, config) {
+                    let surface = primitive_type::TRIANGLE as f64;
+                    points.iter_mut().for_each(|point| point.attributes.push(surface));
+                }
+                points.extend(Self::primitive_points(vertex_mesh, config));
                 points
             }
         }
     }
+
+    /// Random offset of a sampled point in output units, as `config.jitter_mode` describes;
+    /// `diagonal` is the diagonal of the model's bounds after scaling
+    fn jitter_offset(config: &PointCloudConfig, normal: Option<Vec3>, diagonal: f32, rng: &mut impl Rng) -> Vec3 {
+        let amount = match config.jitter_mode {
+            JitterMode::BoundsFraction => config.jitter.clamp(0.0, 1.0) * diagonal,
+            JitterMode::Absolute | JitterMode::Normal => config.jitter,
+        };
+        if amount <= 0.0 {
+            return Vec3::ZERO;
+        }
+        match config.jitter_mode {
+            JitterMode::Normal => normal.map_or(Vec3::ZERO, |normal| normal * rng.random_range(-amount..amount)),
+            JitterMode::Absolute | JitterMode::BoundsFraction => {
+                Vec3::from_array([0; 3].map(|_| rng.random_range(-amount..amount)))
+            }
+        }
+    }
+
+    /// Whether sampled points carry a "primitive_type" attribute: for meshes with point or
+    /// line primitives, sampled on their surface
+    fn has_primitive_types(mesh: &Mesh, config: &PointCloudConfig) -> bool {
+        (!mesh.points.is_empty() || !mesh.lines.is_empty())
+            && !mesh.point_set
+            && matches!(config.sampling_strategy, SamplingStrategy::Uniform | SamplingStrategy::AreaWeighted)
+    }
+
+    /// Points of point and line primitives: point vertices pass through, and lines get
+    /// points spread by length at `line_density`, by default the spacing of the surface
+    /// samples (or the whole point budget when there are no triangles)
+    fn primitive_points(mesh: &Mesh, config: &PointCloudConfig) -> Vec<Point> {
+        let tagged = Self::has_primitive_types(mesh, config);
+        let tag = |mut point: Point, kind: u8| {
+            if tagged {
+                point.attributes.push(kind as f64);
+            }
+            point
+        };
+
+        let mut points: Vec<Point> = mesh
+            .points
+            .iter()
+            .map(|&i| tag(Self::vertex_point(mesh, config, i), primitive_type::POINT))
+            .collect();
+
+        let segments: Vec<_> = mesh.lines.chunks_exact(2).collect();
+        let lengths: Vec<f32> = segments
+            .iter()
+            .map(|s| mesh.vertices[s[0]].distance(mesh.vertices[s[1]]))
+            .collect();
+        let total_length: f32 = lengths.iter().sum();
+        if total_length <= 0.0 {
+            return points;
+        }
+
+        let count = if let Some(density) = config.line_density {
+            (total_length * config.scale.abs() * density).round() as usize
+        } else if mesh.triangle_count() > 0 {
+            let spacing = (mesh.surface_area() / config.point_count.max(1) as f32).sqrt();
+            if spacing > 0.0 { (total_length / spacing).round() as usize } else { 0 }
+        } else {
+            config.point_count
+        };
+
+        // Cumulative lengths, so picking a segment is a binary search
+        let cumulative: Vec<f32> = lengths
+            .iter()
+            .scan(0.0, |sum, &length| {
+                *sum += length;
+                Some(*sum)
+            })
+            .collect();
+
+        let has_normals = !mesh.normals.is_empty() && config.include_normals;
+        let has_colors = !mesh.colors.is_empty() && config.include_colors;
+        let samples: Vec<usize> = (0..count).collect();
+        points.par_extend(samples.par_chunks(random::CHUNK_SIZE).enumerate().flat_map_iter(|(chunk, samples)| {
+            let mut rng = random::chunk_rng(config.seed, random::stream::LINES, chunk as u64);
+            let (segments, cumulative) = (&segments, &cumulative);
+            samples.iter().map(move |_| {
+                let target = rng.random::<f32>() * total_length;
+                let segment = segments[cumulative.partition_point(|&c| c < target).min(segments.len() - 1)];
+                let t = rng.random::<f32>();
+                let [a, b] = [segment[0], segment[1]];
+
+                let mut point = Point::new(mesh.vertices[a].lerp(mesh.vertices[b], t) * config.scale);
+                if has_normals {
+                    point = point.with_normal(mesh.normals[a].lerp(mesh.normals[b], t).normalize_or_zero());
+                }
+                if has_colors {
+                    point = point.with_color(mesh.colors[a].lerp(mesh.colors[b], t));
+                }
+                if has_colors && let (Some(&alpha_a), Some(&alpha_b)) = (mesh.alphas.get(a), mesh.alphas.get(b)) {
+                    point = point.with_alpha(alpha_a + (alpha_b - alpha_a) * t);
+                }
+                if let Some(&material_id) = mesh.material_ids.get(a) {
+                    point = point.with_material_id(material_id);
+                }
+                tag(point, primitive_type::LINE)
+            })
+        }));
+
+        points
+    }
+}
+
+/// Optional attributes read for one glTF primitive
+struct PrimitiveAttributes {
+    /// The primitive's vertices in the mesh
+    vertices: Range<usize>,
+    normals: bool,
+    colors: bool,
+}
+
+/// Import a glTF/GLB file with its buffers and images, accepting files that require the
+/// extensions handled by the loader. Also returns a warning for each image that could not be
+/// decoded and was left empty
+pub(crate) fn import_gltf(
+    path: &Path,
+) -> Result<(gltf::Document, Vec<gltf::buffer::Data>, Vec<gltf::image::Data>, Vec<String>)> {
+    let bytes = std::fs::read(path)?;
+    let gltf = match gltf::Gltf::from_slice(&bytes) {
+        Ok(gltf) => gltf,
+        // Files without uncompressed fallbacks must require the extension, which fails validation
+        Err(gltf::Error::Validation(errors)) if errors.iter().any(|(path, _)| path.0.contains(DRACO_EXTENSION)) => {
+            return Err(ModelParserError::UnsupportedFormat(format!(
+                "{} (Draco-compressed glTF); decompress it first, e.g. by re-exporting without Draco compression",
+                DRACO_EXTENSION
+            )));
+        }
+        // Extensions handled here are unknown to the gltf crate, which rejects files requiring
+        // them; load those without validation
+        Err(gltf::Error::Validation(errors))
+            if errors.iter().all(|(path, _)| path.0.contains(GPU_INSTANCING_EXTENSION)) =>
+        {
+            gltf::Gltf::from_slice_without_validation(&bytes)?
+        }
+        Err(error) => return Err(error.into()),
+    };
+
+    let base = Some(path.parent().unwrap_or(Path::new("./")));
+    let buffers = gltf::import_buffers(&gltf.document, base, gltf.blob)?;
+    let (images, warnings) = import_images(&gltf.document, base, &buffers)?;
+    Ok((gltf.document, buffers, images, warnings))
+}
+
+/// Decode the images of a document: PNG and JPEG through the gltf crate, and the KTX2
+/// (Basis Universal) images of `KHR_texture_basisu` through the transcoder
+fn import_images(
+    document: &gltf::Document,
+    base: Option<&Path>,
+    buffers: &[gltf::buffer::Data],
+) -> Result<(Vec<gltf::image::Data>, Vec<String>)> {
+    use gltf::image::Source;
+
+    let mut warnings = Vec::new();
+    let images = document
+        .images()
+        .map(|image| {
+            let bytes: Option<Vec<u8>> = match image.source() {
+                Source::View { view, .. } => {
+                    let bytes = buffers
+                        .get(view.buffer().index())
+                        .and_then(|buffer| buffer.get(view.offset()..view.offset().checked_add(view.length())?))
+                        .ok_or_else(|| {
+                            ModelParserError::InvalidData(format!(
+                                "image {} views bytes outside its buffer",
+                                image.index()
+                            ))
+                        })?;
+                    texture::is_ktx2(bytes).then(|| bytes.to_vec())
+                }
+                Source::Uri { uri, mime_type } => match uri.strip_prefix("data:") {
+                    Some(data) if data.starts_with("image/ktx2") => data
+                        .split_once(";base64,")
+                        .and_then(|(_, encoded)| base64::decode(encoded).ok()),
+                    Some(_) => None,
+                    None if mime_type == Some("image/ktx2") || uri.ends_with(".ktx2") => {
+                        Some(std::fs::read(base.unwrap_or(Path::new("")).join(uri))?)
+                    }
+                    None => None,
+                },
+            };
+            match bytes {
+                // An undecodable texture is left empty, so sampling falls back to the base color
+                Some(bytes) if texture::is_ktx2(&bytes) => Ok(texture::decode_ktx2(&bytes).unwrap_or_else(|error| {
+                    warnings.push(format!("skipped image {}: {}", image.index(), error));
+                    gltf::image::Data {
+                        pixels: Vec::new(),
+                        format: gltf::image::Format::R8G8B8A8,
+                        width: 0,
+                        height: 0,
+                    }
+                })),
+                _ => Ok(gltf::image::Data::from_source(image.source(), base, buffers)?),
+            }
+        })
+        .collect::<Result<_>>()?;
+    Ok((images, warnings))
+}
+
+/// Image a texture samples: the `KHR_texture_basisu` KTX2 image when there is one, else the
+/// texture's own (PNG or JPEG) source
+fn texture_image(texture: &gltf::Texture) -> Option<usize> {
+    texture
+        .extension_value(BASISU_EXTENSION)
+        .and_then(|basisu| basisu["source"].as_u64())
+        .map(|index| index as usize)
+        .or_else(|| texture.source().map(|image| image.index()))
+}
+
+/// UV set a texture reads, which `KHR_texture_transform` may override
+fn texture_tex_coord(info: &gltf::texture::Info) -> u32 {
+    info.texture_transform()
+        .and_then(|transform| transform.tex_coord())
+        .unwrap_or_else(|| info.tex_coord())
+}
+
+/// `KHR_texture_transform` of a texture as a UV matrix: scaled, then rotated counter-clockwise,
+/// then offset. The identity when the texture has none
+fn texture_transform(info: &gltf::texture::Info) -> Mat3 {
+    let Some(transform) = info.texture_transform() else {
+        return Mat3::IDENTITY;
+    };
+    let (sin, cos) = transform.rotation().sin_cos();
+    let rotation = Mat3::from_cols(Vec3::new(cos, -sin, 0.0), Vec3::new(sin, cos, 0.0), Vec3::Z);
+    Mat3::from_translation(Vec2::from(transform.offset())) * rotation * Mat3::from_scale(Vec2::from(transform.scale()))
+}
+
+/// Scene by name, or by its index in the file
+fn find_scene<'a>(document: &'a gltf::Document, name: &str) -> Result<gltf::Scene<'a>> {
+    document
+        .scenes()
+        .find(|scene| scene.name() == Some(name))
+        .or_else(|| name.parse().ok().and_then(|index| document.scenes().nth(index)))
+        .ok_or_else(|| {
+            let names: Vec<String> = document
+                .scenes()
+                .map(|scene| scene.name().map(str::to_string).unwrap_or_else(|| scene.index().to_string()))
+                .collect();
+            ModelParserError::InvalidData(format!(
+                "scene '{}' not found (available: {})",
+                name,
+                if names.is_empty() { "none".to_string() } else { names.join(", ") }
+            ))
+        })
+}
+
+/// Index of a `KHR_materials_variants` variant, by name or index
+fn find_variant(document: &gltf::Document, name: &str) -> Result<u32> {
+    let names: Vec<&str> = document.variants().map(|variants| variants.map(|v| v.name()).collect()).unwrap_or_default();
+    names
+        .iter()
+        .position(|&variant| variant == name)
+        .or_else(|| name.parse().ok().filter(|&index| index < names.len()))
+        .map(|index| index as u32)
+        .ok_or_else(|| {
+            ModelParserError::InvalidData(format!(
+                "material variant '{}' not found (available: {})",
+                name,
+                if names.is_empty() { "none".to_string() } else { names.join(", ") }
+            ))
+        })
+}
+
+/// A glTF mesh placed in the scene
+struct MeshInstance<'a> {
+    mesh: gltf::Mesh<'a>,
+
+    /// World transform of the mesh
+    transform: Mat4,
+
+    /// Node using the mesh, when placed by the node hierarchy
+    node: Option<gltf::Node<'a>>,
+
+    /// Names of the node and its ancestors, followed by the mesh name
+    names: Vec<&'a str>,
+}
+
+/// Append the meshes below `node` with their world transforms, looked up by node index in
+/// `world`, and names, `ancestors` being the names of the nodes above. Nodes using GPU
+/// instancing add their mesh once per instance
+fn collect_mesh_instances<'a>(
+    node: gltf::Node<'a>,
+    world: &[Mat4],
+    document: &gltf::Document,
+    buffers: &[gltf::buffer::Data],
+    ancestors: &[&'a str],
+    instances: &mut Vec<MeshInstance<'a>>,
+) {
+    let transform = world[node.index()];
+    let names: Vec<&'a str> = ancestors.iter().copied().chain(node.name()).collect();
+    if let Some(mesh) = node.mesh() {
+        let local = gpu_instance_transforms(&node, document, buffers).unwrap_or_else(|| vec![Mat4::IDENTITY]);
+        instances.extend(local.into_iter().map(|t| MeshInstance {
+            mesh: mesh.clone(),
+            transform: transform * t,
+            node: Some(node.clone()),
+            names: names.iter().copied().chain(mesh.name()).collect(),
+        }));
+    }
+    for child in node.children() {
+        collect_mesh_instances(child, world, document, buffers, &names, instances);
+    }
+}
+
+/// Per-vertex transforms of a skinned primitive: its joint matrices blended by the vertex
+/// weights of every JOINTS_n/WEIGHTS_n set. None without joints or with any unweighted vertex
+fn skin_transforms<'a, 's, F>(reader: &gltf::mesh::Reader<'a, 's, F>, joints: &[Mat4], vertex_count: usize) -> Option<Vec<Mat4>>
+where
+    F: Clone + Fn(gltf::Buffer<'a>) -> Option<&'s [u8]>,
+{
+    let mut blended = vec![Mat4::ZERO; vertex_count];
+    let mut totals = vec![0.0f32; vertex_count];
+    let mut set = 0;
+    while let (Some(indices), Some(weights)) = (reader.read_joints(set), reader.read_weights(set)) {
+        for (i, (indices, weights)) in indices.into_u16().zip(weights.into_f32()).enumerate().take(vertex_count) {
+            for (&joint, &weight) in indices.iter().zip(&weights) {
+                if weight != 0.0 {
+                    blended[i] += *joints.get(joint as usize)? * weight;
+                    totals[i] += weight;
+                }
+            }
+        }
+        set += 1;
+    }
+
+    // Weights should sum to one; exporters often leave small rounding errors
+    if set == 0 || totals.iter().any(|&total| total <= 0.0) {
+        return None;
+    }
+    for (matrix, total) in blended.iter_mut().zip(totals) {
+        *matrix *= 1.0 / total;
+    }
+    Some(blended)
+}
+
+/// Per-instance transforms of a node using `EXT_mesh_gpu_instancing`, relative to the node.
+/// Attributes an instance set leaves out are identity
+fn gpu_instance_transforms(
+    node: &gltf::Node,
+    document: &gltf::Document,
+    buffers: &[gltf::buffer::Data],
+) -> Option<Vec<Mat4>> {
+    let attributes = node.extension_value(GPU_INSTANCING_EXTENSION)?.get("attributes")?;
+    // The gltf crate does not know the extension and never validated these accessors, so
+    // their shape is checked before they are read as the element types below
+    let accessor = |name: &str, dimensions: Dimensions| {
+        let index = attributes.get(name)?.as_u64()?;
+        document
+            .accessors()
+            .nth(index as usize)
+            .filter(|accessor| accessor.dimensions() == dimensions && accessor_in_bounds(accessor, buffers))
+    };
+    let get_buffer = |buffer: gltf::Buffer| buffers.get(buffer.index()).map(|data| &data.0[..]);
+    let vectors = |name: &str| -> Option<Vec<Vec3>> {
+        let accessor = accessor(name, Dimensions::Vec3).filter(|accessor| accessor.data_type() == DataType::F32)?;
+        Some(gltf::accessor::Iter::<[f32; 3]>::new(accessor, get_buffer)?.map(Vec3::from).collect())
+    };
+
+    let translations = vectors("TRANSLATION");
+    let scales = vectors("SCALE");
+    // Rotations may be stored as normalized signed integers
+    let rotations: Option<Vec<Quat>> = accessor("ROTATION", Dimensions::Vec4).and_then(|rotation| {
+        let quat = |[x, y, z, w]: [f32; 4]| Quat::from_xyzw(x, y, z, w).normalize();
+        Some(match rotation.data_type() {
+            DataType::I8 => gltf::accessor::Iter::<[i8; 4]>::new(rotation, get_buffer)?
+                .map(|q| quat(q.map(|c| (c as f32 / 127.0).max(-1.0))))
+                .collect(),
+            DataType::I16 => gltf::accessor::Iter::<[i16; 4]>::new(rotation, get_buffer)?
+                .map(|q| quat(q.map(|c| (c as f32 / 32767.0).max(-1.0))))
+                .collect(),
+            DataType::F32 => gltf::accessor::Iter::<[f32; 4]>::new(rotation, get_buffer)?.map(quat).collect(),
+            _ => return None,
+        })
+    });
+
+    let count = [translations.as_ref().map(Vec::len), rotations.as_ref().map(Vec::len), scales.as_ref().map(Vec::len)]
+        .into_iter()
+        .flatten()
+        .max()?;
+    Some(
+        (0..count)
+            .map(|i| {
+                Mat4::from_scale_rotation_translation(
+                    scales.as_ref().and_then(|s| s.get(i)).copied().unwrap_or(Vec3::ONE),
+                    rotations.as_ref().and_then(|r| r.get(i)).copied().unwrap_or(Quat::IDENTITY),
+                    translations.as_ref().and_then(|t| t.get(i)).copied().unwrap_or(Vec3::ZERO),
+                )
+            })
+            .collect(),
+    )
+}
+
+/// Whether every element of a non-sparse accessor lies inside its buffer view and buffer
+fn accessor_in_bounds(accessor: &gltf::Accessor, buffers: &[gltf::buffer::Data]) -> bool {
+    let Some(view) = accessor.view() else {
+        return false;
+    };
+    let size = accessor.size();
+    let stride = view.stride().unwrap_or(size);
+    let end = accessor.count().checked_sub(1).and_then(|last| {
+        stride.checked_mul(last)?.checked_add(size)?.checked_add(accessor.offset())
+    });
+    let buffer_length = buffers.get(view.buffer().index()).map_or(0, |data| data.0.len());
+    end.is_some_and(|end| end <= view.length() && view.offset().saturating_add(view.length()) <= buffer_length)
+}
+
+/// Uniformly distributed unit vector
+fn uniform_sphere(rng: &mut impl Rng) -> Vec3 {
+    let z = rng.random_range(-1.0f32..=1.0);
+    let phi = rng.random_range(0.0..std::f32::consts::TAU);
+    let r = (1.0 - z * z).max(0.0).sqrt();
+    Vec3::new(r * phi.cos(), r * phi.sin(), z)
+}
+
+/// Cosine-weighted unit vector in the hemisphere around `normal`
+fn cosine_hemisphere(rng: &mut impl Rng, normal: Vec3) -> Vec3 {
+    let r1 = rng.random::<f32>();
+    let r2 = rng.random::<f32>();
+    let phi = std::f32::consts::TAU * r1;
+    let r = r2.sqrt();
+    let local = Vec3::new(r * phi.cos(), r * phi.sin(), (1.0 - r2).max(0.0).sqrt());
+
+    let (tangent, bitangent) = normal.any_orthonormal_pair();
+    (tangent * local.x + bitangent * local.y + normal * local.z).normalize()
 }
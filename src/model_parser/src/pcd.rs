@@ -0,0 +1,600 @@
+use crate::{
+    attributes::{AttributeDescriptor, AttributeType},
+    budget::SizeEstimate,
+    color::{self, ColorSpace},
+    config::PointCloudConfig,
+    error::{ModelParserError, Result},
+    mesh::Mesh,
+    point_cloud::{Point, PointCloud},
+};
+use glam::Vec3;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// One column of a PCD file
+#[derive(Debug, Clone)]
+struct Field {
+    name: String,
+    size: usize,
+    kind: u8,
+    count: usize,
+}
+
+impl Field {
+    /// Value of element `i` of this field, starting at `bytes`
+    fn read(&self, bytes: &[u8], i: usize) -> f64 {
+        let at = &bytes[i * self.size..(i + 1) * self.size];
+        match (self.kind, self.size) {
+            (b'F', 4) => f32::from_le_bytes(at.try_into().unwrap_or_default()) as f64,
+            (b'F', 8) => f64::from_le_bytes(at.try_into().unwrap_or_default()),
+            (b'U', 1) => at[0] as f64,
+            (b'U', 2) => u16::from_le_bytes(at.try_into().unwrap_or_default()) as f64,
+            (b'U', 4) => u32::from_le_bytes(at.try_into().unwrap_or_default()) as f64,
+            (b'U', 8) => u64::from_le_bytes(at.try_into().unwrap_or_default()) as f64,
+            (b'I', 1) => at[0] as i8 as f64,
+            (b'I', 2) => i16::from_le_bytes(at.try_into().unwrap_or_default()) as f64,
+            (b'I', 4) => i32::from_le_bytes(at.try_into().unwrap_or_default()) as f64,
+            (b'I', 8) => i64::from_le_bytes(at.try_into().unwrap_or_default()) as f64,
+            _ => f64::NAN,
+        }
+    }
+
+    /// Packed `rgb`/`rgba` values are 0x00RRGGBB bit patterns, often stored in a float
+    fn read_packed_color(&self, bytes: &[u8]) -> Option<[u8; 3]> {
+        let packed = u32::from_le_bytes(bytes.get(..4)?.try_into().ok()?);
+        Some([(packed >> 16) as u8, (packed >> 8) as u8, packed as u8])
+    }
+
+    fn attribute_type(&self) -> AttributeType {
+        match (self.kind, self.size) {
+            (b'U', 1) => AttributeType::U8,
+            (b'U', 2) => AttributeType::U16,
+            (b'U', 4) => AttributeType::U32,
+            (b'I', 1 | 2 | 4) => AttributeType::I32,
+            (b'F', 8) | (_, 8) => AttributeType::F64,
+            _ => AttributeType::F32,
+        }
+    }
+}
+
+/// Load a PCL `.pcd` file (ASCII, binary or binary_compressed) as a point set. `x`/`y`/`z`,
+/// `normal_x`/`normal_y`/`normal_z` and packed `rgb`/`rgba` map to positions, normals and
+/// colors; every other single-valued field (e.g. `intensity`, `label`) becomes a generic
+/// attribute. Points with NaN positions, which organized clouds use as placeholders, are skipped
+pub fn load(path: &Path, config: &PointCloudConfig) -> Result<Mesh> {
+    let bytes = std::fs::read(path)?;
+    let invalid = |message: &str| ModelParserError::InvalidData(format!("{}: {}", path.display(), message));
+
+    // Header lines up to and including DATA
+    let mut fields: Vec<Field> = Vec::new();
+    let mut points = None;
+    let mut data = None;
+    let mut position = 0;
+    while data.is_none() {
+        let end = bytes[position..]
+            .iter()
+            .position(|&b| b == b'\n')
+            .map_or(bytes.len(), |i| position + i + 1);
+        if end == position {
+            return Err(invalid("missing DATA line"));
+        }
+        let line = String::from_utf8_lossy(&bytes[position..end]);
+        position = end;
+
+        let mut parts = line.split_whitespace();
+        let Some(keyword) = parts.next() else {
+            continue;
+        };
+        let values: Vec<&str> = parts.collect();
+        match keyword.to_ascii_uppercase().as_str() {
+            "FIELDS" => {
+                fields = values
+                    .iter()
+                    .map(|name| Field { name: name.to_string(), size: 4, kind: b'F', count: 1 })
+                    .collect()
+            }
+            "SIZE" | "TYPE" | "COUNT" if values.len() != fields.len() => {
+                return Err(invalid(&format!("{} does not match FIELDS", keyword)));
+            }
+            "SIZE" => {
+                for (field, value) in fields.iter_mut().zip(&values) {
+                    field.size = value.parse().map_err(|_| invalid("invalid SIZE"))?;
+                }
+            }
+            "TYPE" => {
+                for (field, value) in fields.iter_mut().zip(&values) {
+                    field.kind = value.bytes().next().unwrap_or(b'F').to_ascii_uppercase();
+                }
+            }
+            "COUNT" => {
+                for (field, value) in fields.iter_mut().zip(&values) {
+                    field.count = value.parse().map_err(|_| invalid("invalid COUNT"))?;
+                }
+            }
+            "POINTS" => points = values.first().and_then(|v| v.parse::<usize>().ok()),
+            "DATA" => data = Some(values.first().map(|v| v.to_ascii_lowercase()).unwrap_or_default()),
+            _ => {}
+        }
+    }
+    for field in &fields {
+        let valid = match field.kind {
+            b'F' => matches!(field.size, 4 | 8),
+            b'U' | b'I' => matches!(field.size, 1 | 2 | 4 | 8),
+            _ => false,
+        };
+        if !valid || field.count == 0 {
+            return Err(invalid(&format!(
+                "unsupported field '{}' (SIZE {}, TYPE {}, COUNT {})",
+                field.name, field.size, field.kind as char, field.count
+            )));
+        }
+    }
+    let count = points.ok_or_else(|| invalid("missing POINTS"))?;
+
+    // Bytes of each field's column and of one record; a huge POINTS or COUNT is an error
+    let too_large = || invalid("POINTS or COUNT too large");
+    let lengths: Vec<usize> = fields
+        .iter()
+        .map(|f| f.size.checked_mul(f.count)?.checked_mul(count))
+        .collect::<Option<_>>()
+        .ok_or_else(too_large)?;
+    let record_size = fields
+        .iter()
+        .try_fold(0usize, |sum, f| sum.checked_add(f.size * f.count))
+        .ok_or_else(too_large)?;
+    let data_size = count.checked_mul(record_size).ok_or_else(too_large)?;
+
+    // Column-major buffers: one byte column per field, `count` elements each
+    let columns: Vec<Vec<u8>> = match data.as_deref() {
+        Some("ascii") => read_ascii(&bytes[position..], &fields, &lengths, count).ok_or_else(|| invalid("invalid ASCII data"))?,
+        Some("binary") => {
+            let body = position
+                .checked_add(data_size)
+                .and_then(|end| bytes.get(position..end))
+                .ok_or_else(|| invalid("truncated binary data"))?;
+            let mut columns: Vec<Vec<u8>> = lengths.iter().map(|&length| Vec::with_capacity(length)).collect();
+            for record in body.chunks_exact(record_size.max(1)) {
+                let mut offset = 0;
+                for (field, column) in fields.iter().zip(&mut columns) {
+                    let length = field.size * field.count;
+                    column.extend_from_slice(&record[offset..offset + length]);
+                    offset += length;
+                }
+            }
+            columns
+        }
+        Some("binary_compressed") => {
+            let header = bytes.get(position..position + 8).ok_or_else(|| invalid("truncated compressed data"))?;
+            let compressed = u32::from_le_bytes(header[..4].try_into().unwrap_or_default()) as usize;
+            let expected = u32::from_le_bytes(header[4..].try_into().unwrap_or_default()) as usize;
+            let body = (position + 8)
+                .checked_add(compressed)
+                .and_then(|end| bytes.get(position + 8..end))
+                .ok_or_else(|| invalid("truncated compressed data"))?;
+            let raw = lzf_decompress(body, expected).ok_or_else(|| invalid("corrupt compressed data"))?;
+            if raw.len() < data_size {
+                return Err(invalid("truncated compressed data"));
+            }
+            let mut offset = 0;
+            lengths
+                .iter()
+                .map(|&length| {
+                    let column = raw[offset..offset + length].to_vec();
+                    offset += length;
+                    column
+                })
+                .collect()
+        }
+        other => return Err(invalid(&format!("unsupported DATA '{}'", other.unwrap_or("")))),
+    };
+
+    let find = |name: &str| fields.iter().position(|f| f.name == name);
+    let (Some(x), Some(y), Some(z)) = (find("x"), find("y"), find("z")) else {
+        return Err(invalid("missing x/y/z fields"));
+    };
+    let normal = [find("normal_x"), find("normal_y"), find("normal_z")];
+    let packed_color = find("rgb").or_else(|| find("rgba"));
+    let value = |field: usize, i: usize| fields[field].read(&columns[field], i * fields[field].count);
+
+    let keep: Vec<usize> = (0..count)
+        .filter(|&i| [x, y, z].iter().all(|&axis| value(axis, i).is_finite()))
+        .collect();
+    if keep.is_empty() {
+        return Err(ModelParserError::NoMeshData);
+    }
+
+    let mut mesh = Mesh::new();
+    mesh.point_set = true;
+    mesh.vertices = keep
+        .iter()
+        .map(|&i| Vec3::new(value(x, i) as f32, value(y, i) as f32, value(z, i) as f32))
+        .collect();
+
+    if config.include_normals
+        && let [Some(nx), Some(ny), Some(nz)] = normal
+    {
+        mesh.normals = keep
+            .iter()
+            .map(|&i| Vec3::new(value(nx, i) as f32, value(ny, i) as f32, value(nz, i) as f32))
+            .collect();
+    }
+
+    if config.include_colors {
+        mesh.has_source_colors = packed_color.is_some();
+        mesh.colors = match packed_color {
+            Some(field) => keep
+                .iter()
+                .map(|&i| {
+                    let size = fields[field].size;
+                    let rgb = fields[field]
+                        .read_packed_color(&columns[field][i * size..])
+                        .unwrap_or([255; 3]);
+                    Vec3::from(rgb.map(|c| color::srgb_to_linear(c as f32 / 255.0)))
+                })
+                .collect(),
+            None => vec![Vec3::ONE; keep.len()],
+        };
+    }
+
+    let mapped = [Some(x), Some(y), Some(z), normal[0], normal[1], normal[2], packed_color, find("curvature")];
+    mesh.point_attributes = fields
+        .iter()
+        .enumerate()
+        .filter(|(index, field)| field.count == 1 && !field.name.starts_with('_') && !mapped.contains(&Some(*index)))
+        .map(|(index, field)| {
+            let values = keep.iter().map(|&i| value(index, i)).collect();
+            (AttributeDescriptor::new(field.name.clone(), field.attribute_type()), values)
+        })
+        .collect();
+
+    Ok(mesh)
+}
+
+/// Parse whitespace-separated records into little-endian column buffers of `lengths` bytes.
+/// Field sizes must have been validated: 4 or 8 for floats, 1, 2, 4 or 8 for integers
+fn read_ascii(body: &[u8], fields: &[Field], lengths: &[usize], count: usize) -> Option<Vec<Vec<u8>>> {
+    let text = String::from_utf8_lossy(body);
+    let mut tokens = text.split_whitespace();
+    // Every value takes at least two bytes of text, so the body bounds what POINTS can claim
+    let mut columns: Vec<Vec<u8>> = lengths.iter().map(|&length| Vec::with_capacity(length.min(body.len() * 4))).collect();
+
+    for _ in 0..count {
+        for (field, column) in fields.iter().zip(&mut columns) {
+            for _ in 0..field.count {
+                let token = tokens.next()?;
+                match (field.kind, field.size) {
+                    // Packed colors are written as the float with the same bit pattern
+                    (b'F', 4) => column.extend_from_slice(&token.parse::<f32>().ok()?.to_le_bytes()),
+                    (b'F', _) => column.extend_from_slice(&token.parse::<f64>().ok()?.to_le_bytes()),
+                    (b'I', size) => column.extend_from_slice(&token.parse::<i64>().ok()?.to_le_bytes()[..size]),
+                    (_, size) => column.extend_from_slice(&token.parse::<u64>().ok()?.to_le_bytes()[..size]),
+                }
+            }
+        }
+    }
+    Some(columns)
+}
+
+/// Storage of the point data following a PCD header
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PcdData {
+    /// Whitespace-separated text, one point per line
+    Ascii,
+
+    /// Packed little-endian records
+    #[default]
+    Binary,
+
+    /// LZF-compressed columns, one per field
+    BinaryCompressed,
+}
+
+impl PcdData {
+    /// Value of the DATA header line
+    fn name(&self) -> &'static str {
+        match self {
+            PcdData::Ascii => "ascii",
+            PcdData::Binary => "binary",
+            PcdData::BinaryCompressed => "binary_compressed",
+        }
+    }
+}
+
+/// A field written for every point
+struct Column {
+    name: String,
+    data_type: AttributeType,
+    value: Box<dyn Fn(&Point) -> f64>,
+}
+
+/// Fields of `point_cloud` in PCL naming: `x`/`y`/`z`, `normal_x`/`normal_y`/`normal_z`,
+/// packed `rgb` (or `rgba` with alpha), `material_id` and the generic attributes
+fn columns(point_cloud: &PointCloud) -> Vec<Column> {
+    let metadata = &point_cloud.metadata;
+    let column = |name: &str, data_type: AttributeType, value: Box<dyn Fn(&Point) -> f64>| Column {
+        name: name.to_string(),
+        data_type,
+        value,
+    };
+
+    let mut columns: Vec<Column> = ["x", "y", "z"]
+        .into_iter()
+        .enumerate()
+        .map(|(axis, name)| column(name, AttributeType::F32, Box::new(move |p: &Point| p.position[axis] as f64)))
+        .collect();
+    if metadata.has_normals {
+        for (axis, name) in ["normal_x", "normal_y", "normal_z"].into_iter().enumerate() {
+            columns.push(column(name, AttributeType::F32, Box::new(move |p: &Point| p.normal.map_or(0.0, |n| n[axis] as f64))));
+        }
+    }
+    if metadata.has_colors {
+        // PCL packs 8-bit sRGB as 0x00RRGGBB in a float, or as 0xAARRGGBB in an integer with alpha
+        let linear = metadata.color_space == ColorSpace::Linear;
+        let packed = move |p: &Point| {
+            let mut rgb = p.color.unwrap_or([1.0; 3]);
+            if linear {
+                rgb = rgb.map(color::linear_to_srgb);
+            }
+            let [r, g, b] = color::to_rgb8(rgb);
+            (r as u32) << 16 | (g as u32) << 8 | b as u32
+        };
+        if metadata.has_alpha {
+            columns.push(column(
+                "rgba",
+                AttributeType::U32,
+                Box::new(move |p: &Point| ((color::to_u8(p.alpha.unwrap_or(1.0)) as u32) << 24 | packed(p)) as f64),
+            ));
+        } else {
+            columns.push(column("rgb", AttributeType::F32, Box::new(move |p: &Point| f32::from_bits(packed(p)) as f64)));
+        }
+    }
+    if metadata.has_material_ids {
+        columns.push(column("material_id", AttributeType::U32, Box::new(|p: &Point| p.material_id.unwrap_or(0) as f64)));
+    }
+    for (index, attribute) in metadata.attributes.iter().enumerate() {
+        columns.push(column(
+            &attribute.name,
+            attribute.data_type,
+            Box::new(move |p: &Point| p.attributes.get(index).copied().unwrap_or(0.0)),
+        ));
+    }
+    columns
+}
+
+/// PCD TYPE letter of an attribute type
+fn type_letter(data_type: AttributeType) -> char {
+    match data_type {
+        AttributeType::U8 | AttributeType::U16 | AttributeType::U32 => 'U',
+        AttributeType::I32 => 'I',
+        AttributeType::F32 | AttributeType::F64 => 'F',
+    }
+}
+
+fn header(point_cloud: &PointCloud, columns: &[Column], data: PcdData) -> String {
+    let join = |f: &dyn Fn(&Column) -> String| columns.iter().map(f).collect::<Vec<_>>().join(" ");
+    let mut header = String::from("# .PCD v0.7 - Point Cloud Data file format\n");
+    if let Some([x, y, z]) = point_cloud.metadata.offset {
+        header += &format!("# offset {} {} {} removed from positions\n", x, y, z);
+    }
+    header += "VERSION 0.7\n";
+    header += &format!("FIELDS {}\n", join(&|c: &Column| c.name.clone()));
+    header += &format!("SIZE {}\n", join(&|c: &Column| c.data_type.size().to_string()));
+    header += &format!("TYPE {}\n", join(&|c: &Column| type_letter(c.data_type).to_string()));
+    header += &format!("COUNT {}\n", join(&|_: &Column| "1".to_string()));
+    header += &format!("WIDTH {}\nHEIGHT 1\n", point_cloud.points.len());
+    header += "VIEWPOINT 0 0 0 1 0 0 0\n";
+    header += &format!("POINTS {}\nDATA {}\n", point_cloud.points.len(), data.name());
+    header
+}
+
+/// One value as ASCII text. Integers are rounded and clamped as in binary output; tiny floats
+/// (such as packed colors) use scientific notation to stay short
+fn ascii_value(data_type: AttributeType, value: f64) -> String {
+    let mut bytes = Vec::new();
+    data_type.encode(value, &mut bytes);
+    match data_type {
+        AttributeType::F32 => {
+            let value = f32::from_le_bytes(bytes[..4].try_into().unwrap_or_default());
+            if value != 0.0 && value.abs() < 1e-6 { format!("{:e}", value) } else { value.to_string() }
+        }
+        _ => data_type.decode(&bytes).to_string(),
+    }
+}
+
+/// Write `point_cloud` as a PCL `.pcd` file (version 0.7, unorganized), readable by
+/// `pcl::io::loadPCDFile` into point types such as `PointXYZRGBNormal`. Colors are packed
+/// 8-bit sRGB; clouds with an offset keep float positions near the origin and note the offset
+/// in a header comment, as PCL has no field for it
+pub fn write(point_cloud: &PointCloud, path: &Path, data: PcdData) -> Result<()> {
+    let columns = columns(point_cloud);
+    let mut writer = BufWriter::new(std::fs::File::create(path)?);
+    writer.write_all(header(point_cloud, &columns, data).as_bytes())?;
+
+    match data {
+        PcdData::Ascii => {
+            let mut line = Vec::with_capacity(columns.len());
+            for point in &point_cloud.points {
+                line.clear();
+                line.extend(columns.iter().map(|c| ascii_value(c.data_type, (c.value)(point))));
+                writeln!(writer, "{}", line.join(" "))?;
+            }
+        }
+        PcdData::Binary => {
+            let mut record = Vec::new();
+            for point in &point_cloud.points {
+                record.clear();
+                columns.iter().for_each(|c| c.data_type.encode((c.value)(point), &mut record));
+                writer.write_all(&record)?;
+            }
+        }
+        PcdData::BinaryCompressed => {
+            // Field-major: all values of the first field, then of the second, ...
+            let record_size: usize = columns.iter().map(|c| c.data_type.size() as usize).sum();
+            let mut raw = Vec::with_capacity(record_size * point_cloud.points.len());
+            for column in &columns {
+                point_cloud.points.iter().for_each(|point| column.data_type.encode((column.value)(point), &mut raw));
+            }
+            let compressed = lzf_compress(&raw);
+            writer.write_all(&(compressed.len() as u32).to_le_bytes())?;
+            writer.write_all(&(raw.len() as u32).to_le_bytes())?;
+            writer.write_all(&compressed)?;
+        }
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Size of the PCD output: the header plus one record per point. ASCII records are measured on
+/// a sample of the points, and compressed output is usually smaller than estimated
+pub fn estimate(point_cloud: &PointCloud, data: PcdData) -> SizeEstimate {
+    const SAMPLE: usize = 1000;
+
+    let columns = columns(point_cloud);
+    let binary: usize = columns.iter().map(|c| c.data_type.size() as usize).sum();
+    let bytes_per_point = match data {
+        _ if point_cloud.points.is_empty() => 0.0,
+        PcdData::Ascii => {
+            let step = (point_cloud.points.len() / SAMPLE).max(1);
+            let sampled: Vec<&Point> = point_cloud.points.iter().step_by(step).collect();
+            let bytes: usize = sampled
+                .iter()
+                .map(|point| columns.iter().map(|c| ascii_value(c.data_type, (c.value)(point)).len() + 1).sum::<usize>())
+                .sum();
+            bytes as f64 / sampled.len() as f64
+        }
+        PcdData::Binary | PcdData::BinaryCompressed => binary as f64,
+    };
+
+    SizeEstimate {
+        bytes_per_point,
+        // Room for longer point counts and the compressed data sizes
+        overhead: header(point_cloud, &columns, data).len() as u64 + 48,
+    }
+}
+
+/// LZF compression as used by PCL's binary_compressed format, the inverse of [`lzf_decompress`]
+fn lzf_compress(input: &[u8]) -> Vec<u8> {
+    const HASH_BITS: u32 = 14;
+    const MAX_LITERAL: usize = 32;
+    const MAX_DISTANCE: usize = 1 << 13;
+    const MAX_LENGTH: usize = 264;
+
+    fn literals(output: &mut Vec<u8>, bytes: &[u8]) {
+        for run in bytes.chunks(MAX_LITERAL) {
+            output.push((run.len() - 1) as u8);
+            output.extend_from_slice(run);
+        }
+    }
+
+    let mut output = Vec::with_capacity(input.len() + input.len() / MAX_LITERAL + 1);
+    // Latest position + 1 of each hashed 3-byte sequence, 0 when unseen
+    let mut table = vec![0usize; 1 << HASH_BITS];
+    let mut literal_start = 0;
+    let mut i = 0;
+    while i + 3 <= input.len() {
+        let key = u32::from_be_bytes([0, input[i], input[i + 1], input[i + 2]]);
+        let slot = (key.wrapping_mul(2_654_435_761) >> (32 - HASH_BITS)) as usize;
+        let candidate = std::mem::replace(&mut table[slot], i + 1);
+
+        if let Some(reference) = candidate.checked_sub(1)
+            && i - reference <= MAX_DISTANCE
+            && input[reference..reference + 3] == input[i..i + 3]
+        {
+            let limit = (input.len() - i).min(MAX_LENGTH);
+            let mut length = 3;
+            while length < limit && input[reference + length] == input[i + length] {
+                length += 1;
+            }
+
+            literals(&mut output, &input[literal_start..i]);
+            // Back reference: length - 2 in the top 3 bits (7 = extended), 13-bit distance - 1
+            let (encoded, distance) = (length - 2, i - reference - 1);
+            if encoded < 7 {
+                output.push((encoded << 5 | distance >> 8) as u8);
+            } else {
+                output.push((7 << 5 | distance >> 8) as u8);
+                output.push((encoded - 7) as u8);
+            }
+            output.push(distance as u8);
+
+            i += length;
+            literal_start = i;
+        } else {
+            i += 1;
+        }
+    }
+    literals(&mut output, &input[literal_start..]);
+    output
+}
+
+/// LZF decompression as used by PCL's binary_compressed format
+fn lzf_decompress(input: &[u8], expected: usize) -> Option<Vec<u8>> {
+    let mut output = Vec::with_capacity(expected);
+    let mut i = 0;
+    while i < input.len() {
+        let control = input[i] as usize;
+        i += 1;
+        if control < 32 {
+            // Literal run of control + 1 bytes
+            let run = input.get(i..i + control + 1)?;
+            output.extend_from_slice(run);
+            i += control + 1;
+        } else {
+            // Back reference: length in the top 3 bits (7 = extended), 13-bit distance
+            let mut length = control >> 5;
+            if length == 7 {
+                length += *input.get(i)? as usize;
+                i += 1;
+            }
+            let distance = ((control & 0x1f) << 8) + *input.get(i)? as usize + 1;
+            i += 1;
+            let start = output.len().checked_sub(distance)?;
+            for k in 0..length + 2 {
+                output.push(output[start + k]);
+            }
+        }
+    }
+    (output.len() == expected).then_some(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::point_cloud::test_cloud;
+
+    fn round_trip(data: PcdData) {
+        let cloud = test_cloud();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cloud.pcd");
+        write(&cloud, &path, data).unwrap();
+        let mesh = load(&path, &PointCloudConfig::default()).unwrap();
+
+        assert_eq!(mesh.vertices.len(), cloud.points.len());
+        assert_eq!(mesh.normals.len(), cloud.points.len());
+        assert!(mesh.has_source_colors);
+        let (descriptor, intensity) = mesh.point_attributes.iter().find(|(a, _)| a.name == "intensity").unwrap();
+        assert_eq!(descriptor.data_type, AttributeType::U16);
+        for (i, point) in cloud.points.iter().enumerate() {
+            assert!((mesh.vertices[i] - Vec3::from(point.position)).abs().max_element() < 1e-6, "point {}", i);
+            assert!((mesh.normals[i] - Vec3::from(point.normal.unwrap())).abs().max_element() < 1e-6, "point {}", i);
+            // Colors are packed as 8-bit sRGB
+            assert!((mesh.colors[i] - Vec3::from(point.color.unwrap())).abs().max_element() < 1e-2, "point {}", i);
+            assert_eq!(intensity[i], point.attributes[0], "point {}", i);
+        }
+    }
+
+    #[test]
+    fn binary_round_trip() {
+        round_trip(PcdData::Binary);
+    }
+
+    #[test]
+    fn binary_compressed_round_trip() {
+        round_trip(PcdData::BinaryCompressed);
+    }
+
+    #[test]
+    fn ascii_round_trip() {
+        round_trip(PcdData::Ascii);
+    }
+}
@@ -0,0 +1,521 @@
+use crate::{
+    attributes::AttributeType,
+    budget::SizeEstimate,
+    color::{self, ColorSpace},
+    config::PointCloudConfig,
+    error::{ModelParserError, Result},
+    mesh::Mesh,
+    point_cloud::{Point, PointCloud},
+};
+use glam::Vec3;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// Storage of the element data following the header
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Ascii,
+    BinaryLittleEndian,
+    BinaryBigEndian,
+}
+
+/// Scalar property types
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ScalarType {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    F64,
+}
+
+impl ScalarType {
+    fn parse(name: &str) -> Option<Self> {
+        Some(match name {
+            "char" | "int8" => Self::I8,
+            "uchar" | "uint8" => Self::U8,
+            "short" | "int16" => Self::I16,
+            "ushort" | "uint16" => Self::U16,
+            "int" | "int32" => Self::I32,
+            "uint" | "uint32" => Self::U32,
+            "float" | "float32" => Self::F32,
+            "double" | "float64" => Self::F64,
+            _ => return None,
+        })
+    }
+
+    fn size(self) -> usize {
+        match self {
+            Self::I8 | Self::U8 => 1,
+            Self::I16 | Self::U16 => 2,
+            Self::I32 | Self::U32 | Self::F32 => 4,
+            Self::F64 => 8,
+        }
+    }
+
+    /// Largest value of integer types, used to normalize integer colors
+    fn max_value(self) -> Option<f64> {
+        match self {
+            Self::U8 => Some(u8::MAX as f64),
+            Self::U16 => Some(u16::MAX as f64),
+            Self::I8 => Some(i8::MAX as f64),
+            Self::I16 => Some(i16::MAX as f64),
+            Self::I32 => Some(i32::MAX as f64),
+            Self::U32 => Some(u32::MAX as f64),
+            Self::F32 | Self::F64 => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Property {
+    Scalar { name: String, kind: ScalarType },
+    List { name: String, count: ScalarType, item: ScalarType },
+}
+
+#[derive(Debug, Clone)]
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+/// Reads values from the body in the file's format
+struct Reader<'a> {
+    format: Format,
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl Reader<'_> {
+    fn read(&mut self, kind: ScalarType) -> Option<f64> {
+        if self.format == Format::Ascii {
+            return self.next_token()?.parse().ok();
+        }
+
+        let bytes = self.bytes.get(self.position..self.position + kind.size())?;
+        self.position += kind.size();
+        let little = self.format == Format::BinaryLittleEndian;
+        macro_rules! decode {
+            ($t:ty) => {{
+                let array = bytes.try_into().ok()?;
+                (if little { <$t>::from_le_bytes(array) } else { <$t>::from_be_bytes(array) }) as f64
+            }};
+        }
+        Some(match kind {
+            ScalarType::I8 => bytes[0] as i8 as f64,
+            ScalarType::U8 => bytes[0] as f64,
+            ScalarType::I16 => decode!(i16),
+            ScalarType::U16 => decode!(u16),
+            ScalarType::I32 => decode!(i32),
+            ScalarType::U32 => decode!(u32),
+            ScalarType::F32 => decode!(f32),
+            ScalarType::F64 => decode!(f64),
+        })
+    }
+
+    fn next_token(&mut self) -> Option<&str> {
+        let rest = &self.bytes[self.position..];
+        let start = rest.iter().position(|b| !b.is_ascii_whitespace())?;
+        let length = rest[start..].iter().position(|b| b.is_ascii_whitespace()).unwrap_or(rest.len() - start);
+        self.position += start + length;
+        std::str::from_utf8(&rest[start..start + length]).ok()
+    }
+}
+
+/// Load a PLY file (ASCII or binary, either byte order). Files with faces become a mesh
+/// for sampling; files with only vertices are flagged as a point set and passed through.
+/// Integer colors are sRGB and converted to linear like other mesh colors
+pub fn load(path: &Path, config: &PointCloudConfig) -> Result<Mesh> {
+    let bytes = std::fs::read(path)?;
+    let invalid = |message: &str| ModelParserError::InvalidData(format!("{}: {}", path.display(), message));
+
+    let (format, elements, body_start) = parse_header(&bytes).map_err(|message| invalid(&message))?;
+    let mut reader = Reader {
+        format,
+        bytes: &bytes,
+        position: body_start,
+    };
+
+    let mut mesh = Mesh::new();
+    let mut normals = Vec::new();
+    let mut colors = Vec::new();
+    let mut has_faces = false;
+
+    for element in &elements {
+        match element.name.as_str() {
+            "vertex" => {
+                let find = |names: &[&str]| {
+                    element.properties.iter().position(|p| matches!(p, Property::Scalar { name, .. } if names.contains(&name.as_str())))
+                };
+                let position = [find(&["x"]), find(&["y"]), find(&["z"])];
+                let normal = [find(&["nx"]), find(&["ny"]), find(&["nz"])];
+                let color = [
+                    find(&["red", "r", "diffuse_red"]),
+                    find(&["green", "g", "diffuse_green"]),
+                    find(&["blue", "b", "diffuse_blue"]),
+                ];
+                let [Some(x), Some(y), Some(z)] = position else {
+                    return Err(invalid("vertex element lacks x/y/z"));
+                };
+                let normal = if let [Some(nx), Some(ny), Some(nz)] = normal { Some([nx, ny, nz]) } else { None };
+                let color = if let [Some(r), Some(g), Some(b)] = color { Some([r, g, b]) } else { None };
+
+                let mut values = vec![0.0; element.properties.len()];
+                for _ in 0..element.count {
+                    read_record(&mut reader, element, &mut values, &mut |_, _| Some(()))
+                        .ok_or_else(|| invalid("truncated vertex data"))?;
+                    mesh.vertices.push(Vec3::new(values[x] as f32, values[y] as f32, values[z] as f32));
+                    if let Some(n) = normal {
+                        normals.push(Vec3::new(values[n[0]] as f32, values[n[1]] as f32, values[n[2]] as f32));
+                    }
+                    if let Some(c) = color {
+                        colors.push(Vec3::from(c.map(|i| color_value(element, i, values[i]))));
+                    }
+                }
+            }
+            "face" => {
+                has_faces = true;
+                let mut values = vec![0.0; element.properties.len()];
+                let vertex_count = mesh.vertices.len();
+                for _ in 0..element.count {
+                    read_record(&mut reader, element, &mut values, &mut |name, list| {
+                        if name != "vertex_indices" && name != "vertex_index" {
+                            return Some(());
+                        }
+                        if list.iter().any(|&i| i < 0.0 || i as usize >= vertex_count) {
+                            return None;
+                        }
+                        // Fan triangulation for polygons
+                        for i in 1..list.len().saturating_sub(1) {
+                            mesh.indices.extend([list[0] as usize, list[i] as usize, list[i + 1] as usize]);
+                        }
+                        Some(())
+                    })
+                    .ok_or_else(|| invalid("invalid face data"))?;
+                }
+            }
+            _ => {
+                let mut values = vec![0.0; element.properties.len()];
+                for _ in 0..element.count {
+                    read_record(&mut reader, element, &mut values, &mut |_, _| Some(()))
+                        .ok_or_else(|| invalid(&format!("truncated {} data", element.name)))?;
+                }
+            }
+        }
+    }
+
+    if mesh.vertices.is_empty() {
+        return Err(ModelParserError::NoMeshData);
+    }
+    mesh.point_set = !has_faces;
+
+    if config.include_normals {
+        if normals.len() == mesh.vertices.len() {
+            mesh.normals = normals;
+        } else if !mesh.point_set {
+            mesh.compute_vertex_normals();
+        }
+    }
+
+    if config.include_colors {
+        mesh.has_source_colors = colors.len() == mesh.vertices.len();
+        mesh.colors = if mesh.has_source_colors {
+            colors
+        } else {
+            vec![Vec3::ONE; mesh.vertices.len()]
+        };
+    }
+
+    Ok(mesh)
+}
+
+/// Linear color channel from a stored value: integers are normalized sRGB, floats are taken as is
+fn color_value(element: &Element, property: usize, value: f64) -> f32 {
+    match &element.properties[property] {
+        Property::Scalar { kind, .. } => match kind.max_value() {
+            Some(max) => color::srgb_to_linear((value / max) as f32),
+            None => value as f32,
+        },
+        Property::List { .. } => 0.0,
+    }
+}
+
+/// Read one record, storing scalars in `values` and handing lists to `on_list`
+fn read_record(
+    reader: &mut Reader,
+    element: &Element,
+    values: &mut [f64],
+    on_list: &mut dyn FnMut(&str, &[f64]) -> Option<()>,
+) -> Option<()> {
+    for (index, property) in element.properties.iter().enumerate() {
+        match property {
+            Property::Scalar { kind, .. } => values[index] = reader.read(*kind)?,
+            Property::List { name, count, item } => {
+                let length = reader.read(*count)? as usize;
+                let list = (0..length).map(|_| reader.read(*item)).collect::<Option<Vec<_>>>()?;
+                on_list(name, &list)?;
+            }
+        }
+    }
+    Some(())
+}
+
+/// Parse the header, returning the format, the elements in file order and the body offset
+fn parse_header(bytes: &[u8]) -> std::result::Result<(Format, Vec<Element>, usize), String> {
+    const END: &[u8] = b"end_header";
+    let end = bytes
+        .windows(END.len())
+        .position(|window| window == END)
+        .ok_or("missing end_header")?;
+    let body_start = bytes[end..]
+        .iter()
+        .position(|&b| b == b'\n')
+        .map_or(bytes.len(), |newline| end + newline + 1);
+
+    let header = std::str::from_utf8(&bytes[..end]).map_err(|_| "header is not text")?;
+    let mut lines = header.lines().map(str::trim);
+    if lines.next() != Some("ply") {
+        return Err("not a PLY file".to_string());
+    }
+
+    let mut format = None;
+    let mut elements: Vec<Element> = Vec::new();
+    for line in lines {
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        match parts.as_slice() {
+            ["format", kind, ..] => {
+                format = Some(match *kind {
+                    "ascii" => Format::Ascii,
+                    "binary_little_endian" => Format::BinaryLittleEndian,
+                    "binary_big_endian" => Format::BinaryBigEndian,
+                    other => return Err(format!("unknown format '{}'", other)),
+                });
+            }
+            ["element", name, count] => elements.push(Element {
+                name: name.to_string(),
+                count: count.parse().map_err(|_| format!("invalid element count '{}'", count))?,
+                properties: Vec::new(),
+            }),
+            ["property", "list", count, item, name] => {
+                let element = elements.last_mut().ok_or("property before element")?;
+                element.properties.push(Property::List {
+                    name: name.to_string(),
+                    count: ScalarType::parse(count).ok_or(format!("unknown type '{}'", count))?,
+                    item: ScalarType::parse(item).ok_or(format!("unknown type '{}'", item))?,
+                });
+            }
+            ["property", kind, name] => {
+                let element = elements.last_mut().ok_or("property before element")?;
+                element.properties.push(Property::Scalar {
+                    name: name.to_string(),
+                    kind: ScalarType::parse(kind).ok_or(format!("unknown type '{}'", kind))?,
+                });
+            }
+            _ => {}
+        }
+    }
+
+    Ok((format.ok_or("missing format line")?, elements, body_start))
+}
+
+/// Write `point_cloud` as a PLY vertex list, binary little-endian or ASCII: x/y/z, then
+/// nx/ny/nz, red/green/blue (8-bit sRGB), alpha and the generic attributes as the cloud has
+/// them. Clouds with an offset get double positions with the offset added back
+pub fn write(point_cloud: &PointCloud, path: &Path, ascii: bool) -> Result<()> {
+    let mut writer = BufWriter::new(std::fs::File::create(path)?);
+    writer.write_all(header(point_cloud, ascii).as_bytes())?;
+
+    let mut record = Vec::new();
+    for point in &point_cloud.points {
+        record.clear();
+        if ascii {
+            let line: Vec<String> = record_values(point_cloud, point).iter().map(Value::to_string).collect();
+            record.extend_from_slice(line.join(" ").as_bytes());
+            record.push(b'\n');
+        } else {
+            record_values(point_cloud, point).iter().for_each(|value| value.encode(&mut record));
+        }
+        writer.write_all(&record)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Size of the PLY output: the header plus the bytes of each record. ASCII records are
+/// measured on a sample of the points
+pub fn estimate(point_cloud: &PointCloud, ascii: bool) -> SizeEstimate {
+    const SAMPLE: usize = 1000;
+
+    let values = |point: &Point| record_values(point_cloud, point);
+    let bytes_per_point = match point_cloud.points.first() {
+        None => 0.0,
+        Some(point) if !ascii => values(point).iter().map(Value::size).sum::<usize>() as f64,
+        Some(_) => {
+            let step = (point_cloud.points.len() / SAMPLE).max(1);
+            let sampled: Vec<&Point> = point_cloud.points.iter().step_by(step).collect();
+            let bytes: usize = sampled
+                .iter()
+                .map(|point| values(point).iter().map(|value| value.to_string().len() + 1).sum::<usize>())
+                .sum();
+            bytes as f64 / sampled.len() as f64
+        }
+    };
+
+    SizeEstimate {
+        bytes_per_point,
+        // Room for a longer point count
+        overhead: header(point_cloud, ascii).len() as u64 + 20,
+    }
+}
+
+/// One value of a written record
+enum Value {
+    Attribute(AttributeType, f64),
+    Float(f32),
+    Double(f64),
+    Byte(u8),
+}
+
+impl Value {
+    fn size(&self) -> usize {
+        match self {
+            Value::Attribute(data_type, _) => data_type.size() as usize,
+            Value::Float(_) => 4,
+            Value::Double(_) => 8,
+            Value::Byte(_) => 1,
+        }
+    }
+
+    fn encode(&self, out: &mut Vec<u8>) {
+        match *self {
+            Value::Attribute(data_type, value) => data_type.encode(value, out),
+            Value::Float(value) => out.extend_from_slice(&value.to_le_bytes()),
+            Value::Double(value) => out.extend_from_slice(&value.to_le_bytes()),
+            Value::Byte(value) => out.push(value),
+        }
+    }
+}
+
+impl std::fmt::Display for Value {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match *self {
+            // Round-trip through the binary encoding so integers are rounded and clamped alike
+            Value::Attribute(data_type, value) => {
+                let mut bytes = Vec::new();
+                data_type.encode(value, &mut bytes);
+                write!(formatter, "{}", data_type.decode(&bytes))
+            }
+            Value::Float(value) => write!(formatter, "{}", value),
+            Value::Double(value) => write!(formatter, "{}", value),
+            Value::Byte(value) => write!(formatter, "{}", value),
+        }
+    }
+}
+
+fn header(point_cloud: &PointCloud, ascii: bool) -> String {
+    let metadata = &point_cloud.metadata;
+    let mut header = String::from("ply\n");
+    header += if ascii { "format ascii 1.0\n" } else { "format binary_little_endian 1.0\n" };
+    if let Some([x, y, z]) = metadata.offset {
+        header += &format!("comment offset {} {} {} added to positions\n", x, y, z);
+    }
+    header += &format!("element vertex {}\n", point_cloud.points.len());
+
+    let position = if metadata.offset.is_some() { "double" } else { "float" };
+    for axis in ["x", "y", "z"] {
+        header += &format!("property {} {}\n", position, axis);
+    }
+    if metadata.has_normals {
+        header += "property float nx\nproperty float ny\nproperty float nz\n";
+    }
+    if metadata.has_colors {
+        header += "property uchar red\nproperty uchar green\nproperty uchar blue\n";
+    }
+    if metadata.has_alpha {
+        header += "property uchar alpha\n";
+    }
+    for attribute in &metadata.attributes {
+        header += &format!("property {} {}\n", scalar_name(attribute.data_type), attribute.name);
+    }
+    header += "end_header\n";
+    header
+}
+
+/// PLY scalar type storing an attribute type
+fn scalar_name(data_type: AttributeType) -> &'static str {
+    match data_type {
+        AttributeType::U8 => "uchar",
+        AttributeType::U16 => "ushort",
+        AttributeType::U32 => "uint",
+        AttributeType::I32 => "int",
+        AttributeType::F32 => "float",
+        AttributeType::F64 => "double",
+    }
+}
+
+/// Values of one point in header order. Colors are written as sRGB, the convention of
+/// integer PLY colors
+fn record_values(point_cloud: &PointCloud, point: &Point) -> Vec<Value> {
+    let metadata = &point_cloud.metadata;
+    let mut values = Vec::with_capacity(10 + metadata.attributes.len());
+    match metadata.offset {
+        Some(offset) => values.extend((0..3).map(|i| Value::Double(point.position[i] as f64 + offset[i]))),
+        None => values.extend(point.position.map(Value::Float)),
+    }
+    if metadata.has_normals {
+        values.extend(point.normal.unwrap_or_default().map(Value::Float));
+    }
+    if metadata.has_colors {
+        let mut rgb = point.color.unwrap_or_default();
+        if metadata.color_space == ColorSpace::Linear {
+            rgb = rgb.map(color::linear_to_srgb);
+        }
+        values.extend(color::to_rgb8(rgb).map(Value::Byte));
+    }
+    if metadata.has_alpha {
+        values.push(Value::Byte(color::to_u8(point.alpha.unwrap_or(1.0))));
+    }
+    for (index, attribute) in metadata.attributes.iter().enumerate() {
+        values.push(Value::Attribute(attribute.data_type, point.attributes.get(index).copied().unwrap_or(0.0)));
+    }
+    values
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::point_cloud::test_cloud;
+
+    fn round_trip(ascii: bool) {
+        let cloud = test_cloud();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cloud.ply");
+        write(&cloud, &path, ascii).unwrap();
+        let mesh = load(&path, &PointCloudConfig::default()).unwrap();
+
+        assert!(mesh.point_set);
+        assert_eq!(mesh.vertices.len(), cloud.points.len());
+        assert_eq!(mesh.normals.len(), cloud.points.len());
+        assert!(mesh.has_source_colors);
+        for (i, point) in cloud.points.iter().enumerate() {
+            assert!((mesh.vertices[i] - Vec3::from(point.position)).abs().max_element() < 1e-6, "point {}", i);
+            assert!((mesh.normals[i] - Vec3::from(point.normal.unwrap())).abs().max_element() < 1e-6, "point {}", i);
+            // Colors are stored as 8-bit sRGB
+            assert!((mesh.colors[i] - Vec3::from(point.color.unwrap())).abs().max_element() < 1e-2, "point {}", i);
+        }
+    }
+
+    #[test]
+    fn binary_round_trip() {
+        round_trip(false);
+    }
+
+    #[test]
+    fn ascii_round_trip() {
+        round_trip(true);
+    }
+}
@@ -1,5 +1,13 @@
+use crate::{
+    attributes::{AttributeDescriptor, AttributeType},
+    color::{self, ColorSpace},
+    colormap::Colormap,
+    random,
+    validation::ValidationCounts,
+};
 use serde::{Deserialize, Serialize};
 use glam::Vec3;
+use rayon::prelude::*;
 
 /// Represents a single point in the point cloud
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -14,6 +22,18 @@ pub struct Point {
     /// Color [r, g, b] (optional, values 0.0-1.0)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub color: Option<[f32; 3]>,
+
+    /// Opacity (optional, 0.0 transparent to 1.0 opaque)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alpha: Option<f32>,
+
+    /// Source material index into `PointCloudMetadata::materials` (optional)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub material_id: Option<u32>,
+
+    /// Generic attribute values, aligned with `PointCloudMetadata::attributes`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attributes: Vec<f64>,
 }
 
 impl Point {
@@ -22,6 +42,9 @@ impl Point {
             position: position.to_array(),
             normal: None,
             color: None,
+            alpha: None,
+            material_id: None,
+            attributes: Vec::new(),
         }
     }
 
@@ -34,6 +57,16 @@ impl Point {
         self.color = Some(color.to_array());
         self
     }
+
+    pub fn with_alpha(mut self, alpha: f32) -> Self {
+        self.alpha = Some(alpha);
+        self
+    }
+
+    pub fn with_material_id(mut self, material_id: u32) -> Self {
+        self.material_id = Some(material_id);
+        self
+    }
 }
 
 /// Point cloud data structure optimized for JSON serialization
@@ -65,6 +98,68 @@ pub struct PointCloudMetadata {
 
     /// Whether colors are included
     pub has_colors: bool,
+
+    /// Color space of the point colors
+    #[serde(default)]
+    pub color_space: ColorSpace,
+
+    /// Whether per-point alpha is included
+    #[serde(default)]
+    pub has_alpha: bool,
+
+    /// Whether per-point material ids are included
+    #[serde(default)]
+    pub has_material_ids: bool,
+
+    /// Material names, indexed by point material id
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub materials: Vec<String>,
+
+    /// Generic per-point attributes, in the order values are stored on each point
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attributes: Vec<AttributeDescriptor>,
+
+    /// Colors of a quantized cloud in `color_space`, indexed by the "palette_index" attribute
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub palette: Vec<[f32; 3]>,
+
+    /// Translation removed by re-centering; add it to positions to restore source coordinates
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub offset: Option<[f64; 3]>,
+
+    /// Invalid values found and handled by validation, when there were any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub validation: Option<ValidationCounts>,
+
+    /// Bounds ignoring outliers, for camera framing; `bounds_min`/`bounds_max` stay exact
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub robust_bounds: Option<RobustBounds>,
+}
+
+/// Bounds of the central part of a cloud: each axis ignores the given percentage of points
+/// at either end, so a few stray points don't inflate the extent
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RobustBounds {
+    /// Percentage (0-50) of points ignored at each end of each axis
+    pub percentile: f32,
+
+    pub min: [f32; 3],
+
+    pub max: [f32; 3],
+}
+
+/// Reference point moved to the origin by `PointCloud::recenter`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Centering {
+    /// Leave positions unchanged
+    #[default]
+    None,
+
+    /// Center of the bounding box
+    BoundsCenter,
+
+    /// Mean of the point positions
+    Centroid,
 }
 
 impl PointCloud {
@@ -72,6 +167,8 @@ impl PointCloud {
         let bounds = Self::calculate_bounds(&points);
         let has_normals = points.iter().any(|p| p.normal.is_some());
         let has_colors = points.iter().any(|p| p.color.is_some());
+        let has_alpha = points.iter().any(|p| p.alpha.is_some());
+        let has_material_ids = points.iter().any(|p| p.material_id.is_some());
 
         Self {
             metadata: PointCloudMetadata {
@@ -81,11 +178,65 @@ impl PointCloud {
                 source_file,
                 has_normals,
                 has_colors,
+                color_space: ColorSpace::Linear,
+                has_alpha,
+                has_material_ids,
+                materials: Vec::new(),
+                attributes: Vec::new(),
+                palette: Vec::new(),
+                offset: None,
+                validation: None,
+                robust_bounds: None,
             },
             points,
         }
     }
 
+    /// Recompute counts, bounds and presence flags after points were modified
+    pub fn update_metadata(&mut self) {
+        let bounds = Self::calculate_bounds(&self.points);
+        self.metadata.point_count = self.points.len();
+        self.metadata.bounds_min = bounds.0;
+        self.metadata.bounds_max = bounds.1;
+        self.metadata.has_normals = self.points.iter().any(|p| p.normal.is_some());
+        self.metadata.has_colors = self.points.iter().any(|p| p.color.is_some());
+        self.metadata.has_alpha = self.points.iter().any(|p| p.alpha.is_some());
+        self.metadata.has_material_ids = self.points.iter().any(|p| p.material_id.is_some());
+        if let Some(robust) = self.metadata.robust_bounds {
+            self.metadata.robust_bounds = self.robust_bounds(robust.percentile);
+        }
+    }
+
+    /// Per-axis bounds ignoring `percentile` percent of the points at each end, `None` for an
+    /// empty cloud
+    pub fn robust_bounds(&self, percentile: f32) -> Option<RobustBounds> {
+        if self.points.is_empty() {
+            return None;
+        }
+
+        let percentile = percentile.clamp(0.0, 50.0);
+        let last = self.points.len() - 1;
+        let low = ((last as f32 * percentile / 100.0).round() as usize).min(last);
+        let high = last - low;
+
+        let mut min = [0.0; 3];
+        let mut max = [0.0; 3];
+        let mut values = Vec::with_capacity(self.points.len());
+        for axis in 0..3 {
+            values.clear();
+            values.extend(self.points.iter().map(|p| p.position[axis]));
+            min[axis] = *values.select_nth_unstable_by(low, f32::total_cmp).1;
+            max[axis] = *values.select_nth_unstable_by(high, f32::total_cmp).1;
+        }
+
+        Some(RobustBounds { percentile, min, max })
+    }
+
+    /// Store robust bounds in the metadata; they are kept up to date by `update_metadata`
+    pub fn set_robust_bounds(&mut self, percentile: f32) {
+        self.metadata.robust_bounds = self.robust_bounds(percentile);
+    }
+
     fn calculate_bounds(points: &[Point]) -> ([f32; 3], [f32; 3]) {
         if points.is_empty() {
             return ([0.0, 0.0, 0.0], [0.0, 0.0, 0.0]);
@@ -103,6 +254,227 @@ impl PointCloud {
         (min.to_array(), max.to_array())
     }
 
+    /// Convert point colors to `color_space`, updating the metadata
+    pub fn convert_color_space(&mut self, color_space: ColorSpace) {
+        let from = self.metadata.color_space;
+        if from != color_space {
+            self.points.par_iter_mut().for_each(|point| {
+                if let Some(c) = point.color.as_mut() {
+                    *c = color::convert(Vec3::from(*c), from, color_space).to_array();
+                }
+            });
+        }
+        self.metadata.color_space = color_space;
+    }
+
+    /// Replace negative zeros with positive zeros so equal values always format identically
+    pub fn canonicalize_floats(&mut self) {
+        fn canonical(v: &mut f32) {
+            if *v == 0.0 {
+                *v = 0.0;
+            }
+        }
+
+        self.points.par_iter_mut().for_each(|point| {
+            point.position.iter_mut().for_each(canonical);
+            if let Some(normal) = point.normal.as_mut() {
+                normal.iter_mut().for_each(canonical);
+            }
+            if let Some(color) = point.color.as_mut() {
+                color.iter_mut().for_each(canonical);
+            }
+            for value in point.attributes.iter_mut() {
+                if *value == 0.0 {
+                    *value = 0.0;
+                }
+            }
+        });
+
+        for v in self.metadata.bounds_min.iter_mut().chain(self.metadata.bounds_max.iter_mut()) {
+            canonical(v);
+        }
+    }
+
+    /// Store a uniformly distributed "random_key" attribute (0 to 2^32-1) hashed from each
+    /// point's position, so viewers can show a stable fraction of the points (those with keys
+    /// below fraction × 2^32) without the data being reordered. Equal positions and seeds
+    /// always give equal keys
+    pub fn assign_random_keys(&mut self, seed: Option<u64>) {
+        let keys: Vec<f64> = self
+            .points
+            .par_iter()
+            .map(|point| {
+                let bits = point.position.map(|v| if v == 0.0 { 0 } else { v.to_bits() });
+                (random::hash(seed.unwrap_or(0), &bits) >> 32) as f64
+            })
+            .collect();
+        self.set_attribute_values("random_key", AttributeType::U32, &keys);
+    }
+
+    /// Index of a generic attribute by name
+    pub fn attribute_index(&self, name: &str) -> Option<usize> {
+        self.metadata.attributes.iter().position(|a| a.name == name)
+    }
+
+    /// Value of a generic attribute for the point at `point_index`
+    pub fn attribute(&self, point_index: usize, name: &str) -> Option<f64> {
+        let index = self.attribute_index(name)?;
+        self.points.get(point_index)?.attributes.get(index).copied()
+    }
+
+    /// Register an attribute (or retype an existing one) and return its index
+    fn attribute_slot(&mut self, name: &str, data_type: AttributeType) -> usize {
+        match self.attribute_index(name) {
+            Some(index) => {
+                self.metadata.attributes[index].data_type = data_type;
+                index
+            }
+            None => {
+                self.metadata.attributes.push(AttributeDescriptor::new(name, data_type));
+                self.metadata.attributes.len() - 1
+            }
+        }
+    }
+
+    /// Add or overwrite a generic attribute, computing each point's value with `value`
+    pub fn set_attribute<F>(&mut self, name: &str, data_type: AttributeType, value: F)
+    where
+        F: Fn(&Point) -> f64 + Sync,
+    {
+        let index = self.attribute_slot(name, data_type);
+
+        let attribute_count = self.metadata.attributes.len();
+        self.points.par_iter_mut().for_each(|point| {
+            let v = value(point);
+            point.attributes.resize(attribute_count, 0.0);
+            point.attributes[index] = v;
+        });
+    }
+
+    /// Add or overwrite a generic attribute from precomputed per-point values
+    pub fn set_attribute_values(&mut self, name: &str, data_type: AttributeType, values: &[f64]) {
+        let index = self.attribute_slot(name, data_type);
+
+        let attribute_count = self.metadata.attributes.len();
+        self.points
+            .par_iter_mut()
+            .zip(values.par_iter())
+            .for_each(|(point, &v)| {
+                point.attributes.resize(attribute_count, 0.0);
+                point.attributes[index] = v;
+            });
+    }
+
+    /// Translate the cloud so the chosen reference sits at the origin, accumulating the
+    /// applied translation in `metadata.offset`. Returns the translation removed
+    pub fn recenter(&mut self, centering: Centering) -> [f64; 3] {
+        if self.points.is_empty() {
+            return [0.0; 3];
+        }
+
+        let reference = match centering {
+            Centering::None => return [0.0; 3],
+            Centering::BoundsCenter => {
+                let min = self.metadata.bounds_min;
+                let max = self.metadata.bounds_max;
+                [0, 1, 2].map(|i| (min[i] as f64 + max[i] as f64) * 0.5)
+            }
+            Centering::Centroid => {
+                let sum = self
+                    .points
+                    .par_iter()
+                    .map(|p| p.position.map(|v| v as f64))
+                    .reduce(|| [0.0; 3], |a, b| [a[0] + b[0], a[1] + b[1], a[2] + b[2]]);
+                sum.map(|v| v / self.points.len() as f64)
+            }
+        };
+
+        self.points.par_iter_mut().for_each(|point| {
+            for (component, offset) in point.position.iter_mut().zip(reference) {
+                *component = (*component as f64 - offset) as f32;
+            }
+        });
+
+        let previous = self.metadata.offset.unwrap_or([0.0; 3]);
+        self.metadata.offset = Some([0, 1, 2].map(|i| previous[i] + reference[i]));
+        self.update_metadata();
+        reference
+    }
+
+    /// Keep the points whose entry in `keep` is true, preserving their order
+    pub fn retain_by_mask(&mut self, keep: &[bool]) {
+        let mut flags = keep.iter();
+        self.points.retain(|_| *flags.next().unwrap_or(&false));
+        self.update_metadata();
+    }
+
+    /// Values of a generic attribute for every point, in point order
+    pub fn attribute_values(&self, name: &str) -> Option<Vec<f64>> {
+        let index = self.attribute_index(name)?;
+        Some(
+            self.points
+                .par_iter()
+                .map(|point| point.attributes.get(index).copied().unwrap_or(0.0))
+                .collect(),
+        )
+    }
+
+    /// Replace point colors by mapping `values` (one per point) through `colormap`,
+    /// normalized to `range` or to the min/max of the values
+    pub fn color_by_values(&mut self, values: &[f64], colormap: &Colormap, range: Option<[f64; 2]>) {
+        let [min, max] = range.unwrap_or_else(|| {
+            values
+                .iter()
+                .filter(|v| v.is_finite())
+                .fold([f64::MAX, f64::MIN], |[min, max], &v| [min.min(v), max.max(v)])
+        });
+        let span = if max > min { max - min } else { 1.0 };
+        let color_space = self.metadata.color_space;
+
+        self.points
+            .par_iter_mut()
+            .zip(values.par_iter())
+            .for_each(|(point, &value)| {
+                let t = ((value - min) / span) as f32;
+                point.color = Some(colormap.sample_in(t, color_space).to_array());
+            });
+        self.metadata.has_colors = !self.points.is_empty();
+    }
+
+    /// Color points by height along the Y (up) axis, normalized to the cloud bounds
+    pub fn color_by_elevation(&mut self, colormap: &Colormap) {
+        let heights: Vec<f64> = self.points.iter().map(|p| p.position[1] as f64).collect();
+        let range = [self.metadata.bounds_min[1] as f64, self.metadata.bounds_max[1] as f64];
+        self.color_by_values(&heights, colormap, Some(range));
+    }
+
+    /// Color points by normal direction (X/Y/Z mapped to red/green/blue); points
+    /// without a normal become mid gray
+    pub fn color_by_normal(&mut self) {
+        let color_space = self.metadata.color_space;
+        self.points.par_iter_mut().for_each(|point| {
+            let normal = point.normal.map(Vec3::from).unwrap_or(Vec3::ZERO).normalize_or_zero();
+            let display = normal * 0.5 + Vec3::splat(0.5);
+            point.color = Some(color::convert(display, ColorSpace::Srgb, color_space).to_array());
+        });
+        self.metadata.has_colors = !self.points.is_empty();
+    }
+
+    /// Remove a generic attribute; returns whether it existed
+    pub fn remove_attribute(&mut self, name: &str) -> bool {
+        let Some(index) = self.attribute_index(name) else {
+            return false;
+        };
+
+        self.metadata.attributes.remove(index);
+        self.points.par_iter_mut().for_each(|point| {
+            if index < point.attributes.len() {
+                point.attributes.remove(index);
+            }
+        });
+        true
+    }
+
     /// Save point cloud to JSON file
     pub fn save_to_file(&self, path: &std::path::Path) -> crate::error::Result<()> {
         let json = serde_json::to_string_pretty(self)?;
@@ -110,10 +482,291 @@ impl PointCloud {
         Ok(())
     }
 
+    /// Save point cloud to JSON file in the given layout. The arrays layout is written
+    /// compactly, since it is meant for machines rather than for reading
+    pub fn save_to_file_with_layout(&self, path: &std::path::Path, layout: JsonLayout) -> crate::error::Result<()> {
+        match layout {
+            JsonLayout::Points => self.save_to_file(path),
+            JsonLayout::Arrays => {
+                let mut writer = std::io::BufWriter::new(std::fs::File::create(path)?);
+                serde_json::to_writer(&mut writer, &PointCloudArrays::from(self))?;
+                std::io::Write::flush(&mut writer)?;
+                Ok(())
+            }
+        }
+    }
+
     /// Load point cloud from JSON file
     pub fn load_from_file(path: &std::path::Path) -> crate::error::Result<Self> {
-        let json = std::fs::read_to_string(path)?;
-        let point_cloud = serde_json::from_str(&json)?;
+        Self::load_with_limits(path, &LoadLimits::default())
+    }
+
+    /// Load point cloud from JSON file in either layout, streaming it instead of reading it
+    /// whole and failing as soon as the file or its point list exceeds `limits`
+    pub fn load_with_limits(path: &std::path::Path, limits: &LoadLimits) -> crate::error::Result<Self> {
+        let file = std::fs::File::open(path)?;
+        let size = file.metadata()?.len();
+        if let Some(max) = limits.max_file_size
+            && size > max
+        {
+            return Err(crate::error::ModelParserError::InvalidData(format!(
+                "{:?} is {} bytes, above the limit of {} bytes",
+                path, size, max
+            )));
+        }
+
+        let mut deserializer = serde_json::Deserializer::from_reader(std::io::BufReader::new(file));
+        let point_cloud = serde::de::DeserializeSeed::deserialize(LimitedPointCloud(limits), &mut deserializer)?;
+        deserializer.end()?;
         Ok(point_cloud)
     }
+
+    /// Save point cloud as a binary `.hgpc` container, a fraction of the size of JSON, with
+    /// the point records zlib-compressed when `compress` is set
+    pub fn save_hgpc(&self, path: &std::path::Path, compress: bool) -> crate::error::Result<()> {
+        crate::hgpc::write(self, path, compress)
+    }
+
+    /// Load point cloud from an `.hgpc` container
+    pub fn load_hgpc(path: &std::path::Path) -> crate::error::Result<Self> {
+        Self::load_hgpc_with_limits(path, &LoadLimits::default())
+    }
+
+    /// Load point cloud from an `.hgpc` container, failing before the points are read when
+    /// the file or its point count exceeds `limits`
+    pub fn load_hgpc_with_limits(path: &std::path::Path, limits: &LoadLimits) -> crate::error::Result<Self> {
+        crate::hgpc::read(path, limits)
+    }
+}
+
+/// Limits for loading point clouds from untrusted files; `None` means unlimited
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LoadLimits {
+    /// Largest accepted file size in bytes
+    pub max_file_size: Option<u64>,
+
+    /// Largest accepted number of points
+    pub max_points: Option<usize>,
+}
+
+/// Layout of JSON point cloud files
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum JsonLayout {
+    /// Array of point objects ("array of structs")
+    #[default]
+    Points,
+
+    /// One flat numeric array per property ("struct of arrays"), which browsers parse many
+    /// times faster straight into typed arrays and which is much smaller
+    Arrays,
+}
+
+/// Struct-of-arrays form of a point cloud. Properties are interleaved per point (x, y, z,
+/// x, y, z, ...); normals, colors, alphas and material ids are present when every point has them,
+/// and `attributes` holds one array per entry of `metadata.attributes`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PointCloudArrays {
+    pub positions: Vec<f32>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub normals: Option<Vec<f32>>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub colors: Option<Vec<f32>>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alphas: Option<Vec<f32>>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub material_ids: Option<Vec<u32>>,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attributes: Vec<Vec<f64>>,
+
+    pub metadata: PointCloudMetadata,
+}
+
+impl From<&PointCloud> for PointCloudArrays {
+    fn from(point_cloud: &PointCloud) -> Self {
+        let points = &point_cloud.points;
+        let all = |present: fn(&Point) -> bool| !points.is_empty() && points.iter().all(present);
+
+        Self {
+            positions: points.iter().flat_map(|p| p.position).collect(),
+            normals: all(|p| p.normal.is_some()).then(|| points.iter().flat_map(|p| p.normal.unwrap_or_default()).collect()),
+            colors: all(|p| p.color.is_some()).then(|| points.iter().flat_map(|p| p.color.unwrap_or_default()).collect()),
+            alphas: all(|p| p.alpha.is_some()).then(|| points.iter().map(|p| p.alpha.unwrap_or(1.0)).collect()),
+            material_ids: all(|p| p.material_id.is_some())
+                .then(|| points.iter().map(|p| p.material_id.unwrap_or_default()).collect()),
+            attributes: (0..point_cloud.metadata.attributes.len())
+                .map(|i| points.iter().map(|p| p.attributes.get(i).copied().unwrap_or(0.0)).collect())
+                .collect(),
+            metadata: point_cloud.metadata.clone(),
+        }
+    }
+}
+
+impl TryFrom<PointCloudArrays> for PointCloud {
+    type Error = crate::error::ModelParserError;
+
+    fn try_from(arrays: PointCloudArrays) -> crate::error::Result<Self> {
+        let count = arrays.positions.len() / 3;
+        let mismatch = |name: &str, len: usize, expected: usize| {
+            crate::error::ModelParserError::InvalidData(format!(
+                "'{}' has {} values, expected {} for {} points",
+                name, len, expected, count
+            ))
+        };
+        if !arrays.positions.len().is_multiple_of(3) {
+            return Err(crate::error::ModelParserError::InvalidData(format!(
+                "'positions' has {} values, not a multiple of 3",
+                arrays.positions.len()
+            )));
+        }
+        for (name, values) in [("normals", &arrays.normals), ("colors", &arrays.colors)] {
+            if let Some(values) = values
+                && values.len() != count * 3
+            {
+                return Err(mismatch(name, values.len(), count * 3));
+            }
+        }
+        if let Some(alphas) = &arrays.alphas
+            && alphas.len() != count
+        {
+            return Err(mismatch("alphas", alphas.len(), count));
+        }
+        if let Some(ids) = &arrays.material_ids
+            && ids.len() != count
+        {
+            return Err(mismatch("material_ids", ids.len(), count));
+        }
+        if let Some(values) = arrays.attributes.iter().find(|values| values.len() != count) {
+            return Err(mismatch("attributes", values.len(), count));
+        }
+
+        let triple = |values: &Option<Vec<f32>>, i: usize| values.as_ref().map(|v| [v[3 * i], v[3 * i + 1], v[3 * i + 2]]);
+        let points = (0..count)
+            .map(|i| Point {
+                position: [arrays.positions[3 * i], arrays.positions[3 * i + 1], arrays.positions[3 * i + 2]],
+                normal: triple(&arrays.normals, i),
+                color: triple(&arrays.colors, i),
+                alpha: arrays.alphas.as_ref().map(|alphas| alphas[i]),
+                material_id: arrays.material_ids.as_ref().map(|ids| ids[i]),
+                attributes: arrays.attributes.iter().map(|values| values[i]).collect(),
+            })
+            .collect();
+
+        Ok(PointCloud { points, metadata: arrays.metadata })
+    }
+}
+
+/// Point cloud deserialization enforcing [`LoadLimits`] while the points are read
+struct LimitedPointCloud<'a>(&'a LoadLimits);
+
+impl<'de> serde::de::DeserializeSeed<'de> for LimitedPointCloud<'_> {
+    type Value = PointCloud;
+
+    fn deserialize<D: serde::Deserializer<'de>>(self, deserializer: D) -> Result<PointCloud, D::Error> {
+        deserializer.deserialize_struct("PointCloud", &["points", "metadata"], self)
+    }
+}
+
+impl<'de> serde::de::Visitor<'de> for LimitedPointCloud<'_> {
+    type Value = PointCloud;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("a point cloud object")
+    }
+
+    fn visit_map<A: serde::de::MapAccess<'de>>(self, mut map: A) -> Result<PointCloud, A::Error> {
+        use serde::de::Error;
+
+        // Either layout; arrays are limited to the values of `max_points` points
+        let max_points = self.0.max_points;
+        let values = |per_point: usize| max_points.map(|max| max.saturating_mul(per_point));
+        let (mut points, mut metadata) = (None, None);
+        let (mut positions, mut normals, mut colors, mut alphas, mut material_ids, mut attributes) =
+            (None, None, None, None, None, Vec::new());
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "points" => points = Some(map.next_value_seed(Limited::new(max_points))?),
+                "metadata" => metadata = Some(map.next_value()?),
+                "positions" => positions = Some(map.next_value_seed(Limited::new(values(3)))?),
+                "normals" => normals = Some(map.next_value_seed(Limited::new(values(3)))?),
+                "colors" => colors = Some(map.next_value_seed(Limited::new(values(3)))?),
+                "alphas" => alphas = Some(map.next_value_seed(Limited::new(max_points))?),
+                "material_ids" => material_ids = Some(map.next_value_seed(Limited::new(max_points))?),
+                "attributes" => attributes = map.next_value()?,
+                _ => {
+                    map.next_value::<serde::de::IgnoredAny>()?;
+                }
+            }
+        }
+
+        let metadata = metadata.ok_or_else(|| A::Error::missing_field("metadata"))?;
+        match (points, positions) {
+            (Some(points), _) => Ok(PointCloud { points, metadata }),
+            (None, Some(positions)) => {
+                PointCloud::try_from(PointCloudArrays { positions, normals, colors, alphas, material_ids, attributes, metadata })
+                    .map_err(A::Error::custom)
+            }
+            (None, None) => Err(A::Error::missing_field("points")),
+        }
+    }
+}
+
+/// List deserialization failing once more than the given number of elements were read
+struct Limited<T> {
+    max: Option<usize>,
+    element: std::marker::PhantomData<T>,
+}
+
+impl<T> Limited<T> {
+    fn new(max: Option<usize>) -> Self {
+        Self { max, element: std::marker::PhantomData }
+    }
+}
+
+impl<'de, T: Deserialize<'de>> serde::de::DeserializeSeed<'de> for Limited<T> {
+    type Value = Vec<T>;
+
+    fn deserialize<D: serde::Deserializer<'de>>(self, deserializer: D) -> Result<Vec<T>, D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de, T: Deserialize<'de>> serde::de::Visitor<'de> for Limited<T> {
+    type Value = Vec<T>;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("an array")
+    }
+
+    fn visit_seq<A: serde::de::SeqAccess<'de>>(self, mut seq: A) -> Result<Vec<T>, A::Error> {
+        let max = self.max.unwrap_or(usize::MAX);
+        let mut elements = Vec::new();
+        while let Some(element) = seq.next_element()? {
+            if elements.len() == max {
+                return Err(serde::de::Error::custom(format!("more than {} values", max)));
+            }
+            elements.push(element);
+        }
+        Ok(elements)
+    }
+}
+
+/// Small cloud with normals, linear colors and an `intensity` attribute, for writer round trips
+#[cfg(test)]
+pub(crate) fn test_cloud() -> PointCloud {
+    let points = (0..64)
+        .map(|i| {
+            let t = i as f32;
+            Point::new(Vec3::new(t * 0.25 - 8.0, (t * 0.7).sin() * 3.0, (t * 0.3).cos() + 1.5))
+                .with_normal(Vec3::new(t.sin(), t.cos(), 0.5).normalize())
+                .with_color(Vec3::new(0.05 + (i % 8) as f32 * 0.1, 0.9 - (i % 5) as f32 * 0.15, 0.5))
+        })
+        .collect();
+    let mut cloud = PointCloud::new(points, "test".to_string());
+    cloud.set_attribute("intensity", AttributeType::U16, |point| (point.position[0] as f64 * 100.0 + 1000.0).round());
+    cloud
 }
@@ -0,0 +1,137 @@
+use serde::{Deserialize, Serialize};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::Instant;
+
+/// Wall time and memory high-water marks of one pipeline stage
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StageTiming {
+    pub name: String,
+
+    /// Wall-clock duration in seconds
+    pub seconds: f64,
+
+    /// Process peak resident set size after the stage (Linux only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub peak_rss_bytes: Option<u64>,
+
+    /// Peak heap allocation during the stage (requires `TrackingAllocator`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub peak_allocated_bytes: Option<u64>,
+}
+
+/// Collects stage timings, optionally logging each one as it finishes
+#[derive(Debug, Default)]
+pub struct Profiler {
+    stages: Vec<StageTiming>,
+    verbose: bool,
+}
+
+impl Profiler {
+    pub fn new(verbose: bool) -> Self {
+        Self {
+            stages: Vec::new(),
+            verbose,
+        }
+    }
+
+    /// Run `f` as a named stage and record its timing
+    pub fn stage<T>(&mut self, name: &str, f: impl FnOnce() -> T) -> T {
+        reset_peak_allocated();
+        let start = Instant::now();
+        let result = f();
+
+        let timing = StageTiming {
+            name: name.to_string(),
+            seconds: start.elapsed().as_secs_f64(),
+            peak_rss_bytes: peak_rss(),
+            peak_allocated_bytes: peak_allocated(),
+        };
+        if self.verbose {
+            println!("[timing] {}", format_timing(&timing));
+        }
+        self.stages.push(timing);
+        result
+    }
+
+    pub fn stages(&self) -> &[StageTiming] {
+        &self.stages
+    }
+}
+
+fn format_timing(timing: &StageTiming) -> String {
+    let mut line = format!("{}: {:.3}s", timing.name, timing.seconds);
+    if let Some(rss) = timing.peak_rss_bytes {
+        line += &format!(", peak RSS {:.1} MiB", rss as f64 / (1024.0 * 1024.0));
+    }
+    if let Some(allocated) = timing.peak_allocated_bytes {
+        line += &format!(", peak heap {:.1} MiB", allocated as f64 / (1024.0 * 1024.0));
+    }
+    line
+}
+
+/// Peak resident set size of this process (VmHWM), where the platform exposes it
+pub fn peak_rss() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmHWM:"))?;
+    let kib: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kib * 1024)
+}
+
+static TRACKING: AtomicBool = AtomicBool::new(false);
+static ALLOCATED: AtomicU64 = AtomicU64::new(0);
+static PEAK_ALLOCATED: AtomicU64 = AtomicU64::new(0);
+
+/// System allocator wrapper counting live and peak heap bytes. Install it with
+/// `#[global_allocator]` (the CLI does so with the `alloc-tracking` feature)
+pub struct TrackingAllocator;
+
+unsafe impl GlobalAlloc for TrackingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = unsafe { System.alloc(layout) };
+        if !ptr.is_null() {
+            record_alloc(layout.size() as u64);
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = unsafe { System.alloc_zeroed(layout) };
+        if !ptr.is_null() {
+            record_alloc(layout.size() as u64);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) };
+        ALLOCATED.fetch_sub(layout.size() as u64, Ordering::Relaxed);
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = unsafe { System.realloc(ptr, layout, new_size) };
+        if !new_ptr.is_null() {
+            ALLOCATED.fetch_sub(layout.size() as u64, Ordering::Relaxed);
+            record_alloc(new_size as u64);
+        }
+        new_ptr
+    }
+}
+
+fn record_alloc(size: u64) {
+    TRACKING.store(true, Ordering::Relaxed);
+    let current = ALLOCATED.fetch_add(size, Ordering::Relaxed) + size;
+    PEAK_ALLOCATED.fetch_max(current, Ordering::Relaxed);
+}
+
+/// Peak heap bytes since the last reset, when `TrackingAllocator` is installed
+pub fn peak_allocated() -> Option<u64> {
+    TRACKING
+        .load(Ordering::Relaxed)
+        .then(|| PEAK_ALLOCATED.load(Ordering::Relaxed))
+}
+
+/// Start a new peak measurement from the current heap size
+pub fn reset_peak_allocated() {
+    PEAK_ALLOCATED.store(ALLOCATED.load(Ordering::Relaxed), Ordering::Relaxed);
+}
@@ -0,0 +1,110 @@
+use parking_lot::RwLock;
+use rand::{RngCore, SeedableRng, rngs::StdRng};
+use rand_pcg::Pcg64;
+use std::sync::Arc;
+
+/// Number of items processed with one random stream. Fixed so that seeded runs
+/// produce identical results regardless of the thread count
+pub const CHUNK_SIZE: usize = 4096;
+
+/// Stream identifiers keeping the random sequences of pipeline stages independent
+pub mod stream {
+    pub const SAMPLING: u64 = 1;
+    pub const AMBIENT_OCCLUSION: u64 = 2;
+    pub const LIDAR_NOISE: u64 = 3;
+    pub const OCTREE: u64 = 4;
+    pub const COVERAGE: u64 = 5;
+    pub const THINNING: u64 = 6;
+    pub const HOLES: u64 = 7;
+    pub const LINES: u64 = 8;
+    pub const STYLIZE: u64 = 9;
+    pub const PALETTE: u64 = 10;
+}
+
+/// Random source of one chunk of a stage
+pub type StreamRng = Box<dyn RngCore + Send>;
+
+/// Family of random generators behind every stage. A generator hands out one independent
+/// source per (seed, stream, chunk), so a seeded run draws the same numbers whichever
+/// thread processes a chunk. Implement it to plug in other generators, e.g. quasi-random
+/// sequences, and install it with `set_generator`
+pub trait Generator: Send + Sync {
+    /// Name of the generator
+    fn name(&self) -> &str;
+
+    /// Source for `chunk` of `stream` under `seed`
+    fn source(&self, seed: u64, stream: u64, chunk: u64) -> StreamRng;
+}
+
+/// ChaCha-based `StdRng`, seeded per chunk from a hash of (seed, stream, chunk). The default
+pub struct StdGenerator;
+
+impl Generator for StdGenerator {
+    fn name(&self) -> &str {
+        "std"
+    }
+
+    fn source(&self, seed: u64, stream: u64, chunk: u64) -> StreamRng {
+        Box::new(StdRng::seed_from_u64(mix(mix(seed ^ stream) ^ chunk)))
+    }
+}
+
+/// PCG-XSL-RR 128/64 with one sequence per stream (the PCG stream selector) that chunks
+/// jump ahead into, 2^64 draws apart, so chunks are disjoint slices of a single sequence
+pub struct PcgGenerator;
+
+impl Generator for PcgGenerator {
+    fn name(&self) -> &str {
+        "pcg"
+    }
+
+    fn source(&self, seed: u64, stream: u64, chunk: u64) -> StreamRng {
+        let state = ((mix(seed) as u128) << 64) | mix(!seed) as u128;
+        let mut rng = Pcg64::new(state, mix(stream) as u128);
+        rng.advance((chunk as u128) << 64);
+        Box::new(rng)
+    }
+}
+
+/// Generator used by `chunk_rng`; the standard one until `set_generator` is called
+static GENERATOR: RwLock<Option<Arc<dyn Generator>>> = RwLock::new(None);
+
+/// Built-in generator by name: "std" or "pcg"
+pub fn builtin(name: &str) -> Option<Arc<dyn Generator>> {
+    match name.to_lowercase().as_str() {
+        "std" => Some(Arc::new(StdGenerator)),
+        "pcg" => Some(Arc::new(PcgGenerator)),
+        _ => None,
+    }
+}
+
+/// Use `generator` for every random stream drawn from now on, process-wide
+pub fn set_generator(generator: Arc<dyn Generator>) {
+    *GENERATOR.write() = Some(generator);
+}
+
+/// The generator random streams are drawn from
+pub fn generator() -> Arc<dyn Generator> {
+    GENERATOR.read().clone().unwrap_or_else(|| Arc::new(StdGenerator))
+}
+
+/// Random generator for one chunk of a stage: derived from `seed` when given,
+/// otherwise from a fresh random seed
+pub fn chunk_rng(seed: Option<u64>, stream: u64, chunk: u64) -> StreamRng {
+    let seed = seed.unwrap_or_else(rand::random);
+    generator().source(seed, stream, chunk)
+}
+
+/// Well-mixed hash of `values` under `seed`, for per-item keys that must not depend on
+/// processing order
+pub fn hash(seed: u64, values: &[u32]) -> u64 {
+    values.iter().fold(mix(seed), |hash, &value| mix(hash ^ value as u64))
+}
+
+/// SplitMix64 finalizer, spreading nearby inputs over the whole seed space
+fn mix(value: u64) -> u64 {
+    let mut z = value.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
@@ -0,0 +1,91 @@
+use crate::{
+    coverage::CoverageMetrics,
+    ept::EptBuildReport,
+    error::Result,
+    point_cloud::{PointCloud, RobustBounds},
+    profiling::{self, StageTiming},
+};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Summary of one conversion, written with `--report`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunReport {
+    /// Input model path
+    pub input: String,
+
+    /// Output file or directory
+    pub output: String,
+
+    /// Output format ("json" or "ept")
+    pub format: String,
+
+    /// Number of points written
+    pub point_count: usize,
+
+    pub bounds_min: [f32; 3],
+    pub bounds_max: [f32; 3],
+
+    /// Bounds ignoring outliers, when requested
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub robust_bounds: Option<RobustBounds>,
+
+    /// Surface coverage of the sampled cloud (before instancing)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub coverage: Option<CoverageMetrics>,
+
+    /// Outcome of the EPT build, for EPT output
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ept: Option<EptBuildReport>,
+
+    /// Wall time and memory per pipeline stage, in execution order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub stages: Vec<StageTiming>,
+
+    /// Total wall time of all stages in seconds
+    pub total_seconds: f64,
+
+    /// Process peak resident set size (Linux only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub peak_rss_bytes: Option<u64>,
+}
+
+impl RunReport {
+    pub fn new(input: &Path, output: &Path, format: &str, point_cloud: &PointCloud) -> Self {
+        Self {
+            input: input.display().to_string(),
+            output: output.display().to_string(),
+            format: format.to_string(),
+            point_count: point_cloud.metadata.point_count,
+            bounds_min: point_cloud.metadata.bounds_min,
+            bounds_max: point_cloud.metadata.bounds_max,
+            robust_bounds: point_cloud.metadata.robust_bounds,
+            coverage: None,
+            ept: None,
+            stages: Vec::new(),
+            total_seconds: 0.0,
+            peak_rss_bytes: profiling::peak_rss(),
+        }
+    }
+
+    pub fn with_coverage(mut self, coverage: Option<CoverageMetrics>) -> Self {
+        self.coverage = coverage;
+        self
+    }
+
+    pub fn with_ept(mut self, ept: Option<EptBuildReport>) -> Self {
+        self.ept = ept;
+        self
+    }
+
+    pub fn with_stages(mut self, stages: &[StageTiming]) -> Self {
+        self.total_seconds = stages.iter().map(|s| s.seconds).sum();
+        self.stages = stages.to_vec();
+        self
+    }
+
+    pub fn save_to_file(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,59 @@
+use serde::{Deserialize, Serialize};
+
+/// Glob patterns selecting meshes by name: the names of the node placing a mesh, of its
+/// ancestor nodes and of the mesh itself are matched
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct NameFilter {
+    /// Keep only meshes with a name matching one of these; all meshes when empty
+    #[serde(default)]
+    pub include: Vec<String>,
+
+    /// Skip meshes with a name matching one of these, e.g. "Collision_*" proxies
+    #[serde(default)]
+    pub exclude: Vec<String>,
+}
+
+impl NameFilter {
+    pub fn is_empty(&self) -> bool {
+        self.include.is_empty() && self.exclude.is_empty()
+    }
+
+    /// Whether a mesh known by `names` is selected
+    pub fn accepts(&self, names: &[&str]) -> bool {
+        let matches = |patterns: &[String]| {
+            patterns.iter().any(|pattern| names.iter().any(|name| glob_match(pattern, name)))
+        };
+        (self.include.is_empty() || matches(&self.include)) && !matches(&self.exclude)
+    }
+}
+
+/// Case-sensitive glob match where `*` matches any run of characters and `?` any one character
+pub fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+
+    // Greedy matching, backtracking to the last '*' on a mismatch
+    let (mut p, mut n) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match star {
+                Some((star_p, star_n)) => {
+                    p = star_p + 1;
+                    n = star_n + 1;
+                    star = Some((star_p, star_n + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
@@ -0,0 +1,78 @@
+use crate::{animation, error::Result, parser};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// File name of the sequence index written next to JSON frames or into the EPT directory
+pub const SEQUENCE_FILE: &str = "sequence.json";
+
+/// Index of a point cloud sequence sampled from an animation, for playback in the viewer
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Sequence {
+    /// Animation the frames were posed with
+    pub animation: String,
+
+    pub frames: Vec<SequenceFrame>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SequenceFrame {
+    /// Animation time in seconds
+    pub time: f32,
+
+    /// Path of the frame's cloud relative to the sequence index, using '/' separators
+    pub path: String,
+}
+
+impl Sequence {
+    /// Plan `frames` evenly spaced frames from the first to the last keyframe of `animation`
+    /// in the glTF file at `path`. Frames written as JSON are numbered files beside `output`
+    /// ("walk.json" becomes "walk_0000.json"), EPT frames numbered subdirectories of it
+    pub fn plan(path: &Path, animation: &str, frames: usize, output: &Path, ept: bool) -> Result<Self> {
+        let (document, buffers, _, _) = parser::import_gltf(path)?;
+        let (start, end) = animation::time_range(&animation::find(&document, animation)?, &buffers);
+        let step = if frames > 1 { (end - start) / (frames - 1) as f32 } else { 0.0 };
+
+        Ok(Self {
+            animation: animation.to_string(),
+            frames: (0..frames)
+                .map(|frame| SequenceFrame {
+                    time: start + step * frame as f32,
+                    path: frame_name(output, frame, frames, ept),
+                })
+                .collect(),
+        })
+    }
+
+    /// Output path of `frame`
+    pub fn frame_path(&self, output: &Path, frame: usize, ept: bool) -> PathBuf {
+        let name = &self.frames[frame].path;
+        if ept {
+            output.join(name)
+        } else {
+            output.with_file_name(name)
+        }
+    }
+
+    /// Path of the sequence index for `output`
+    pub fn index_path(output: &Path, ept: bool) -> PathBuf {
+        if ept { output.join(SEQUENCE_FILE) } else { output.with_extension(SEQUENCE_FILE) }
+    }
+
+    pub fn save_to_file(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+/// Zero-padded frame file or directory name, numbered with at least four digits
+fn frame_name(output: &Path, frame: usize, frames: usize, ept: bool) -> String {
+    let width = frames.saturating_sub(1).to_string().len().max(4);
+    if ept {
+        return format!("frame_{:0width$}", frame);
+    }
+    let stem = output.file_stem().map(|s| s.to_string_lossy()).unwrap_or_default();
+    match output.extension() {
+        Some(extension) => format!("{}_{:0width$}.{}", stem, frame, extension.to_string_lossy()),
+        None => format!("{}_{:0width$}", stem, frame),
+    }
+}
@@ -0,0 +1,147 @@
+use crate::point_cloud::PointCloud;
+use glam::Vec3;
+use serde::{Deserialize, Serialize};
+
+/// A slab of space around a plane, used to cut cross-sections out of a cloud
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Slab {
+    /// Point on the center plane
+    pub origin: Vec3,
+
+    /// Unit normal of the center plane
+    pub normal: Vec3,
+
+    /// Full thickness along the normal; points within half of it on either side are kept
+    pub thickness: f32,
+}
+
+/// Points of a slab projected onto its plane
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Section {
+    /// Signed distance of the slab plane from the world origin along the normal
+    /// (the height of horizontal slices)
+    pub offset: f32,
+
+    /// In-plane coordinates along the slab's `u` and `v` axes
+    pub points: Vec<[f32; 2]>,
+
+    /// Index of each section point in the source cloud
+    pub indices: Vec<usize>,
+}
+
+impl Slab {
+    pub fn new(origin: Vec3, normal: Vec3, thickness: f32) -> Self {
+        Self {
+            origin,
+            normal: normal.normalize_or(Vec3::Y),
+            thickness: thickness.max(0.0),
+        }
+    }
+
+    /// Horizontal slab (Y up) centered at the given height; sections project onto X/Z,
+    /// as seen from above, which is what floorplans are drawn from
+    pub fn horizontal(height: f32, thickness: f32) -> Self {
+        Self::new(Vec3::new(0.0, height, 0.0), Vec3::Y, thickness)
+    }
+
+    /// In-plane axes `(u, v)`. Horizontal slabs use X and Z; other planes get an
+    /// orthonormal basis derived from the normal
+    pub fn axes(&self) -> (Vec3, Vec3) {
+        if self.normal.abs_diff_eq(Vec3::Y, 1e-6) {
+            (Vec3::X, Vec3::Z)
+        } else {
+            let u = self.normal.any_orthonormal_vector();
+            (u, self.normal.cross(u))
+        }
+    }
+
+    /// Signed distance of a position from the center plane
+    pub fn distance(&self, position: Vec3) -> f32 {
+        (position - self.origin).dot(self.normal)
+    }
+
+    pub fn contains(&self, position: Vec3) -> bool {
+        self.distance(position).abs() <= self.thickness * 0.5
+    }
+
+    /// Extract the points inside the slab, projected onto the plane
+    pub fn extract(&self, point_cloud: &PointCloud) -> Section {
+        let (u, v) = self.axes();
+        let mut section = Section {
+            offset: self.origin.dot(self.normal),
+            ..Default::default()
+        };
+
+        for (index, point) in point_cloud.points.iter().enumerate() {
+            let position = Vec3::from(point.position);
+            if self.contains(position) {
+                let relative = position - self.origin;
+                section.points.push([relative.dot(u), relative.dot(v)]);
+                section.indices.push(index);
+            }
+        }
+
+        section
+    }
+}
+
+impl Section {
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    /// 2D bounding box as `([min_u, min_v], [max_u, max_v])`, `None` when empty
+    pub fn bounds(&self) -> Option<([f32; 2], [f32; 2])> {
+        let first = *self.points.first()?;
+        Some(self.points.iter().fold((first, first), |(min, max), p| {
+            ([min[0].min(p[0]), min[1].min(p[1])], [max[0].max(p[0]), max[1].max(p[1])])
+        }))
+    }
+
+    pub fn save_to_file(&self, path: &std::path::Path) -> crate::error::Result<()> {
+        let json = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, json)?;
+        Ok(())
+    }
+}
+
+/// Cut a stack of horizontal slices every `spacing` units over the cloud's height, starting
+/// half a spacing above the bottom. Empty slices are kept so indices map to heights
+pub fn horizontal_slices(point_cloud: &PointCloud, spacing: f32, thickness: f32) -> Vec<Section> {
+    if point_cloud.points.is_empty() || spacing <= 0.0 {
+        return Vec::new();
+    }
+
+    let min = point_cloud.metadata.bounds_min[1];
+    let max = point_cloud.metadata.bounds_max[1];
+    let count = (((max - min) / spacing).floor() as usize).max(1);
+
+    // Bucket points by slice instead of scanning the cloud once per slab
+    let half = thickness.max(0.0) * 0.5;
+    let heights: Vec<f32> = (0..count).map(|i| min + spacing * (i as f32 + 0.5)).collect();
+    let mut sections: Vec<Section> = heights
+        .iter()
+        .map(|&height| Section { offset: height, ..Default::default() })
+        .collect();
+
+    for (index, point) in point_cloud.points.iter().enumerate() {
+        let [x, y, z] = point.position;
+        let first = ((y - half - min) / spacing - 0.5).ceil().max(0.0) as usize;
+        let last = (((y + half - min) / spacing - 0.5).floor().max(-1.0) + 1.0) as usize;
+        for (height, section) in heights[first.min(count)..last.min(count)]
+            .iter()
+            .zip(&mut sections[first.min(count)..last.min(count)])
+        {
+            if (y - height).abs() <= half {
+                section.points.push([x, z]);
+                section.indices.push(index);
+            }
+        }
+    }
+
+    sections
+}
@@ -0,0 +1,211 @@
+use glam::Vec3;
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+const LEAF_SIZE: usize = 16;
+
+/// Static k-d tree over a set of positions for nearest-neighbour queries
+pub struct KdTree {
+    positions: Vec<Vec3>,
+    /// Point indices, permuted so every node owns a contiguous range
+    indices: Vec<usize>,
+    nodes: Vec<KdNode>,
+}
+
+enum KdNode {
+    Leaf {
+        start: usize,
+        end: usize,
+    },
+    Split {
+        axis: usize,
+        value: f32,
+        left: usize,
+        right: usize,
+    },
+}
+
+/// Max-heap entry used to keep the k best candidates
+#[derive(PartialEq)]
+struct Candidate {
+    distance_sq: f32,
+    index: usize,
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.distance_sq
+            .total_cmp(&other.distance_sq)
+            .then(self.index.cmp(&other.index))
+    }
+}
+
+impl KdTree {
+    pub fn new(positions: Vec<Vec3>) -> Self {
+        let mut tree = Self {
+            indices: (0..positions.len()).collect(),
+            positions,
+            nodes: Vec::new(),
+        };
+
+        if !tree.positions.is_empty() {
+            let len = tree.indices.len();
+            tree.build(0, len);
+        }
+
+        tree
+    }
+
+    pub fn from_points(points: &[crate::point_cloud::Point]) -> Self {
+        Self::new(points.iter().map(|p| Vec3::from(p.position)).collect())
+    }
+
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    pub fn position(&self, index: usize) -> Vec3 {
+        self.positions[index]
+    }
+
+    fn build(&mut self, start: usize, end: usize) -> usize {
+        let node_index = self.nodes.len();
+
+        if end - start <= LEAF_SIZE {
+            self.nodes.push(KdNode::Leaf { start, end });
+            return node_index;
+        }
+
+        // Split along the axis with the largest extent
+        let (min, max) = self.indices[start..end].iter().fold(
+            (Vec3::splat(f32::MAX), Vec3::splat(f32::MIN)),
+            |(min, max), &i| (min.min(self.positions[i]), max.max(self.positions[i])),
+        );
+        let extent = max - min;
+        let axis = if extent.x >= extent.y && extent.x >= extent.z {
+            0
+        } else if extent.y >= extent.z {
+            1
+        } else {
+            2
+        };
+
+        let mid = (start + end) / 2;
+        let positions = &self.positions;
+        self.indices[start..end].select_nth_unstable_by(mid - start, |&a, &b| {
+            positions[a][axis].total_cmp(&positions[b][axis])
+        });
+        let value = self.positions[self.indices[mid]][axis];
+
+        // Reserve the slot, then fill in children
+        self.nodes.push(KdNode::Leaf { start, end });
+        let left = self.build(start, mid);
+        let right = self.build(mid, end);
+        self.nodes[node_index] = KdNode::Split {
+            axis,
+            value,
+            left,
+            right,
+        };
+
+        node_index
+    }
+
+    /// The `k` nearest points to `query` as (index, distance) pairs, closest first
+    pub fn nearest(&self, query: Vec3, k: usize) -> Vec<(usize, f32)> {
+        if self.is_empty() || k == 0 {
+            return Vec::new();
+        }
+
+        let mut heap = BinaryHeap::with_capacity(k + 1);
+        self.nearest_recursive(0, query, k, &mut heap);
+
+        let mut result: Vec<_> = heap
+            .into_iter()
+            .map(|c| (c.index, c.distance_sq.sqrt()))
+            .collect();
+        result.sort_by(|a, b| a.1.total_cmp(&b.1));
+        result
+    }
+
+    fn nearest_recursive(&self, node: usize, query: Vec3, k: usize, heap: &mut BinaryHeap<Candidate>) {
+        match self.nodes[node] {
+            KdNode::Leaf { start, end } => {
+                for &index in &self.indices[start..end] {
+                    let distance_sq = self.positions[index].distance_squared(query);
+                    if heap.len() < k {
+                        heap.push(Candidate { distance_sq, index });
+                    } else if heap.peek().is_some_and(|worst| distance_sq < worst.distance_sq) {
+                        heap.pop();
+                        heap.push(Candidate { distance_sq, index });
+                    }
+                }
+            }
+            KdNode::Split {
+                axis,
+                value,
+                left,
+                right,
+            } => {
+                let delta = query[axis] - value;
+                let (near, far) = if delta < 0.0 { (left, right) } else { (right, left) };
+
+                self.nearest_recursive(near, query, k, heap);
+
+                let must_visit_far = heap.len() < k
+                    || heap.peek().is_some_and(|worst| delta * delta < worst.distance_sq);
+                if must_visit_far {
+                    self.nearest_recursive(far, query, k, heap);
+                }
+            }
+        }
+    }
+
+    /// Indices of all points within `radius` of `query`
+    pub fn within_radius(&self, query: Vec3, radius: f32) -> Vec<usize> {
+        let mut result = Vec::new();
+        if !self.is_empty() {
+            self.radius_recursive(0, query, radius * radius, &mut result);
+        }
+        result
+    }
+
+    fn radius_recursive(&self, node: usize, query: Vec3, radius_sq: f32, result: &mut Vec<usize>) {
+        match self.nodes[node] {
+            KdNode::Leaf { start, end } => {
+                result.extend(
+                    self.indices[start..end]
+                        .iter()
+                        .copied()
+                        .filter(|&i| self.positions[i].distance_squared(query) <= radius_sq),
+                );
+            }
+            KdNode::Split {
+                axis,
+                value,
+                left,
+                right,
+            } => {
+                let delta = query[axis] - value;
+                if delta <= 0.0 || delta * delta <= radius_sq {
+                    self.radius_recursive(left, query, radius_sq, result);
+                }
+                if delta >= 0.0 || delta * delta <= radius_sq {
+                    self.radius_recursive(right, query, radius_sq, result);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,116 @@
+use crate::{
+    config::PointCloudConfig,
+    error::{ModelParserError, Result},
+    mesh::Mesh,
+};
+use glam::Vec3;
+use std::path::Path;
+
+/// Size of the binary STL header and triangle count
+const HEADER_SIZE: usize = 84;
+
+/// Size of one binary STL triangle record: normal, three vertices and an attribute word
+const TRIANGLE_SIZE: usize = 50;
+
+/// Load a binary or ASCII STL file. Triangles don't share vertices, so every sample gets
+/// the flat normal of its facet, computed from the winding as stored normals are often unset
+pub fn load(path: &Path, config: &PointCloudConfig) -> Result<Mesh> {
+    let bytes = std::fs::read(path)?;
+    let triangles = if is_binary(&bytes) {
+        read_binary(&bytes)
+    } else {
+        read_ascii(&String::from_utf8_lossy(&bytes), path)?
+    };
+
+    if triangles.is_empty() {
+        return Err(ModelParserError::NoMeshData);
+    }
+
+    let mut mesh = Mesh::new();
+    for (stored_normal, corners) in triangles {
+        let computed = (corners[1] - corners[0]).cross(corners[2] - corners[0]).normalize_or_zero();
+        let normal = if computed == Vec3::ZERO {
+            stored_normal.normalize_or_zero()
+        } else {
+            computed
+        };
+
+        let first = mesh.vertices.len();
+        mesh.vertices.extend(corners);
+        mesh.indices.extend(first..first + 3);
+        if config.include_normals {
+            mesh.normals.extend([normal; 3]);
+        }
+    }
+
+    if config.include_colors {
+        mesh.colors = vec![Vec3::ONE; mesh.vertices.len()];
+    }
+
+    Ok(mesh)
+}
+
+/// ASCII files start with "solid", but so do some binary headers: trust the size check first
+fn is_binary(bytes: &[u8]) -> bool {
+    if bytes.len() >= HEADER_SIZE {
+        let count = u32::from_le_bytes([bytes[80], bytes[81], bytes[82], bytes[83]]) as usize;
+        if HEADER_SIZE + count * TRIANGLE_SIZE == bytes.len() {
+            return true;
+        }
+    }
+    !bytes.trim_ascii_start().starts_with(b"solid")
+}
+
+fn read_binary(bytes: &[u8]) -> Vec<(Vec3, [Vec3; 3])> {
+    let vec3 = |record: &[u8], offset: usize| {
+        let value = |i: usize| {
+            let at = offset + i * 4;
+            f32::from_le_bytes([record[at], record[at + 1], record[at + 2], record[at + 3]])
+        };
+        Vec3::new(value(0), value(1), value(2))
+    };
+
+    // Truncated files keep their complete records
+    bytes
+        .get(HEADER_SIZE..)
+        .unwrap_or_default()
+        .chunks_exact(TRIANGLE_SIZE)
+        .map(|record| (vec3(record, 0), [vec3(record, 12), vec3(record, 24), vec3(record, 36)]))
+        .collect()
+}
+
+fn read_ascii(text: &str, path: &Path) -> Result<Vec<(Vec3, [Vec3; 3])>> {
+    let mut triangles = Vec::new();
+    let mut normal = Vec3::ZERO;
+    let mut corners = Vec::with_capacity(3);
+
+    for (line_number, line) in text.lines().enumerate() {
+        let mut parts = line.split_whitespace();
+        let invalid = || {
+            ModelParserError::InvalidData(format!("{}:{}: invalid STL line '{}'", path.display(), line_number + 1, line.trim()))
+        };
+        let read_vec3 = |parts: &mut std::str::SplitWhitespace| -> Result<Vec3> {
+            let mut value = || parts.next().and_then(|v| v.parse::<f32>().ok()).ok_or_else(invalid);
+            Ok(Vec3::new(value()?, value()?, value()?))
+        };
+
+        match parts.next() {
+            Some("facet") => {
+                // "facet normal nx ny nz"
+                parts.next();
+                normal = read_vec3(&mut parts)?;
+                corners.clear();
+            }
+            Some("vertex") => corners.push(read_vec3(&mut parts)?),
+            Some("endfacet") => {
+                if corners.len() != 3 {
+                    return Err(invalid());
+                }
+                triangles.push((normal, [corners[0], corners[1], corners[2]]));
+            }
+            _ => {}
+        }
+    }
+
+    Ok(triangles)
+}
@@ -0,0 +1,300 @@
+use crate::{
+    error::{ModelParserError, Result},
+    manifest::{self, Manifest, ManifestEntry},
+};
+use futures::{StreamExt, TryStreamExt};
+use object_store::{PutPayload, WriteMultipart, aws::AmazonS3Builder, gcp::GoogleCloudStorageBuilder, path::Path as ObjectPath};
+use parking_lot::Mutex;
+use std::path::Path;
+use std::sync::Arc;
+use tokio::io::AsyncReadExt;
+
+/// Files at least this large are sent as multipart uploads
+const MULTIPART_THRESHOLD: u64 = 16 * 1024 * 1024;
+
+/// Size of each part of a multipart upload
+const PART_SIZE: usize = 8 * 1024 * 1024;
+
+/// Parts of one multipart upload in flight at once
+const PART_JOBS: usize = 4;
+
+/// Object storage service of a destination URL
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Provider {
+    /// Amazon S3 (`s3://`)
+    S3,
+
+    /// Google Cloud Storage (`gs://`)
+    Gcs,
+}
+
+/// An object storage location such as `s3://bucket/prefix`.
+///
+/// Uploads go straight to the service's API with credentials from the environment
+/// (`AWS_ACCESS_KEY_ID`/`AWS_SECRET_ACCESS_KEY`/`AWS_REGION` or instance metadata for S3,
+/// `GOOGLE_APPLICATION_CREDENTIALS` for GCS). Large files are split into parts uploaded in
+/// parallel, and files are uploaded concurrently on top of that
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ObjectStore {
+    pub provider: Provider,
+    pub bucket: String,
+
+    /// Object key or key prefix, without leading or trailing '/'
+    pub key: String,
+}
+
+impl ObjectStore {
+    /// Parse an `s3://` or `gs://` URL; `None` for anything else (e.g. local paths)
+    pub fn parse(url: &str) -> Option<Self> {
+        let (provider, rest) = if let Some(rest) = url.strip_prefix("s3://") {
+            (Provider::S3, rest)
+        } else if let Some(rest) = url.strip_prefix("gs://") {
+            (Provider::Gcs, rest)
+        } else {
+            return None;
+        };
+
+        let (bucket, key) = rest.split_once('/').unwrap_or((rest, ""));
+        if bucket.is_empty() {
+            return None;
+        }
+        Some(Self {
+            provider,
+            bucket: bucket.to_string(),
+            key: key.trim_matches('/').to_string(),
+        })
+    }
+
+    /// Last key segment, e.g. the file name of a single-object destination
+    pub fn name(&self) -> &str {
+        self.key.rsplit('/').next().unwrap_or("")
+    }
+
+    /// Location of the key's parent "directory"
+    pub fn parent(&self) -> Self {
+        Self {
+            key: self.key.rsplit_once('/').map_or("", |(parent, _)| parent).to_string(),
+            ..self.clone()
+        }
+    }
+
+    /// URL of an object below this location
+    pub fn url(&self, relative: &str) -> String {
+        let scheme = match self.provider {
+            Provider::S3 => "s3",
+            Provider::Gcs => "gs",
+        };
+        format!("{}://{}/{}", scheme, self.bucket, self.object_key(relative))
+    }
+
+    /// Key of an object below this location
+    fn object_key(&self, relative: &str) -> String {
+        [self.key.as_str(), relative.trim_start_matches('/')]
+            .iter()
+            .filter(|part| !part.is_empty())
+            .copied()
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    /// Client for the bucket, configured from the environment
+    fn client(&self) -> Result<Arc<dyn object_store::ObjectStore>> {
+        let client: Arc<dyn object_store::ObjectStore> = match self.provider {
+            Provider::S3 => Arc::new(
+                AmazonS3Builder::from_env()
+                    .with_bucket_name(&self.bucket)
+                    .build()
+                    .map_err(|e| ModelParserError::UploadError(e.to_string()))?,
+            ),
+            Provider::Gcs => Arc::new(
+                GoogleCloudStorageBuilder::from_env()
+                    .with_bucket_name(&self.bucket)
+                    .build()
+                    .map_err(|e| ModelParserError::UploadError(e.to_string()))?,
+            ),
+        };
+        Ok(client)
+    }
+
+    /// Upload every file below `dir`, keeping relative paths, with up to `jobs` uploads at
+    /// once. Returns the uploaded URLs
+    pub fn upload_directory(&self, dir: &Path, jobs: usize) -> Result<Vec<String>> {
+        let mut files = Vec::new();
+        manifest::collect_files(dir, &mut files)?;
+
+        let client = self.client()?;
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()
+            .map_err(|e| ModelParserError::UploadError(e.to_string()))?;
+        runtime.block_on(
+            futures::stream::iter(&files)
+                .map(|file| {
+                    let client = client.as_ref();
+                    async move {
+                        let relative = file.strip_prefix(dir).unwrap_or(file);
+                        let relative = relative
+                            .components()
+                            .map(|c| c.as_os_str().to_string_lossy())
+                            .collect::<Vec<_>>()
+                            .join("/");
+                        let url = self.url(&relative);
+                        upload_file(client, file, &ObjectPath::from(self.object_key(&relative)))
+                            .await
+                            .map_err(|e| ModelParserError::UploadError(format!("{} -> {}: {}", file.display(), url, e)))?;
+                        Ok(url)
+                    }
+                })
+                .buffer_unordered(jobs.max(1))
+                .try_collect(),
+        )
+    }
+}
+
+/// Writes objects below a location as they are produced, so outputs such as EPT tiles go
+/// straight to the bucket without a local copy. `put` may be called from any thread; up to
+/// `jobs` transfers run at once
+pub struct Uploader {
+    store: ObjectStore,
+    client: Arc<dyn object_store::ObjectStore>,
+    runtime: tokio::runtime::Runtime,
+    permits: tokio::sync::Semaphore,
+
+    /// Size and checksum of every object written, for the manifest
+    entries: Mutex<Vec<ManifestEntry>>,
+}
+
+impl Uploader {
+    pub fn new(store: &ObjectStore, jobs: usize) -> Result<Self> {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()
+            .map_err(|e| ModelParserError::UploadError(e.to_string()))?;
+        Ok(Self {
+            store: store.clone(),
+            client: store.client()?,
+            runtime,
+            permits: tokio::sync::Semaphore::new(jobs.max(1)),
+            entries: Mutex::new(Vec::new()),
+        })
+    }
+
+    /// Location the objects are written below
+    pub fn store(&self) -> &ObjectStore {
+        &self.store
+    }
+
+    /// Write `data` to the object at `relative`, blocking until it is stored
+    pub fn put(&self, relative: &str, data: Vec<u8>) -> Result<()> {
+        let entry = ManifestEntry {
+            path: relative.to_string(),
+            size: data.len() as u64,
+            sha256: manifest::sha256_hex(&data),
+        };
+        let location = ObjectPath::from(self.store.object_key(relative));
+        self.runtime
+            .block_on(async {
+                let _permit = self.permits.acquire().await.map_err(|e| e.to_string())?;
+                upload_bytes(self.client.as_ref(), data, &location).await
+            })
+            .map_err(|e| ModelParserError::UploadError(format!("{}: {}", self.store.url(relative), e)))?;
+        self.entries.lock().push(entry);
+        Ok(())
+    }
+
+    /// Number of objects written so far
+    pub fn len(&self) -> usize {
+        self.entries.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.lock().is_empty()
+    }
+
+    /// Manifest of the objects written so far
+    pub fn manifest(&self) -> Manifest {
+        let mut files = self.entries.lock().clone();
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+        Manifest { files }
+    }
+}
+
+/// Upload `data` to `location`, in parallel parts when it is large. A failed multipart
+/// upload is aborted so the service discards the parts already sent
+async fn upload_bytes(
+    client: &dyn object_store::ObjectStore,
+    data: Vec<u8>,
+    location: &ObjectPath,
+) -> std::result::Result<(), String> {
+    if (data.len() as u64) < MULTIPART_THRESHOLD {
+        client.put(location, PutPayload::from(data)).await.map_err(|e| e.to_string())?;
+        return Ok(());
+    }
+
+    let upload = client.put_multipart(location).await.map_err(|e| e.to_string())?;
+    let mut writer = WriteMultipart::new_with_chunk_size(upload, PART_SIZE);
+    for part in data.chunks(PART_SIZE) {
+        if let Err(e) = writer.wait_for_capacity(PART_JOBS).await {
+            let _ = writer.abort().await;
+            return Err(e.to_string());
+        }
+        writer.write(part);
+    }
+    writer.finish().await.map(|_| ()).map_err(|e| e.to_string())
+}
+
+/// Upload one file to `location`, in parallel parts when it is large. A failed multipart
+/// upload is aborted so the service discards the parts already sent
+async fn upload_file(
+    client: &dyn object_store::ObjectStore,
+    file: &Path,
+    location: &ObjectPath,
+) -> std::result::Result<(), String> {
+    let size = tokio::fs::metadata(file).await.map_err(|e| e.to_string())?.len();
+    if size < MULTIPART_THRESHOLD {
+        let bytes = tokio::fs::read(file).await.map_err(|e| e.to_string())?;
+        return upload_bytes(client, bytes, location).await;
+    }
+
+    let upload = client.put_multipart(location).await.map_err(|e| e.to_string())?;
+    let mut writer = WriteMultipart::new_with_chunk_size(upload, PART_SIZE);
+    let copied: std::result::Result<(), String> = async {
+        let mut reader = tokio::fs::File::open(file).await.map_err(|e| e.to_string())?;
+        let mut buffer = vec![0; PART_SIZE];
+        loop {
+            let read = reader.read(&mut buffer).await.map_err(|e| e.to_string())?;
+            if read == 0 {
+                return Ok(());
+            }
+            writer.wait_for_capacity(PART_JOBS).await.map_err(|e| e.to_string())?;
+            writer.write(&buffer[..read]);
+        }
+    }
+    .await;
+    match copied {
+        Ok(()) => writer.finish().await.map(|_| ()).map_err(|e| e.to_string()),
+        Err(e) => {
+            let _ = writer.abort().await;
+            Err(e)
+        }
+    }
+}
+
+/// Local directory for outputs that are uploaded after they are written. It gets a fresh,
+/// randomly named directory readable only by its owner, so no other local user can plant
+/// files in it, and it is removed when dropped, so failed runs don't leave it behind
+#[derive(Debug)]
+pub struct Staging {
+    dir: tempfile::TempDir,
+}
+
+impl Staging {
+    /// Create a new staging directory below the system temp directory
+    pub fn new() -> Result<Self> {
+        Ok(Self { dir: tempfile::Builder::new().prefix("model_parser-").tempdir()? })
+    }
+
+    pub fn path(&self) -> &Path {
+        self.dir.path()
+    }
+}
@@ -0,0 +1,76 @@
+use crate::{
+    color::{self, ColorSpace},
+    point_cloud::PointCloud,
+    random,
+};
+use glam::Vec3;
+use rand::Rng;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Artistic per-point randomization for stylized "particle" looks
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Stylize {
+    /// Largest tilt of each normal away from its original direction, in degrees
+    pub normal_jitter: f32,
+
+    /// Largest hue shift as a fraction of the color wheel (0.0-0.5)
+    pub hue_jitter: f32,
+
+    /// Largest relative brightness change (0.0-1.0)
+    pub value_jitter: f32,
+}
+
+impl Stylize {
+    pub fn is_active(&self) -> bool {
+        self.normal_jitter > 0.0 || self.hue_jitter > 0.0 || self.value_jitter > 0.0
+    }
+}
+
+/// Tilt normals uniformly within a cone and shift hue and brightness of colors; colors must
+/// still be linear. Hue and value are varied in sRGB so the noise is perceptually even
+pub fn apply(point_cloud: &mut PointCloud, style: &Stylize, seed: Option<u64>) {
+    if !style.is_active() {
+        return;
+    }
+
+    let min_cosine = style.normal_jitter.clamp(0.0, 180.0).to_radians().cos();
+    let hue_jitter = style.hue_jitter.clamp(0.0, 0.5);
+    let value_jitter = style.value_jitter.clamp(0.0, 1.0);
+
+    point_cloud
+        .points
+        .par_chunks_mut(random::CHUNK_SIZE)
+        .enumerate()
+        .for_each(|(chunk, points)| {
+            let mut rng = random::chunk_rng(seed, random::stream::STYLIZE, chunk as u64);
+            for point in points {
+                if style.normal_jitter > 0.0
+                    && let Some(normal) = point
+                        .normal
+                        .map(Vec3::from)
+                        .filter(|n| n.is_finite() && n.length_squared() > 0.0)
+                {
+                    // Uniform over the spherical cap: cosine of the tilt is uniform
+                    let normal = normal.normalize();
+                    let cosine = 1.0 - rng.random::<f32>() * (1.0 - min_cosine);
+                    let sine = (1.0 - cosine * cosine).max(0.0).sqrt();
+                    let angle = rng.random::<f32>() * std::f32::consts::TAU;
+                    let (u, v) = normal.any_orthonormal_pair();
+                    let tilted = normal * cosine + (u * angle.cos() + v * angle.sin()) * sine;
+                    point.normal = Some(tilted.normalize_or_zero().to_array());
+                }
+
+                if (hue_jitter > 0.0 || value_jitter > 0.0)
+                    && let Some(linear) = point.color.map(Vec3::from)
+                {
+                    let srgb = color::convert(linear, ColorSpace::Linear, ColorSpace::Srgb);
+                    let [hue, saturation, value] = color::rgb_to_hsv(srgb);
+                    let hue = (hue + rng.random_range(-1.0..=1.0) * hue_jitter).rem_euclid(1.0);
+                    let value = (value * (1.0 + rng.random_range(-1.0..=1.0) * value_jitter)).clamp(0.0, 1.0);
+                    let jittered = color::hsv_to_rgb([hue, saturation, value]);
+                    point.color = Some(color::convert(jittered, ColorSpace::Srgb, ColorSpace::Linear).to_array());
+                }
+            }
+        });
+}
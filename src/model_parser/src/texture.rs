@@ -0,0 +1,271 @@
+use crate::{
+    color,
+    error::{ModelParserError, Result},
+};
+use glam::{Vec2, Vec3, Vec4};
+use std::cell::OnceCell;
+use std::sync::{Arc, OnceLock};
+
+/// Decoded texture with linear-light RGB texels and their alpha, sampled with repeat wrapping
+#[derive(Debug, Clone)]
+pub struct Texture {
+    pub width: usize,
+    pub height: usize,
+
+    /// Whether the image has an alpha channel; opaque images sample an alpha of 1
+    pub has_alpha: bool,
+    base: Level,
+
+    /// Box-filtered mip levels below the full resolution image, built on first use
+    mips: OnceLock<Vec<Level>>,
+}
+
+/// One reduced-resolution copy of a texture
+#[derive(Debug, Clone)]
+struct Level {
+    width: usize,
+    height: usize,
+    pixels: Vec<Vec4>,
+}
+
+impl Level {
+    fn texel(&self, x: i64, y: i64) -> Vec4 {
+        let x = x.rem_euclid(self.width as i64) as usize;
+        let y = y.rem_euclid(self.height as i64) as usize;
+        self.pixels[y * self.width + x]
+    }
+
+    fn sample(&self, uv: Vec2) -> Vec4 {
+        let x = uv.x * self.width as f32 - 0.5;
+        let y = uv.y * self.height as f32 - 0.5;
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+        let (x0, y0) = (x0 as i64, y0 as i64);
+
+        let top = self.texel(x0, y0).lerp(self.texel(x0 + 1, y0), fx);
+        let bottom = self.texel(x0, y0 + 1).lerp(self.texel(x0 + 1, y0 + 1), fx);
+        top.lerp(bottom, fy)
+    }
+
+    /// Half-resolution copy averaging 2x2 blocks (edge texels repeat on odd sizes)
+    fn reduce(&self) -> Level {
+        let width = (self.width / 2).max(1);
+        let height = (self.height / 2).max(1);
+        let at = |x: usize, y: usize| self.pixels[y.min(self.height - 1) * self.width + x.min(self.width - 1)];
+        let pixels = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x * 2, y * 2)))
+            .map(|(x, y)| (at(x, y) + at(x + 1, y) + at(x, y + 1) + at(x + 1, y + 1)) * 0.25)
+            .collect();
+        Level { width, height, pixels }
+    }
+}
+
+impl Texture {
+    /// Convert a decoded glTF image; color textures are sRGB encoded and converted to linear
+    pub fn from_gltf_image(image: &gltf::image::Data, srgb: bool) -> Option<Self> {
+        use gltf::image::Format;
+
+        let (channels, bytes_per_channel) = match image.format {
+            Format::R8 => (1, 1),
+            Format::R8G8 => (2, 1),
+            Format::R8G8B8 => (3, 1),
+            Format::R8G8B8A8 => (4, 1),
+            Format::R16 => (1, 2),
+            Format::R16G16 => (2, 2),
+            Format::R16G16B16 => (3, 2),
+            Format::R16G16B16A16 => (4, 2),
+            Format::R32G32B32FLOAT => (3, 4),
+            Format::R32G32B32A32FLOAT => (4, 4),
+        };
+
+        let stride = channels * bytes_per_channel;
+        let width = image.width as usize;
+        let height = image.height as usize;
+        if width == 0 || height == 0 || image.pixels.len() < width * height * stride {
+            return None;
+        }
+
+        let channel = |texel: &[u8], c: usize| -> f32 {
+            let bytes = &texel[c * bytes_per_channel..(c + 1) * bytes_per_channel];
+            match bytes_per_channel {
+                1 => bytes[0] as f32 / 255.0,
+                2 => u16::from_le_bytes([bytes[0], bytes[1]]) as f32 / 65535.0,
+                _ => f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+            }
+        };
+
+        let pixels = image
+            .pixels
+            .chunks_exact(stride)
+            .take(width * height)
+            .map(|texel| {
+                // Grayscale images replicate red; two-channel images are luminance + alpha
+                let (rgb, alpha) = match channels {
+                    1 => (Vec3::splat(channel(texel, 0)), 1.0),
+                    2 => (Vec3::splat(channel(texel, 0)), channel(texel, 1)),
+                    3 => (Vec3::new(channel(texel, 0), channel(texel, 1), channel(texel, 2)), 1.0),
+                    _ => (Vec3::new(channel(texel, 0), channel(texel, 1), channel(texel, 2)), channel(texel, 3)),
+                };
+                // Alpha is linear coverage and is never sRGB encoded
+                let rgb = if srgb && bytes_per_channel < 4 {
+                    Vec3::new(
+                        color::srgb_to_linear(rgb.x),
+                        color::srgb_to_linear(rgb.y),
+                        color::srgb_to_linear(rgb.z),
+                    )
+                } else {
+                    rgb
+                };
+                rgb.extend(alpha)
+            })
+            .collect();
+
+        Some(Self {
+            width,
+            height,
+            has_alpha: channels == 2 || channels == 4,
+            base: Level { width, height, pixels },
+            mips: OnceLock::new(),
+        })
+    }
+
+    /// Bilinear sample at `uv` (glTF convention: origin at the top-left texel)
+    pub fn sample(&self, uv: Vec2) -> Vec3 {
+        self.sample_level(uv, 0.0)
+    }
+
+    /// Trilinear sample at `uv` from mip level `lod` (0 is full resolution, each level halves it)
+    pub fn sample_level(&self, uv: Vec2, lod: f32) -> Vec3 {
+        self.sample_rgba(uv, lod).truncate()
+    }
+
+    /// Trilinear sample of the alpha channel, like `sample_level`
+    pub fn sample_alpha(&self, uv: Vec2, lod: f32) -> f32 {
+        if self.has_alpha { self.sample_rgba(uv, lod).w } else { 1.0 }
+    }
+
+    fn sample_rgba(&self, uv: Vec2, lod: f32) -> Vec4 {
+        if !uv.is_finite() {
+            return self.base.pixels[0];
+        }
+
+        let lod = if lod.is_finite() { lod.clamp(0.0, self.max_lod()) } else { 0.0 };
+        let lower = lod.floor();
+        let fraction = lod - lower;
+        let lower = lower as usize;
+        let sample = |index: usize| match index {
+            0 => self.base.sample(uv),
+            _ => self.mips()[index - 1].sample(uv),
+        };
+
+        if fraction > 0.0 {
+            sample(lower).lerp(sample(lower + 1), fraction)
+        } else {
+            sample(lower)
+        }
+    }
+
+    /// Mip level whose texels best match a footprint of `uv_area` in texture space (the UV
+    /// area one sample stands for), so minified textures are averaged instead of aliased
+    pub fn lod(&self, uv_area: f32) -> f32 {
+        let texels = uv_area * (self.width * self.height) as f32;
+        if texels > 1.0 { (0.5 * texels.log2()).min(self.max_lod()) } else { 0.0 }
+    }
+
+    fn max_lod(&self) -> f32 {
+        self.width.max(self.height).ilog2() as f32
+    }
+
+    fn mips(&self) -> &[Level] {
+        self.mips.get_or_init(|| {
+            let mut levels: Vec<Level> = Vec::new();
+            let mut current = self.base.reduce();
+            loop {
+                let done = current.width == 1 && current.height == 1;
+                levels.push(current);
+                if done {
+                    break levels;
+                }
+                current = levels[levels.len() - 1].reduce();
+            }
+        })
+    }
+}
+
+/// Signature of a KTX2 container
+const KTX2_MAGIC: &[u8] = b"\xABKTX 20\xBB\r\n\x1A\n";
+
+/// Whether `bytes` hold a KTX2 container, as `KHR_texture_basisu` images do
+pub fn is_ktx2(bytes: &[u8]) -> bool {
+    bytes.starts_with(KTX2_MAGIC)
+}
+
+/// Transcode the full-resolution level of a Basis Universal (ETC1S or UASTC) KTX2 texture
+/// to 8-bit RGBA, keeping its encoding (sRGB for color textures)
+pub fn decode_ktx2(bytes: &[u8]) -> Result<gltf::image::Data> {
+    let invalid = |error: basisu::Error| ModelParserError::InvalidData(format!("KTX2 texture: {:?}", error));
+    let transcoder = basisu::Transcoder::new(bytes).map_err(invalid)?;
+    let (width, height) = transcoder.base_dimensions();
+    let pixels = transcoder
+        .transcode(0, basisu::TargetFormat::Rgba32, basisu::DecodeFlags::NONE)
+        .map_err(invalid)?;
+    Ok(gltf::image::Data {
+        pixels,
+        format: gltf::image::Format::R8G8B8A8,
+        width,
+        height,
+    })
+}
+
+/// Textures of a glTF document, decoded on first use so images no material samples are
+/// never converted and each shared image is converted once
+pub struct TextureCache<'a> {
+    images: &'a [gltf::image::Data],
+    textures: Vec<OnceCell<Option<Arc<Texture>>>>,
+}
+
+impl<'a> TextureCache<'a> {
+    pub fn new(images: &'a [gltf::image::Data]) -> Self {
+        Self {
+            images,
+            textures: images.iter().map(|_| OnceCell::new()).collect(),
+        }
+    }
+
+    /// Color texture decoded from image `index`
+    pub fn get(&self, index: usize) -> Option<Arc<Texture>> {
+        let image = self.images.get(index)?;
+        self.textures[index]
+            .get_or_init(|| Texture::from_gltf_image(image, true).map(Arc::new))
+            .clone()
+    }
+}
+
+/// UV area each vertex color stands for: the larger of one sample's surface area and the
+/// vertex's share of its triangles, scaled by the local UV stretch. `triangles` index
+/// `positions`/`uvs` offset by `base`
+pub fn uv_footprints(positions: &[Vec3], uvs: &[Vec2], triangles: &[usize], base: usize, sample_area: f32) -> Vec<f32> {
+    let mut areas = vec![(0.0f32, 0.0f32); uvs.len()];
+    for triangle in triangles.chunks_exact(3) {
+        let corners = [0, 1, 2].map(|i| triangle[i].wrapping_sub(base));
+        if corners.iter().any(|&i| i >= uvs.len() || i >= positions.len()) {
+            continue;
+        }
+
+        let [p0, p1, p2] = corners.map(|i| positions[i]);
+        let [t0, t1, t2] = corners.map(|i| uvs[i]);
+        let world = (p1 - p0).cross(p2 - p0).length() * 0.5;
+        let uv = (t1 - t0).perp_dot(t2 - t0).abs() * 0.5;
+        for i in corners {
+            areas[i].0 += world;
+            areas[i].1 += uv;
+        }
+    }
+
+    areas
+        .into_iter()
+        .map(|(world, uv)| {
+            if world > 0.0 { uv / world * sample_area.max(world / 3.0) } else { 0.0 }
+        })
+        .collect()
+}
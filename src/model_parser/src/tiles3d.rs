@@ -0,0 +1,233 @@
+use crate::{
+    attributes::AttributeType,
+    budget::SizeEstimate,
+    color::{self, ColorSpace},
+    composite::{TILESET_FILE, bounds_box},
+    ept::{EptBuildReport, EptBuilder, LevelAverages, OctreeKey},
+    error::Result,
+    point_cloud::{Point, PointCloud},
+};
+use rayon::prelude::*;
+use serde_json::{Map, Value, json};
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+/// Directory of the `.pnts` tiles, next to `tileset.json`
+pub const TILES_DIR: &str = "tiles";
+
+/// Size of the `.pnts` header
+const PNTS_HEADER_SIZE: usize = 28;
+
+/// Writes clouds as Cesium 3D Tiles 1.0 point cloud tilesets: a `tileset.json` with one tile
+/// per octree node and the node's points in `tiles/D-X-Y-Z.pnts`. The octree is built exactly
+/// as for EPT output, so coarse tiles hold an even subset of everything below them and children
+/// add detail (`"refine": "ADD"`)
+#[derive(Default)]
+pub struct TilesetWriter {
+    octree: EptBuilder,
+}
+
+impl TilesetWriter {
+    /// Writer using the node capacities, depth, bounds, seed and LOD averaging of `octree`; tile
+    /// overlap is not applied
+    pub fn new(octree: EptBuilder) -> Self {
+        Self { octree }
+    }
+
+    /// Estimated output size: one record per point plus a header and feature table per tile
+    pub fn estimate_size(&self, point_cloud: &PointCloud) -> SizeEstimate {
+        let metadata = &point_cloud.metadata;
+        let mut bytes_per_point = 12;
+        if metadata.has_colors {
+            bytes_per_point += if metadata.has_alpha { 4 } else { 3 };
+        }
+        if metadata.has_normals {
+            bytes_per_point += 12;
+        }
+        if metadata.has_material_ids {
+            bytes_per_point += 4;
+        }
+        bytes_per_point += metadata.attributes.iter().map(|a| a.data_type.size()).sum::<u32>();
+
+        let tiles = point_cloud.points.len().div_ceil(self.octree.capacity(0).max(1)) as u64;
+        SizeEstimate {
+            bytes_per_point: bytes_per_point as f64,
+            overhead: 1024 + tiles * 512,
+        }
+    }
+
+    /// Write `tileset.json` and the tiles into `output_dir`. Points are treated as Y-up like
+    /// glTF: the root transform turns them Z-up, as 3D Tiles expects, and adds back the
+    /// cloud's offset
+    pub fn write(&self, point_cloud: &PointCloud, output_dir: &Path) -> Result<EptBuildReport> {
+        std::fs::create_dir_all(output_dir.join(TILES_DIR))?;
+
+        let (point_cloud, _, bounds, outside) = self.octree.frame(point_cloud);
+        let point_cloud = point_cloud.as_ref();
+        let nodes = self.octree.octree(point_cloud, &bounds);
+        let parents: HashSet<OctreeKey> = nodes.iter().filter_map(|(key, _)| key.parent()).collect();
+        let averages = self.octree.level_averages(point_cloud, &nodes, &bounds);
+
+        nodes.par_iter().try_for_each(|(key, indices)| -> Result<()> {
+            let level = averages.get(key.depth as usize).filter(|_| parents.contains(key));
+            let node = key.bounds(&bounds);
+            let center = [0, 1, 2].map(|i| (node[i] + node[i + 3]) * 0.5);
+            let path = output_dir.join(TILES_DIR).join(format!("{}.pnts", key.to_path_string()));
+            std::fs::write(path, pnts(point_cloud, indices, center, level)?)?;
+            Ok(())
+        })?;
+
+        // Y-up to Z-up (a quarter turn about X), then the offset, column-major
+        let [x, y, z] = point_cloud.metadata.offset.unwrap_or([0.0; 3]);
+        let transform = [1.0, 0.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, -1.0, 0.0, 0.0, x, -z, y, 1.0];
+
+        let size = bounds[3] - bounds[0];
+        let counts: HashMap<OctreeKey, usize> = nodes.iter().map(|(key, indices)| (*key, indices.len())).collect();
+        let mut root = self.tile(OctreeKey::root(), &counts, &parents, &bounds);
+        root["transform"] = json!(transform);
+        let tileset = json!({
+            "asset": { "version": "1.0", "generator": concat!("model_parser ", env!("CARGO_PKG_VERSION")) },
+            // Error of showing nothing: about the size of the whole cloud
+            "geometricError": size,
+            "root": root,
+        });
+        std::fs::write(output_dir.join(TILESET_FILE), serde_json::to_string_pretty(&tileset)?)?;
+
+        Ok(EptBuildReport {
+            points_written: nodes.iter().map(|(_, indices)| indices.len() as u64).sum(),
+            outside_bounds: outside,
+            out_of_bounds: self.octree.out_of_bounds(),
+            tiles_resumed: 0,
+        })
+    }
+
+    /// Tile of `key` with its descendants; nodes without points (only an empty cloud's root)
+    /// get no content
+    fn tile(&self, key: OctreeKey, counts: &HashMap<OctreeKey, usize>, parents: &HashSet<OctreeKey>, bounds: &[f64; 6]) -> Value {
+        let mut tile = Map::new();
+        tile.insert("boundingVolume".to_string(), json!({ "box": bounds_box(&key.bounds(bounds)) }));
+        let has_children = parents.contains(&key);
+        let error = self.octree.geometric_error(&key, has_children, bounds[3] - bounds[0]);
+        tile.insert("geometricError".to_string(), json!(error));
+        tile.insert("refine".to_string(), json!("ADD"));
+        if counts.contains_key(&key) {
+            tile.insert("content".to_string(), json!({ "uri": format!("{}/{}.pnts", TILES_DIR, key.to_path_string()) }));
+        }
+        if has_children {
+            let children: Vec<Value> = key
+                .children()
+                .into_iter()
+                .filter(|child| counts.contains_key(child))
+                .map(|child| self.tile(child, counts, parents, bounds))
+                .collect();
+            tile.insert("children".to_string(), Value::Array(children));
+        }
+        Value::Object(tile)
+    }
+}
+
+/// Encode the points at `indices` as a `.pnts` tile: float positions relative to
+/// `RTC_CENTER`, float normals, 8-bit sRGB(A) colors, and material ids and generic attributes
+/// as per-point batch table properties
+fn pnts(point_cloud: &PointCloud, indices: &[usize], center: [f64; 3], averages: Option<&LevelAverages>) -> Result<Vec<u8>> {
+    let metadata = &point_cloud.metadata;
+    let points: Vec<Cow<_>> = indices
+        .iter()
+        .map(|&index| {
+            let point = &point_cloud.points[index];
+            averages.map_or(Cow::Borrowed(point), |averages| averages.apply(point))
+        })
+        .collect();
+
+    // Feature table: floats first so every property stays 4-byte aligned
+    let mut features = json!({ "POINTS_LENGTH": points.len(), "RTC_CENTER": center, "POSITION": { "byteOffset": 0 } });
+    let mut feature_data = Vec::with_capacity(points.len() * 28);
+    for point in &points {
+        for (component, center) in point.position.iter().zip(center) {
+            feature_data.extend_from_slice(&((*component as f64 - center) as f32).to_le_bytes());
+        }
+    }
+    if metadata.has_normals {
+        features["NORMAL"] = json!({ "byteOffset": feature_data.len() });
+        for point in &points {
+            for component in point.normal.unwrap_or([0.0, 0.0, 1.0]) {
+                feature_data.extend_from_slice(&component.to_le_bytes());
+            }
+        }
+    }
+    if metadata.has_colors {
+        features[if metadata.has_alpha { "RGBA" } else { "RGB" }] = json!({ "byteOffset": feature_data.len() });
+        for point in &points {
+            let mut rgb = point.color.unwrap_or([1.0; 3]);
+            if metadata.color_space == ColorSpace::Linear {
+                rgb = rgb.map(color::linear_to_srgb);
+            }
+            feature_data.extend_from_slice(&color::to_rgb8(rgb));
+            if metadata.has_alpha {
+                feature_data.push(color::to_u8(point.alpha.unwrap_or(1.0)));
+            }
+        }
+    }
+
+    // Batch table: one property per attribute, each 8-byte aligned
+    let mut batch = Map::new();
+    let mut batch_data = Vec::new();
+    let mut property = |name: &str, data_type: AttributeType, value: &dyn Fn(&Point) -> f64| {
+        pad(&mut batch_data, 8, 0);
+        batch.insert(
+            name.to_string(),
+            json!({ "byteOffset": batch_data.len(), "componentType": component_type(data_type), "type": "SCALAR" }),
+        );
+        for point in &points {
+            data_type.encode(value(point), &mut batch_data);
+        }
+    };
+    if metadata.has_material_ids {
+        property("material_id", AttributeType::U32, &|point: &Point| point.material_id.unwrap_or(0) as f64);
+    }
+    for (index, attribute) in metadata.attributes.iter().enumerate() {
+        property(&attribute.name, attribute.data_type, &|point: &Point| point.attributes.get(index).copied().unwrap_or(0.0));
+    }
+
+    // Every section ends on an 8-byte boundary of the file; JSON is padded with spaces
+    let mut feature_json = serde_json::to_vec(&features)?;
+    let end = (PNTS_HEADER_SIZE + feature_json.len()).next_multiple_of(8);
+    feature_json.resize(end - PNTS_HEADER_SIZE, b' ');
+    let mut batch_json = if batch.is_empty() { Vec::new() } else { serde_json::to_vec(&batch)? };
+    pad(&mut batch_json, 8, b' ');
+    pad(&mut feature_data, 8, 0);
+    pad(&mut batch_data, 8, 0);
+
+    let lengths = [feature_json.len(), feature_data.len(), batch_json.len(), batch_data.len()];
+    let total = PNTS_HEADER_SIZE + lengths.iter().sum::<usize>();
+    let mut tile = Vec::with_capacity(total);
+    tile.extend_from_slice(b"pnts");
+    tile.extend_from_slice(&1u32.to_le_bytes());
+    tile.extend_from_slice(&(total as u32).to_le_bytes());
+    for length in lengths {
+        tile.extend_from_slice(&(length as u32).to_le_bytes());
+    }
+    tile.extend_from_slice(&feature_json);
+    tile.extend_from_slice(&feature_data);
+    tile.extend_from_slice(&batch_json);
+    tile.extend_from_slice(&batch_data);
+    Ok(tile)
+}
+
+/// Pad `bytes` with `fill` to a multiple of `alignment`
+fn pad(bytes: &mut Vec<u8>, alignment: usize, fill: u8) {
+    bytes.resize(bytes.len().next_multiple_of(alignment), fill);
+}
+
+/// Batch table component type of an attribute type
+fn component_type(data_type: AttributeType) -> &'static str {
+    match data_type {
+        AttributeType::U8 => "UNSIGNED_BYTE",
+        AttributeType::U16 => "UNSIGNED_SHORT",
+        AttributeType::U32 => "UNSIGNED_INT",
+        AttributeType::I32 => "INT",
+        AttributeType::F32 => "FLOAT",
+        AttributeType::F64 => "DOUBLE",
+    }
+}
@@ -0,0 +1,132 @@
+use crate::{
+    error::{ModelParserError, Result},
+    point_cloud::PointCloud,
+};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// What to do with invalid values found by `validate`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ValidationPolicy {
+    /// Drop points with any invalid value
+    Drop,
+
+    /// Repair what can be repaired: normalize or remove normals, clamp colors, zero
+    /// non-finite attributes. Points with non-finite positions are always dropped
+    #[default]
+    Fix,
+
+    /// Fail the conversion
+    Error,
+}
+
+/// Number of points with each kind of invalid value
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ValidationCounts {
+    /// NaN or infinite position components
+    pub invalid_positions: usize,
+
+    /// Non-finite or zero-length normals
+    pub invalid_normals: usize,
+
+    /// Non-finite or out of 0.0-1.0 colors or alpha
+    pub invalid_colors: usize,
+
+    /// Non-finite generic attribute values
+    pub invalid_attributes: usize,
+
+    /// Points removed
+    pub dropped: usize,
+}
+
+impl ValidationCounts {
+    pub fn is_clean(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+#[derive(Clone, Copy, Default)]
+struct PointIssues {
+    position: bool,
+    normal: bool,
+    color: bool,
+    attributes: bool,
+}
+
+/// Check every point and apply `policy`. Counts are recorded in `metadata.validation`
+/// when anything was found
+pub fn validate(point_cloud: &mut PointCloud, policy: ValidationPolicy) -> Result<ValidationCounts> {
+    let issues: Vec<PointIssues> = point_cloud
+        .points
+        .par_iter()
+        .map(|point| PointIssues {
+            position: point.position.iter().any(|v| !v.is_finite()),
+            normal: point.normal.is_some_and(|n| {
+                n.iter().any(|v| !v.is_finite()) || n.iter().map(|v| v * v).sum::<f32>() <= f32::EPSILON
+            }),
+            color: point.color.iter().flatten().chain(&point.alpha).any(|v| !v.is_finite() || *v < 0.0 || *v > 1.0),
+            attributes: point.attributes.iter().any(|v| !v.is_finite()),
+        })
+        .collect();
+
+    let mut counts = ValidationCounts {
+        invalid_positions: issues.iter().filter(|i| i.position).count(),
+        invalid_normals: issues.iter().filter(|i| i.normal).count(),
+        invalid_colors: issues.iter().filter(|i| i.color).count(),
+        invalid_attributes: issues.iter().filter(|i| i.attributes).count(),
+        dropped: 0,
+    };
+    if counts.is_clean() {
+        return Ok(counts);
+    }
+
+    if policy == ValidationPolicy::Error {
+        return Err(ModelParserError::InvalidData(format!(
+            "{} invalid positions, {} invalid normals, {} invalid colors, {} invalid attribute values",
+            counts.invalid_positions, counts.invalid_normals, counts.invalid_colors, counts.invalid_attributes
+        )));
+    }
+
+    if policy == ValidationPolicy::Fix {
+        point_cloud
+            .points
+            .par_iter_mut()
+            .zip(issues.par_iter())
+            .for_each(|(point, issues)| {
+                if issues.normal {
+                    point.normal = None;
+                } else if let Some(normal) = point.normal.as_mut() {
+                    let length = normal.iter().map(|v| v * v).sum::<f32>().sqrt();
+                    normal.iter_mut().for_each(|v| *v /= length);
+                }
+                if issues.color
+                    && let Some(color) = point.color.as_mut()
+                {
+                    color
+                        .iter_mut()
+                        .for_each(|v| *v = if v.is_finite() { v.clamp(0.0, 1.0) } else { 0.0 });
+                }
+                if issues.color
+                    && let Some(alpha) = point.alpha.as_mut()
+                {
+                    *alpha = if alpha.is_finite() { alpha.clamp(0.0, 1.0) } else { 1.0 };
+                }
+                if issues.attributes {
+                    point.attributes.iter_mut().filter(|v| !v.is_finite()).for_each(|v| *v = 0.0);
+                }
+            });
+    }
+
+    let keep: Vec<bool> = issues
+        .iter()
+        .map(|i| match policy {
+            ValidationPolicy::Drop => !(i.position || i.normal || i.color || i.attributes),
+            _ => !i.position,
+        })
+        .collect();
+    counts.dropped = keep.iter().filter(|k| !**k).count();
+    point_cloud.retain_by_mask(&keep);
+    point_cloud.metadata.validation = Some(counts);
+
+    Ok(counts)
+}
@@ -0,0 +1,300 @@
+use crate::point_cloud::{PointCloud, PointCloudMetadata};
+use serde::{Deserialize, Serialize};
+
+/// Settings embedded into generated viewer pages
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ViewerConfig {
+    /// Page title
+    pub title: String,
+
+    /// Point size in world units
+    pub point_size: f32,
+
+    /// Background color as a CSS hex string
+    pub background: String,
+
+    /// Maximum number of points loaded from tiled sources
+    pub point_budget: usize,
+
+    /// Whether stored colors are sRGB encoded ("Srgb") or linear ("Linear")
+    pub color_space: String,
+
+    /// Bounds `[min, max]` the camera is framed on; the whole cloud when absent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub frame_bounds: Option<[[f32; 3]; 2]>,
+}
+
+impl Default for ViewerConfig {
+    fn default() -> Self {
+        Self {
+            title: "HueGraphics Point Cloud".to_string(),
+            point_size: 0.01,
+            background: "#101018".to_string(),
+            point_budget: 2_000_000,
+            color_space: "Srgb".to_string(),
+            frame_bounds: None,
+        }
+    }
+}
+
+impl ViewerConfig {
+    /// Defaults adapted to a cloud: titled after its source, sized to and framed on its
+    /// robust bounds when known, otherwise its full bounds
+    pub fn for_metadata(metadata: &PointCloudMetadata) -> Self {
+        let (min, max) = metadata
+            .robust_bounds
+            .map_or((metadata.bounds_min, metadata.bounds_max), |b| (b.min, b.max));
+        let extent = (0..3).map(|i| max[i] - min[i]).fold(0.0f32, f32::max);
+
+        Self {
+            title: metadata.source_file.clone(),
+            point_size: if extent > 0.0 { extent / 500.0 } else { 0.01 },
+            color_space: format!("{:?}", metadata.color_space),
+            frame_bounds: metadata.robust_bounds.map(|b| [b.min, b.max]),
+            ..Default::default()
+        }
+    }
+}
+
+/// Version of three.js loaded by generated pages, matching the web client
+pub const THREE_VERSION: &str = "0.169.0";
+
+/// Render a standalone HTML page that streams an EPT dataset located next to it
+pub fn render_ept_viewer(config: &ViewerConfig) -> String {
+    render(config, EPT_SOURCE)
+}
+
+/// Render a single self-contained HTML page with the cloud's positions and colors embedded
+/// as base64 float arrays, so it opens straight from disk without a server
+pub fn render_embedded_viewer(config: &ViewerConfig, point_cloud: &PointCloud) -> String {
+    let mut positions = Vec::with_capacity(point_cloud.points.len() * 12);
+    let mut colors = Vec::with_capacity(point_cloud.points.len() * 12);
+    for point in &point_cloud.points {
+        for value in point.position {
+            positions.extend_from_slice(&value.to_le_bytes());
+        }
+        if let Some(color) = point.color {
+            for value in color {
+                colors.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+    }
+
+    // Colors are only usable when every point has one
+    let colors = if point_cloud.metadata.has_colors && colors.len() == positions.len() {
+        format!("'{}'", base64(&colors))
+    } else {
+        "null".to_string()
+    };
+
+    let source = EMBEDDED_SOURCE
+        .replace("{{POSITIONS}}", &base64(&positions))
+        .replace("{{COLORS}}", &colors);
+    render(config, &source)
+}
+
+fn render(config: &ViewerConfig, source: &str) -> String {
+    let config_json = serde_json::to_string_pretty(config)
+        .unwrap_or_else(|_| "{}".to_string())
+        .replace("</", "<\\/");
+
+    VIEWER_TEMPLATE
+        .replace("{{TITLE}}", &html_escape(&config.title))
+        .replace("{{THREE_VERSION}}", THREE_VERSION)
+        .replace("{{CONFIG}}", &config_json)
+        .replace("{{SOURCE}}", source)
+}
+
+fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Standard base64 with padding
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let b = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        encoded.push(ALPHABET[(n >> 18) as usize & 63] as char);
+        encoded.push(ALPHABET[(n >> 12) as usize & 63] as char);
+        encoded.push(if chunk.len() > 1 { ALPHABET[(n >> 6) as usize & 63] as char } else { '=' });
+        encoded.push(if chunk.len() > 2 { ALPHABET[n as usize & 63] as char } else { '=' });
+    }
+    encoded
+}
+
+/// Decodes the base64 float arrays embedded by `render_embedded_viewer`
+const EMBEDDED_SOURCE: &str = r#"
+const EMBEDDED_POSITIONS = '{{POSITIONS}}';
+const EMBEDDED_COLORS = {{COLORS}};
+
+function decodeFloats(text) {
+  const bytes = Uint8Array.from(atob(text), (c) => c.charCodeAt(0));
+  return new Float32Array(bytes.buffer);
+}
+
+async function loadSource(config) {
+  return {
+    positions: decodeFloats(EMBEDDED_POSITIONS),
+    colors: EMBEDDED_COLORS ? decodeFloats(EMBEDDED_COLORS) : null,
+  };
+}
+"#;
+
+/// Loads ept.json, the hierarchy and tiles breadth-first until the point budget is reached
+const EPT_SOURCE: &str = r#"
+async function loadSource(config) {
+  const metadata = await (await fetch('ept.json')).json();
+  const hierarchy = await (await fetch('ept-hierarchy/0-0-0-0.json')).json();
+  const keys = Object.keys(hierarchy).sort((a, b) => Number(a.split('-')[0]) - Number(b.split('-')[0]));
+
+  const chunks = [];
+  let loaded = 0;
+  for (const key of keys) {
+    if (loaded + hierarchy[key] > config.pointBudget && loaded > 0) break;
+    const buffer = await (await fetch(`ept-data/${key}.bin`)).arrayBuffer();
+    chunks.push(decodeRecords(buffer, metadata.schema));
+    loaded += hierarchy[key];
+  }
+  return mergeChunks(chunks);
+}
+
+function decodeRecords(buffer, schema) {
+  const view = new DataView(buffer);
+  const offsets = {};
+  let stride = 0;
+  for (const dim of schema) {
+    offsets[dim.name] = { offset: stride, type: dim.type, size: dim.size, scale: dim.scale ?? 1, shift: dim.offset ?? 0 };
+    stride += dim.size;
+  }
+  const read = (base, dim) => {
+    const at = base + dim.offset;
+    if (dim.type === 'floating') return dim.size === 8 ? view.getFloat64(at, true) : view.getFloat32(at, true);
+    if (dim.type === 'signed') return dim.size === 1 ? view.getInt8(at) : dim.size === 2 ? view.getInt16(at, true) : view.getInt32(at, true);
+    return dim.size === 1 ? view.getUint8(at) : dim.size === 2 ? view.getUint16(at, true) : view.getUint32(at, true);
+  };
+
+  const count = Math.floor(buffer.byteLength / stride);
+  const positions = new Float32Array(count * 3);
+  const hasColor = offsets.Red && offsets.Green && offsets.Blue;
+  const colors = hasColor ? new Float32Array(count * 3) : null;
+  const colorMax = hasColor ? Math.pow(2, offsets.Red.size * 8) - 1 : 1;
+  for (let i = 0; i < count; i++) {
+    const base = i * stride;
+    positions[i * 3] = read(base, offsets.X) * offsets.X.scale + offsets.X.shift;
+    positions[i * 3 + 1] = read(base, offsets.Y) * offsets.Y.scale + offsets.Y.shift;
+    positions[i * 3 + 2] = read(base, offsets.Z) * offsets.Z.scale + offsets.Z.shift;
+    if (hasColor) {
+      colors[i * 3] = read(base, offsets.Red) / colorMax;
+      colors[i * 3 + 1] = read(base, offsets.Green) / colorMax;
+      colors[i * 3 + 2] = read(base, offsets.Blue) / colorMax;
+    }
+  }
+  return { positions, colors };
+}
+
+function mergeChunks(chunks) {
+  const total = chunks.reduce((n, c) => n + c.positions.length, 0);
+  const positions = new Float32Array(total);
+  const colors = chunks.every((c) => c.colors) ? new Float32Array(total) : null;
+  let offset = 0;
+  for (const chunk of chunks) {
+    positions.set(chunk.positions, offset);
+    if (colors) colors.set(chunk.colors, offset);
+    offset += chunk.positions.length;
+  }
+  return { positions, colors };
+}
+"#;
+
+const VIEWER_TEMPLATE: &str = r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+  <meta charset="utf-8">
+  <meta name="viewport" content="width=device-width, initial-scale=1">
+  <title>{{TITLE}}</title>
+  <style>
+    html, body { margin: 0; height: 100%; overflow: hidden; font-family: sans-serif; }
+    #status { position: absolute; top: 8px; left: 8px; color: #ccc; font-size: 12px; }
+  </style>
+  <script type="importmap">
+    {
+      "imports": {
+        "three": "https://unpkg.com/three@{{THREE_VERSION}}/build/three.module.js",
+        "three/addons/": "https://unpkg.com/three@{{THREE_VERSION}}/examples/jsm/"
+      }
+    }
+  </script>
+  <script id="viewer-config" type="application/json">
+{{CONFIG}}
+  </script>
+</head>
+<body>
+  <div id="status">Loading…</div>
+  <script type="module">
+    import * as THREE from 'three';
+    import { OrbitControls } from 'three/addons/controls/OrbitControls.js';
+
+    const config = JSON.parse(document.getElementById('viewer-config').textContent);
+    const status = document.getElementById('status');
+{{SOURCE}}
+    const srgbToLinear = (c) => (c <= 0.04045 ? c / 12.92 : Math.pow((c + 0.055) / 1.055, 2.4));
+
+    const renderer = new THREE.WebGLRenderer({ antialias: true });
+    renderer.setPixelRatio(window.devicePixelRatio);
+    renderer.setSize(window.innerWidth, window.innerHeight);
+    document.body.appendChild(renderer.domElement);
+
+    const scene = new THREE.Scene();
+    scene.background = new THREE.Color(config.background);
+    const camera = new THREE.PerspectiveCamera(60, window.innerWidth / window.innerHeight, 0.001, 100000);
+    const controls = new OrbitControls(camera, renderer.domElement);
+
+    try {
+      const { positions, colors } = await loadSource(config);
+      const geometry = new THREE.BufferGeometry();
+      geometry.setAttribute('position', new THREE.BufferAttribute(positions, 3));
+      if (colors) {
+        if (config.colorSpace === 'Srgb') {
+          for (let i = 0; i < colors.length; i++) colors[i] = srgbToLinear(colors[i]);
+        }
+        geometry.setAttribute('color', new THREE.BufferAttribute(colors, 3));
+      }
+      const material = new THREE.PointsMaterial({ size: config.pointSize, vertexColors: !!colors });
+      scene.add(new THREE.Points(geometry, material));
+
+      geometry.computeBoundingSphere();
+      let { center, radius } = geometry.boundingSphere;
+      if (config.frameBounds) {
+        const frame = new THREE.Box3(new THREE.Vector3(...config.frameBounds[0]), new THREE.Vector3(...config.frameBounds[1]));
+        center = frame.getCenter(new THREE.Vector3());
+        radius = frame.getSize(new THREE.Vector3()).length() / 2;
+      }
+      controls.target.copy(center);
+      camera.position.copy(center).add(new THREE.Vector3(0, radius * 0.5, radius * 2.5));
+      controls.update();
+      status.textContent = `${positions.length / 3} points`;
+    } catch (error) {
+      status.textContent = `Failed to load point cloud: ${error}`;
+    }
+
+    window.addEventListener('resize', () => {
+      camera.aspect = window.innerWidth / window.innerHeight;
+      camera.updateProjectionMatrix();
+      renderer.setSize(window.innerWidth, window.innerHeight);
+    });
+
+    renderer.setAnimationLoop(() => {
+      controls.update();
+      renderer.render(scene, camera);
+    });
+  </script>
+</body>
+</html>
+"#;
@@ -0,0 +1,125 @@
+use crate::mesh::Mesh;
+use glam::{IVec3, Vec3};
+use std::collections::{HashMap, HashSet};
+
+/// Default largest angle between the normals of two vertices that are welded, in degrees.
+/// Flat-shaded exports of curved surfaces differ by a few degrees per face and are merged,
+/// while hard edges (CAD models are mostly 90°) keep their separate vertices
+pub const DEFAULT_NORMAL_ANGLE: f32 = 30.0;
+
+/// Merge vertices closer than `tolerance` whose normals differ by at most `normal_angle`
+/// degrees and that share a material, so exporters' per-face vertex copies become one
+/// connected surface. Merged normals and colors are averaged. Triangles left degenerate by
+/// the merge, and repeated copies of the same triangle, are removed. Returns the number of
+/// vertices removed
+pub fn weld(mesh: &mut Mesh, tolerance: f32, normal_angle: f32) -> usize {
+    if mesh.point_set || mesh.vertices.is_empty() {
+        return 0;
+    }
+
+    let min_cosine = normal_angle.to_radians().cos();
+    let cell = |p: Vec3| {
+        if tolerance > 0.0 { (p / tolerance).floor().as_ivec3() } else { IVec3::ZERO }
+    };
+    let exact = |p: Vec3| p.to_array().map(f32::to_bits);
+
+    // Greedy: each vertex joins the first compatible representative near it, or becomes one.
+    // With zero tolerance only identical positions match, looked up by their bits
+    let mut grid: HashMap<IVec3, Vec<usize>> = HashMap::new();
+    let mut identical: HashMap<[u32; 3], Vec<usize>> = HashMap::new();
+    let mut representatives: Vec<usize> = Vec::new();
+    let mut remap = Vec::with_capacity(mesh.vertices.len());
+    for (vertex, &position) in mesh.vertices.iter().enumerate() {
+        let normal = mesh.normals.get(vertex).copied();
+        let material = mesh.material_ids.get(vertex);
+        let compatible = |&other: &usize| {
+            let other_vertex = representatives[other];
+            mesh.vertices[other_vertex].distance(position) <= tolerance
+                && mesh.material_ids.get(other_vertex) == material
+                && match (normal, mesh.normals.get(other_vertex)) {
+                    (Some(a), Some(&b)) if a != Vec3::ZERO && b != Vec3::ZERO => {
+                        a.normalize().dot(b.normalize()) >= min_cosine
+                    }
+                    _ => true,
+                }
+        };
+
+        let found = if tolerance > 0.0 {
+            let home = cell(position);
+            (-1..=1)
+                .flat_map(|x| (-1..=1).flat_map(move |y| (-1..=1).map(move |z| home + IVec3::new(x, y, z))))
+                .find_map(|key| grid.get(&key)?.iter().copied().find(|other| compatible(other)))
+        } else {
+            identical.get(&exact(position)).and_then(|list| list.iter().copied().find(|other| compatible(other)))
+        };
+
+        let index = found.unwrap_or_else(|| {
+            let index = representatives.len();
+            representatives.push(vertex);
+            if tolerance > 0.0 {
+                grid.entry(cell(position)).or_default().push(index);
+            } else {
+                identical.entry(exact(position)).or_default().push(index);
+            }
+            index
+        });
+        remap.push(index);
+    }
+
+    let removed = mesh.vertices.len() - representatives.len();
+    if removed == 0 {
+        return 0;
+    }
+
+    let mut counts = vec![0u32; representatives.len()];
+    remap.iter().for_each(|&index| counts[index] += 1);
+    let average = |values: &[Vec3]| -> Vec<Vec3> {
+        if values.is_empty() {
+            return Vec::new();
+        }
+        let mut sums = vec![Vec3::ZERO; representatives.len()];
+        for (&index, &value) in remap.iter().zip(values) {
+            sums[index] += value;
+        }
+        sums
+    };
+
+    mesh.normals = average(&mesh.normals).into_iter().map(Vec3::normalize_or_zero).collect();
+    mesh.colors = average(&mesh.colors)
+        .into_iter()
+        .zip(&counts)
+        .map(|(sum, &count)| sum / count as f32)
+        .collect();
+    if !mesh.alphas.is_empty() {
+        let mut sums = vec![0.0f32; representatives.len()];
+        for (&index, &alpha) in remap.iter().zip(&mesh.alphas) {
+            sums[index] += alpha;
+        }
+        mesh.alphas = sums.into_iter().zip(&counts).map(|(sum, &count)| sum / count as f32).collect();
+    }
+    mesh.vertices = representatives.iter().map(|&v| mesh.vertices[v]).collect();
+    if !mesh.material_ids.is_empty() {
+        mesh.material_ids = representatives.iter().map(|&v| mesh.material_ids[v]).collect();
+    }
+
+    // Degenerate triangles and repeats of a triangle (in any rotation of its corners) would
+    // add area that isn't there
+    let mut seen = HashSet::new();
+    let triangles: Vec<[usize; 3]> = mesh.indices.chunks_exact(3).map(|t| [t[0], t[1], t[2]].map(|i| remap[i])).collect();
+    mesh.indices = triangles
+        .into_iter()
+        .filter(|&[a, b, c]| a != b && b != c && c != a)
+        .filter(|triangle| {
+            let first = (0..3).min_by_key(|&i| triangle[i]).unwrap_or(0);
+            seen.insert([0, 1, 2].map(|i| triangle[(first + i) % 3]))
+        })
+        .flatten()
+        .collect();
+    for index in mesh.points.iter_mut().chain(mesh.lines.iter_mut()) {
+        *index = remap[*index];
+    }
+    let mut seen = HashSet::new();
+    mesh.points.retain(|&index| seen.insert(index));
+
+    removed
+}
@@ -0,0 +1,113 @@
+/// Minimal XML element tree: enough for COLLADA and E57, which use no entities in their data
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Element {
+    pub name: String,
+    pub attributes: Vec<(String, String)>,
+    pub children: Vec<Element>,
+    pub text: String,
+}
+
+impl Element {
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes.iter().find(|(key, _)| key == name).map(|(_, value)| value.as_str())
+    }
+
+    pub fn child(&self, name: &str) -> Option<&Element> {
+        self.children.iter().find(|child| child.name == name)
+    }
+
+    pub fn children<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Element> {
+        self.children.iter().filter(move |child| child.name == name)
+    }
+
+    /// Parse a document, returning its root element
+    pub fn parse(text: &str) -> std::result::Result<Self, String> {
+        let mut stack: Vec<Element> = vec![Element::default()];
+        let mut rest = text;
+
+        while let Some(start) = rest.find('<') {
+            stack.last_mut().ok_or("unbalanced tags")?.text.push_str(&rest[..start]);
+            rest = &rest[start..];
+
+            if let Some(after) = rest.strip_prefix("<!--") {
+                rest = &after[after.find("-->").ok_or("unterminated comment")? + 3..];
+            } else if let Some(after) = rest.strip_prefix("<![CDATA[") {
+                let end = after.find("]]>").ok_or("unterminated CDATA")?;
+                stack.last_mut().ok_or("unbalanced tags")?.text.push_str(&after[..end]);
+                rest = &after[end + 3..];
+            } else if rest.starts_with("<?") || rest.starts_with("<!") {
+                rest = &rest[rest.find('>').ok_or("unterminated declaration")? + 1..];
+            } else if let Some(after) = rest.strip_prefix("</") {
+                let end = after.find('>').ok_or("unterminated closing tag")?;
+                let element = stack.pop().ok_or("unbalanced tags")?;
+                if element.name != after[..end].trim() {
+                    return Err(format!("mismatched closing tag '{}'", after[..end].trim()));
+                }
+                stack.last_mut().ok_or("unbalanced tags")?.children.push(element);
+                rest = &after[end + 1..];
+            } else {
+                let end = tag_end(rest).ok_or("unterminated tag")?;
+                let tag = &rest[1..end];
+                let (tag, self_closing) = match tag.strip_suffix('/') {
+                    Some(tag) => (tag, true),
+                    None => (tag, false),
+                };
+                let element = Self::open_tag(tag)?;
+                if self_closing {
+                    stack.last_mut().ok_or("unbalanced tags")?.children.push(element);
+                } else {
+                    stack.push(element);
+                }
+                rest = &rest[end + 1..];
+            }
+        }
+
+        let mut document = stack.pop().ok_or("unbalanced tags")?;
+        if !stack.is_empty() {
+            return Err(format!("unclosed element '{}'", document.name));
+        }
+        document.children.pop().ok_or_else(|| "empty document".to_string())
+    }
+
+    fn open_tag(tag: &str) -> std::result::Result<Self, String> {
+        let name_end = tag.find(|c: char| c.is_whitespace()).unwrap_or(tag.len());
+        let mut element = Element {
+            name: tag[..name_end].to_string(),
+            ..Default::default()
+        };
+
+        let mut rest = tag[name_end..].trim_start();
+        while !rest.is_empty() {
+            let equals = rest.find('=').ok_or_else(|| format!("invalid attribute in <{}>", element.name))?;
+            let key = rest[..equals].trim().to_string();
+            let value = rest[equals + 1..].trim_start();
+            let quote = value.chars().next().filter(|&c| c == '"' || c == '\'').ok_or("unquoted attribute")?;
+            let end = value[1..].find(quote).ok_or("unterminated attribute")? + 1;
+            element.attributes.push((key, unescape(&value[1..end])));
+            rest = value[end + 1..].trim_start();
+        }
+        Ok(element)
+    }
+}
+
+/// End of a start tag, skipping '>' inside quoted attribute values
+fn tag_end(text: &str) -> Option<usize> {
+    let mut quote = None;
+    for (i, c) in text.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), _) if c == q => quote = None,
+            (None, '>') => return Some(i),
+            _ => {}
+        }
+    }
+    None
+}
+
+fn unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
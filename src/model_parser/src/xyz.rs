@@ -0,0 +1,183 @@
+use crate::{
+    attributes::{AttributeDescriptor, AttributeType},
+    color,
+    config::PointCloudConfig,
+    error::{ModelParserError, Result},
+    las::SHIFT_THRESHOLD,
+    mesh::Mesh,
+};
+use glam::Vec3;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Meaning of each column of a text point file, in order: `x`, `y`, `z`, `r`/`red`,
+/// `g`/`green`, `b`/`blue`, `nx`/`normal_x`, `ny`/`normal_y`, `nz`/`normal_z`, `_` to skip a
+/// column, or any other name to keep the column as an attribute
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ColumnMapping {
+    pub columns: Vec<String>,
+}
+
+impl ColumnMapping {
+    /// Parse a comma-separated list of column names, e.g. `x,y,z,_,r,g,b`
+    pub fn parse(spec: &str) -> Result<Self> {
+        let mapping = Self::from_names(spec.split(','));
+        for axis in ["x", "y", "z"] {
+            if !mapping.columns.iter().any(|c| c == axis) {
+                return Err(ModelParserError::InvalidData(format!(
+                    "column mapping '{}' has no '{}' column",
+                    spec, axis
+                )));
+            }
+        }
+        Ok(mapping)
+    }
+
+    fn from_names<'a>(names: impl IntoIterator<Item = &'a str>) -> Self {
+        Self {
+            columns: names.into_iter().map(|name| name.trim().to_ascii_lowercase()).collect(),
+        }
+    }
+
+    /// `x y z r g b` for files with at least six columns, otherwise `x y z`
+    fn default_for(columns: usize) -> Self {
+        let names: &[&str] = if columns >= 6 { &["x", "y", "z", "r", "g", "b"] } else { &["x", "y", "z"] };
+        Self::from_names(names.iter().copied())
+    }
+
+    fn find(&self, names: &[&str]) -> Option<usize> {
+        self.columns.iter().position(|c| names.contains(&c.as_str()))
+    }
+}
+
+impl std::str::FromStr for ColumnMapping {
+    type Err = ModelParserError;
+
+    fn from_str(spec: &str) -> Result<Self> {
+        Self::parse(spec)
+    }
+}
+
+const COLOR_NAMES: [&[&str]; 3] = [&["r", "red"], &["g", "green"], &["b", "blue"]];
+const NORMAL_NAMES: [&[&str]; 3] = [&["nx", "normal_x"], &["ny", "normal_y"], &["nz", "normal_z"]];
+
+/// Load a plain-text point file (`.xyz`, `.csv`, `.txt`) as a point set. Values are separated
+/// by commas, semicolons, tabs or spaces; lines starting with `#` or `//` are comments. Columns
+/// follow `config.text_columns`, else a header line of names, else `x y z [r g b]`. Colors are
+/// sRGB, scaled by their largest value as 0-1, 0-255 or 0-65535
+pub fn load(path: &Path, config: &PointCloudConfig) -> Result<Mesh> {
+    let text = std::fs::read_to_string(path)?;
+    let invalid = |line: usize, message: &str| {
+        ModelParserError::InvalidData(format!("{}:{}: {}", path.display(), line + 1, message))
+    };
+
+    let mut mapping = config.text_columns.clone();
+    let mut rows: Vec<Vec<f64>> = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with("//") {
+            continue;
+        }
+
+        let fields: Vec<&str> = if line.contains(',') {
+            line.split(',').map(str::trim).collect()
+        } else if line.contains(';') {
+            line.split(';').map(str::trim).collect()
+        } else {
+            line.split_whitespace().collect()
+        };
+
+        let values: Option<Vec<f64>> = fields.iter().map(|f| f.parse().ok()).collect();
+        match values {
+            Some(values) => rows.push(values),
+            // A leading line of names is a header; it names the columns unless a mapping was given
+            None if rows.is_empty() => {
+                if mapping.is_none() {
+                    mapping = Some(ColumnMapping::from_names(fields.iter().copied()));
+                }
+            }
+            None => return Err(invalid(number, "expected numeric values")),
+        }
+    }
+
+    let mapping = mapping.unwrap_or_else(|| ColumnMapping::default_for(rows.first().map_or(0, Vec::len)));
+    let (Some(x), Some(y), Some(z)) = (mapping.find(&["x"]), mapping.find(&["y"]), mapping.find(&["z"])) else {
+        return Err(ModelParserError::InvalidData(format!("{}: no x/y/z columns", path.display())));
+    };
+
+    let value = |row: &[f64], column: usize| row.get(column).copied().unwrap_or(f64::NAN);
+    rows.retain(|row| [x, y, z].iter().all(|&c| value(row, c).is_finite()));
+    if rows.is_empty() {
+        return Err(ModelParserError::NoMeshData);
+    }
+
+    // Shift georeferenced coordinates near the origin, as for LAS files
+    let (min, max) = rows.iter().fold(([f64::MAX; 3], [f64::MIN; 3]), |(min, max), row| {
+        let p = [value(row, x), value(row, y), value(row, z)];
+        ([0, 1, 2].map(|i| min[i].min(p[i])), [0, 1, 2].map(|i| max[i].max(p[i])))
+    });
+    let center = [0, 1, 2].map(|i| (min[i] + max[i]) * 0.5);
+    let shift = center.iter().any(|c| c.abs() > SHIFT_THRESHOLD).then(|| center.map(f64::round));
+    let origin = shift.unwrap_or([0.0; 3]);
+
+    let mut mesh = Mesh::new();
+    mesh.point_set = true;
+    mesh.offset = shift;
+    mesh.vertices = rows
+        .iter()
+        .map(|row| {
+            let p = [value(row, x), value(row, y), value(row, z)];
+            Vec3::from([0, 1, 2].map(|i| (p[i] - origin[i]) as f32))
+        })
+        .collect();
+
+    if config.include_normals
+        && let [Some(nx), Some(ny), Some(nz)] = NORMAL_NAMES.map(|names| mapping.find(names))
+    {
+        mesh.normals = rows
+            .iter()
+            .map(|row| Vec3::new(value(row, nx) as f32, value(row, ny) as f32, value(row, nz) as f32))
+            .collect();
+    }
+
+    let color_columns = COLOR_NAMES.map(|names| mapping.find(names));
+    if config.include_colors {
+        mesh.colors = match color_columns {
+            [Some(r), Some(g), Some(b)] => {
+                mesh.has_source_colors = true;
+                let largest = rows
+                    .iter()
+                    .flat_map(|row| [r, g, b].map(|c| value(row, c)))
+                    .filter(|v| v.is_finite())
+                    .fold(0.0, f64::max);
+                let range = match largest {
+                    l if l <= 1.0 => 1.0,
+                    l if l <= 255.0 => 255.0,
+                    _ => 65535.0,
+                };
+                rows.iter()
+                    .map(|row| Vec3::from([r, g, b].map(|c| color::srgb_to_linear((value(row, c) / range) as f32))))
+                    .collect()
+            }
+            _ => vec![Vec3::ONE; rows.len()],
+        };
+    }
+
+    let known: Vec<&str> = ["x", "y", "z", "_", ""]
+        .into_iter()
+        .chain(COLOR_NAMES.into_iter().flatten().copied())
+        .chain(NORMAL_NAMES.into_iter().flatten().copied())
+        .collect();
+    mesh.point_attributes = mapping
+        .columns
+        .iter()
+        .enumerate()
+        .filter(|(_, name)| !known.contains(&name.as_str()))
+        .map(|(column, name)| {
+            let values = rows.iter().map(|row| value(row, column)).collect();
+            (AttributeDescriptor::new(name.clone(), AttributeType::F32), values)
+        })
+        .collect();
+
+    Ok(mesh)
+}